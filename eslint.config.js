@@ -8,7 +8,12 @@ import react from "eslint-plugin-react";
 
 export default [
   {
-    ignores: ["dist", "src-tauri"],
+    ignores: [
+      "dist",
+      "src-tauri",
+      "src/bindings",
+      "packages/client-js/src/generated",
+    ],
   },
   {
     files: ["**/*.{ts,tsx}"],
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MarkerKind } from "./MarkerKind";
+
+export type Marker = { id: string, kind: MarkerKind, label: string | null, timestamp: number, };
@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ErrorHint } from "./ErrorHint";
+import type { Headers } from "./Headers";
+import type { Initiator } from "./Initiator";
+import type { NetworkResponse } from "./NetworkResponse";
+import type { Occurrences } from "./Occurrences";
+import type { ProvenanceEntry } from "./ProvenanceEntry";
+import type { RequestNote } from "./RequestNote";
+import type { RequestState } from "./RequestState";
+import type { SpilledBody } from "./SpilledBody";
+import type { StateTransition } from "./StateTransition";
+
+export type NetworkRequest = { id: string, url: string, method: string, headers: Headers, body: string | null, body_file: SpilledBody | null, response: NetworkResponse | null, timestamp: number, duration: number | null, initiator: Initiator | null, context: Record<string, unknown> | null, experiments: { [key in string]?: string }, notes: Array<RequestNote>, tags: Array<string>, derived: { [key in string]?: string }, aliases: Array<string>, state: RequestState, transitions: Array<StateTransition>, provenance: Array<ProvenanceEntry>, pinned: boolean, hints: Array<ErrorHint>, client_id: string | null, occurrences: Occurrences | null, };
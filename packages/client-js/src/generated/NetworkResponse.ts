@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ContentClass } from "./ContentClass";
+import type { Headers } from "./Headers";
+import type { ServerTimingMetric } from "./ServerTimingMetric";
+import type { SpilledBody } from "./SpilledBody";
+
+export type NetworkResponse = { status: number, status_text: string, headers: Headers, body: string | null, body_file: SpilledBody | null, content_class: ContentClass | null, received_bytes: number | null, server_timing: Array<ServerTimingMetric>, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ParseErrorCode } from "./ParseErrorCode";
+
+export type ParseError = { code: ParseErrorCode, message: string, message_type: string | null, id: string | null, path: string | null, expected: string | null, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NetworkResponse } from "./NetworkResponse";
+import type { RequestState } from "./RequestState";
+
+export type RequestEnd = { id: string, response: NetworkResponse, duration: number | null, state: RequestState | null, error: string | null, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Attachment } from "./Attachment";
+
+export type RequestNote = { id: string, text: string, attachments: Array<Attachment>, created_at: number, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ParseError } from "./ParseError";
+
+export type ServerMessage = { "type": "welcome", protocol: number, server_version: string, } | { "type": "error" } & ParseError;
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SourceLocation } from "./SourceLocation";
+
+export type StackFrame = { function: string | null, file: string | null, line: number | null, column: number | null, original: SourceLocation | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Batch } from "./Batch";
+import type { Hello } from "./Hello";
+import type { Marker } from "./Marker";
+import type { NetworkRequest } from "./NetworkRequest";
+import type { OfflineQueue } from "./OfflineQueue";
+import type { RequestEnd } from "./RequestEnd";
+import type { RequestStateUpdate } from "./RequestStateUpdate";
+
+export type WebSocketMessage = { "type": "network-request" } & NetworkRequest | { "type": "offline-queue" } & OfflineQueue | { "type": "marker" } & Marker | { "type": "hello" } & Hello | { "type": "request-start" } & NetworkRequest | { "type": "request-end" } & RequestEnd | { "type": "request-state" } & RequestStateUpdate | { "type": "batch" } & Batch;
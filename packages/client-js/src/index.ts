@@ -0,0 +1,96 @@
+import { interceptFetch } from "./interceptors/fetch";
+import { interceptWebSocket } from "./interceptors/websocket";
+import { interceptXhr } from "./interceptors/xhr";
+import type { MarkerKind } from "./protocol";
+import { Recorder } from "./recorder";
+import { Transport } from "./transport";
+import { createLogger, type LogLevel } from "./util";
+
+export * from "./protocol";
+export type { LogLevel } from "./util";
+
+export const CLIENT_VERSION = "0.1.0";
+
+export interface NetworkObserverOptions {
+  host?: string;
+  port?: number;
+  // Full observer URL; overrides host and port. Defaults to the
+  // NETWORK_OBSERVER_URL environment variable the observer's launcher sets.
+  url?: string;
+  // Name reported in the hello message
+  client?: string;
+  fetch?: boolean;
+  xhr?: boolean;
+  webSocket?: boolean;
+  // Requests matching any of these are never reported
+  ignoreUrls?: (string | RegExp)[];
+  batchSize?: number;
+  batchIntervalMs?: number;
+  maxQueueSize?: number;
+  autoReconnect?: boolean;
+  logging?: LogLevel;
+}
+
+export interface NetworkObserver {
+  // Pins a moment on the observer timeline, e.g. the start of a screen recording
+  marker: (kind: MarkerKind, label?: string) => void;
+  // Restores the original fetch/XHR/WebSocket and closes the connection
+  stop: () => void;
+}
+
+// Set when the app was started through the observer (`--launch`, `launch_app`)
+function launcherUrl(): string | undefined {
+  const env = (
+    globalThis as { process?: { env?: Record<string, string | undefined> } }
+  ).process?.env;
+  return env?.NETWORK_OBSERVER_URL || undefined;
+}
+
+export function setupNetworkObserver(
+  options: NetworkObserverOptions = {}
+): NetworkObserver {
+  const log = createLogger(options.logging ?? "minimal");
+  const url =
+    options.url ??
+    launcherUrl() ??
+    `ws://${options.host ?? "localhost"}:${options.port ?? 8085}`;
+  const NativeWebSocket = globalThis.WebSocket;
+
+  const transport = new Transport({
+    url,
+    client: options.client ?? "client-js",
+    clientVersion: CLIENT_VERSION,
+    batchSize: options.batchSize ?? 50,
+    batchIntervalMs: options.batchIntervalMs ?? 100,
+    maxQueueSize: options.maxQueueSize ?? 500,
+    autoReconnect: options.autoReconnect ?? true,
+    helloTimeoutMs: 1000,
+    WebSocket: NativeWebSocket,
+    log,
+  });
+  const recorder = new Recorder(transport, options.ignoreUrls ?? [], log);
+
+  const restore: (() => void)[] = [];
+  if (options.fetch ?? true) restore.push(interceptFetch(recorder));
+  if (options.xhr ?? true) restore.push(interceptXhr(recorder));
+  if ((options.webSocket ?? true) && typeof NativeWebSocket === "function") {
+    restore.push(interceptWebSocket(recorder, NativeWebSocket));
+  }
+
+  transport.connect();
+  log.minimal(`Reporting traffic to ${url}`);
+
+  return {
+    marker: (kind, label) =>
+      transport.send({
+        type: "marker",
+        kind,
+        label: label ?? null,
+        timestamp: Date.now(),
+      }),
+    stop: () => {
+      restore.forEach((undo) => undo());
+      transport.close();
+    },
+  };
+}
@@ -0,0 +1,67 @@
+import type { Recorder } from "../recorder";
+import { bodyToString, toHeaderPairs } from "../util";
+
+// Wraps the global fetch(); returns a function that restores the original
+export function interceptFetch(recorder: Recorder): () => void {
+  const originalFetch = globalThis.fetch;
+  if (typeof originalFetch !== "function") return () => {};
+
+  globalThis.fetch = async (
+    input: RequestInfo | URL,
+    init: RequestInit = {}
+  ): Promise<Response> => {
+    const isRequest = typeof Request !== "undefined" && input instanceof Request;
+    const url = isRequest ? input.url : input.toString();
+    const method = (init.method ?? (isRequest ? input.method : "GET")).toUpperCase();
+    const headers = toHeaderPairs(
+      (init.headers as Record<string, string> | undefined) ??
+        (isRequest ? input.headers : undefined)
+    );
+
+    const pending = recorder.begin({
+      url,
+      method,
+      headers,
+      body: bodyToString(init.body),
+    });
+
+    try {
+      const response = await originalFetch(input, init);
+      recorder.headers(pending);
+
+      // Read a clone so the caller still gets an unread body
+      response
+        .clone()
+        .text()
+        .catch(() => "[Unable to read response body]")
+        .then((body) =>
+          recorder.end(pending, {
+            status: response.status,
+            status_text: response.statusText || "",
+            headers: toHeaderPairs(response.headers),
+            body,
+          })
+        );
+
+      return response;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      const aborted = error instanceof Error && error.name === "AbortError";
+      recorder.end(
+        pending,
+        {
+          status: 0,
+          status_text: message,
+          headers: [],
+          body: `Error: ${message}`,
+        },
+        { state: aborted ? "aborted" : "failed", error: message }
+      );
+      throw error;
+    }
+  };
+
+  return () => {
+    globalThis.fetch = originalFetch;
+  };
+}
@@ -0,0 +1,62 @@
+import type { Recorder } from "../recorder";
+
+// Each socket is reported as one request spanning its lifetime: it starts at
+// the handshake and completes on close with status 101 and frame counts
+export function interceptWebSocket(
+  recorder: Recorder,
+  NativeWebSocket: typeof WebSocket
+): () => void {
+  class ObservedWebSocket extends NativeWebSocket {
+    constructor(url: string | URL, protocols?: string | string[]) {
+      super(url, protocols);
+
+      const pending = recorder.begin({
+        url: url.toString(),
+        method: "GET",
+        headers: [
+          ["Upgrade", "websocket"],
+          ...[protocols ?? []]
+            .flat()
+            .map((p): [string, string] => ["Sec-WebSocket-Protocol", p]),
+        ],
+      });
+      let opened = false;
+      let sent = 0;
+      let received = 0;
+
+      const nativeSend = this.send.bind(this);
+      this.send = (data) => {
+        sent += 1;
+        nativeSend(data);
+      };
+      this.addEventListener("open", () => {
+        opened = true;
+      });
+      this.addEventListener("message", () => {
+        received += 1;
+      });
+      this.addEventListener("close", (event) => {
+        recorder.end(pending, {
+          status: opened ? 101 : 0,
+          status_text: opened
+            ? `Closed (${event.code})`
+            : "Connection failed",
+          headers: this.protocol
+            ? [["Sec-WebSocket-Protocol", this.protocol]]
+            : [],
+          body: JSON.stringify({
+            code: event.code,
+            reason: event.reason,
+            frames_sent: sent,
+            frames_received: received,
+          }),
+        });
+      });
+    }
+  }
+
+  globalThis.WebSocket = ObservedWebSocket;
+  return () => {
+    globalThis.WebSocket = NativeWebSocket;
+  };
+}
@@ -0,0 +1,114 @@
+import type { Failure, Pending, Recorder } from "../recorder";
+import { bodyToString, parseRawHeaders } from "../util";
+
+interface XhrState {
+  method: string;
+  url: string;
+  headers: [string, string][];
+  pending: Pending | null;
+  failure?: Failure;
+}
+
+function responseBody(xhr: XMLHttpRequest): string {
+  try {
+    switch (xhr.responseType) {
+      case "":
+      case "text":
+        return xhr.responseText;
+      case "json":
+        return JSON.stringify(xhr.response);
+      case "blob":
+        return "[Blob data - unable to read as text]";
+      case "arraybuffer":
+        return "[ArrayBuffer data - unable to read as text]";
+      default:
+        return String(xhr.response ?? "");
+    }
+  } catch {
+    return "[Unable to read response body]";
+  }
+}
+
+// Patches XMLHttpRequest.prototype (used by Axios and friends); returns a
+// function that restores the original methods
+export function interceptXhr(recorder: Recorder): () => void {
+  if (typeof XMLHttpRequest === "undefined") return () => {};
+
+  const proto = XMLHttpRequest.prototype;
+  const { open, send, setRequestHeader } = proto;
+  const states = new WeakMap<XMLHttpRequest, XhrState>();
+
+  proto.open = function (
+    this: XMLHttpRequest,
+    method: string,
+    url: string | URL,
+    ...rest: unknown[]
+  ) {
+    states.set(this, {
+      method: method.toUpperCase(),
+      url: url.toString(),
+      headers: [],
+      pending: null,
+    });
+    return (open as (...args: unknown[]) => void).call(this, method, url, ...rest);
+  } as typeof proto.open;
+
+  proto.setRequestHeader = function (
+    this: XMLHttpRequest,
+    name: string,
+    value: string
+  ) {
+    states.get(this)?.headers.push([name, value]);
+    return setRequestHeader.call(this, name, value);
+  };
+
+  proto.send = function (
+    this: XMLHttpRequest,
+    body?: Document | XMLHttpRequestBodyInit | null
+  ) {
+    const state = states.get(this);
+    if (state) {
+      state.pending = recorder.begin({
+        url: state.url,
+        method: state.method,
+        headers: state.headers,
+        body: bodyToString(body),
+      });
+      this.addEventListener("readystatechange", () => {
+        if (this.readyState === XMLHttpRequest.HEADERS_RECEIVED) {
+          recorder.headers(state.pending);
+        }
+      });
+      // These fire just before loadend, which only says that it's over
+      this.addEventListener("error", () => {
+        state.failure = { state: "failed", error: "Network error" };
+      });
+      this.addEventListener("timeout", () => {
+        state.failure = { state: "failed", error: "Timed out" };
+      });
+      this.addEventListener("abort", () => {
+        state.failure = { state: "aborted", error: "Aborted" };
+      });
+      // loadend fires once for success, error, abort and timeout alike
+      this.addEventListener("loadend", () => {
+        recorder.end(
+          state.pending,
+          {
+            status: this.status,
+            status_text: this.statusText || "",
+            headers: parseRawHeaders(this.getAllResponseHeaders() ?? ""),
+            body: responseBody(this),
+          },
+          state.failure
+        );
+      });
+    }
+    return send.call(this, body);
+  };
+
+  return () => {
+    proto.open = open;
+    proto.send = send;
+    proto.setRequestHeader = setRequestHeader;
+  };
+}
@@ -0,0 +1,52 @@
+// Wire types are generated from the observer's Rust structs into ./generated
+// (`pnpm generate`), so the SDK and the server can't drift apart silently.
+import type { Marker } from "./generated/Marker";
+import type { NetworkRequest } from "./generated/NetworkRequest";
+import type { NetworkResponse } from "./generated/NetworkResponse";
+import type { RequestEnd } from "./generated/RequestEnd";
+import type { RequestStateUpdate } from "./generated/RequestStateUpdate";
+
+export type { Headers } from "./generated/Headers";
+export type { Initiator } from "./generated/Initiator";
+export type { MarkerKind } from "./generated/MarkerKind";
+export type { NetworkRequest } from "./generated/NetworkRequest";
+export type { ParseError } from "./generated/ParseError";
+export type { ParseErrorCode } from "./generated/ParseErrorCode";
+export type { RequestState } from "./generated/RequestState";
+export type { ServerMessage } from "./generated/ServerMessage";
+export type { WebSocketMessage } from "./generated/WebSocketMessage";
+
+// Matches `protocol::PROTOCOL_VERSION` on the observer
+export const PROTOCOL_VERSION = 2;
+
+// Fields the client reports; everything else on NetworkRequest is filled in
+// by the observer (tags, notes, derived fields, ...)
+export type ClientRequest = Pick<
+  NetworkRequest,
+  "id" | "url" | "method" | "headers" | "timestamp"
+> &
+  Partial<
+    Pick<NetworkRequest, "body" | "duration" | "initiator" | "context" | "state">
+  > & {
+    response?: ClientResponse;
+  };
+
+export type ClientResponse = Pick<
+  NetworkResponse,
+  "status" | "status_text" | "headers" | "body"
+> &
+  Partial<Pick<NetworkResponse, "received_bytes">>;
+
+export type ClientMessage =
+  | { type: "hello"; protocol: number; client: string; client_version: string }
+  | ({ type: "network-request" } & ClientRequest)
+  | ({ type: "request-start" } & ClientRequest)
+  | ({ type: "request-end" } & Pick<RequestEnd, "id" | "duration"> &
+      Partial<Pick<RequestEnd, "state" | "error">> & {
+        response: ClientResponse;
+      })
+  | ({ type: "request-state" } & Pick<RequestStateUpdate, "id" | "state"> &
+      Partial<Pick<RequestStateUpdate, "timestamp" | "detail">>)
+  | { type: "offline-queue"; requests: ClientRequest[] }
+  | ({ type: "marker" } & Omit<Marker, "id"> & { id?: string })
+  | { type: "batch"; messages: ClientMessage[] };
@@ -0,0 +1,58 @@
+import type { ClientRequest, ClientResponse } from "./protocol";
+import type { Started, Transport } from "./transport";
+import { generateId, matchesAny, type Logger } from "./util";
+
+export interface Pending {
+  request: ClientRequest;
+  started: Started | null;
+}
+
+// How a request that never got a proper response ended
+export interface Failure {
+  state: "failed" | "aborted";
+  error: string;
+}
+
+// Shared by the interceptors: stamps requests and hands them to the transport
+export class Recorder {
+  constructor(
+    private transport: Transport,
+    private ignoreUrls: (string | RegExp)[],
+    private log: Logger
+  ) {}
+
+  begin(
+    request: Omit<ClientRequest, "id" | "timestamp" | "response">
+  ): Pending | null {
+    if (matchesAny(request.url, this.ignoreUrls)) return null;
+    const stamped: ClientRequest = {
+      ...request,
+      id: generateId(),
+      timestamp: Date.now(),
+    };
+    this.log.verbose(`Intercepted ${stamped.method} ${stamped.url}`);
+    return { request: stamped, started: this.transport.start(stamped) };
+  }
+
+  // Response headers are in but the body is still downloading
+  headers(pending: Pending | null): void {
+    if (!pending) return;
+    this.transport.progress(pending.request, "headers-received", pending.started);
+  }
+
+  end(
+    pending: Pending | null,
+    response: ClientResponse,
+    failure?: Failure
+  ): void {
+    if (!pending) return;
+    const duration = Date.now() - pending.request.timestamp;
+    this.transport.finish(
+      pending.request,
+      response,
+      duration,
+      pending.started,
+      failure
+    );
+  }
+}
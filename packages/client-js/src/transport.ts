@@ -0,0 +1,255 @@
+import {
+  PROTOCOL_VERSION,
+  type ClientMessage,
+  type ClientRequest,
+  type ClientResponse,
+  type RequestState,
+  type ServerMessage,
+} from "./protocol";
+import type { Failure } from "./recorder";
+import type { Logger } from "./util";
+
+export interface TransportOptions {
+  url: string;
+  client: string;
+  clientVersion: string;
+  batchSize: number;
+  batchIntervalMs: number;
+  // Completed requests buffered while disconnected, flushed as an offline queue
+  maxQueueSize: number;
+  autoReconnect: boolean;
+  // How long to wait for `welcome` before assuming an observer that predates the
+  // versioned protocol
+  helloTimeoutMs: number;
+  // The unpatched constructor, so the observer connection is never reported
+  WebSocket: typeof WebSocket;
+  log: Logger;
+}
+
+// Handle for a request announced with `request-start` on a given connection
+export interface Started {
+  connection: number;
+}
+
+export class Transport {
+  private socket: WebSocket | null = null;
+  private outbox: ClientMessage[] = [];
+  private offline: ClientRequest[] = [];
+  private flushTimer: ReturnType<typeof setTimeout> | null = null;
+  private reconnectTimer: ReturnType<typeof setTimeout> | null = null;
+  private helloTimer: ReturnType<typeof setTimeout> | null = null;
+  private reconnectAttempts = 0;
+  private closed = false;
+  // Negotiated version; 0 until `welcome` arrives
+  private protocol = 0;
+  private ready = false;
+  private connection = 0;
+
+  constructor(private options: TransportOptions) {}
+
+  connect(): void {
+    if (this.closed || this.socket) return;
+    const { log } = this.options;
+
+    let socket: WebSocket;
+    try {
+      socket = new this.options.WebSocket(this.options.url);
+    } catch (error) {
+      log.minimal("Failed to create WebSocket connection:", error);
+      this.scheduleReconnect();
+      return;
+    }
+    this.socket = socket;
+
+    socket.onopen = () => {
+      log.minimal("Connected to observer");
+      this.reconnectAttempts = 0;
+      this.connection += 1;
+      socket.send(
+        JSON.stringify({
+          type: "hello",
+          protocol: PROTOCOL_VERSION,
+          client: this.options.client,
+          client_version: this.options.clientVersion,
+        } satisfies ClientMessage)
+      );
+      this.helloTimer = setTimeout(
+        () => this.onReady(0),
+        this.options.helloTimeoutMs
+      );
+    };
+
+    socket.onmessage = (event) => {
+      let message: ServerMessage;
+      try {
+        message = JSON.parse(String(event.data));
+      } catch {
+        return;
+      }
+      if (message.type === "welcome") {
+        this.onReady(Math.min(message.protocol, PROTOCOL_VERSION));
+      } else if (message.type === "error") {
+        // The observer rejected one of our messages; surface why
+        const field = message.path ? ` at \`${message.path}\`` : "";
+        log.minimal(
+          `Observer rejected ${message.message_type ?? "message"} ${message.id ?? ""}${field}: ${message.message}`
+        );
+      }
+    };
+
+    socket.onclose = () => {
+      log.minimal("Disconnected from observer");
+      this.socket = null;
+      this.ready = false;
+      this.protocol = 0;
+      if (this.helloTimer) clearTimeout(this.helloTimer);
+      this.scheduleReconnect();
+    };
+
+    socket.onerror = (error) => {
+      log.verbose("WebSocket error:", error);
+    };
+  }
+
+  close(): void {
+    this.closed = true;
+    this.flush();
+    if (this.reconnectTimer) clearTimeout(this.reconnectTimer);
+    if (this.flushTimer) clearTimeout(this.flushTimer);
+    if (this.helloTimer) clearTimeout(this.helloTimer);
+    this.socket?.close();
+    this.socket = null;
+  }
+
+  // Announces an in-flight request when the observer supports correlation.
+  // Returns null when the request should be reported once it completes instead.
+  start(request: ClientRequest): Started | null {
+    if (!this.ready || this.protocol < 1) return null;
+    this.enqueue({ type: "request-start", ...request });
+    return { connection: this.connection };
+  }
+
+  // Reports an intermediate state of a started request. Dropped when the
+  // observer predates lifecycle states; it still sees the request complete.
+  progress(
+    request: ClientRequest,
+    state: RequestState,
+    started: Started | null
+  ): void {
+    if (!this.isCurrent(started) || this.protocol < 2) return;
+    this.enqueue({
+      type: "request-state",
+      id: request.id,
+      state,
+      timestamp: Date.now(),
+    });
+  }
+
+  finish(
+    request: ClientRequest,
+    response: ClientResponse,
+    duration: number,
+    started: Started | null,
+    failure?: Failure
+  ): void {
+    if (this.isCurrent(started)) {
+      this.enqueue({
+        type: "request-end",
+        id: request.id,
+        response,
+        duration,
+        ...failure,
+      });
+      return;
+    }
+    this.report({ ...request, response, duration, state: failure?.state });
+  }
+
+  send(message: ClientMessage): void {
+    if (this.ready) {
+      this.enqueue(message);
+    }
+  }
+
+  // request-end and request-state only make sense on the connection that saw
+  // request-start
+  private isCurrent(started: Started | null): boolean {
+    return (
+      started !== null && this.ready && started.connection === this.connection
+    );
+  }
+
+  private report(request: ClientRequest): void {
+    if (this.ready) {
+      this.enqueue({ type: "network-request", ...request });
+      return;
+    }
+    this.offline.push(request);
+    const overflow = this.offline.length - this.options.maxQueueSize;
+    if (overflow > 0) this.offline.splice(0, overflow);
+  }
+
+  private onReady(protocol: number): void {
+    if (this.ready || !this.socket) return;
+    if (this.helloTimer) clearTimeout(this.helloTimer);
+    this.protocol = protocol;
+    this.ready = true;
+    this.options.log.verbose(`Observer speaks protocol ${protocol}`);
+
+    if (this.offline.length > 0) {
+      const requests = this.offline;
+      this.offline = [];
+      if (protocol >= 1) {
+        this.enqueue({ type: "offline-queue", requests });
+      } else {
+        requests.forEach((request) => this.report(request));
+      }
+    }
+    this.flush();
+  }
+
+  private enqueue(message: ClientMessage): void {
+    this.outbox.push(message);
+    if (this.outbox.length >= this.options.batchSize) {
+      this.flush();
+    } else if (!this.flushTimer) {
+      this.flushTimer = setTimeout(
+        () => this.flush(),
+        this.options.batchIntervalMs
+      );
+    }
+  }
+
+  private flush(): void {
+    if (this.flushTimer) {
+      clearTimeout(this.flushTimer);
+      this.flushTimer = null;
+    }
+    const socket = this.socket;
+    if (!socket || socket.readyState !== this.options.WebSocket.OPEN) return;
+    if (this.outbox.length === 0) return;
+
+    const messages = this.outbox;
+    this.outbox = [];
+    // Observers before protocol 1 only understand one message per frame
+    if (messages.length === 1 || this.protocol < 1) {
+      messages.forEach((message) => socket.send(JSON.stringify(message)));
+    } else {
+      socket.send(JSON.stringify({ type: "batch", messages }));
+    }
+  }
+
+  private scheduleReconnect(): void {
+    if (this.closed || !this.options.autoReconnect || this.reconnectTimer)
+      return;
+    const delay = Math.min(1000 * 2 ** this.reconnectAttempts, 30000);
+    this.reconnectAttempts += 1;
+    this.reconnectTimer = setTimeout(() => {
+      this.reconnectTimer = null;
+      this.options.log.verbose(
+        `Reconnecting (attempt ${this.reconnectAttempts})...`
+      );
+      this.connect();
+    }, delay);
+  }
+}
@@ -0,0 +1,89 @@
+import type { Headers } from "./protocol";
+
+export type LogLevel = "silent" | "minimal" | "verbose";
+
+export interface Logger {
+  minimal: (...args: unknown[]) => void;
+  verbose: (...args: unknown[]) => void;
+}
+
+export function createLogger(level: LogLevel): Logger {
+  const write =
+    (enabled: boolean) =>
+    (...args: unknown[]) => {
+      if (enabled) console.log("[NetworkObserver]", ...args);
+    };
+  return {
+    minimal: write(level !== "silent"),
+    verbose: write(level === "verbose"),
+  };
+}
+
+let counter = 0;
+
+export function generateId(): string {
+  counter = (counter + 1) % Number.MAX_SAFE_INTEGER;
+  return `req_${Date.now().toString(36)}_${counter.toString(36)}_${Math.random()
+    .toString(36)
+    .slice(2, 8)}`;
+}
+
+type HeaderInit =
+  | globalThis.Headers
+  | Record<string, string | string[]>
+  | [string, string][]
+  | undefined;
+
+// Ordered [name, value] pairs, keeping repeated headers as separate entries
+export function toHeaderPairs(init: HeaderInit): Headers {
+  if (!init) return [];
+  if (Array.isArray(init)) return init.map(([name, value]) => [name, value]);
+  if (typeof (init as globalThis.Headers).forEach === "function") {
+    const pairs: Headers = [];
+    (init as globalThis.Headers).forEach((value, name) => {
+      pairs.push([name, value]);
+    });
+    return pairs;
+  }
+  return Object.entries(init as Record<string, string | string[]>).flatMap(
+    ([name, value]): Headers =>
+      Array.isArray(value)
+        ? value.map((v): [string, string] => [name, v])
+        : [[name, String(value)]]
+  );
+}
+
+// XHR exposes response headers as one CRLF-separated block
+export function parseRawHeaders(raw: string): Headers {
+  return raw
+    .split(/\r?\n/)
+    .filter((line) => line.includes(":"))
+    .map((line): [string, string] => {
+      const split = line.indexOf(":");
+      return [line.slice(0, split).trim(), line.slice(split + 1).trim()];
+    });
+}
+
+export function bodyToString(body: unknown): string | undefined {
+  if (body === undefined || body === null) return undefined;
+  if (typeof body === "string") return body;
+  if (typeof URLSearchParams !== "undefined" && body instanceof URLSearchParams)
+    return body.toString();
+  if (typeof FormData !== "undefined" && body instanceof FormData)
+    return "[FormData]";
+  if (typeof Blob !== "undefined" && body instanceof Blob)
+    return `[Blob ${body.size} bytes]`;
+  if (body instanceof ArrayBuffer || ArrayBuffer.isView(body))
+    return `[Binary ${(body as ArrayBuffer).byteLength} bytes]`;
+  try {
+    return JSON.stringify(body);
+  } catch {
+    return String(body);
+  }
+}
+
+export function matchesAny(url: string, patterns: (string | RegExp)[]): boolean {
+  return patterns.some((pattern) =>
+    typeof pattern === "string" ? url.includes(pattern) : pattern.test(url)
+  );
+}
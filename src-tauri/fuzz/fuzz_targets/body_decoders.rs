@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use network_observer_lib::fuzzing;
+
+fuzz_target!(|input: (String, Option<String>, String, String, String)| {
+    let (url, content_type, body, path, timestamp) = input;
+    fuzzing::decode_body(url, content_type, body, &path);
+    fuzzing::parse_timestamp(&timestamp);
+});
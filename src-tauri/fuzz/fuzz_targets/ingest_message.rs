@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use network_observer_lib::fuzzing;
+
+// Arbitrary text frames, valid JSON or not, through the full message handler
+fuzz_target!(|data: &[u8]| {
+    fuzzing::ingest_text(&String::from_utf8_lossy(data));
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use network_observer_lib::fuzzing;
+
+// The dedup signature keeps the head and tail of long bodies, which used to
+// slice through multi-byte characters
+fuzz_target!(|input: (String, String, Option<String>, Option<u16>)| {
+    let (method, url, body, status) = input;
+    fuzzing::request_signature(method, url, body, status);
+});
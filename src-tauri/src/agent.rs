@@ -0,0 +1,417 @@
+// Headless capture. The app binary started with `--agent` runs only the ingest
+// server, so traffic is captured before the UI is opened (app startup, CI
+// runs) and while it is closed. When the UI starts it takes the agent's
+// capture over the control port; the agent then steps aside and binds the
+// ingest port again once the UI has quit.
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use ts_rs::TS;
+
+use crate::events::EventSink;
+use crate::server::{self, IngestState};
+use crate::{limits, storage, OfflineQueue};
+
+pub const AGENT_FLAG: &str = "--agent";
+pub(crate) const CONTROL_ADDR: &str = "127.0.0.1:8086";
+
+// Control requests, one line each
+const HANDOVER: &str = "handover";
+const QUIT: &str = "quit";
+const STATUS: &str = "status";
+
+// How often a waiting agent checks for control requests and whether the UI
+// has released the ingest port
+const CONTROL_POLL: Duration = Duration::from_millis(500);
+// The UI binds right after a handover; the agent doesn't compete for the port
+// until this has passed
+const HANDOVER_GRACE: Duration = Duration::from_secs(10);
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Login item name on every platform
+const AGENT_LABEL: &str = "network-observer-agent";
+
+// Nobody is watching the agent; events only matter once the UI has the data
+struct Detached;
+
+impl EventSink for Detached {
+    fn emit_event(&self, _name: &str, _payload: serde_json::Value) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+enum Control {
+    Handover(std::net::TcpStream),
+    Quit,
+    // Only checks that an agent is there
+    Status,
+}
+
+fn read_control(stream: std::net::TcpStream) -> Option<Control> {
+    stream.set_read_timeout(Some(CONTROL_TIMEOUT)).ok()?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line).ok()?;
+    match line.trim() {
+        HANDOVER => Some(Control::Handover(stream)),
+        QUIT => Some(Control::Quit),
+        STATUS => Some(Control::Status),
+        other => {
+            println!("Agent: unknown control request {:?}", other);
+            None
+        }
+    }
+}
+
+fn send_queue(mut stream: std::net::TcpStream, queue: &OfflineQueue) {
+    let result = serde_json::to_vec(queue)
+        .map_err(|e| e.to_string())
+        .and_then(|json| stream.write_all(&json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        eprintln!("Agent: failed to hand over capture: {}", e);
+    }
+}
+
+// Waits for the ingest port, trying no earlier than `not_before` and answering
+// handovers with nothing in the meantime: a UI that starts now finds no
+// capture to take. None when asked to quit.
+fn wait_for_ingest_port(
+    control: &std::net::TcpListener,
+    not_before: Instant,
+) -> Option<std::net::TcpListener> {
+    control.set_nonblocking(true).ok()?;
+    let addr = server::ingest_addr();
+    loop {
+        if Instant::now() >= not_before {
+            if let Ok(listener) = std::net::TcpListener::bind(addr) {
+                control.set_nonblocking(false).ok()?;
+                return Some(listener);
+            }
+        }
+        while let Ok((stream, _)) = control.accept() {
+            stream.set_nonblocking(false).ok()?;
+            match read_control(stream) {
+                Some(Control::Handover(stream)) => {
+                    send_queue(stream, &OfflineQueue { requests: vec![] })
+                }
+                Some(Control::Quit) => return None,
+                Some(Control::Status) | None => {}
+            }
+        }
+        std::thread::sleep(CONTROL_POLL);
+    }
+}
+
+// Entry point for `--agent`; returns when asked to quit
+pub fn run() {
+    let control = match std::net::TcpListener::bind(CONTROL_ADDR) {
+        Ok(control) => control,
+        Err(e) => {
+            eprintln!(
+                "Agent: control port {} unavailable, is an agent already running? {}",
+                CONTROL_ADDR, e
+            );
+            return;
+        }
+    };
+    println!("Agent: control port listening on {}", CONTROL_ADDR);
+
+    let mut not_before = Instant::now();
+    while let Some(listener) = wait_for_ingest_port(&control, not_before) {
+        let runtime = match tokio::runtime::Runtime::new() {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                eprintln!("Agent: failed to start runtime: {}", e);
+                return;
+            }
+        };
+        let state = IngestState::new();
+        // Headless runs keep only what they hand over, unless asked for durable
+        // storage; each capture starts from what it holds
+        if let Some(spec) = storage::configured() {
+            match runtime
+                .block_on(storage::open(&spec))
+                .and_then(|backend| state.attach_storage(backend))
+            {
+                Ok(loaded) => println!("Agent: loaded {} stored requests", loaded),
+                Err(e) => eprintln!("Agent: request storage {} unavailable: {}", spec, e),
+            }
+        }
+        let serving = listener.set_nonblocking(true).and_then(|()| {
+            let _entered = runtime.enter();
+            tokio::net::TcpListener::from_std(listener)
+        });
+        match serving {
+            Ok(listener) => {
+                println!("Agent: capturing on {}", server::ingest_addr());
+                runtime.spawn(server::serve(listener, state.clone(), Arc::new(Detached)));
+                runtime.spawn(storage::flusher(state.storage.clone()));
+                runtime.spawn(limits::spiller(state.store_limits.clone()));
+            }
+            Err(e) => {
+                eprintln!(
+                    "Agent: failed to listen on {}: {}",
+                    server::ingest_addr(),
+                    e
+                );
+                return;
+            }
+        }
+
+        let handover = loop {
+            let Ok((stream, _)) = control.accept() else {
+                std::thread::sleep(Duration::from_millis(100));
+                continue;
+            };
+            match read_control(stream) {
+                Some(Control::Handover(stream)) => break Some(stream),
+                Some(Control::Quit) => break None,
+                Some(Control::Status) | None => {}
+            }
+        };
+
+        // Drops the listener and every client connection, so SDKs reconnect
+        // (and queue offline meanwhile) to whoever binds the port next
+        runtime.shutdown_timeout(Duration::from_secs(1));
+        storage::flush(&state.storage);
+        let Some(stream) = handover else {
+            return;
+        };
+        let requests = state.requests();
+        println!("Agent: handing {} requests over to the UI", requests.len());
+        send_queue(stream, &OfflineQueue { requests });
+        not_before = Instant::now() + HANDOVER_GRACE;
+    }
+}
+
+async fn control_request(request: &str) -> Option<tokio::net::TcpStream> {
+    let connect = tokio::net::TcpStream::connect(CONTROL_ADDR);
+    let mut stream = tokio::time::timeout(CONTROL_TIMEOUT, connect)
+        .await
+        .ok()?
+        .ok()?;
+    stream
+        .write_all(format!("{}\n", request).as_bytes())
+        .await
+        .ok()?;
+    Some(stream)
+}
+
+// What a running agent captured so far; it releases the ingest port before
+// answering. None when no agent is running.
+pub(crate) async fn take_over() -> Option<OfflineQueue> {
+    let mut stream = control_request(HANDOVER).await?;
+    let mut json = Vec::new();
+    if let Err(e) = stream.read_to_end(&mut json).await {
+        eprintln!("Failed to take over the agent's capture: {}", e);
+        return None;
+    }
+    match serde_json::from_slice(&json) {
+        Ok(queue) => Some(queue),
+        Err(e) => {
+            eprintln!("Invalid capture from agent: {}", e);
+            None
+        }
+    }
+}
+
+async fn agent_running() -> bool {
+    control_request(STATUS).await.is_some()
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct AgentStatus {
+    // Starts at login
+    pub installed: bool,
+    pub running: bool,
+    // Login item file, or registry value on Windows
+    pub location: String,
+}
+
+fn executable() -> Result<String, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Can't locate the app: {}", e))?;
+    Ok(exe.to_string_lossy().into_owned())
+}
+
+#[cfg(target_os = "macos")]
+mod login_item {
+    use super::*;
+    use tauri::Manager;
+
+    fn xml_escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    // A launch agent, started by launchd at login
+    pub fn location(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+        let home = app_handle.path().home_dir().map_err(|e| e.to_string())?;
+        Ok(home
+            .join("Library/LaunchAgents")
+            .join(format!("{}.plist", AGENT_LABEL)))
+    }
+
+    pub fn contents(exe: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+            AGENT_LABEL,
+            xml_escape(exe),
+            AGENT_FLAG
+        )
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod login_item {
+    use super::*;
+    use tauri::Manager;
+
+    // XDG autostart entry, started by the desktop session at login
+    pub fn location(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+        let config = app_handle.path().config_dir().map_err(|e| e.to_string())?;
+        Ok(config
+            .join("autostart")
+            .join(format!("{}.desktop", AGENT_LABEL)))
+    }
+
+    pub fn contents(exe: &str) -> String {
+        format!(
+            "[Desktop Entry]\nType=Application\nName=Network Observer capture agent\nExec=\"{}\" {}\nNoDisplay=true\nX-GNOME-Autostart-enabled=true\n",
+            exe.replace('"', "\\\""),
+            AGENT_FLAG
+        )
+    }
+}
+
+#[cfg(not(windows))]
+fn install_login_item(app_handle: &tauri::AppHandle, exe: &str) -> Result<String, String> {
+    let path = login_item::location(app_handle)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, login_item::contents(exe))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path.display().to_string())
+}
+
+#[cfg(not(windows))]
+fn remove_login_item(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let path = login_item::location(app_handle)?;
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove {}: {}", path.display(), e)),
+    }
+}
+
+#[cfg(not(windows))]
+fn login_item_status(app_handle: &tauri::AppHandle) -> Result<(bool, String), String> {
+    let path = login_item::location(app_handle)?;
+    Ok((path.exists(), path.display().to_string()))
+}
+
+// The per-user Run key; no service install or elevation needed
+#[cfg(windows)]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+#[cfg(windows)]
+fn reg(args: &[&str]) -> Result<bool, String> {
+    let status = std::process::Command::new("reg")
+        .args(args)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_err(|e| format!("Failed to run reg: {}", e))?;
+    Ok(status.success())
+}
+
+#[cfg(windows)]
+fn install_login_item(_app_handle: &tauri::AppHandle, exe: &str) -> Result<String, String> {
+    let command = format!("\"{}\" {}", exe, AGENT_FLAG);
+    let args = [
+        "add",
+        RUN_KEY,
+        "/v",
+        AGENT_LABEL,
+        "/t",
+        "REG_SZ",
+        "/d",
+        &command,
+        "/f",
+    ];
+    if !reg(&args)? {
+        return Err(format!("Failed to write {}\\{}", RUN_KEY, AGENT_LABEL));
+    }
+    Ok(format!("{}\\{}", RUN_KEY, AGENT_LABEL))
+}
+
+#[cfg(windows)]
+fn remove_login_item(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    if login_item_status(app_handle)?.0 && !reg(&["delete", RUN_KEY, "/v", AGENT_LABEL, "/f"])? {
+        return Err(format!("Failed to remove {}\\{}", RUN_KEY, AGENT_LABEL));
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn login_item_status(_app_handle: &tauri::AppHandle) -> Result<(bool, String), String> {
+    let installed = reg(&["query", RUN_KEY, "/v", AGENT_LABEL])?;
+    Ok((installed, format!("{}\\{}", RUN_KEY, AGENT_LABEL)))
+}
+
+async fn status(app_handle: &tauri::AppHandle) -> Result<AgentStatus, String> {
+    let (installed, location) = login_item_status(app_handle)?;
+    Ok(AgentStatus {
+        installed,
+        running: agent_running().await,
+        location,
+    })
+}
+
+// Registers the agent to start at login and starts it now. While the UI is
+// open it only waits; it captures from the moment the UI quits.
+#[tauri::command]
+pub async fn install_agent(app_handle: tauri::AppHandle) -> Result<AgentStatus, String> {
+    let exe = executable()?;
+    install_login_item(&app_handle, &exe)?;
+    if !agent_running().await {
+        std::process::Command::new(&exe)
+            .arg(AGENT_FLAG)
+            .spawn()
+            .map_err(|e| format!("Failed to start the agent: {}", e))?;
+    }
+    status(&app_handle).await
+}
+
+// Removes the login item and stops a running agent
+#[tauri::command]
+pub async fn uninstall_agent(app_handle: tauri::AppHandle) -> Result<AgentStatus, String> {
+    remove_login_item(&app_handle)?;
+    control_request(QUIT).await;
+    status(&app_handle).await
+}
+
+#[tauri::command]
+pub async fn get_agent_status(app_handle: tauri::AppHandle) -> Result<AgentStatus, String> {
+    status(&app_handle).await
+}
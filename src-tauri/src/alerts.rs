@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::events::{self, AppEvent, EventSink, Events};
+use crate::filter::RequestFilter;
+use crate::markers::now_millis;
+use crate::screenshot::{self, ScreenshotConfig};
+use crate::NetworkRequest;
+
+// Older alert records are dropped once this many have fired
+const MAX_ALERT_RECORDS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct AlertRule {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub filter: RequestFilter,
+    // Capture the configured screen/window when this rule fires
+    #[serde(default)]
+    pub screenshot: bool,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct AlertRecord {
+    pub id: String,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub request_id: String,
+    pub url: String,
+    pub status: Option<u16>,
+    #[ts(type = "number")]
+    pub fired_at: u64,
+    pub screenshot_path: Option<String>,
+    pub screenshot_error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Alerts {
+    pub rules: Vec<AlertRule>,
+    pub fired: Vec<AlertRecord>,
+    pub screenshot: ScreenshotConfig,
+}
+
+pub type AlertStore = Arc<Mutex<Alerts>>;
+
+// Runs the alert rules against a newly stored request
+pub fn evaluate(alerts: &AlertStore, request: &NetworkRequest, sink: &Events) {
+    let (records, screenshot_config) = {
+        let mut alerts = alerts.lock().unwrap();
+        let records: Vec<(AlertRecord, bool)> = alerts
+            .rules
+            .iter()
+            .filter(|rule| rule.filter.matches(request))
+            .map(|rule| {
+                let record = AlertRecord {
+                    id: Uuid::new_v4().to_string(),
+                    rule_id: rule.id.clone(),
+                    rule_name: rule.name.clone(),
+                    request_id: request.id.clone(),
+                    url: request.url.clone(),
+                    status: request.response.as_ref().map(|r| r.status),
+                    fired_at: now_millis(),
+                    screenshot_path: None,
+                    screenshot_error: None,
+                };
+                (record, rule.screenshot)
+            })
+            .collect();
+
+        alerts
+            .fired
+            .extend(records.iter().map(|(record, _)| record.clone()));
+        let overflow = alerts.fired.len().saturating_sub(MAX_ALERT_RECORDS);
+        alerts.fired.drain(..overflow);
+        (records, alerts.screenshot.clone())
+    };
+
+    for (record, wants_screenshot) in records {
+        println!("Alert fired: {} for {}", record.rule_name, record.url);
+        if let Err(e) = events::emit(&**sink, AppEvent::AlertFired(&record)) {
+            println!("Failed to emit alert-fired event: {}", e);
+        }
+
+        if wants_screenshot && screenshot_config.enabled {
+            let alerts = alerts.clone();
+            let sink = sink.clone();
+            let config = screenshot_config.clone();
+            tauri::async_runtime::spawn(async move {
+                attach_screenshot(&alerts, &config, record.id, &*sink).await;
+            });
+        }
+    }
+}
+
+// The screenshot is taken after the alert event so a slow capture tool never delays it;
+// the record is re-emitted as `alert-updated` once the path is known
+async fn attach_screenshot(
+    alerts: &AlertStore,
+    config: &ScreenshotConfig,
+    alert_id: String,
+    sink: &dyn EventSink,
+) {
+    let result = screenshot::capture(config, &format!("alert-{}", alert_id)).await;
+
+    let updated = {
+        let mut alerts = alerts.lock().unwrap();
+        alerts
+            .fired
+            .iter_mut()
+            .find(|r| r.id == alert_id)
+            .map(|record| {
+                match result {
+                    Ok(path) => record.screenshot_path = Some(path),
+                    Err(e) => {
+                        eprintln!("Failed to capture alert screenshot: {}", e);
+                        record.screenshot_error = Some(e);
+                    }
+                }
+                record.clone()
+            })
+    };
+
+    if let Some(record) = updated {
+        events::emit(sink, AppEvent::AlertUpdated(&record)).ok();
+    }
+}
+
+#[tauri::command]
+pub async fn set_alert_rules(
+    alerts: tauri::State<'_, AlertStore>,
+    rules: Vec<AlertRule>,
+) -> Result<Vec<AlertRule>, String> {
+    for rule in &rules {
+        rule.filter.validate()?;
+    }
+    let mut alerts = alerts.lock().map_err(|e| e.to_string())?;
+    alerts.rules = rules
+        .into_iter()
+        .map(|mut rule| {
+            if rule.id.is_empty() {
+                rule.id = Uuid::new_v4().to_string();
+            }
+            rule
+        })
+        .collect();
+    Ok(alerts.rules.clone())
+}
+
+#[tauri::command]
+pub async fn get_alert_rules(
+    alerts: tauri::State<'_, AlertStore>,
+) -> Result<Vec<AlertRule>, String> {
+    let alerts = alerts.lock().map_err(|e| e.to_string())?;
+    Ok(alerts.rules.clone())
+}
+
+#[tauri::command]
+pub async fn get_alerts(alerts: tauri::State<'_, AlertStore>) -> Result<Vec<AlertRecord>, String> {
+    let alerts = alerts.lock().map_err(|e| e.to_string())?;
+    Ok(alerts.fired.clone())
+}
+
+#[tauri::command]
+pub async fn set_screenshot_config(
+    alerts: tauri::State<'_, AlertStore>,
+    config: ScreenshotConfig,
+) -> Result<(), String> {
+    let mut alerts = alerts.lock().map_err(|e| e.to_string())?;
+    alerts.screenshot = config;
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use crate::limits::{self, StoreLimitState};
+use crate::overflow;
+use crate::provenance::{self, ProvenanceKind};
+use crate::{NetworkRequest, RequestStore};
+
+impl NetworkRequest {
+    // Whether `id` is this request's id or one of the ids it absorbed through dedup
+    pub fn has_id(&self, id: &str) -> bool {
+        self.id == id || self.aliases.iter().any(|alias| alias == id)
+    }
+}
+
+// Records `alias` on the stored request `id` after a duplicate carrying the
+// client's own id was merged into it, so lookups by either id keep working.
+// Returns the request when it changed.
+pub fn record_alias(store: &RequestStore, id: &str, alias: &str) -> Option<NetworkRequest> {
+    if id == alias {
+        return None;
+    }
+    let mut requests = store.lock().unwrap();
+    let request = requests.iter_mut().find(|r| r.id == id)?;
+    if request.has_id(alias) {
+        return None;
+    }
+    request.aliases.push(alias.to_string());
+    provenance::record(
+        request,
+        ProvenanceKind::DuplicateMerged,
+        Some(alias.to_string()),
+    );
+    Some(request.clone())
+}
+
+// Looks a request up by its own id or any alias, in the store or else its
+// overflow on disk. Opening a request counts as using it for LRU eviction.
+#[tauri::command]
+pub async fn get_request(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    id: String,
+) -> Result<NetworkRequest, String> {
+    let found = {
+        let requests = store.lock().map_err(|e| e.to_string())?;
+        requests.iter().find(|r| r.has_id(&id)).cloned()
+    };
+    let Some(request) = found else {
+        return overflow::get(&limits::overflow(&store_limits), &id)
+            .ok_or_else(|| format!("Request not found: {}", id));
+    };
+    limits::touch(&store_limits, &request.id);
+    Ok(request)
+}
@@ -0,0 +1,8 @@
+// Analyzers that scan the captured traffic and produce reports for the frontend.
+
+pub mod auth;
+pub mod cdn;
+pub mod compression;
+pub mod headers;
+pub mod images;
+pub mod lengths;
@@ -0,0 +1,328 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use ts_rs::TS;
+
+use crate::limits::{self, StoreLimitState};
+use crate::{NetworkRequest, RequestStore};
+
+// Failures closer together than this belong to the same burst
+const BURST_GAP_MS: u64 = 10_000;
+// A handful of rejected calls is normal (expired token, one retry); more is a burst
+const MIN_BURST_SIZE: usize = 3;
+// How far before a burst we look for the refresh/redirect calls that led up to it
+const LOOKBACK_MS: u64 = 30_000;
+
+const REFRESH_URL_MARKERS: &[&str] = &[
+    "/token", "refresh", "/oauth", "/auth/", "/login", "/session", "/signin",
+];
+
+#[derive(Debug, Clone, Serialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum AuthEventKind {
+    Failure,
+    TokenRefresh,
+    Redirect,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct AuthTimelineEvent {
+    pub request_id: String,
+    #[ts(type = "number")]
+    pub timestamp: u64,
+    pub method: String,
+    pub url: String,
+    pub status: Option<u16>,
+    pub kind: AuthEventKind,
+    pub location: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct AuthFailureBurst {
+    #[ts(type = "number")]
+    pub start: u64,
+    #[ts(type = "number")]
+    pub end: u64,
+    pub failure_count: usize,
+    pub status_counts: HashMap<u16, usize>,
+    pub refresh_count: usize,
+    pub timeline: Vec<AuthTimelineEvent>,
+    pub insight: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct AuthFailureReport {
+    pub total_failures: usize,
+    pub bursts: Vec<AuthFailureBurst>,
+}
+
+// A rejected refresh call isn't counted here but as a failed refresh
+fn is_auth_failure(request: &NetworkRequest) -> bool {
+    matches!(request.response.as_ref().map(|r| r.status), Some(401 | 403))
+        && !is_refresh_call(request)
+}
+
+fn has_refresh_body(request: &NetworkRequest) -> bool {
+    request
+        .body
+        .as_ref()
+        .map(|body| body.contains("refresh_token") || body.contains("grant_type"))
+        .unwrap_or(false)
+}
+
+// Certainly a token exchange: a grant in the body or a token endpoint like
+// `/oauth/token`. Looser URL hints would also take in APIs under `/session/`
+// and bad logins, which are real failures.
+fn is_refresh_call(request: &NetworkRequest) -> bool {
+    let path = request.url.split(['?', '#']).next().unwrap_or_default();
+    has_refresh_body(request)
+        || path
+            .trim_end_matches('/')
+            .to_ascii_lowercase()
+            .ends_with("/token")
+}
+
+fn is_token_refresh(request: &NetworkRequest) -> bool {
+    let url = request.url.to_ascii_lowercase();
+    REFRESH_URL_MARKERS
+        .iter()
+        .any(|marker| url.contains(marker))
+        || has_refresh_body(request)
+}
+
+fn redirect_location(request: &NetworkRequest) -> Option<String> {
+    let response = request.response.as_ref()?;
+    if !(300..400).contains(&response.status) {
+        return None;
+    }
+    Some(
+        response
+            .headers
+            .get("location")
+            .unwrap_or_default()
+            .to_string(),
+    )
+}
+
+fn timeline_event(
+    request: &NetworkRequest,
+    kind: AuthEventKind,
+    location: Option<String>,
+) -> AuthTimelineEvent {
+    AuthTimelineEvent {
+        request_id: request.id.clone(),
+        timestamp: request.timestamp,
+        method: request.method.clone(),
+        url: request.url.clone(),
+        status: request.response.as_ref().map(|r| r.status),
+        kind,
+        location,
+    }
+}
+
+fn describe_burst(timeline: &[AuthTimelineEvent], start: u64, end: u64) -> String {
+    let refreshes: Vec<&AuthTimelineEvent> = timeline
+        .iter()
+        .filter(|e| e.kind == AuthEventKind::TokenRefresh)
+        .collect();
+
+    if refreshes.is_empty() {
+        return "No token refresh was attempted around this burst - the client may not handle expired credentials".to_string();
+    }
+
+    let failed_refresh = refreshes
+        .iter()
+        .find(|e| e.status.map(|s| s >= 400).unwrap_or(false));
+    if let Some(refresh) = failed_refresh {
+        return format!(
+            "Token refresh {} failed with {} - subsequent requests kept using the rejected credentials",
+            refresh.url,
+            refresh.status.unwrap_or_default()
+        );
+    }
+
+    let last_refresh = refreshes.last().map(|e| e.timestamp).unwrap_or_default();
+    if last_refresh > start && last_refresh <= end {
+        return "Requests were rejected while a token refresh was in flight - calls are racing the refresh instead of waiting for it".to_string();
+    }
+    if refreshes.len() > 1 {
+        return format!(
+            "{} refresh calls were made for one burst - concurrent requests are each triggering their own refresh",
+            refreshes.len()
+        );
+    }
+    "Token refresh succeeded but requests were still rejected - the new token is likely not being applied".to_string()
+}
+
+pub fn build_auth_failure_report(requests: &[NetworkRequest]) -> AuthFailureReport {
+    let mut sorted: Vec<&NetworkRequest> = requests.iter().collect();
+    sorted.sort_by_key(|r| r.timestamp);
+
+    let failures: Vec<&NetworkRequest> = sorted
+        .iter()
+        .copied()
+        .filter(|r| is_auth_failure(r))
+        .collect();
+
+    // Group failures into bursts separated by quiet gaps
+    let mut groups: Vec<Vec<&NetworkRequest>> = Vec::new();
+    for failure in &failures {
+        match groups.last_mut() {
+            Some(group) if failure.timestamp - group.last().unwrap().timestamp <= BURST_GAP_MS => {
+                group.push(failure)
+            }
+            _ => groups.push(vec![failure]),
+        }
+    }
+
+    let bursts = groups
+        .into_iter()
+        .filter(|group| group.len() >= MIN_BURST_SIZE)
+        .map(|group| {
+            let start = group.first().unwrap().timestamp;
+            let end = group.last().unwrap().timestamp;
+            let window_start = start.saturating_sub(LOOKBACK_MS);
+
+            let mut status_counts = HashMap::new();
+            for failure in &group {
+                if let Some(response) = &failure.response {
+                    *status_counts.entry(response.status).or_insert(0) += 1;
+                }
+            }
+
+            let timeline: Vec<AuthTimelineEvent> = sorted
+                .iter()
+                .filter(|r| r.timestamp >= window_start && r.timestamp <= end)
+                .filter_map(|r| {
+                    if is_auth_failure(r) {
+                        Some(timeline_event(r, AuthEventKind::Failure, None))
+                    } else if let Some(location) = redirect_location(r) {
+                        Some(timeline_event(r, AuthEventKind::Redirect, Some(location)))
+                    } else if is_token_refresh(r) {
+                        Some(timeline_event(r, AuthEventKind::TokenRefresh, None))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            let refresh_count = timeline
+                .iter()
+                .filter(|e| e.kind == AuthEventKind::TokenRefresh)
+                .count();
+            let insight = describe_burst(&timeline, start, end);
+
+            AuthFailureBurst {
+                start,
+                end,
+                failure_count: group.len(),
+                status_counts,
+                refresh_count,
+                timeline,
+                insight,
+            }
+        })
+        .collect();
+
+    AuthFailureReport {
+        total_failures: failures.len(),
+        bursts,
+    }
+}
+
+#[tauri::command]
+pub async fn analyze_auth_failures(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+) -> Result<AuthFailureReport, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    Ok(build_auth_failure_report(&requests))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::request;
+
+    fn at(id: &str, url: &str, status: u16, timestamp: u64) -> NetworkRequest {
+        let mut request = request(id, url, status);
+        request.timestamp = timestamp;
+        request
+    }
+
+    #[test]
+    fn counts_a_burst_on_a_session_api() {
+        let requests: Vec<NetworkRequest> = (0..4)
+            .map(|i| {
+                let url = format!("https://api.example.com/api/session/{i}/items");
+                at(&i.to_string(), &url, 401, 1_000 + i * 100)
+            })
+            .collect();
+        let report = build_auth_failure_report(&requests);
+        assert_eq!(report.total_failures, 4);
+        assert_eq!(report.bursts.len(), 1);
+        assert_eq!(report.bursts[0].failure_count, 4);
+        assert_eq!(report.bursts[0].status_counts[&401], 4);
+    }
+
+    #[test]
+    fn counts_a_rejected_login() {
+        assert!(is_auth_failure(&request(
+            "a",
+            "https://example.com/login",
+            401
+        )));
+        assert!(is_auth_failure(&request(
+            "a",
+            "https://example.com/api/tokens",
+            403
+        )));
+    }
+
+    #[test]
+    fn leaves_rejected_refreshes_out_of_the_failures() {
+        for url in [
+            "https://auth.example.com/oauth/token",
+            "https://auth.example.com/oauth/TOKEN/?scope=all",
+        ] {
+            assert!(!is_auth_failure(&request("a", url, 401)), "{url}");
+        }
+        let mut exchange = request("a", "https://auth.example.com/connect", 401);
+        exchange.body = Some("grant_type=refresh_token&refresh_token=abc".into());
+        assert!(!is_auth_failure(&exchange));
+    }
+
+    #[test]
+    fn explains_a_burst_after_a_failed_refresh() {
+        let mut requests = vec![at("refresh", "https://example.com/oauth/token", 401, 500)];
+        requests.extend((0..3).map(|i| {
+            let url = format!("https://example.com/api/{i}");
+            at(&i.to_string(), &url, 401, 1_000 + i)
+        }));
+        let report = build_auth_failure_report(&requests);
+        assert_eq!(report.total_failures, 3);
+        let burst = &report.bursts[0];
+        assert_eq!(burst.refresh_count, 1);
+        assert_eq!(burst.timeline[0].kind, AuthEventKind::TokenRefresh);
+        assert!(
+            burst.insight.starts_with("Token refresh"),
+            "{}",
+            burst.insight
+        );
+    }
+
+    #[test]
+    fn splits_bursts_at_quiet_gaps() {
+        let requests: Vec<NetworkRequest> = [0, 1, 2, 20_000, 20_001, 40_000, 40_001, 40_002]
+            .into_iter()
+            .map(|t| at(&t.to_string(), "https://example.com/api", 403, t))
+            .collect();
+        let report = build_auth_failure_report(&requests);
+        assert_eq!(report.total_failures, 8);
+        let sizes: Vec<usize> = report.bursts.iter().map(|b| b.failure_count).collect();
+        assert_eq!(sizes, [3, 3]);
+    }
+}
@@ -0,0 +1,275 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use ts_rs::TS;
+
+use crate::endpoint::endpoint_key;
+use crate::limits::{self, StoreLimitState};
+use crate::{Headers, NetworkRequest, RequestStore};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum CacheStatus {
+    Hit,
+    // Fetched from the origin, including entries that had expired
+    Miss,
+    // Served from cache past its freshness lifetime
+    Stale,
+    // Cached copy confirmed with the origin (a 304 behind the CDN)
+    Revalidated,
+    // Deliberately not cached: private, dynamic or passed through
+    Bypass,
+    // A cache status header with a value we don't recognise
+    Other,
+}
+
+impl CacheStatus {
+    // Served without a full origin fetch
+    fn is_hit(self) -> bool {
+        matches!(
+            self,
+            CacheStatus::Hit | CacheStatus::Stale | CacheStatus::Revalidated
+        )
+    }
+
+    // Whether the request could have been a hit; bypasses don't count against the ratio
+    fn is_cacheable(self) -> bool {
+        self.is_hit() || self == CacheStatus::Miss
+    }
+}
+
+// Response headers each CDN reports its cache outcome in. When a value lists
+// several cache layers the last one is the edge nearest the client.
+const STATUS_HEADERS: &[&str] = &[
+    "cf-cache-status",
+    "x-vercel-cache",
+    "x-cache-status",
+    "x-cache",
+];
+
+// `HIT`, `TCP_REFRESH_HIT`, `RefreshHit from cloudfront`, ...
+fn status_from_token(token: &str) -> Option<CacheStatus> {
+    let token = token.trim().to_ascii_uppercase();
+    if token.is_empty() {
+        return None;
+    }
+    let status = if token.contains("REFRESH") || token.contains("REVALIDATED") {
+        CacheStatus::Revalidated
+    } else if token.contains("STALE") || token.contains("UPDATING") {
+        CacheStatus::Stale
+    } else if token.contains("HIT") {
+        CacheStatus::Hit
+    } else if token.contains("MISS") || token.contains("EXPIRED") {
+        CacheStatus::Miss
+    } else if token.contains("BYPASS") || token.contains("DYNAMIC") || token.contains("PASS") {
+        CacheStatus::Bypass
+    } else {
+        CacheStatus::Other
+    };
+    Some(status)
+}
+
+// RFC 9211 `Cache-Status: Origin; fwd=miss, Edge; hit`
+fn status_from_cache_status(value: &str) -> Option<CacheStatus> {
+    let nearest = value.rsplit(',').next()?;
+    let mut params = nearest.split(';').skip(1).map(str::trim);
+    let status = params.find_map(|param| match param.split_once('=') {
+        None if param.eq_ignore_ascii_case("hit") => Some(CacheStatus::Hit),
+        Some((key, reason)) if key.trim().eq_ignore_ascii_case("fwd") => {
+            Some(match reason.trim().to_ascii_lowercase().as_str() {
+                "stale" => CacheStatus::Revalidated,
+                "bypass" | "method" | "request" => CacheStatus::Bypass,
+                _ => CacheStatus::Miss,
+            })
+        }
+        _ => None,
+    });
+    // A cache that lists itself without `hit` or `fwd` is ill-formed
+    Some(status.unwrap_or(CacheStatus::Other))
+}
+
+fn detect_status(headers: &Headers) -> Option<CacheStatus> {
+    if let Some(value) = headers.get("cache-status") {
+        return status_from_cache_status(value);
+    }
+    STATUS_HEADERS.iter().find_map(|name| {
+        let value = headers.get(name)?;
+        status_from_token(value.rsplit(',').next()?)
+    })
+}
+
+// Airport-style location code: `LHR`, `IAD`, `FRA`
+fn pop_code(code: &str) -> Option<String> {
+    let code = code.trim();
+    ((3..=4).contains(&code.len()) && code.chars().all(|c| c.is_ascii_alphabetic()))
+        .then(|| code.to_ascii_uppercase())
+}
+
+fn detect_pop(headers: &Headers) -> Option<String> {
+    // CloudFront: `LHR62-C1`
+    if let Some(value) = headers.get("x-amz-cf-pop") {
+        let letters: String = value
+            .trim()
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+        if let Some(pop) = pop_code(&letters) {
+            return Some(pop);
+        }
+    }
+    // Cloudflare: `8f2c1d3e4b5a6978-LHR`
+    if let Some(value) = headers.get("cf-ray") {
+        if let Some(pop) = value.rsplit_once('-').and_then(|(_, pop)| pop_code(pop)) {
+            return Some(pop);
+        }
+    }
+    // Fastly: `cache-iad-kiad7000031-IAD, cache-lhr7380-LHR`
+    let served_by = headers.get("x-served-by")?.rsplit(',').next()?;
+    served_by
+        .rsplit_once('-')
+        .and_then(|(_, pop)| pop_code(pop))
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct PopCount {
+    pub pop: String,
+    pub requests: usize,
+    pub hits: usize,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct EndpointCacheStats {
+    pub endpoint: String,
+    pub requests: usize,
+    pub counts: HashMap<CacheStatus, usize>,
+    pub hit_ratio: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct CdnReport {
+    pub requests_scanned: usize,
+    // Responses that carried a cache status
+    pub cdn_requests: usize,
+    pub counts: HashMap<CacheStatus, usize>,
+    // Hits (stale and revalidated included) over hits and misses; bypassed
+    // and unrecognised responses are left out
+    pub hit_ratio: Option<f64>,
+    pub pops: Vec<PopCount>,
+    // Busiest first
+    pub endpoints: Vec<EndpointCacheStats>,
+}
+
+// Where a custom setup reports cache status or POP: names of derived fields
+// (e.g. promoted headers) to read instead of the well-known CDN headers
+#[derive(Debug, Clone)]
+pub struct CdnFields {
+    pub status: Option<String>,
+    pub pop: Option<String>,
+}
+
+fn hit_ratio(counts: &HashMap<CacheStatus, usize>) -> Option<f64> {
+    let count = |keep: fn(CacheStatus) -> bool| {
+        counts
+            .iter()
+            .filter(|(status, _)| keep(**status))
+            .map(|(_, n)| n)
+            .sum::<usize>()
+    };
+    let cacheable = count(CacheStatus::is_cacheable);
+    (cacheable > 0).then(|| count(CacheStatus::is_hit) as f64 / cacheable as f64)
+}
+
+pub fn build_cdn_report(requests: &[NetworkRequest], fields: &CdnFields) -> CdnReport {
+    let mut counts = HashMap::new();
+    let mut pops: HashMap<String, PopCount> = HashMap::new();
+    let mut endpoints: HashMap<String, EndpointCacheStats> = HashMap::new();
+    let mut cdn_requests = 0;
+
+    for request in requests {
+        let Some(response) = &request.response else {
+            continue;
+        };
+        let derived = |name: &Option<String>| {
+            name.as_ref()
+                .and_then(|name| request.derived.get(name))
+                .map(String::as_str)
+        };
+        let status = match derived(&fields.status) {
+            Some(value) => value.rsplit(',').next().and_then(status_from_token),
+            None => detect_status(&response.headers),
+        };
+        let Some(status) = status else {
+            continue;
+        };
+        cdn_requests += 1;
+        *counts.entry(status).or_insert(0) += 1;
+
+        let pop = match derived(&fields.pop) {
+            Some(value) => Some(value.trim().to_string()).filter(|pop| !pop.is_empty()),
+            None => detect_pop(&response.headers),
+        };
+        if let Some(pop) = pop {
+            let entry = pops.entry(pop.clone()).or_insert(PopCount {
+                pop,
+                requests: 0,
+                hits: 0,
+            });
+            entry.requests += 1;
+            entry.hits += status.is_hit() as usize;
+        }
+
+        let key = endpoint_key(request);
+        let entry = endpoints
+            .entry(key.clone())
+            .or_insert_with(|| EndpointCacheStats {
+                endpoint: key,
+                requests: 0,
+                counts: HashMap::new(),
+                hit_ratio: None,
+            });
+        entry.requests += 1;
+        *entry.counts.entry(status).or_insert(0) += 1;
+    }
+
+    let mut pops: Vec<PopCount> = pops.into_values().collect();
+    pops.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.pop.cmp(&b.pop)));
+    let mut endpoints: Vec<EndpointCacheStats> = endpoints
+        .into_values()
+        .map(|mut stats| {
+            stats.hit_ratio = hit_ratio(&stats.counts);
+            stats
+        })
+        .collect();
+    endpoints.sort_by(|a, b| {
+        b.requests
+            .cmp(&a.requests)
+            .then_with(|| a.endpoint.cmp(&b.endpoint))
+    });
+
+    CdnReport {
+        requests_scanned: requests.len(),
+        cdn_requests,
+        hit_ratio: hit_ratio(&counts),
+        counts,
+        pops,
+        endpoints,
+    }
+}
+
+#[tauri::command]
+pub async fn get_cdn_report(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    status_field: Option<String>,
+    pop_field: Option<String>,
+) -> Result<CdnReport, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    let fields = CdnFields {
+        status: status_field,
+        pop: pop_field,
+    };
+    Ok(build_cdn_report(&requests, &fields))
+}
@@ -0,0 +1,213 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use ts_rs::TS;
+
+use super::lengths::is_placeholder;
+use crate::classify::ContentClass;
+use crate::endpoint::endpoint_key;
+use crate::limits::{self, StoreLimitState};
+use crate::{NetworkRequest, NetworkResponse, RequestStore};
+
+// Below this, compression overhead eats most of the gain and servers usually
+// don't bother
+const MIN_COMPRESSIBLE_BYTES: usize = 1024;
+// Savings are estimated from a prefix of bigger bodies and scaled up
+const MAX_SAMPLE_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct CompressionCandidate {
+    pub request_id: String,
+    pub url: String,
+    pub content_class: Option<ContentClass>,
+    #[ts(type = "number")]
+    pub size: u64,
+    // What gzip at its default level would have sent
+    #[ts(type = "number")]
+    pub estimated_gzip_size: u64,
+    #[ts(type = "number")]
+    pub estimated_savings: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct EndpointCompression {
+    pub endpoint: String,
+    pub responses: usize,
+    pub compressed: usize,
+    // Content-Encoding -> responses
+    pub encodings: HashMap<String, usize>,
+    // Wire and decoded sizes of compressed responses whose wire size is known
+    #[ts(type = "number")]
+    pub encoded_bytes: u64,
+    #[ts(type = "number")]
+    pub decoded_bytes: u64,
+    pub compression_ratio: Option<f64>,
+    // Sent uncompressed although they would have compressed
+    pub compressible: usize,
+    #[ts(type = "number")]
+    pub estimated_savings: u64,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct CompressionReport {
+    pub responses_scanned: usize,
+    pub compressed: usize,
+    pub compressible: usize,
+    #[ts(type = "number")]
+    pub encoded_bytes: u64,
+    #[ts(type = "number")]
+    pub decoded_bytes: u64,
+    pub compression_ratio: Option<f64>,
+    #[ts(type = "number")]
+    pub estimated_savings: u64,
+    // Most bytes to save first
+    pub endpoints: Vec<EndpointCompression>,
+    pub candidates: Vec<CompressionCandidate>,
+}
+
+fn is_text(class: Option<ContentClass>) -> bool {
+    matches!(
+        class,
+        Some(
+            ContentClass::Json
+                | ContentClass::Html
+                | ContentClass::Xml
+                | ContentClass::JavaScript
+                | ContentClass::Css
+                | ContentClass::Text
+        )
+    )
+}
+
+fn encoding(response: &NetworkResponse) -> Option<String> {
+    let encoding = response.headers.get("content-encoding")?.trim();
+    (!encoding.is_empty() && !encoding.eq_ignore_ascii_case("identity"))
+        .then(|| encoding.to_ascii_lowercase())
+}
+
+// Compressed size on the wire, when the client or Content-Length tells us
+fn wire_size(response: &NetworkResponse) -> Option<u64> {
+    response.received_bytes.or_else(|| {
+        response
+            .headers
+            .get("content-length")
+            .and_then(|length| length.trim().parse().ok())
+    })
+}
+
+fn gzip_size(body: &[u8]) -> Option<u64> {
+    let sample = &body[..body.len().min(MAX_SAMPLE_BYTES)];
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(sample).ok()?;
+    let compressed = encoder.finish().ok()?.len() as u64;
+    Some(compressed * body.len() as u64 / sample.len() as u64)
+}
+
+fn ratio(encoded: u64, decoded: u64) -> Option<f64> {
+    (decoded > 0).then(|| encoded as f64 / decoded as f64)
+}
+
+pub fn build_compression_report(requests: &[NetworkRequest]) -> CompressionReport {
+    let mut endpoints: HashMap<String, EndpointCompression> = HashMap::new();
+    let mut candidates = Vec::new();
+    let mut responses_scanned = 0;
+
+    for request in requests {
+        let Some(response) = &request.response else {
+            continue;
+        };
+        let Some(body) = response
+            .body
+            .as_deref()
+            .filter(|body| !is_placeholder(body))
+        else {
+            continue;
+        };
+        responses_scanned += 1;
+
+        let key = endpoint_key(request);
+        let stats = endpoints
+            .entry(key.clone())
+            .or_insert_with(|| EndpointCompression {
+                endpoint: key,
+                ..Default::default()
+            });
+        stats.responses += 1;
+
+        if let Some(encoding) = encoding(response) {
+            stats.compressed += 1;
+            *stats.encodings.entry(encoding).or_insert(0) += 1;
+            // Only text bodies survive storage at their decoded size
+            if let Some(wire) = wire_size(response).filter(|_| is_text(response.content_class)) {
+                stats.encoded_bytes += wire;
+                stats.decoded_bytes += body.len() as u64;
+            }
+            continue;
+        }
+
+        if !is_text(response.content_class) || body.len() < MIN_COMPRESSIBLE_BYTES {
+            continue;
+        }
+        let Some(estimated) = gzip_size(body.as_bytes()) else {
+            continue;
+        };
+        let size = body.len() as u64;
+        if estimated >= size {
+            continue;
+        }
+        stats.compressible += 1;
+        stats.estimated_savings += size - estimated;
+        candidates.push(CompressionCandidate {
+            request_id: request.id.clone(),
+            url: request.url.clone(),
+            content_class: response.content_class,
+            size,
+            estimated_gzip_size: estimated,
+            estimated_savings: size - estimated,
+        });
+    }
+
+    let mut endpoints: Vec<EndpointCompression> = endpoints
+        .into_values()
+        .map(|mut stats| {
+            stats.compression_ratio = ratio(stats.encoded_bytes, stats.decoded_bytes);
+            stats
+        })
+        .collect();
+    endpoints.sort_by(|a, b| {
+        b.estimated_savings
+            .cmp(&a.estimated_savings)
+            .then_with(|| b.responses.cmp(&a.responses))
+            .then_with(|| a.endpoint.cmp(&b.endpoint))
+    });
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.estimated_savings));
+
+    let encoded_bytes = endpoints.iter().map(|e| e.encoded_bytes).sum();
+    let decoded_bytes = endpoints.iter().map(|e| e.decoded_bytes).sum();
+    CompressionReport {
+        responses_scanned,
+        compressed: endpoints.iter().map(|e| e.compressed).sum(),
+        compressible: candidates.len(),
+        encoded_bytes,
+        decoded_bytes,
+        compression_ratio: ratio(encoded_bytes, decoded_bytes),
+        estimated_savings: candidates.iter().map(|c| c.estimated_savings).sum(),
+        endpoints,
+        candidates,
+    }
+}
+
+#[tauri::command]
+pub async fn analyze_compression(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+) -> Result<CompressionReport, String> {
+    // A snapshot, so the gzip estimates don't hold up ingest
+    let requests = limits::captured(&store_limits, &store)?;
+    Ok(build_compression_report(&requests))
+}
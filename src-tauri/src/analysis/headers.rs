@@ -0,0 +1,240 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use ts_rs::TS;
+
+use crate::headers::canonical_name;
+use crate::limits::{self, StoreLimitState};
+use crate::{Headers, NetworkRequest, RequestStore};
+
+// Most servers and CDNs reject single header lines above 8 KiB
+const MAX_HEADER_BYTES: usize = 8 * 1024;
+// and whole header blocks somewhere between 16 and 64 KiB
+const MAX_HEADER_BLOCK_BYTES: usize = 32 * 1024;
+
+// Headers that must appear at most once; two differing values mean something
+// between client and server is rewriting or appending
+const SINGLETON_HEADERS: &[&str] = &[
+    "authorization",
+    "content-encoding",
+    "content-length",
+    "content-type",
+    "date",
+    "etag",
+    "expires",
+    "host",
+    "last-modified",
+    "location",
+    "origin",
+    "referer",
+    "retry-after",
+    "server",
+    "transfer-encoding",
+    "user-agent",
+    "access-control-allow-origin",
+    "access-control-allow-credentials",
+];
+
+// Headers defined as comma-separated lists (or Set-Cookie) that may legitimately repeat
+const REPEATABLE_HEADERS: &[&str] = &[
+    "set-cookie",
+    "accept",
+    "accept-encoding",
+    "accept-language",
+    "cache-control",
+    "link",
+    "vary",
+    "via",
+    "warning",
+    "www-authenticate",
+    "proxy-authenticate",
+    "x-forwarded-for",
+    "forwarded",
+    "access-control-allow-headers",
+    "access-control-allow-methods",
+    "access-control-expose-headers",
+    "server-timing",
+];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum HeaderIssueKind {
+    ConflictingDuplicate,
+    Duplicate,
+    InvalidName,
+    InvalidValue,
+    NonAsciiValue,
+    Oversized,
+    OversizedBlock,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum HeaderSide {
+    Request,
+    Response,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct HeaderIssue {
+    pub request_id: String,
+    pub url: String,
+    pub side: HeaderSide,
+    pub header: String,
+    pub kind: HeaderIssueKind,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct HeaderReport {
+    pub requests_scanned: usize,
+    pub counts: HashMap<HeaderIssueKind, usize>,
+    pub issues: Vec<HeaderIssue>,
+}
+
+// RFC 9110 token characters
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+fn scan_headers(headers: &Headers) -> Vec<(String, HeaderIssueKind, String)> {
+    let mut issues = Vec::new();
+    let mut block_size = 0;
+    let mut seen: HashMap<String, Vec<&str>> = HashMap::new();
+
+    for (name, value) in headers.iter() {
+        block_size += name.len() + value.len() + 4;
+
+        if name.is_empty() || !name.chars().all(is_token_char) {
+            issues.push((
+                name.to_string(),
+                HeaderIssueKind::InvalidName,
+                format!(
+                    "Header name {:?} contains characters outside the HTTP token set",
+                    name
+                ),
+            ));
+        }
+        if value.chars().any(|c| c.is_ascii_control() && c != '\t') {
+            issues.push((
+                name.to_string(),
+                HeaderIssueKind::InvalidValue,
+                "Value contains control characters (CR, LF or NUL) - a header injection or encoding bug".to_string(),
+            ));
+        } else if !value.is_ascii() {
+            issues.push((
+                name.to_string(),
+                HeaderIssueKind::NonAsciiValue,
+                "Value contains non-ASCII characters, which proxies may mangle or reject"
+                    .to_string(),
+            ));
+        }
+        if name.len() + value.len() > MAX_HEADER_BYTES {
+            issues.push((
+                name.to_string(),
+                HeaderIssueKind::Oversized,
+                format!(
+                    "Header is {} bytes, above the common {} byte limit",
+                    name.len() + value.len(),
+                    MAX_HEADER_BYTES
+                ),
+            ));
+        }
+
+        seen.entry(canonical_name(name)).or_default().push(value);
+    }
+
+    let mut duplicates: Vec<(String, Vec<&str>)> = seen
+        .into_iter()
+        .filter(|(_, values)| values.len() > 1)
+        .collect();
+    duplicates.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (name, values) in duplicates {
+        if REPEATABLE_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        let conflicting = values.iter().any(|v| v.trim() != values[0].trim());
+        if conflicting && SINGLETON_HEADERS.contains(&name.as_str()) {
+            issues.push((
+                name,
+                HeaderIssueKind::ConflictingDuplicate,
+                format!(
+                    "Sent {} times with different values: {:?}",
+                    values.len(),
+                    values
+                ),
+            ));
+        } else {
+            issues.push((
+                name,
+                HeaderIssueKind::Duplicate,
+                format!("Sent {} times", values.len()),
+            ));
+        }
+    }
+
+    if block_size > MAX_HEADER_BLOCK_BYTES {
+        issues.push((
+            String::new(),
+            HeaderIssueKind::OversizedBlock,
+            format!(
+                "Header block is {} bytes, above the common {} byte limit",
+                block_size, MAX_HEADER_BLOCK_BYTES
+            ),
+        ));
+    }
+
+    issues
+}
+
+pub fn build_header_report(requests: &[NetworkRequest]) -> HeaderReport {
+    let mut issues = Vec::new();
+
+    for request in requests {
+        let mut sides = vec![(HeaderSide::Request, &request.headers)];
+        if let Some(response) = &request.response {
+            sides.push((HeaderSide::Response, &response.headers));
+        }
+        for (side, headers) in sides {
+            for (header, kind, detail) in scan_headers(headers) {
+                issues.push(HeaderIssue {
+                    request_id: request.id.clone(),
+                    url: request.url.clone(),
+                    side,
+                    header,
+                    kind,
+                    detail,
+                });
+            }
+        }
+    }
+
+    let mut counts = HashMap::new();
+    for issue in &issues {
+        *counts.entry(issue.kind).or_insert(0) += 1;
+    }
+
+    HeaderReport {
+        requests_scanned: requests.len(),
+        counts,
+        issues,
+    }
+}
+
+#[tauri::command]
+pub async fn analyze_headers(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    kind: Option<HeaderIssueKind>,
+) -> Result<HeaderReport, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    let mut report = build_header_report(&requests);
+    if let Some(kind) = kind {
+        report.issues.retain(|issue| issue.kind == kind);
+    }
+    Ok(report)
+}
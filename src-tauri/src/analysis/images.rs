@@ -0,0 +1,401 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use ts_rs::TS;
+
+use super::lengths::is_placeholder;
+use crate::classify::ContentClass;
+use crate::limits::{self, StoreLimitState};
+use crate::{NetworkRequest, NetworkResponse, RequestStore};
+
+// Defaults for what counts as too big; a hero image rarely needs more
+const DEFAULT_MAX_BYTES: u64 = 200 * 1024;
+const DEFAULT_MAX_PIXELS: u64 = 2560 * 1600;
+// PNGs this large without transparency are almost always photos
+const PNG_PHOTO_MIN_PIXELS: u64 = 100_000;
+// Animated GIFs this big would be a fraction of the size as video or WebP
+const LARGE_GIF_BYTES: u64 = 100 * 1024;
+// Typical upper bounds for well-compressed images of each format
+const MAX_BYTES_PER_PIXEL: &[(ImageFormat, f64)] = &[
+    (ImageFormat::Jpeg, 0.5),
+    (ImageFormat::Webp, 0.3),
+    (ImageFormat::Avif, 0.2),
+];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+    Avif,
+    Svg,
+    Ico,
+    Bmp,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum ImageIssueKind {
+    // More bytes than the threshold
+    Oversized,
+    // More pixels than any display needs
+    HighResolution,
+    // An opaque photo-sized PNG; JPEG, WebP or AVIF would be far smaller
+    PngPhoto,
+    // A large GIF, likely animated; video or animated WebP would be smaller
+    LargeGif,
+    // Many bytes per pixel for the format: little or no compression applied
+    PoorlyCompressed,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ImageInfo {
+    pub request_id: String,
+    pub url: String,
+    pub format: ImageFormat,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    #[ts(type = "number | null")]
+    pub bytes: Option<u64>,
+    pub bytes_per_pixel: Option<f64>,
+    pub has_alpha: Option<bool>,
+    pub issues: Vec<ImageIssueKind>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ImageReport {
+    pub images_scanned: usize,
+    // Images whose header could be read for dimensions
+    pub images_measured: usize,
+    #[ts(type = "number")]
+    pub total_bytes: u64,
+    pub formats: HashMap<ImageFormat, usize>,
+    pub counts: HashMap<ImageIssueKind, usize>,
+    // Flagged images, biggest first
+    pub images: Vec<ImageInfo>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImageThresholds {
+    pub max_bytes: u64,
+    pub max_pixels: u64,
+}
+
+impl Default for ImageThresholds {
+    fn default() -> Self {
+        ImageThresholds {
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_pixels: DEFAULT_MAX_PIXELS,
+        }
+    }
+}
+
+// The body's original bytes, when the client kept them recoverable: base64
+// (optionally as a data URL) or a binary string with one char per byte.
+// Bodies forced through UTF-8 lost their high bytes and can't be read.
+fn image_bytes(body: &str) -> Option<Vec<u8>> {
+    let trimmed = body.trim();
+    let encoded = match trimmed.split_once(";base64,") {
+        Some((prefix, data)) if prefix.starts_with("data:") => data,
+        _ => trimmed,
+    };
+    if let Ok(bytes) = STANDARD.decode(encoded) {
+        return Some(bytes);
+    }
+    body.chars()
+        .map(|c| u8::try_from(u32::from(c)).ok())
+        .collect()
+}
+
+struct Header {
+    format: ImageFormat,
+    width: Option<u32>,
+    height: Option<u32>,
+    has_alpha: Option<bool>,
+}
+
+fn be16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn le16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn be32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le24(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 3)?;
+    Some(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16)
+}
+
+fn header(format: ImageFormat, size: Option<(u32, u32)>, has_alpha: Option<bool>) -> Header {
+    Header {
+        format,
+        width: size.map(|(width, _)| width),
+        height: size.map(|(_, height)| height),
+        has_alpha,
+    }
+}
+
+// Width and height from the first start-of-frame marker
+fn jpeg_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut at = 2;
+    while at + 4 <= bytes.len() {
+        if bytes[at] != 0xFF {
+            return None;
+        }
+        let marker = bytes[at + 1];
+        // Fill bytes before a marker
+        if marker == 0xFF {
+            at += 1;
+            continue;
+        }
+        let length = be16(bytes, at + 2)? as usize;
+        let is_frame = matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+        if is_frame {
+            return Some((be16(bytes, at + 7)?, be16(bytes, at + 5)?));
+        }
+        at += 2 + length;
+    }
+    None
+}
+
+fn webp_header(bytes: &[u8]) -> Header {
+    let parsed = match bytes.get(12..16) {
+        // Lossy: 14-bit sizes after the frame tag and start code
+        Some(b"VP8 ") => le16(bytes, 26)
+            .zip(le16(bytes, 28))
+            .map(|(w, h)| ((w & 0x3FFF, h & 0x3FFF), Some(false))),
+        // Lossless: 14-bit sizes minus one, packed after the signature byte
+        Some(b"VP8L") => be32(bytes, 21).map(|_| {
+            let b = &bytes[21..25];
+            let width = 1 + (b[0] as u32 | ((b[1] as u32 & 0x3F) << 8));
+            let height =
+                1 + ((b[1] as u32 >> 6) | (b[2] as u32) << 2 | ((b[3] as u32 & 0x0F) << 10));
+            ((width, height), Some(b[3] & 0x10 != 0))
+        }),
+        // Extended: flags, then 24-bit canvas sizes minus one
+        Some(b"VP8X") => bytes.get(20).and_then(|flags| {
+            let size = (1 + le24(bytes, 24)?, 1 + le24(bytes, 27)?);
+            Some((size, Some(flags & 0x10 != 0)))
+        }),
+        _ => None,
+    };
+    match parsed {
+        Some((size, alpha)) => header(ImageFormat::Webp, Some(size), alpha),
+        None => header(ImageFormat::Webp, None, None),
+    }
+}
+
+fn parse_header(bytes: &[u8]) -> Option<Header> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        let size = be32(bytes, 16).zip(be32(bytes, 20));
+        // Colour types 4 and 6 carry alpha; a tRNS chunk could add it to the
+        // others, but photos don't use one
+        let alpha = bytes.get(25).map(|color| matches!(color, 4 | 6));
+        return Some(header(ImageFormat::Png, size, alpha));
+    }
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some(header(ImageFormat::Jpeg, jpeg_size(bytes), Some(false)));
+    }
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        let size = le16(bytes, 6).zip(le16(bytes, 8));
+        return Some(header(ImageFormat::Gif, size, None));
+    }
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        return Some(webp_header(bytes));
+    }
+    if bytes.get(4..8) == Some(b"ftyp") && matches!(bytes.get(8..12), Some(b"avif" | b"avis")) {
+        return Some(header(ImageFormat::Avif, None, None));
+    }
+    if bytes.starts_with(b"BM") {
+        // Height is negative for top-down bitmaps
+        let size = le32(bytes, 18).zip(le32(bytes, 22).map(|h| (h as i32).unsigned_abs()));
+        return Some(header(ImageFormat::Bmp, size, None));
+    }
+    if bytes.starts_with(&[0, 0, 1, 0]) {
+        return Some(header(ImageFormat::Ico, None, None));
+    }
+    None
+}
+
+fn format_from_content_type(response: &NetworkResponse) -> ImageFormat {
+    let mime = response
+        .headers
+        .get("content-type")
+        .unwrap_or_default()
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    match mime.as_str() {
+        "image/png" => ImageFormat::Png,
+        "image/jpeg" | "image/jpg" | "image/pjpeg" => ImageFormat::Jpeg,
+        "image/gif" => ImageFormat::Gif,
+        "image/webp" => ImageFormat::Webp,
+        "image/avif" => ImageFormat::Avif,
+        "image/svg+xml" => ImageFormat::Svg,
+        "image/x-icon" | "image/vnd.microsoft.icon" => ImageFormat::Ico,
+        "image/bmp" => ImageFormat::Bmp,
+        _ => ImageFormat::Other,
+    }
+}
+
+// Wire size when the client or Content-Length says, otherwise the decoded body
+fn image_size(response: &NetworkResponse, decoded: Option<&[u8]>) -> Option<u64> {
+    response
+        .received_bytes
+        .or_else(|| {
+            response
+                .headers
+                .get("content-length")
+                .and_then(|length| length.trim().parse().ok())
+        })
+        .or_else(|| decoded.map(|bytes| bytes.len() as u64))
+}
+
+fn inspect(
+    request: &NetworkRequest,
+    response: &NetworkResponse,
+    limits: ImageThresholds,
+) -> ImageInfo {
+    let decoded = response
+        .body
+        .as_deref()
+        .filter(|body| !is_placeholder(body))
+        .and_then(image_bytes);
+    let parsed = decoded.as_deref().and_then(parse_header);
+    let header = match parsed {
+        Some(header) => header,
+        // SVG is text, so it never decodes to an image header
+        None => Header {
+            format: format_from_content_type(response),
+            width: None,
+            height: None,
+            has_alpha: None,
+        },
+    };
+    let bytes = image_size(response, decoded.as_deref());
+    let pixels = header
+        .width
+        .zip(header.height)
+        .map(|(width, height)| width as u64 * height as u64)
+        .filter(|pixels| *pixels > 0);
+    let bytes_per_pixel = bytes
+        .zip(pixels)
+        .map(|(bytes, pixels)| bytes as f64 / pixels as f64);
+
+    let mut issues = Vec::new();
+    if bytes.is_some_and(|bytes| bytes > limits.max_bytes) {
+        issues.push(ImageIssueKind::Oversized);
+    }
+    if pixels.is_some_and(|pixels| pixels > limits.max_pixels) {
+        issues.push(ImageIssueKind::HighResolution);
+    }
+    if header.format == ImageFormat::Png
+        && header.has_alpha == Some(false)
+        && pixels.is_some_and(|pixels| pixels >= PNG_PHOTO_MIN_PIXELS)
+    {
+        issues.push(ImageIssueKind::PngPhoto);
+    }
+    if header.format == ImageFormat::Gif && bytes.is_some_and(|bytes| bytes > LARGE_GIF_BYTES) {
+        issues.push(ImageIssueKind::LargeGif);
+    }
+    let max_per_pixel = MAX_BYTES_PER_PIXEL
+        .iter()
+        .find(|(format, _)| *format == header.format)
+        .map(|(_, max)| *max);
+    if max_per_pixel
+        .zip(bytes_per_pixel)
+        .is_some_and(|(max, actual)| actual > max)
+    {
+        issues.push(ImageIssueKind::PoorlyCompressed);
+    }
+
+    ImageInfo {
+        request_id: request.id.clone(),
+        url: request.url.clone(),
+        format: header.format,
+        width: header.width,
+        height: header.height,
+        bytes,
+        bytes_per_pixel,
+        has_alpha: header.has_alpha,
+        issues,
+    }
+}
+
+pub fn build_image_report(requests: &[NetworkRequest], limits: ImageThresholds) -> ImageReport {
+    let mut formats = HashMap::new();
+    let mut counts = HashMap::new();
+    let mut images = Vec::new();
+    let mut images_scanned = 0;
+    let mut images_measured = 0;
+    let mut total_bytes = 0;
+
+    for request in requests {
+        let Some(response) = &request.response else {
+            continue;
+        };
+        if response.content_class != Some(ContentClass::Image) {
+            continue;
+        }
+        let info = inspect(request, response, limits);
+        images_scanned += 1;
+        images_measured += info.width.is_some() as usize;
+        total_bytes += info.bytes.unwrap_or(0);
+        *formats.entry(info.format).or_insert(0) += 1;
+        for issue in &info.issues {
+            *counts.entry(*issue).or_insert(0) += 1;
+        }
+        if !info.issues.is_empty() {
+            images.push(info);
+        }
+    }
+    images.sort_by_key(|image| std::cmp::Reverse(image.bytes.unwrap_or(0)));
+
+    ImageReport {
+        images_scanned,
+        images_measured,
+        total_bytes,
+        formats,
+        counts,
+        images,
+    }
+}
+
+#[tauri::command]
+pub async fn analyze_images(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    max_bytes: Option<u64>,
+    max_pixels: Option<u64>,
+    kind: Option<ImageIssueKind>,
+) -> Result<ImageReport, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    let defaults = ImageThresholds::default();
+    let limits = ImageThresholds {
+        max_bytes: max_bytes.unwrap_or(defaults.max_bytes),
+        max_pixels: max_pixels.unwrap_or(defaults.max_pixels),
+    };
+    let mut report = build_image_report(&requests, limits);
+    if let Some(kind) = kind {
+        report.images.retain(|image| image.issues.contains(&kind));
+    }
+    Ok(report)
+}
@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use ts_rs::TS;
+
+use super::headers::HeaderSide;
+use crate::classify::ContentClass;
+use crate::lifecycle::RequestState;
+use crate::limits::{self, StoreLimitState};
+use crate::{Headers, NetworkRequest, RequestStore};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum LengthIssueKind {
+    // Fewer bytes than declared: the body was cut off somewhere on the way
+    Truncated,
+    // More bytes than declared: a framing bug, or whatever follows gets misread
+    Overlong,
+    // Content-Length that isn't a number, or repeated with different values
+    InvalidDeclared,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum LengthSource {
+    // Size of the body as stored
+    StoredBody,
+    // `received_bytes` as reported by the client
+    ReceivedBytes,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct LengthIssue {
+    pub request_id: String,
+    pub url: String,
+    pub side: HeaderSide,
+    pub kind: LengthIssueKind,
+    #[ts(type = "number | null")]
+    pub declared: Option<u64>,
+    #[ts(type = "number | null")]
+    pub actual: Option<u64>,
+    pub measured_by: Option<LengthSource>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct LengthReport {
+    pub requests_scanned: usize,
+    // Bodies that had a declared length to compare against
+    pub bodies_checked: usize,
+    pub counts: HashMap<LengthIssueKind, usize>,
+    pub issues: Vec<LengthIssue>,
+}
+
+enum Declared {
+    Missing,
+    Length(u64),
+    Invalid(String),
+}
+
+fn declared_length(headers: &Headers) -> Declared {
+    let values: Vec<&str> = headers.get_all("content-length").map(str::trim).collect();
+    let Some(first) = values.first() else {
+        return Declared::Missing;
+    };
+    if values.iter().any(|v| v != first) {
+        return Declared::Invalid(format!(
+            "Content-Length sent with different values: {:?}",
+            values
+        ));
+    }
+    match first.parse() {
+        Ok(length) => Declared::Length(length),
+        Err(_) => Declared::Invalid(format!("Content-Length {:?} is not a byte count", first)),
+    }
+}
+
+// What `packages/client-js` stores in place of bodies it can't turn into text
+const SDK_PLACEHOLDERS: &[&str] = &[
+    "[FormData]",
+    "[Blob ",
+    "[Binary ",
+    "[Unable to read response body]",
+];
+
+pub(super) fn is_placeholder(body: &str) -> bool {
+    SDK_PLACEHOLDERS
+        .iter()
+        .any(|placeholder| body.starts_with(placeholder))
+}
+
+// Stored bodies are decoded strings, so their size only matches the wire when
+// nothing was content-encoded and the body is text (binary bodies arrive
+// mangled through UTF-8)
+fn stored_body_comparable(
+    headers: &Headers,
+    body: Option<&str>,
+    class: Option<ContentClass>,
+) -> bool {
+    let placeholder = body.is_some_and(is_placeholder);
+    let encoded = headers
+        .get("content-encoding")
+        .is_some_and(|encoding| !encoding.trim().eq_ignore_ascii_case("identity"));
+    let binary = matches!(
+        class,
+        Some(
+            ContentClass::Image
+                | ContentClass::Font
+                | ContentClass::Video
+                | ContentClass::Audio
+                | ContentClass::Binary
+        )
+    );
+    !encoded && !binary && !placeholder
+}
+
+// Responses that carry no body whatever their Content-Length says
+fn bodiless_response(request: &NetworkRequest, status: u16) -> bool {
+    request.method.eq_ignore_ascii_case("HEAD")
+        || (100..200).contains(&status)
+        || status == 204
+        || status == 304
+}
+
+struct Check<'a> {
+    request: &'a NetworkRequest,
+    side: HeaderSide,
+    headers: &'a Headers,
+    body: Option<&'a str>,
+    class: Option<ContentClass>,
+    received_bytes: Option<u64>,
+}
+
+fn check(check: Check, issues: &mut Vec<LengthIssue>) -> bool {
+    let issue = |kind, declared, actual, measured_by, detail| LengthIssue {
+        request_id: check.request.id.clone(),
+        url: check.request.url.clone(),
+        side: check.side,
+        kind,
+        declared,
+        actual,
+        measured_by,
+        detail,
+    };
+
+    let declared = match declared_length(check.headers) {
+        Declared::Missing => return false,
+        Declared::Invalid(detail) => {
+            issues.push(issue(
+                LengthIssueKind::InvalidDeclared,
+                None,
+                None,
+                None,
+                detail,
+            ));
+            return false;
+        }
+        Declared::Length(length) => length,
+    };
+
+    // Wire bytes count for any encoding, so they win over the stored body
+    let measured = match check.received_bytes {
+        Some(received) => Some((received, LengthSource::ReceivedBytes)),
+        None if stored_body_comparable(check.headers, check.body, check.class) => Some((
+            check.body.map(|body| body.len()).unwrap_or(0) as u64,
+            LengthSource::StoredBody,
+        )),
+        None => None,
+    };
+    let Some((actual, source)) = measured else {
+        return false;
+    };
+
+    if actual < declared {
+        issues.push(issue(
+            LengthIssueKind::Truncated,
+            Some(declared),
+            Some(actual),
+            Some(source),
+            format!("{} of {} declared bytes arrived", actual, declared),
+        ));
+    } else if actual > declared {
+        issues.push(issue(
+            LengthIssueKind::Overlong,
+            Some(declared),
+            Some(actual),
+            Some(source),
+            format!("{} bytes, {} more than declared", actual, actual - declared),
+        ));
+    }
+    true
+}
+
+pub fn build_length_report(requests: &[NetworkRequest]) -> LengthReport {
+    let mut issues = Vec::new();
+    let mut bodies_checked = 0;
+
+    for request in requests {
+        let request_check = Check {
+            request,
+            side: HeaderSide::Request,
+            headers: &request.headers,
+            body: request.body.as_deref(),
+            class: None,
+            received_bytes: None,
+        };
+        bodies_checked += check(request_check, &mut issues) as usize;
+
+        let Some(response) = &request.response else {
+            continue;
+        };
+        // A failed or aborted request is expected to stop short
+        if request.state != RequestState::Complete || bodiless_response(request, response.status) {
+            continue;
+        }
+        let response_check = Check {
+            request,
+            side: HeaderSide::Response,
+            headers: &response.headers,
+            body: response.body.as_deref(),
+            class: response.content_class,
+            received_bytes: response.received_bytes,
+        };
+        bodies_checked += check(response_check, &mut issues) as usize;
+    }
+
+    let mut counts = HashMap::new();
+    for issue in &issues {
+        *counts.entry(issue.kind).or_insert(0) += 1;
+    }
+
+    LengthReport {
+        requests_scanned: requests.len(),
+        bodies_checked,
+        counts,
+        issues,
+    }
+}
+
+#[tauri::command]
+pub async fn analyze_content_lengths(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    kind: Option<LengthIssueKind>,
+) -> Result<LengthReport, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    let mut report = build_length_report(&requests);
+    if let Some(kind) = kind {
+        report.issues.retain(|issue| issue.kind == kind);
+    }
+    Ok(report)
+}
@@ -0,0 +1,310 @@
+// Cold storage for requests evicted by the store limits. Instead of being
+// dropped they are written, full bodies included, to a SQLite file next to the
+// session history, where `search_archive` can still find them. Without an open
+// archive (it failed to open, or in tests) evicted requests are dropped.
+// Eviction only queues them, so the store isn't locked while they are written.
+use rusqlite::{params, params_from_iter, Connection, ToSql};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::blobs;
+use crate::filter::{url_host, RequestFilter};
+use crate::migrate::{decode_request, REQUEST_VERSION};
+use crate::NetworkRequest;
+
+pub(crate) const ARCHIVE_FILE: &str = "archive.sqlite3";
+const DEFAULT_SEARCH_LIMIT: usize = 200;
+
+// Schema changes in order, as in `persist`; MIGRATIONS[n] takes the archive
+// from version n to n + 1, tracked in `PRAGMA user_version`
+const MIGRATIONS: &[&str] = &[
+    // 1: IF NOT EXISTS because archives from before migrations were tracked
+    // already have this table at version 0
+    "
+    CREATE TABLE IF NOT EXISTS archived_requests (
+        id TEXT PRIMARY KEY,
+        timestamp INTEGER NOT NULL,
+        format_version INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS archived_by_time ON archived_requests (timestamp);
+    ",
+    // 2: what `search` filters on, so it doesn't decode every row. Rows from
+    // before are filled in by `backfill`.
+    "
+    ALTER TABLE archived_requests ADD COLUMN host TEXT NOT NULL DEFAULT '';
+    ALTER TABLE archived_requests ADD COLUMN method TEXT NOT NULL DEFAULT '';
+    ALTER TABLE archived_requests ADD COLUMN status INTEGER;
+    CREATE INDEX archived_by_host ON archived_requests (host, timestamp);
+    CREATE INDEX archived_by_method ON archived_requests (method, timestamp);
+    CREATE INDEX archived_by_status ON archived_requests (status, timestamp);
+    ",
+];
+
+fn migrate(db: &mut Connection) -> Result<(), String> {
+    let version: usize = db
+        .query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+        .map_err(|e| e.to_string())? as usize;
+    if version > MIGRATIONS.len() {
+        return Err(format!(
+            "Archive was written by a newer version of the app (schema {})",
+            version
+        ));
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = db.transaction().map_err(|e| e.to_string())?;
+        tx.execute_batch(migration)
+            .map_err(|e| format!("Migration {} failed: {}", index + 1, e))?;
+        if index == 1 {
+            backfill(&tx)?;
+        }
+        tx.pragma_update(None, "user_version", (index + 1) as i64)
+            .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// The host, method and status of rows archived before they had columns
+fn backfill(db: &Connection) -> Result<(), String> {
+    let rows: Vec<(String, String, u32)> = {
+        let mut select = db
+            .prepare("SELECT id, data, format_version FROM archived_requests")
+            .map_err(|e| e.to_string())?;
+        let rows = select
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    let mut update = db
+        .prepare("UPDATE archived_requests SET host = ?2, method = ?3, status = ?4 WHERE id = ?1")
+        .map_err(|e| e.to_string())?;
+    for (id, data, version) in rows {
+        if let Ok(request) = decode(&data, version) {
+            let (host, method, status) = columns(&request);
+            update
+                .execute(params![id, host, method, status])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+// Host lowercased and method uppercased, as `search` compares them
+fn columns(request: &NetworkRequest) -> (String, String, Option<u16>) {
+    (
+        url_host(&request.url).to_ascii_lowercase(),
+        request.method.to_ascii_uppercase(),
+        request.response.as_ref().map(|r| r.status),
+    )
+}
+
+fn decode(data: &str, version: u32) -> Result<NetworkRequest, String> {
+    serde_json::from_str::<Value>(data)
+        .map_err(|e| e.to_string())
+        .and_then(|value| decode_request(value, version))
+}
+
+#[derive(Debug)]
+pub struct Archive {
+    db: Connection,
+}
+
+// The archive, and what eviction queued for it. Lock `archive` first when
+// taking both.
+#[derive(Debug, Default)]
+pub struct Archived {
+    archive: Mutex<Option<Archive>>,
+    queued: Mutex<Vec<NetworkRequest>>,
+}
+
+pub type ArchiveState = Arc<Archived>;
+
+impl Archived {
+    // Spill files the queued requests still need, for the body sweeper
+    pub(crate) fn queued_bodies(&self) -> Vec<String> {
+        let queued = self.queued.lock().unwrap();
+        queued
+            .iter()
+            .flat_map(blobs::spilled_bodies)
+            .map(|spilled| spilled.path.clone())
+            .collect()
+    }
+}
+
+impl Archive {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let mut db = Connection::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        db.execute_batch("PRAGMA journal_mode = WAL;")
+            .map_err(|e| e.to_string())?;
+        migrate(&mut db)?;
+        Ok(Archive { db })
+    }
+
+    // A request archived again, e.g. re-imported and evicted twice, replaces
+    // its earlier copy
+    fn insert(&mut self, requests: &[NetworkRequest]) -> Result<(), String> {
+        let tx = self.db.transaction().map_err(|e| e.to_string())?;
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT OR REPLACE INTO archived_requests \
+                     (id, timestamp, format_version, data, host, method, status) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                )
+                .map_err(|e| e.to_string())?;
+            for request in requests {
+                let data = serde_json::to_string(request).map_err(|e| e.to_string())?;
+                let (host, method, status) = columns(request);
+                insert
+                    .execute(params![
+                        request.id,
+                        request.timestamp as i64,
+                        REQUEST_VERSION,
+                        data,
+                        host,
+                        method,
+                        status
+                    ])
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    // Newest first. Host, method and status narrow the rows in SQL; the rest
+    // of the filter is checked on what they decode to. Rows that no longer
+    // decode are skipped.
+    fn search(&self, filter: &RequestFilter, limit: usize) -> Result<Vec<NetworkRequest>, String> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+        if let Some(host) = &filter.host {
+            conditions.push("host = ?");
+            values.push(Box::new(host.to_ascii_lowercase()));
+        }
+        if let Some(method) = &filter.method {
+            conditions.push("method = ?");
+            values.push(Box::new(method.to_ascii_uppercase()));
+        }
+        if let Some(min) = filter.status_min {
+            conditions.push("status >= ?");
+            values.push(Box::new(min));
+        }
+        if let Some(max) = filter.status_max {
+            conditions.push("status <= ?");
+            values.push(Box::new(max));
+        }
+        let mut sql = "SELECT data, format_version FROM archived_requests".to_string();
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY timestamp DESC, rowid DESC");
+
+        let mut select = self.db.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = select
+            .query_map(params_from_iter(values.iter()), |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, u32>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+
+        let mut found = Vec::new();
+        for row in rows {
+            let (data, version) = row.map_err(|e| e.to_string())?;
+            let Ok(request) = decode(&data, version) else {
+                continue;
+            };
+            if filter.matches(&request) {
+                found.push(request);
+                if found.len() == limit {
+                    break;
+                }
+            }
+        }
+        Ok(found)
+    }
+}
+
+pub fn open(state: &ArchiveState, data_dir: &Path) {
+    match Archive::open(&data_dir.join(ARCHIVE_FILE)) {
+        Ok(archive) => *state.archive.lock().unwrap() = Some(archive),
+        Err(e) => println!("Archive disabled, evicted requests will be dropped: {}", e),
+    }
+}
+
+// Queues evicted requests for the archive; cheap enough to call with the
+// store locked
+pub(crate) fn queue(state: &ArchiveState, requests: Vec<NetworkRequest>) {
+    if !requests.is_empty() {
+        state.queued.lock().unwrap().extend(requests);
+    }
+}
+
+// Moves the queue into the archive, bodies read back from their spill files
+// first since those are deleted once nothing points at them
+pub(crate) fn write_queued(state: &ArchiveState) {
+    let mut archive = state.archive.lock().unwrap();
+    let mut requests = state.queued.lock().unwrap().clone();
+    if requests.is_empty() {
+        return;
+    }
+    let written = requests.len();
+    if let Some(archive) = archive.as_mut() {
+        blobs::restore(&mut requests);
+        if let Err(e) = archive.insert(&requests) {
+            println!("Failed to archive {} requests: {}", requests.len(), e);
+        }
+    }
+    // Still holding the archive, so a search never sees a request twice
+    state.queued.lock().unwrap().drain(..written);
+}
+
+#[tauri::command]
+pub async fn search_archive(
+    state: tauri::State<'_, ArchiveState>,
+    filter: RequestFilter,
+    limit: Option<usize>,
+) -> Result<Vec<NetworkRequest>, String> {
+    filter.validate()?;
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let archive = state.archive.lock().map_err(|e| e.to_string())?;
+    let archive = archive
+        .as_ref()
+        .ok_or_else(|| "Archive is not available".to_string())?;
+    // Evicted since the last write, so newest
+    let mut found: Vec<NetworkRequest> = {
+        let queued = state.queued.lock().map_err(|e| e.to_string())?;
+        queued
+            .iter()
+            .rev()
+            .filter(|r| filter.matches(r))
+            .take(limit)
+            .cloned()
+            .collect()
+    };
+    if found.len() < limit {
+        found.extend(archive.search(&filter, limit - found.len())?);
+    }
+    Ok(found)
+}
+
+// Returns the number of archived requests removed
+#[tauri::command]
+pub async fn clear_archive(state: tauri::State<'_, ArchiveState>) -> Result<usize, String> {
+    let archive = state.archive.lock().map_err(|e| e.to_string())?;
+    let archive = archive
+        .as_ref()
+        .ok_or_else(|| "Archive is not available".to_string())?;
+    let queued = std::mem::take(&mut *state.queued.lock().map_err(|e| e.to_string())?).len();
+    let removed = archive
+        .db
+        .execute("DELETE FROM archived_requests", [])
+        .map_err(|e| e.to_string())?;
+    Ok(removed + queued)
+}
@@ -0,0 +1,258 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+use crate::classify::ContentClass;
+use crate::filter::RequestFilter;
+use crate::limits::{self, StoreLimitState};
+use crate::preflight::{PreflightPairs, PreflightState};
+use crate::summaries::{self, Range, RequestSummary, Sort};
+use crate::{NetworkRequest, RequestStore};
+
+const MAX_PAGE_SIZE: usize = 1000;
+// Ids listed on a group row; the rest are reachable through a filter
+const MAX_GROUP_IDS: usize = 100;
+
+// For requests still in flight or without a recognisable body
+const EXTENSION_GROUPS: &[(&[&str], &str)] = &[
+    (
+        &[
+            "png", "jpg", "jpeg", "gif", "webp", "avif", "svg", "ico", "bmp",
+        ],
+        "images",
+    ),
+    (&["woff", "woff2", "ttf", "otf", "eot"], "fonts"),
+    (&["js", "mjs", "cjs", "map"], "scripts"),
+    (&["css"], "stylesheets"),
+    (
+        &["mp4", "webm", "mov", "mp3", "ogg", "wav", "m3u8"],
+        "media",
+    ),
+];
+
+// `pattern` is a regex on the URL. Matching requests are collapsed into
+// `group`, or kept as individual rows when `group` is null, e.g.
+// `{ "pattern": "/static/chunks/", "group": "chunks" }` or
+// `{ "pattern": "/api/avatar", "group": null }`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct AssetPattern {
+    pub pattern: String,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(skip)]
+    compiled: Option<Regex>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct AssetCollapseConfig {
+    // Checked in order before anything else; the first match decides
+    #[serde(default)]
+    pub patterns: Vec<AssetPattern>,
+    // Group images, fonts, scripts, stylesheets and media by content class and
+    // file extension when no pattern matches
+    #[serde(default = "default_by_type")]
+    pub by_type: bool,
+}
+
+fn default_by_type() -> bool {
+    true
+}
+
+impl Default for AssetCollapseConfig {
+    fn default() -> Self {
+        AssetCollapseConfig {
+            patterns: Vec::new(),
+            by_type: true,
+        }
+    }
+}
+
+pub type AssetState = Arc<Mutex<AssetCollapseConfig>>;
+
+fn extension(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let (_, extension) = name.rsplit_once('.')?;
+    Some(extension.to_ascii_lowercase())
+}
+
+fn type_group(request: &NetworkRequest) -> Option<&'static str> {
+    match request.response.as_ref().and_then(|r| r.content_class) {
+        Some(ContentClass::Image) => return Some("images"),
+        Some(ContentClass::Font) => return Some("fonts"),
+        Some(ContentClass::JavaScript) => return Some("scripts"),
+        Some(ContentClass::Css) => return Some("stylesheets"),
+        Some(ContentClass::Video | ContentClass::Audio) => return Some("media"),
+        // API responses stay individual whatever their URL looks like
+        Some(ContentClass::Json | ContentClass::Xml | ContentClass::Html) => return None,
+        _ => {}
+    }
+    let extension = extension(&request.url)?;
+    EXTENSION_GROUPS
+        .iter()
+        .find(|(extensions, _)| extensions.contains(&extension.as_str()))
+        .map(|(_, group)| *group)
+}
+
+impl AssetCollapseConfig {
+    fn compile(&mut self) -> Result<(), String> {
+        for pattern in self.patterns.iter_mut() {
+            pattern.compiled = Some(
+                Regex::new(&pattern.pattern).map_err(|e| format!("{}: {}", pattern.pattern, e))?,
+            );
+        }
+        Ok(())
+    }
+
+    // The group `request` collapses into, or None to keep it as its own row
+    pub fn group_of(&self, request: &NetworkRequest) -> Option<String> {
+        let matched = self.patterns.iter().find(|pattern| {
+            pattern
+                .compiled
+                .as_ref()
+                .is_some_and(|regex| regex.is_match(&request.url))
+        });
+        match matched {
+            Some(pattern) => pattern.group.clone(),
+            None if self.by_type => type_group(request).map(str::to_string),
+            None => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct AssetGroupSummary {
+    pub group: String,
+    pub count: usize,
+    // 4xx/5xx and requests that failed without a response
+    pub errors: usize,
+    pub total_bytes: usize,
+    #[ts(type = "number")]
+    pub first_timestamp: u64,
+    #[ts(type = "number")]
+    pub last_timestamp: u64,
+    #[ts(type = "number | null")]
+    pub slowest_ms: Option<u64>,
+    // Capped at MAX_GROUP_IDS
+    pub ids: Vec<String>,
+}
+
+impl AssetGroupSummary {
+    fn new(group: String, request: &NetworkRequest) -> Self {
+        AssetGroupSummary {
+            group,
+            count: 0,
+            errors: 0,
+            total_bytes: 0,
+            first_timestamp: request.timestamp,
+            last_timestamp: request.timestamp,
+            slowest_ms: None,
+            ids: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, request: &NetworkRequest) {
+        self.count += 1;
+        let status = request.response.as_ref().map(|r| r.status);
+        if status.is_some_and(|s| s >= 400 || s == 0) {
+            self.errors += 1;
+        }
+        self.total_bytes += request
+            .response
+            .as_ref()
+            .and_then(|r| r.body.as_ref())
+            .map(|b| b.len())
+            .unwrap_or(0);
+        self.first_timestamp = self.first_timestamp.min(request.timestamp);
+        self.last_timestamp = self.last_timestamp.max(request.timestamp);
+        self.slowest_ms = self.slowest_ms.max(request.duration);
+        if self.ids.len() < MAX_GROUP_IDS {
+            self.ids.push(request.id.clone());
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum ListingRow {
+    Request(RequestSummary),
+    AssetGroup(AssetGroupSummary),
+}
+
+// API calls stay individual; each asset group becomes one row where its first
+// member would have been in the sorted listing
+pub fn collapse(
+    requests: &[&NetworkRequest],
+    config: &AssetCollapseConfig,
+    pairs: &PreflightPairs,
+) -> Vec<ListingRow> {
+    let mut rows = Vec::new();
+    let mut groups: HashMap<String, usize> = HashMap::new();
+    for request in requests {
+        let Some(group) = config.group_of(request) else {
+            rows.push(ListingRow::Request(summaries::summarize(request, pairs)));
+            continue;
+        };
+        let index = *groups.entry(group.clone()).or_insert_with(|| {
+            rows.push(ListingRow::AssetGroup(AssetGroupSummary::new(
+                group, request,
+            )));
+            rows.len() - 1
+        });
+        if let ListingRow::AssetGroup(summary) = &mut rows[index] {
+            summary.add(request);
+        }
+    }
+    rows
+}
+
+#[tauri::command]
+pub async fn set_asset_collapse_config(
+    assets: tauri::State<'_, AssetState>,
+    config: AssetCollapseConfig,
+) -> Result<AssetCollapseConfig, String> {
+    let mut config = config;
+    config.compile()?;
+    let mut current = assets.lock().map_err(|e| e.to_string())?;
+    *current = config;
+    Ok(current.clone())
+}
+
+#[tauri::command]
+pub async fn get_asset_collapse_config(
+    assets: tauri::State<'_, AssetState>,
+) -> Result<AssetCollapseConfig, String> {
+    Ok(assets.lock().map_err(|e| e.to_string())?.clone())
+}
+
+// `get_request_summaries` with static assets folded into per-type rows. Range
+// applies to rows, not requests.
+#[tauri::command]
+pub async fn get_collapsed_summaries(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    preflight: tauri::State<'_, PreflightState>,
+    assets: tauri::State<'_, AssetState>,
+    range: Range,
+    sort: Option<Sort>,
+    filter: Option<RequestFilter>,
+) -> Result<Vec<ListingRow>, String> {
+    let collapse_preflights = preflight.lock().map_err(|e| e.to_string())?.collapse;
+    let config = assets.lock().map_err(|e| e.to_string())?.clone();
+    let requests = limits::captured(&store_limits, &store)?;
+    let pairs = PreflightPairs::build(&requests);
+    let mut selected = summaries::listed(&requests, filter.as_ref(), &pairs, collapse_preflights);
+    summaries::sort_requests(&mut selected, sort.unwrap_or_default());
+
+    Ok(collapse(&selected, &config, &pairs)
+        .into_iter()
+        .skip(range.offset)
+        .take(range.limit.min(MAX_PAGE_SIZE))
+        .collect())
+}
@@ -0,0 +1,338 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::endpoint::endpoint_key;
+use crate::events::{self, AppEvent, EventSink};
+use crate::markers::now_millis;
+use crate::{data_dir, NetworkRequest};
+
+const MAX_ANOMALIES: usize = 500;
+// Differences smaller than this aren't worth flagging however unusual they are
+const MIN_LATENCY_DIFF_MS: f64 = 100.0;
+
+// Running mean and variance (Welford). Latency and size are tracked as
+// ln(1 + x): both are heavily right-skewed, and z-scores mean little otherwise.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Running {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Running {
+    fn push(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn std_dev(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.count - 1) as f64).sqrt()
+    }
+
+    fn z_score(&self, x: f64) -> Option<f64> {
+        let std_dev = self.std_dev();
+        (std_dev > f64::EPSILON).then(|| (x - self.mean) / std_dev)
+    }
+
+    // Back to the original scale; the geometric mean, close to the median here
+    fn typical(&self) -> f64 {
+        self.mean.exp() - 1.0
+    }
+}
+
+fn log_scale(x: u64) -> f64 {
+    (x as f64).ln_1p()
+}
+
+fn status_class(status: u16) -> String {
+    match status {
+        0 => "failed".to_string(),
+        _ => format!("{}xx", status / 100),
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct EndpointBaseline {
+    latency: Running,
+    size: Running,
+    status_classes: BTreeMap<String, u64>,
+}
+
+impl EndpointBaseline {
+    fn samples(&self) -> u64 {
+        self.status_classes.values().sum()
+    }
+
+    fn typical_status_class(&self) -> Option<&str> {
+        self.status_classes
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(class, _)| class.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct AnomalyConfig {
+    // Calls an endpoint needs before its requests are judged
+    #[ts(type = "number")]
+    pub min_samples: u64,
+    // How many standard deviations from the baseline count as anomalous
+    pub z_threshold: f64,
+    // Share of an endpoint's calls below which a status class is unusual
+    pub rare_status_ratio: f64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        AnomalyConfig {
+            min_samples: 20,
+            z_threshold: 3.0,
+            rare_status_ratio: 0.02,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum AnomalyKind {
+    Latency,
+    Size,
+    Status,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct Anomaly {
+    pub id: String,
+    pub request_id: String,
+    pub endpoint: String,
+    #[ts(type = "number")]
+    pub detected_at: u64,
+    pub kind: AnomalyKind,
+    // Null for status anomalies, which are judged by frequency instead
+    pub z_score: Option<f64>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct BaselineSummary {
+    pub endpoint: String,
+    #[ts(type = "number")]
+    pub samples: u64,
+    pub typical_latency_ms: Option<f64>,
+    pub typical_size_bytes: Option<f64>,
+    pub typical_status: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct BaselineStore {
+    pub config: AnomalyConfig,
+    baselines: HashMap<String, EndpointBaseline>,
+    anomalies: VecDeque<Anomaly>,
+}
+
+pub type BaselineState = Arc<Mutex<BaselineStore>>;
+
+impl BaselineStore {
+    // Judges `request` against what its endpoint usually does, then learns from it
+    fn observe(&mut self, request: &NetworkRequest) -> Vec<Anomaly> {
+        let Some(response) = request.response.as_ref() else {
+            return Vec::new();
+        };
+        let endpoint = endpoint_key(request);
+        let config = self.config;
+        let baseline = self.baselines.entry(endpoint.clone()).or_default();
+        let size = response.body.as_ref().map(|b| b.len()).unwrap_or(0) as u64;
+        let class = status_class(response.status);
+
+        let mut found = Vec::new();
+        let mut flag = |kind, z_score, detail| {
+            found.push(Anomaly {
+                id: Uuid::new_v4().to_string(),
+                request_id: request.id.clone(),
+                endpoint: endpoint.clone(),
+                detected_at: now_millis(),
+                kind,
+                z_score,
+                detail,
+            })
+        };
+
+        if baseline.samples() >= config.min_samples {
+            if let Some(duration) = request.duration {
+                let typical = baseline.latency.typical();
+                let slower_by = duration as f64 - typical;
+                // Only slow is interesting; fast outliers are usually cache hits
+                match baseline.latency.z_score(log_scale(duration)) {
+                    Some(z) if z >= config.z_threshold && slower_by >= MIN_LATENCY_DIFF_MS => {
+                        flag(
+                            AnomalyKind::Latency,
+                            Some(z),
+                            format!("{}ms, usually about {:.0}ms", duration, typical),
+                        );
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(z) = baseline.size.z_score(log_scale(size)) {
+                if z.abs() >= config.z_threshold {
+                    flag(
+                        AnomalyKind::Size,
+                        Some(z),
+                        format!(
+                            "{} bytes, usually about {:.0}",
+                            size,
+                            baseline.size.typical()
+                        ),
+                    );
+                }
+            }
+            let seen = baseline.status_classes.get(&class).copied().unwrap_or(0);
+            if (seen as f64) < config.rare_status_ratio * baseline.samples() as f64 {
+                let typical = baseline.typical_status_class().unwrap_or_default();
+                flag(
+                    AnomalyKind::Status,
+                    None,
+                    format!("{}, usually {}", response.status, typical),
+                );
+            }
+        }
+
+        if let Some(duration) = request.duration {
+            baseline.latency.push(log_scale(duration));
+        }
+        baseline.size.push(log_scale(size));
+        *baseline.status_classes.entry(class).or_default() += 1;
+
+        for anomaly in &found {
+            if self.anomalies.len() >= MAX_ANOMALIES {
+                self.anomalies.pop_front();
+            }
+            self.anomalies.push_back(anomaly.clone());
+        }
+        found
+    }
+}
+
+// Called for every stored request; only finished ones with a response teach
+// or get judged
+pub fn observe(state: &BaselineState, request: &NetworkRequest, sink: &dyn EventSink) {
+    if !request.state.is_final() {
+        return;
+    }
+    let found = state.lock().unwrap().observe(request);
+    for anomaly in &found {
+        if let Err(e) = events::emit(sink, AppEvent::AnomalyDetected(anomaly)) {
+            println!("Failed to emit anomaly-detected event: {}", e);
+        }
+    }
+}
+
+fn baselines_path(app_handle: &tauri::AppHandle) -> PathBuf {
+    data_dir(app_handle).join("baselines.json")
+}
+
+#[tauri::command]
+pub async fn get_anomalies(
+    state: tauri::State<'_, BaselineState>,
+    since: Option<u64>,
+) -> Result<Vec<Anomaly>, String> {
+    let store = state.lock().map_err(|e| e.to_string())?;
+    Ok(store
+        .anomalies
+        .iter()
+        .filter(|a| since.is_none_or(|since| a.detected_at >= since))
+        .cloned()
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_baselines(
+    state: tauri::State<'_, BaselineState>,
+) -> Result<Vec<BaselineSummary>, String> {
+    let store = state.lock().map_err(|e| e.to_string())?;
+    let mut summaries: Vec<BaselineSummary> = store
+        .baselines
+        .iter()
+        .map(|(endpoint, baseline)| BaselineSummary {
+            endpoint: endpoint.clone(),
+            samples: baseline.samples(),
+            typical_latency_ms: (baseline.latency.count > 0).then(|| baseline.latency.typical()),
+            typical_size_bytes: (baseline.size.count > 0).then(|| baseline.size.typical()),
+            typical_status: baseline.typical_status_class().map(str::to_string),
+        })
+        .collect();
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.samples));
+    Ok(summaries)
+}
+
+#[tauri::command]
+pub async fn set_anomaly_config(
+    state: tauri::State<'_, BaselineState>,
+    config: AnomalyConfig,
+) -> Result<AnomalyConfig, String> {
+    if config.z_threshold <= 0.0 {
+        return Err("z_threshold must be positive".to_string());
+    }
+    state.lock().map_err(|e| e.to_string())?.config = config;
+    Ok(config)
+}
+
+// Forgets everything learned so far, and the anomalies flagged with it
+#[tauri::command]
+pub async fn reset_baselines(state: tauri::State<'_, BaselineState>) -> Result<(), String> {
+    let mut store = state.lock().map_err(|e| e.to_string())?;
+    store.baselines.clear();
+    store.anomalies.clear();
+    Ok(())
+}
+
+// Keeps what this session learned so later sessions start from it. Returns
+// the number of endpoints saved.
+#[tauri::command]
+pub async fn save_baselines(
+    state: tauri::State<'_, BaselineState>,
+    app_handle: tauri::AppHandle,
+) -> Result<usize, String> {
+    let (json, count) = {
+        let store = state.lock().map_err(|e| e.to_string())?;
+        let json = serde_json::to_string(&store.baselines).map_err(|e| e.to_string())?;
+        (json, store.baselines.len())
+    };
+    let path = baselines_path(&app_handle);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, json)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(count)
+}
+
+// Replaces the current baselines with the saved ones
+#[tauri::command]
+pub async fn load_baselines(
+    state: tauri::State<'_, BaselineState>,
+    app_handle: tauri::AppHandle,
+) -> Result<usize, String> {
+    let path = baselines_path(&app_handle);
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let baselines: HashMap<String, EndpointBaseline> =
+        serde_json::from_str(&json).map_err(|e| format!("Invalid baselines file: {}", e))?;
+    let mut store = state.lock().map_err(|e| e.to_string())?;
+    store.baselines = baselines;
+    Ok(store.baselines.len())
+}
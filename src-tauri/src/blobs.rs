@@ -0,0 +1,524 @@
+// Large bodies are written to temp files so the store only holds a preview of
+// them, zstd-compressed unless that is switched off. `set_body_limit` sets how
+// big a body can get before that happens and how much of it is kept. Everything that reads
+// bodies before they are spilled (classification, the JSON index, derived
+// fields) still sees them whole; exports load them back. Each process spills to
+// its own directory, locked while in use and removed on a clean exit; ones left
+// by a crash are pruned at the next start.
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::bodies::{self, Body, BodyTable};
+use crate::latest::LatestState;
+use crate::limits::{self, StoreLimitState};
+use crate::provenance::{self, ProvenanceKind};
+use crate::sessions::SessionState;
+use crate::tombstones::TombstoneState;
+use crate::{text, NetworkRequest, RequestStore};
+
+const SPILL_THRESHOLD_BYTES: usize = 1024 * 1024;
+const PREVIEW_BYTES: usize = 16 * 1024;
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+// zstd's default; text bodies shrink several times over at little CPU cost
+const COMPRESSION_LEVEL: i32 = 3;
+
+// Where a body that was too big to keep in memory went
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct SpilledBody {
+    pub path: String,
+    // Of the full body; the one kept inline is a preview
+    #[ts(type = "number")]
+    pub size: u64,
+    // Set when the file is zstd-compressed, to this many bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(type = "number | null")]
+    pub compressed_size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct BodyLimit {
+    // Bodies over this are moved to disk
+    #[ts(type = "number")]
+    pub spill_over_bytes: u64,
+    // How much of a moved body stays in memory
+    #[ts(type = "number")]
+    pub preview_bytes: u64,
+}
+
+impl Default for BodyLimit {
+    fn default() -> Self {
+        BodyLimit {
+            spill_over_bytes: SPILL_THRESHOLD_BYTES as u64,
+            preview_bytes: PREVIEW_BYTES as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct FullBody {
+    pub body: Option<String>,
+    // Of the full body
+    #[ts(type = "number")]
+    pub size: u64,
+    // The body in the store is a preview of this one
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct BodyCompression {
+    pub enabled: bool,
+    // Bodies this big or bigger are compressed and moved out of memory. Below
+    // the spill threshold that only happens with compression enabled.
+    #[ts(type = "number")]
+    pub min_bytes: u64,
+}
+
+impl Default for BodyCompression {
+    fn default() -> Self {
+        BodyCompression {
+            enabled: true,
+            min_bytes: SPILL_THRESHOLD_BYTES as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct BodyStorageStats {
+    // Bodies moved out of memory, and how many of those are compressed
+    pub spilled_bodies: usize,
+    pub compressed_bodies: usize,
+    // Their full size, and what their files take
+    #[ts(type = "number")]
+    pub raw_bytes: u64,
+    #[ts(type = "number")]
+    pub stored_bytes: u64,
+}
+
+pub struct Blobs {
+    dir: PathBuf,
+    limit: Mutex<BodyLimit>,
+    compression: Mutex<BodyCompression>,
+    // Bodies small enough to stay in memory, shared between identical ones
+    table: Mutex<BodyTable>,
+    // Locked from the first spill, so other processes don't prune `dir`
+    claim: Mutex<Option<File>>,
+}
+
+pub type BlobStore = Arc<Blobs>;
+
+impl Default for Blobs {
+    fn default() -> Self {
+        Blobs {
+            // One directory per process, so the agent and the app don't sweep
+            // each other's files
+            dir: std::env::temp_dir()
+                .join("network-observer-bodies")
+                .join(std::process::id().to_string()),
+            limit: Default::default(),
+            compression: Default::default(),
+            table: Default::default(),
+            claim: Default::default(),
+        }
+    }
+}
+
+// Beside the directory rather than in it, where the sweeper would delete it
+fn lock_path(dir: &Path) -> PathBuf {
+    dir.with_extension("lock")
+}
+
+impl Blobs {
+    fn claim(&self) {
+        let Ok(mut claim) = self.claim.lock() else {
+            return;
+        };
+        if claim.is_some() {
+            return;
+        }
+        if let Some(root) = self.dir.parent() {
+            std::fs::create_dir_all(root).ok();
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_path(&self.dir));
+        // Left unclaimed, to try again on the next spill, if that fails
+        if let Ok(file) = file {
+            if file.try_lock().is_ok() {
+                *claim = Some(file);
+            }
+        }
+    }
+
+    // On a clean exit, once nothing needs the spilled bodies
+    pub fn remove_files(&self) {
+        std::fs::remove_dir_all(&self.dir).ok();
+        if let Ok(mut claim) = self.claim.lock() {
+            if claim.take().is_some() {
+                std::fs::remove_file(lock_path(&self.dir)).ok();
+            }
+        }
+    }
+
+    // Removes the spill directories of processes that are gone. At startup,
+    // after recovered requests have had their bodies read back.
+    pub fn prune_stale(&self) {
+        let Some(entries) = self
+            .dir
+            .parent()
+            .and_then(|root| std::fs::read_dir(root).ok())
+        else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path == self.dir || !path.is_dir() {
+                continue;
+            }
+            let lock = lock_path(&path);
+            // Errors other than the lock being taken count as held too
+            let held = File::open(&lock).is_ok_and(|file| file.try_lock().is_err());
+            if !held {
+                std::fs::remove_dir_all(&path).ok();
+                std::fs::remove_file(&lock).ok();
+            }
+        }
+    }
+
+    fn spill_body(
+        &self,
+        body: &mut Option<Body>,
+        spilled: &mut Option<SpilledBody>,
+    ) -> Result<Option<String>, String> {
+        let compression = *self.compression.lock().map_err(|e| e.to_string())?;
+        let limit = *self.limit.lock().map_err(|e| e.to_string())?;
+        let preview_bytes = limit.preview_bytes as usize;
+        let compress = |len: usize| compression.enabled && len as u64 >= compression.min_bytes;
+        // A body no longer than the preview would be kept whole anyway
+        let Some(full) = body.as_deref().filter(|b| {
+            b.len() as u64 > limit.spill_over_bytes
+                || (compress(b.len()) && b.len() > preview_bytes)
+        }) else {
+            return Ok(None);
+        };
+        if spilled.is_some() {
+            return Ok(None);
+        }
+        self.claim();
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create {}: {}", self.dir.display(), e))?;
+        let size = full.len();
+        let (path, compressed_size) = if compress(size) {
+            let compressed = zstd::encode_all(full.as_bytes(), COMPRESSION_LEVEL)
+                .map_err(|e| format!("Failed to compress body: {}", e))?;
+            let path = self.dir.join(format!("{}.body.zst", Uuid::new_v4()));
+            std::fs::write(&path, &compressed)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            (path, Some(compressed.len() as u64))
+        } else {
+            let path = self.dir.join(format!("{}.body", Uuid::new_v4()));
+            std::fs::write(&path, full)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            (path, None)
+        };
+        let detail = match compressed_size {
+            Some(compressed) => format!("{} bytes, compressed to {}", size, compressed),
+            None => format!("{} bytes", size),
+        };
+        *spilled = Some(SpilledBody {
+            path: path.to_string_lossy().into_owned(),
+            size: size as u64,
+            compressed_size,
+        });
+        *body = Some(text::prefix(full, preview_bytes).into());
+        Ok(Some(detail))
+    }
+
+    // Where a newly stored request's bodies go: to disk if they are over the
+    // threshold, otherwise shared with any identical body already in memory
+    pub fn store(&self, request: &mut NetworkRequest) {
+        self.spill(request);
+        if let Ok(mut table) = self.table.lock() {
+            bodies::intern(&mut table, request);
+        }
+    }
+
+    // A body that can't be written stays in memory
+    fn spill(&self, request: &mut NetworkRequest) {
+        let mut spilled = Vec::new();
+        match self.spill_body(&mut request.body, &mut request.body_file) {
+            Ok(Some(detail)) => spilled.push(format!("request body, {}", detail)),
+            Ok(None) => {}
+            Err(e) => println!("Keeping body in memory: {}", e),
+        }
+        if let Some(response) = request.response.as_mut() {
+            match self.spill_body(&mut response.body, &mut response.body_file) {
+                Ok(Some(detail)) => spilled.push(format!("response body, {}", detail)),
+                Ok(None) => {}
+                Err(e) => println!("Keeping body in memory: {}", e),
+            }
+        }
+        for detail in spilled {
+            provenance::record(request, ProvenanceKind::BodySpilled, Some(detail));
+        }
+    }
+}
+
+fn read(spilled: &SpilledBody) -> Result<String, String> {
+    let bytes = std::fs::read(&spilled.path)
+        .map_err(|e| format!("Body is no longer available ({}): {}", spilled.path, e))?;
+    let bytes = match spilled.compressed_size {
+        Some(_) => zstd::decode_all(bytes.as_slice())
+            .map_err(|e| format!("Failed to decompress {}: {}", spilled.path, e))?,
+        None => bytes,
+    };
+    String::from_utf8(bytes).map_err(|e| format!("{} is not text: {}", spilled.path, e))
+}
+
+// The whole text of a body, or its preview when the spill file is gone
+pub(crate) fn full_text(body: Option<&Body>, spilled: Option<&SpilledBody>) -> Option<String> {
+    spilled
+        .and_then(|spilled| read(spilled).ok())
+        .or_else(|| body.map(|body| body.to_string()))
+}
+
+// Puts full bodies back in place of their previews, for exports. Bodies whose
+// files are gone keep their preview.
+pub fn restore(requests: &mut [NetworkRequest]) {
+    let restore_body = |body: &mut Option<Body>, spilled: &mut Option<SpilledBody>| {
+        if let Some(full) = spilled.as_ref().and_then(|s| read(s).ok()) {
+            *body = Some(full.into());
+            *spilled = None;
+        }
+    };
+    for request in requests.iter_mut() {
+        restore_body(&mut request.body, &mut request.body_file);
+        if let Some(response) = request.response.as_mut() {
+            restore_body(&mut response.body, &mut response.body_file);
+        }
+    }
+}
+
+pub(crate) fn spilled_bodies(request: &NetworkRequest) -> impl Iterator<Item = &SpilledBody> {
+    let response = request.response.as_ref().and_then(|r| r.body_file.as_ref());
+    request.body_file.iter().chain(response)
+}
+
+// Everything that can hold a request pointing at a spill file. The archive
+// and the overflow segment aren't among them: bodies are read back before
+// requests go there, though not before they are queued for the segment.
+#[derive(Clone)]
+pub(crate) struct BodyHolders {
+    pub(crate) store: RequestStore,
+    pub(crate) sessions: SessionState,
+    pub(crate) latest: LatestState,
+    pub(crate) tombstones: TombstoneState,
+    pub(crate) store_limits: StoreLimitState,
+}
+
+// Deletes files no request points at any more: in the store, another
+// session, endpoint history, or cleared but still undoable
+fn sweep(blobs: &Blobs, holders: &BodyHolders) {
+    let live: HashSet<String> = {
+        // Sessions first, as ingest takes them, so a switch can't move
+        // requests between stores halfway through
+        let Ok(sessions) = holders.sessions.lock() else {
+            return;
+        };
+        let (Ok(requests), Ok(latest), Ok(tombstones)) = (
+            holders.store.lock(),
+            holders.latest.lock(),
+            holders.tombstones.lock(),
+        ) else {
+            return;
+        };
+        requests
+            .iter()
+            .chain(latest.history.values().flatten())
+            .flat_map(spilled_bodies)
+            .chain(tombstones.spilled_bodies())
+            .map(|spilled| spilled.path.clone())
+            .chain(sessions.parked_bodies())
+            .chain(limits::overflow(&holders.store_limits).queued_bodies())
+            .chain(limits::archive(&holders.store_limits).queued_bodies())
+            .collect()
+    };
+    let Ok(entries) = std::fs::read_dir(&blobs.dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !live.contains(path.to_string_lossy().as_ref()) {
+            std::fs::remove_file(&path).ok();
+        }
+    }
+}
+
+// Runs for the life of the app
+pub(crate) async fn sweeper(blobs: BlobStore, holders: BodyHolders) {
+    let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        let (blobs, holders) = (blobs.clone(), holders.clone());
+        tauri::async_runtime::spawn_blocking(move || sweep(&blobs, &holders))
+            .await
+            .ok();
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum BodyPart {
+    Request,
+    #[default]
+    Response,
+}
+
+// The full body, whether it was kept in memory or spilled to disk
+pub(crate) fn full_body(
+    store: &RequestStore,
+    store_limits: &StoreLimitState,
+    id: &str,
+    part: Option<BodyPart>,
+) -> Result<Option<String>, String> {
+    body_of(&limits::find_request(store_limits, store, id)?, part)
+}
+
+// The full body of a request already looked up
+pub(crate) fn body_of(
+    request: &NetworkRequest,
+    part: Option<BodyPart>,
+) -> Result<Option<String>, String> {
+    let (body, spilled) = match part.unwrap_or_default() {
+        BodyPart::Request => (request.body.as_ref(), request.body_file.as_ref()),
+        BodyPart::Response => match &request.response {
+            Some(response) => (response.body.as_ref(), response.body_file.as_ref()),
+            None => (None, None),
+        },
+    };
+    match spilled {
+        Some(spilled) => read(spilled).map(Some),
+        None => Ok(body.map(|body| body.to_string())),
+    }
+}
+
+#[tauri::command]
+pub async fn get_request_body(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    id: String,
+    part: Option<BodyPart>,
+) -> Result<Option<String>, String> {
+    full_body(&store, &store_limits, &id, part)
+}
+
+// The full body with whether the store only holds a preview of it
+#[tauri::command]
+pub async fn fetch_full_body(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    id: String,
+    part: Option<BodyPart>,
+) -> Result<FullBody, String> {
+    let request = limits::find_request(&store_limits, &store, &id)?;
+    let truncated = match part.unwrap_or_default() {
+        BodyPart::Request => request.body_file.is_some(),
+        BodyPart::Response => request
+            .response
+            .as_ref()
+            .is_some_and(|r| r.body_file.is_some()),
+    };
+    let body = body_of(&request, part)?;
+    Ok(FullBody {
+        size: body.as_ref().map_or(0, |body| body.len() as u64),
+        body,
+        truncated,
+    })
+}
+
+// Bodies captured from now on over `bytes` keep their first `bytes` in memory
+// and the rest on disk; ones already stored stay as they are
+#[tauri::command]
+pub async fn set_body_limit(
+    blob_store: tauri::State<'_, BlobStore>,
+    bytes: u64,
+) -> Result<BodyLimit, String> {
+    if bytes == 0 {
+        return Err("bytes must be at least 1".to_string());
+    }
+    let limit = BodyLimit {
+        spill_over_bytes: bytes,
+        preview_bytes: bytes,
+    };
+    *blob_store.limit.lock().map_err(|e| e.to_string())? = limit;
+    Ok(limit)
+}
+
+#[tauri::command]
+pub async fn get_body_limit(blob_store: tauri::State<'_, BlobStore>) -> Result<BodyLimit, String> {
+    Ok(*blob_store.limit.lock().map_err(|e| e.to_string())?)
+}
+
+#[tauri::command]
+pub async fn set_body_compression(
+    blob_store: tauri::State<'_, BlobStore>,
+    config: BodyCompression,
+) -> Result<BodyCompression, String> {
+    // A smaller body is kept whole as its own preview
+    let preview_bytes = blob_store
+        .limit
+        .lock()
+        .map_err(|e| e.to_string())?
+        .preview_bytes;
+    if config.min_bytes <= preview_bytes {
+        return Err(format!(
+            "min_bytes must be over {} bytes, the size of the preview kept in memory",
+            preview_bytes
+        ));
+    }
+    *blob_store.compression.lock().map_err(|e| e.to_string())? = config;
+    Ok(config)
+}
+
+#[tauri::command]
+pub async fn get_body_compression(
+    blob_store: tauri::State<'_, BlobStore>,
+) -> Result<BodyCompression, String> {
+    Ok(*blob_store.compression.lock().map_err(|e| e.to_string())?)
+}
+
+// Raw vs. stored size of the bodies moved out of memory
+#[tauri::command]
+pub async fn get_body_storage_stats(
+    store: tauri::State<'_, RequestStore>,
+) -> Result<BodyStorageStats, String> {
+    let requests = store.lock().map_err(|e| e.to_string())?;
+    let mut stats = BodyStorageStats {
+        spilled_bodies: 0,
+        compressed_bodies: 0,
+        raw_bytes: 0,
+        stored_bytes: 0,
+    };
+    for spilled in requests.iter().flat_map(spilled_bodies) {
+        stats.spilled_bodies += 1;
+        stats.raw_bytes += spilled.size;
+        if spilled.compressed_size.is_some() {
+            stats.compressed_bodies += 1;
+        }
+        stats.stored_bytes += spilled.compressed_size.unwrap_or(spilled.size);
+    }
+    Ok(stats)
+}
@@ -0,0 +1,210 @@
+// Bodies are immutable once stored and polling endpoints send the same payload
+// over and over, so every stored body goes through a table keyed by its
+// content: byte-identical bodies end up sharing one allocation.
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, Weak};
+use ts_rs::TS;
+
+use crate::limits::{self, StoreLimitState};
+use crate::{NetworkRequest, RequestStore};
+
+// Below this, the table entry costs about as much as it saves
+const MIN_SHARED_BYTES: usize = 64;
+
+// A request or response body. Reads like a `&str`; clones share the text.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Body(Arc<str>);
+
+impl Deref for Body {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Body {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl std::fmt::Display for Body {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<String> for Body {
+    fn from(text: String) -> Self {
+        Body(text.into())
+    }
+}
+
+impl From<&str> for Body {
+    fn from(text: &str) -> Self {
+        Body(text.into())
+    }
+}
+
+impl Serialize for Body {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Body {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Body::from)
+    }
+}
+
+fn content_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Content hash -> bodies with that hash still held by some entry. Weak, so a
+// body is freed with the last entry that uses it.
+#[derive(Default)]
+pub struct BodyTable {
+    bodies: HashMap<u64, Vec<Weak<str>>>,
+    // Size at which dead entries are swept next
+    sweep_at: usize,
+}
+
+impl BodyTable {
+    fn intern(&mut self, body: &mut Body) {
+        if body.len() < MIN_SHARED_BYTES {
+            return;
+        }
+        let candidates = self.bodies.entry(content_hash(body)).or_default();
+        for candidate in candidates.iter().filter_map(Weak::upgrade) {
+            // Equal hashes aren't proof; equal bytes are
+            if *candidate == *body.0 {
+                body.0 = candidate;
+                return;
+            }
+        }
+        candidates.push(Arc::downgrade(&body.0));
+
+        if self.bodies.len() > self.sweep_at {
+            self.bodies.retain(|_, candidates| {
+                candidates.retain(|candidate| candidate.strong_count() > 0);
+                !candidates.is_empty()
+            });
+            self.sweep_at = (self.bodies.len() * 2).max(1024);
+        }
+    }
+}
+
+// Points the request's bodies at the shared copy of the same content, if any
+pub fn intern(table: &mut BodyTable, request: &mut NetworkRequest) {
+    if let Some(body) = request.body.as_mut() {
+        table.intern(body);
+    }
+    if let Some(body) = request.response.as_mut().and_then(|r| r.body.as_mut()) {
+        table.intern(body);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct BodyReference {
+    // Same hash, same content; shared by every entry showing it
+    pub hash: String,
+    #[ts(type = "number")]
+    pub size: u64,
+    // Other entries in the store with this exact body
+    pub shared_with: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct BodyReferences {
+    pub request_id: String,
+    pub request: Option<BodyReference>,
+    pub response: Option<BodyReference>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct BodyDedupStats {
+    // Bodies in the store, counting each entry's
+    pub bodies: usize,
+    pub unique_bodies: usize,
+    // Text held in memory, and what it would take without sharing
+    #[ts(type = "number")]
+    pub stored_bytes: u64,
+    #[ts(type = "number")]
+    pub referenced_bytes: u64,
+}
+
+pub(crate) fn bodies(request: &NetworkRequest) -> impl Iterator<Item = &Body> {
+    let response = request.response.as_ref().and_then(|r| r.body.as_ref());
+    request.body.iter().chain(response)
+}
+
+fn reference(requests: &[NetworkRequest], id: &str, body: &Body) -> BodyReference {
+    BodyReference {
+        hash: format!("{:016x}", content_hash(body)),
+        size: body.len() as u64,
+        shared_with: requests
+            .iter()
+            .filter(|r| r.id != id && bodies(r).any(|other| Arc::ptr_eq(&other.0, &body.0)))
+            .map(|r| r.id.clone())
+            .collect(),
+    }
+}
+
+#[tauri::command]
+pub async fn get_body_references(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    id: String,
+) -> Result<BodyReferences, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    let request = requests
+        .iter()
+        .find(|r| r.has_id(&id))
+        .ok_or_else(|| format!("Request not found: {}", id))?;
+    Ok(BodyReferences {
+        request_id: request.id.clone(),
+        request: request
+            .body
+            .as_ref()
+            .map(|body| reference(&requests, &request.id, body)),
+        response: request
+            .response
+            .as_ref()
+            .and_then(|r| r.body.as_ref())
+            .map(|body| reference(&requests, &request.id, body)),
+    })
+}
+
+#[tauri::command]
+pub async fn get_body_dedup_stats(
+    store: tauri::State<'_, RequestStore>,
+) -> Result<BodyDedupStats, String> {
+    let requests = store.lock().map_err(|e| e.to_string())?;
+    let mut unique: HashMap<*const u8, usize> = HashMap::new();
+    let mut stats = BodyDedupStats {
+        bodies: 0,
+        unique_bodies: 0,
+        stored_bytes: 0,
+        referenced_bytes: 0,
+    };
+    for body in requests.iter().flat_map(bodies) {
+        stats.bodies += 1;
+        stats.referenced_bytes += body.len() as u64;
+        unique.insert(body.0.as_ptr(), body.len());
+    }
+    stats.unique_bodies = unique.len();
+    stats.stored_bytes = unique.values().map(|&len| len as u64).sum();
+    Ok(stats)
+}
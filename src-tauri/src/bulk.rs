@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use ts_rs::TS;
+
+use crate::blobs;
+use crate::events::{self, AppEvent};
+use crate::export::anonymize::{anonymize, AnonymizeProfile};
+use crate::filter::RequestFilter;
+use crate::index::{self, IndexState};
+use crate::limits::{self, StoreLimitState};
+use crate::migrate::envelope;
+use crate::overflow;
+use crate::provenance::{self, ProvenanceKind};
+use crate::storage::{self, StorageState};
+use crate::{NetworkRequest, RequestStore};
+
+// Which requests a bulk command applies to: `{"ids": [...]}` or `{"filter": {...}}`
+#[derive(Debug, Clone, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum Selection {
+    Ids(Vec<String>),
+    Filter(Box<RequestFilter>),
+}
+
+pub enum SelectionMatcher<'a> {
+    Ids(HashSet<&'a str>),
+    Filter(&'a RequestFilter),
+}
+
+impl Selection {
+    pub fn matcher(&self) -> SelectionMatcher<'_> {
+        match self {
+            Selection::Ids(ids) => SelectionMatcher::Ids(ids.iter().map(String::as_str).collect()),
+            Selection::Filter(filter) => SelectionMatcher::Filter(filter),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Selection::Ids(_) => Ok(()),
+            Selection::Filter(filter) => filter.validate(),
+        }
+    }
+}
+
+impl SelectionMatcher<'_> {
+    pub fn matches(&self, request: &NetworkRequest) -> bool {
+        match self {
+            SelectionMatcher::Ids(ids) => {
+                ids.contains(request.id.as_str())
+                    || request
+                        .aliases
+                        .iter()
+                        .any(|alias| ids.contains(alias.as_str()))
+            }
+            SelectionMatcher::Filter(filter) => filter.matches(request),
+        }
+    }
+}
+
+// Positions of the selected requests in store order, through the index for a filter
+fn select(index: &IndexState, requests: &[NetworkRequest], selection: &Selection) -> Vec<usize> {
+    match selection {
+        Selection::Filter(filter) => index::matching(index, requests, filter),
+        Selection::Ids(_) => {
+            let matcher = selection.matcher();
+            (0..requests.len())
+                .filter(|&i| matcher.matches(&requests[i]))
+                .collect()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct BulkResult {
+    pub ids: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn tag_many(
+    store: tauri::State<'_, RequestStore>,
+    index: tauri::State<'_, IndexState>,
+    app_handle: tauri::AppHandle,
+    selection: Selection,
+    add: Option<Vec<String>>,
+    remove: Option<Vec<String>>,
+) -> Result<BulkResult, String> {
+    selection.validate()?;
+    let add = add.unwrap_or_default();
+    let remove = remove.unwrap_or_default();
+
+    let mut ids = Vec::new();
+    {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        for i in select(&index, &requests, &selection) {
+            let request = &mut requests[i];
+            request.tags.retain(|tag| !remove.contains(tag));
+            for tag in &add {
+                if !request.tags.contains(tag) {
+                    request.tags.push(tag.clone());
+                }
+            }
+            let changes: Vec<String> = add
+                .iter()
+                .map(|tag| format!("+{}", tag))
+                .chain(remove.iter().map(|tag| format!("-{}", tag)))
+                .collect();
+            provenance::record(request, ProvenanceKind::Retagged, Some(changes.join(" ")));
+            ids.push(request.id.clone());
+        }
+    }
+
+    let result = BulkResult { ids };
+    events::emit(&app_handle, AppEvent::RequestsUpdated(&result))?;
+    Ok(result)
+}
+
+fn delete(
+    store: &RequestStore,
+    storage: &StorageState,
+    app_handle: &tauri::AppHandle,
+    selection: Selection,
+) -> Result<BulkResult, String> {
+    selection.validate()?;
+    let matcher = selection.matcher();
+
+    let mut ids = Vec::new();
+    let remaining = {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        requests.retain(|request| {
+            let selected = matcher.matches(request);
+            if selected {
+                ids.push(request.id.clone());
+            }
+            !selected
+        });
+        requests.clone()
+    };
+    storage::replace_all(storage, &remaining);
+
+    let result = BulkResult { ids };
+    events::emit(app_handle, AppEvent::RequestsDeleted(&result))?;
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn delete_many(
+    store: tauri::State<'_, RequestStore>,
+    app_handle: tauri::AppHandle,
+    storage: tauri::State<'_, StorageState>,
+    selection: Selection,
+) -> Result<BulkResult, String> {
+    delete(&store, &storage, &app_handle, selection)
+}
+
+#[tauri::command]
+pub async fn delete_request(
+    store: tauri::State<'_, RequestStore>,
+    app_handle: tauri::AppHandle,
+    storage: tauri::State<'_, StorageState>,
+    id: String,
+) -> Result<BulkResult, String> {
+    let result = delete(
+        &store,
+        &storage,
+        &app_handle,
+        Selection::Ids(vec![id.clone()]),
+    )?;
+    if result.ids.is_empty() {
+        return Err(format!("Request not found: {}", id));
+    }
+    Ok(result)
+}
+
+// Ids that aren't in the store are skipped
+#[tauri::command]
+pub async fn delete_requests(
+    store: tauri::State<'_, RequestStore>,
+    app_handle: tauri::AppHandle,
+    storage: tauri::State<'_, StorageState>,
+    ids: Vec<String>,
+) -> Result<BulkResult, String> {
+    delete(&store, &storage, &app_handle, Selection::Ids(ids))
+}
+
+// For pruning noise such as health checks or analytics beacons
+#[tauri::command]
+pub async fn delete_matching(
+    store: tauri::State<'_, RequestStore>,
+    app_handle: tauri::AppHandle,
+    storage: tauri::State<'_, StorageState>,
+    filter: RequestFilter,
+) -> Result<BulkResult, String> {
+    delete(
+        &store,
+        &storage,
+        &app_handle,
+        Selection::Filter(Box::new(filter)),
+    )
+}
+
+// Writes the selected requests in a `migrate::envelope` and returns how many
+// were exported
+#[tauri::command]
+pub async fn export_many(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    index: tauri::State<'_, IndexState>,
+    selection: Selection,
+    path: String,
+    anonymize_with: Option<AnonymizeProfile>,
+) -> Result<usize, String> {
+    selection.validate()?;
+    let live: Vec<NetworkRequest> = {
+        let requests = store.lock().map_err(|e| e.to_string())?;
+        select(&index, &requests, &selection)
+            .into_iter()
+            .map(|i| requests[i].clone())
+            .collect()
+    };
+    let matcher = selection.matcher();
+    let overflow = limits::overflow(&store_limits);
+    let mut selected = overflow::with_matching(&overflow, live, |r| matcher.matches(r));
+    blobs::restore(&mut selected);
+    anonymize(&mut selected, anonymize_with);
+    let json = serde_json::to_string_pretty(&envelope(&selected)).map_err(|e| e.to_string())?;
+    let count = selected.len();
+
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    Ok(count)
+}
@@ -0,0 +1,380 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::events::{self, AppEvent, EventSink, Events};
+use crate::filter::RequestFilter;
+use crate::markers::now_millis;
+use crate::NetworkRequest;
+
+// Pauses capture the moment an interesting request shows up, e.g. the first 500
+// (`{"filter": {"status_min": 500}}`) or the first call to an endpoint
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct BreakRule {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub filter: RequestFilter,
+    // Disarm after the first break instead of pausing again on every match
+    #[serde(default = "default_once")]
+    pub once: bool,
+    #[serde(default)]
+    pub triggered: bool,
+}
+
+fn default_once() -> bool {
+    true
+}
+
+// How often the schedule is checked for windows due to open or close
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum WindowState {
+    #[default]
+    Waiting,
+    Capturing,
+    Done,
+}
+
+// A stretch of time to capture in, for catching what only happens during a
+// particular job, e.g. `{"start_at": <02:00 tonight>, "duration_ms": 1800000}`
+// or `{"start_filter": {"url_contains": "/sync/start"}, "duration_ms": 60000}`.
+// While a schedule is set capture is paused outside its windows; without a
+// stop the window stays open until capture is paused by hand.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct CaptureWindow {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    // Opens at this time, in epoch milliseconds, or with the first request
+    // matching `start_filter`, which is captured
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub start_at: Option<u64>,
+    #[serde(default)]
+    pub start_filter: Option<RequestFilter>,
+    // Closes at this time, or this long after opening
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub stop_at: Option<u64>,
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub duration_ms: Option<u64>,
+    #[serde(default)]
+    pub state: WindowState,
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub started_at: Option<u64>,
+}
+
+impl CaptureWindow {
+    fn ends_by(&self, now: u64) -> bool {
+        let stop = self.stop_at.into_iter().chain(
+            self.started_at
+                .zip(self.duration_ms)
+                .map(|(started, duration)| started.saturating_add(duration)),
+        );
+        stop.min().is_some_and(|stop| stop <= now)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum PauseReason {
+    Manual,
+    // Outside the capture schedule's windows
+    Scheduled,
+    Break {
+        rule_id: String,
+        rule_name: Option<String>,
+        request_id: String,
+        url: String,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct CaptureStatus {
+    pub paused: bool,
+    #[ts(type = "number | null")]
+    pub paused_at: Option<u64>,
+    pub reason: Option<PauseReason>,
+    // Requests that arrived while paused and were not stored
+    #[ts(type = "number")]
+    pub dropped: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct CaptureControl {
+    pub status: CaptureStatus,
+    pub break_rules: Vec<BreakRule>,
+    pub schedule: Vec<CaptureWindow>,
+}
+
+pub type CaptureState = Arc<Mutex<CaptureControl>>;
+
+// Whether an incoming request should be stored; counts it as dropped otherwise.
+// `request` may open a window of the schedule waiting for it.
+pub fn accept(
+    capture: &CaptureState,
+    request: Option<&NetworkRequest>,
+    sink: &dyn EventSink,
+) -> bool {
+    let (accepted, changed) = {
+        let mut capture = capture.lock().unwrap();
+        let changed = advance(&mut capture, now_millis(), request);
+        if capture.status.paused {
+            capture.status.dropped += 1;
+        }
+        (!capture.status.paused, changed)
+    };
+    if let Some(status) = changed {
+        announce(sink, &status);
+    }
+    accepted
+}
+
+fn pause(control: &mut CaptureControl, reason: PauseReason) {
+    control.status = CaptureStatus {
+        paused: true,
+        paused_at: Some(now_millis()),
+        reason: Some(reason),
+        dropped: 0,
+    };
+}
+
+fn resume(control: &mut CaptureControl) {
+    let dropped = control.status.dropped;
+    control.status = CaptureStatus {
+        dropped,
+        ..CaptureStatus::default()
+    };
+}
+
+fn announce(sink: &dyn EventSink, status: &CaptureStatus) {
+    let event = if status.paused {
+        AppEvent::CapturePaused(status)
+    } else {
+        AppEvent::CaptureResumed(status)
+    };
+    if let Err(e) = events::emit(sink, event) {
+        println!("Failed to emit capture event: {}", e);
+    }
+}
+
+// Opens and closes the windows due at `now`, or started by `trigger`, and
+// pauses or resumes capture to match. Only a pause the schedule made is
+// resumed. Returns the status when it changed.
+fn advance(
+    control: &mut CaptureControl,
+    now: u64,
+    trigger: Option<&NetworkRequest>,
+) -> Option<CaptureStatus> {
+    let mut closed = false;
+    for window in control.schedule.iter_mut() {
+        if window.state == WindowState::Waiting {
+            let due = window.start_at.is_some_and(|at| at <= now);
+            let triggered = trigger
+                .zip(window.start_filter.as_ref())
+                .is_some_and(|(request, filter)| filter.matches(request));
+            if due || triggered {
+                window.state = WindowState::Capturing;
+                window.started_at = Some(now);
+            }
+        }
+        if window.state == WindowState::Capturing && window.ends_by(now) {
+            window.state = WindowState::Done;
+            closed = true;
+        }
+    }
+    let capturing = control
+        .schedule
+        .iter()
+        .any(|w| w.state == WindowState::Capturing);
+    let scheduled_pause = matches!(control.status.reason, Some(PauseReason::Scheduled));
+    if capturing && control.status.paused && scheduled_pause {
+        resume(control);
+        return Some(control.status.clone());
+    }
+    if !capturing && closed && !control.status.paused {
+        pause(control, PauseReason::Scheduled);
+        return Some(control.status.clone());
+    }
+    None
+}
+
+// Runs for the life of the app, opening and closing timed windows
+pub(crate) async fn scheduler(capture: CaptureState, sink: Events) {
+    let mut interval = tokio::time::interval(SCHEDULE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let changed = {
+            let mut control = capture.lock().unwrap();
+            if control.schedule.is_empty() {
+                continue;
+            }
+            advance(&mut control, now_millis(), None)
+        };
+        if let Some(status) = changed {
+            announce(&*sink, &status);
+        }
+    }
+}
+
+// Checks a newly stored request against the break rules and pauses capture on the first match
+pub fn check_break_rules(capture: &CaptureState, request: &NetworkRequest, sink: &dyn EventSink) {
+    let status = {
+        let mut control = capture.lock().unwrap();
+        let Some(rule) = control
+            .break_rules
+            .iter_mut()
+            .find(|rule| !rule.triggered && rule.filter.matches(request))
+        else {
+            return;
+        };
+        if rule.once {
+            rule.triggered = true;
+        }
+        let reason = PauseReason::Break {
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            request_id: request.id.clone(),
+            url: request.url.clone(),
+        };
+        pause(&mut control, reason);
+        control.status.clone()
+    };
+
+    println!(
+        "Break rule hit, capture paused at {} {}",
+        request.method, request.url
+    );
+    if let Err(e) = events::emit(sink, AppEvent::CapturePaused(&status)) {
+        println!("Failed to emit capture-paused event: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn pause_capture(
+    capture: tauri::State<'_, CaptureState>,
+    app_handle: tauri::AppHandle,
+) -> Result<CaptureStatus, String> {
+    let status = {
+        let mut control = capture.lock().map_err(|e| e.to_string())?;
+        pause(&mut control, PauseReason::Manual);
+        control.status.clone()
+    };
+    events::emit(&app_handle, AppEvent::CapturePaused(&status))?;
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn resume_capture(
+    capture: tauri::State<'_, CaptureState>,
+    app_handle: tauri::AppHandle,
+) -> Result<CaptureStatus, String> {
+    let status = {
+        let mut control = capture.lock().map_err(|e| e.to_string())?;
+        resume(&mut control);
+        control.status.clone()
+    };
+    events::emit(&app_handle, AppEvent::CaptureResumed(&status))?;
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn get_capture_status(
+    capture: tauri::State<'_, CaptureState>,
+) -> Result<CaptureStatus, String> {
+    let control = capture.lock().map_err(|e| e.to_string())?;
+    Ok(control.status.clone())
+}
+
+#[tauri::command]
+pub async fn set_break_rules(
+    capture: tauri::State<'_, CaptureState>,
+    rules: Vec<BreakRule>,
+) -> Result<Vec<BreakRule>, String> {
+    let mut control = capture.lock().map_err(|e| e.to_string())?;
+    control.break_rules = rules
+        .into_iter()
+        .map(|mut rule| {
+            if rule.id.is_empty() {
+                rule.id = Uuid::new_v4().to_string();
+            }
+            rule
+        })
+        .collect();
+    Ok(control.break_rules.clone())
+}
+
+#[tauri::command]
+pub async fn get_break_rules(
+    capture: tauri::State<'_, CaptureState>,
+) -> Result<Vec<BreakRule>, String> {
+    let control = capture.lock().map_err(|e| e.to_string())?;
+    Ok(control.break_rules.clone())
+}
+
+// Replaces the schedule. Capture pauses until a window opens, unless one is
+// open already; an empty schedule leaves capture as it is.
+#[tauri::command]
+pub async fn set_capture_schedule(
+    capture: tauri::State<'_, CaptureState>,
+    app_handle: tauri::AppHandle,
+    windows: Vec<CaptureWindow>,
+) -> Result<Vec<CaptureWindow>, String> {
+    for window in &windows {
+        if window.start_at.is_none() && window.start_filter.is_none() {
+            return Err("A capture window needs start_at or start_filter".to_string());
+        }
+    }
+    let (schedule, changed) = {
+        let mut control = capture.lock().map_err(|e| e.to_string())?;
+        control.schedule = windows
+            .into_iter()
+            .map(|mut window| {
+                if window.id.is_empty() {
+                    window.id = Uuid::new_v4().to_string();
+                }
+                window
+            })
+            .collect();
+        let mut changed = advance(&mut control, now_millis(), None);
+        let open = control
+            .schedule
+            .iter()
+            .any(|w| w.state == WindowState::Capturing);
+        let waiting = control
+            .schedule
+            .iter()
+            .any(|w| w.state == WindowState::Waiting);
+        if waiting && !open && !control.status.paused {
+            pause(&mut control, PauseReason::Scheduled);
+            changed = Some(control.status.clone());
+        }
+        (control.schedule.clone(), changed)
+    };
+    if let Some(status) = changed {
+        announce(&app_handle, &status);
+    }
+    Ok(schedule)
+}
+
+#[tauri::command]
+pub async fn get_capture_schedule(
+    capture: tauri::State<'_, CaptureState>,
+) -> Result<Vec<CaptureWindow>, String> {
+    let control = capture.lock().map_err(|e| e.to_string())?;
+    Ok(control.schedule.clone())
+}
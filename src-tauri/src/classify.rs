@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use ts_rs::TS;
+
+use crate::limits::{self, StoreLimitState};
+use crate::{Headers, NetworkRequest, NetworkResponse, RequestStore};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub enum ContentClass {
+    Json,
+    Html,
+    Xml,
+    #[serde(rename = "javascript")]
+    JavaScript,
+    Css,
+    Text,
+    Image,
+    Font,
+    Video,
+    Audio,
+    Binary,
+    Empty,
+}
+
+// Magic numbers as they appear once a binary body has been forced through a UTF-8 string,
+// plus the base64 prefixes for clients that ship binary bodies encoded
+const SIGNATURES: &[(&str, ContentClass)] = &[
+    ("PNG\r\n", ContentClass::Image),
+    ("GIF87a", ContentClass::Image),
+    ("GIF89a", ContentClass::Image),
+    ("JFIF", ContentClass::Image),
+    ("Exif", ContentClass::Image),
+    ("WEBP", ContentClass::Image),
+    ("wOFF", ContentClass::Font),
+    ("wOF2", ContentClass::Font),
+    ("OTTO", ContentClass::Font),
+    ("ftyp", ContentClass::Video),
+    ("\u{1a}E\u{fffd}\u{fffd}", ContentClass::Video),
+    ("ID3", ContentClass::Audio),
+    ("OggS", ContentClass::Audio),
+    ("%PDF", ContentClass::Binary),
+    ("PK\u{3}\u{4}", ContentClass::Binary),
+];
+
+const BASE64_SIGNATURES: &[(&str, ContentClass)] = &[
+    ("iVBORw0KGgo", ContentClass::Image),
+    ("/9j/", ContentClass::Image),
+    ("R0lGOD", ContentClass::Image),
+    ("UklGR", ContentClass::Image),
+    ("d09GRg", ContentClass::Font),
+    ("d09GMg", ContentClass::Font),
+    ("AAAAIGZ0eXA", ContentClass::Video),
+    ("AAAAGGZ0eXA", ContentClass::Video),
+];
+
+const JS_PREFIXES: &[&str] = &[
+    "function",
+    "(function",
+    "!function",
+    "var ",
+    "let ",
+    "const ",
+    "import ",
+    "export ",
+    "\"use strict\"",
+    "'use strict'",
+    "(()=>",
+    "(() =>",
+    "window.",
+    "self.",
+];
+
+pub fn class_from_content_type(content_type: &str) -> Option<ContentClass> {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if mime.is_empty() {
+        return None;
+    }
+
+    let class = if mime.ends_with("json") || mime.ends_with("+json") {
+        ContentClass::Json
+    } else if mime == "text/html" || mime == "application/xhtml+xml" {
+        ContentClass::Html
+    } else if mime == "image/svg+xml" || mime.starts_with("image/") {
+        ContentClass::Image
+    } else if mime.ends_with("xml") {
+        ContentClass::Xml
+    } else if mime.contains("javascript") || mime.contains("ecmascript") {
+        ContentClass::JavaScript
+    } else if mime == "text/css" {
+        ContentClass::Css
+    } else if mime.starts_with("font/") || mime.contains("font-") {
+        ContentClass::Font
+    } else if mime.starts_with("video/") {
+        ContentClass::Video
+    } else if mime.starts_with("audio/") {
+        ContentClass::Audio
+    } else if mime.starts_with("text/") || mime == "application/x-www-form-urlencoded" {
+        ContentClass::Text
+    } else {
+        ContentClass::Binary
+    };
+    Some(class)
+}
+
+fn sniff_body(body: &str) -> Option<ContentClass> {
+    // Signatures sit in the first few bytes; looking further invites false positives from text
+    let head: String = body.chars().take(16).collect();
+    for (signature, class) in SIGNATURES {
+        if head.contains(signature) {
+            return Some(*class);
+        }
+    }
+    for (signature, class) in BASE64_SIGNATURES {
+        if body.starts_with(signature) {
+            return Some(*class);
+        }
+    }
+
+    let trimmed = body.trim_start_matches('\u{feff}').trim_start();
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        return Some(ContentClass::Json);
+    }
+
+    let lower: String = trimmed
+        .chars()
+        .take(256)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if lower.starts_with("<!doctype html") || lower.starts_with("<html") || lower.contains("<body")
+    {
+        return Some(ContentClass::Html);
+    }
+    if lower.starts_with("<svg") || (lower.starts_with("<?xml") && lower.contains("<svg")) {
+        return Some(ContentClass::Image);
+    }
+    if lower.starts_with("<?xml") || (lower.starts_with('<') && lower.contains("</")) {
+        return Some(ContentClass::Xml);
+    }
+    if JS_PREFIXES.iter().any(|prefix| lower.starts_with(prefix)) {
+        return Some(ContentClass::JavaScript);
+    }
+
+    // Replacement characters and control bytes mean the body was binary before it became a string
+    let sample: Vec<char> = body.chars().take(512).collect();
+    let garbage = sample
+        .iter()
+        .filter(|c| **c == '\u{fffd}' || (c.is_control() && !c.is_whitespace()))
+        .count();
+    if garbage * 10 > sample.len() {
+        return Some(ContentClass::Binary);
+    }
+    None
+}
+
+// Content wins over the declared type when it is recognisable; the declared type
+// only decides between classes the body itself gives no evidence for
+pub fn classify_body(headers: &Headers, body: Option<&str>) -> ContentClass {
+    let declared = headers
+        .get("content-type")
+        .and_then(class_from_content_type);
+
+    let body = match body {
+        Some(body) if !body.is_empty() => body,
+        _ => return ContentClass::Empty,
+    };
+
+    match (sniff_body(body), declared) {
+        (Some(sniffed), _) => sniffed,
+        (None, Some(declared)) if declared != ContentClass::Binary => declared,
+        _ => ContentClass::Text,
+    }
+}
+
+pub fn classify_response(response: &NetworkResponse) -> ContentClass {
+    classify_body(&response.headers, response.body.as_deref())
+}
+
+pub fn classify_request(request: &mut NetworkRequest) {
+    if let Some(response) = request.response.as_mut() {
+        response.content_class = Some(classify_response(response));
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ContentClassStats {
+    pub class: ContentClass,
+    pub count: usize,
+    pub total_bytes: usize,
+    // Responses whose content-type header names a different class than the body
+    pub mismatched: usize,
+}
+
+#[tauri::command]
+pub async fn get_content_class_stats(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+) -> Result<Vec<ContentClassStats>, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    let mut stats: HashMap<ContentClass, ContentClassStats> = HashMap::new();
+
+    for response in requests.iter().filter_map(|r| r.response.as_ref()) {
+        let class = match response.content_class {
+            Some(class) => class,
+            None => classify_response(response),
+        };
+        let entry = stats.entry(class).or_insert(ContentClassStats {
+            class,
+            count: 0,
+            total_bytes: 0,
+            mismatched: 0,
+        });
+        entry.count += 1;
+        entry.total_bytes += response.body.as_ref().map(|b| b.len()).unwrap_or(0);
+        let declared = response
+            .headers
+            .get("content-type")
+            .and_then(class_from_content_type);
+        if declared.is_some_and(|declared| declared != class) && class != ContentClass::Empty {
+            entry.mismatched += 1;
+        }
+    }
+
+    let mut stats: Vec<ContentClassStats> = stats.into_values().collect();
+    stats.sort_by_key(|s| std::cmp::Reverse(s.count));
+    Ok(stats)
+}
+
+#[tauri::command]
+pub async fn get_requests_by_content_class(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    class: ContentClass,
+) -> Result<Vec<NetworkRequest>, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    Ok(requests
+        .iter()
+        .filter(|r| {
+            r.response
+                .as_ref()
+                .and_then(|response| response.content_class)
+                == Some(class)
+        })
+        .cloned()
+        .collect())
+}
@@ -0,0 +1,172 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::events::{self, AppEvent, EventSink};
+use crate::limits::{self, StoreLimitState};
+use crate::markers::now_millis;
+use crate::protocol::Hello;
+use crate::{NetworkRequest, RequestStore};
+
+// One ingest connection, as passed through message handling
+#[derive(Debug, Clone)]
+pub struct Client {
+    pub id: String,
+    pub peer: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ClientStats {
+    pub id: String,
+    pub peer: String,
+    #[ts(type = "number")]
+    pub connected_at: u64,
+    // Filled in by `hello`; clients on protocol 0 never send one
+    pub client: Option<String>,
+    pub client_version: Option<String>,
+    pub protocol: u32,
+    #[ts(type = "number")]
+    pub messages_received: u64,
+    #[ts(type = "number")]
+    pub bytes_received: u64,
+    #[ts(type = "number")]
+    pub parse_failures: u64,
+    #[ts(type = "number | null")]
+    pub last_message_at: Option<u64>,
+    #[ts(type = "number")]
+    pub commands_sent: u64,
+    // Commands dropped from this client's queue because it fell behind
+    #[ts(type = "number")]
+    pub commands_lagged: u64,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ClientLagWarning {
+    pub client_id: String,
+    pub peer: String,
+    #[ts(type = "number")]
+    pub dropped: u64,
+    #[ts(type = "number")]
+    pub total_dropped: u64,
+    // Commands still waiting to be written to the client
+    pub queued: usize,
+}
+
+pub type ClientRegistry = Arc<Mutex<HashMap<String, ClientStats>>>;
+
+pub fn connect(registry: &ClientRegistry, peer: &str) -> Client {
+    let client = Client {
+        id: Uuid::new_v4().to_string(),
+        peer: peer.to_string(),
+    };
+    let stats = ClientStats {
+        id: client.id.clone(),
+        peer: client.peer.clone(),
+        connected_at: now_millis(),
+        client: None,
+        client_version: None,
+        protocol: 0,
+        messages_received: 0,
+        bytes_received: 0,
+        parse_failures: 0,
+        last_message_at: None,
+        commands_sent: 0,
+        commands_lagged: 0,
+    };
+    registry.lock().unwrap().insert(client.id.clone(), stats);
+    client
+}
+
+pub fn disconnect(registry: &ClientRegistry, client: &Client) {
+    registry.lock().unwrap().remove(&client.id);
+}
+
+fn update(registry: &ClientRegistry, client: &Client, f: impl FnOnce(&mut ClientStats)) {
+    if let Some(stats) = registry.lock().unwrap().get_mut(&client.id) {
+        f(stats);
+    }
+}
+
+pub fn record_message(registry: &ClientRegistry, client: &Client, bytes: usize) {
+    update(registry, client, |stats| {
+        stats.messages_received += 1;
+        stats.bytes_received += bytes as u64;
+        stats.last_message_at = Some(now_millis());
+    });
+}
+
+pub fn record_parse_failure(registry: &ClientRegistry, client: &Client) {
+    update(registry, client, |stats| stats.parse_failures += 1);
+}
+
+pub fn record_hello(registry: &ClientRegistry, client: &Client, hello: &Hello) {
+    update(registry, client, |stats| {
+        stats.client = hello.client.clone();
+        stats.client_version = hello.client_version.clone();
+        stats.protocol = hello.protocol;
+    });
+}
+
+pub fn record_command_sent(registry: &ClientRegistry, client: &Client) {
+    update(registry, client, |stats| stats.commands_sent += 1);
+}
+
+// Counts commands lost to a lagging client and warns the frontend
+pub fn record_lag(
+    registry: &ClientRegistry,
+    client: &Client,
+    dropped: u64,
+    queued: usize,
+    sink: &dyn EventSink,
+) {
+    let mut total_dropped = dropped;
+    update(registry, client, |stats| {
+        stats.commands_lagged += dropped;
+        total_dropped = stats.commands_lagged;
+    });
+    println!(
+        "Client {} fell behind, {} commands dropped, {} queued",
+        client.peer, dropped, queued
+    );
+
+    let warning = ClientLagWarning {
+        client_id: client.id.clone(),
+        peer: client.peer.clone(),
+        dropped,
+        total_dropped,
+        queued,
+    };
+    if let Err(e) = events::emit(sink, AppEvent::ClientLagging(&warning)) {
+        println!("Failed to emit client-lagging event: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn list_clients(
+    registry: tauri::State<'_, ClientRegistry>,
+) -> Result<Vec<ClientStats>, String> {
+    let registry = registry.lock().map_err(|e| e.to_string())?;
+    let mut clients: Vec<ClientStats> = registry.values().cloned().collect();
+    clients.sort_by_key(|c| c.connected_at);
+    Ok(clients)
+}
+
+// What one connection sent, in capture order. Works after it disconnected too,
+// for as long as its requests are in the store.
+#[tauri::command]
+pub async fn get_requests_for_client(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    client_id: String,
+) -> Result<Vec<NetworkRequest>, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    Ok(requests
+        .iter()
+        .filter(|r| r.client_id.as_deref() == Some(client_id.as_str()))
+        .cloned()
+        .collect())
+}
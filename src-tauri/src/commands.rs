@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use ts_rs::TS;
+
+use crate::clients::Client;
+
+// What happens to a command when a client's queue is already `capacity` long
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum OverflowPolicy {
+    // The sender waits until every client has room, so nothing is lost but a
+    // stalled client holds up the rest
+    Block,
+    // The oldest queued command is dropped and the client reported as lagging
+    #[default]
+    DropOldest,
+    // Every client keeps an unbounded queue of its own; past `capacity` the
+    // client is reported as lagging but nothing is dropped
+    Queue,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct CommandChannelConfig {
+    pub capacity: usize,
+    #[serde(default)]
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for CommandChannelConfig {
+    fn default() -> Self {
+        CommandChannelConfig {
+            capacity: 100,
+            overflow: OverflowPolicy::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    commands: VecDeque<String>,
+    // Dropped since the receiver last looked, reported with its next `recv`
+    dropped: u64,
+    backlogged: bool,
+    closed: bool,
+}
+
+// Commands waiting to be written to one client's socket
+#[derive(Debug, Default)]
+pub struct ClientQueue {
+    state: Mutex<QueueState>,
+    available: Notify,
+    space: Notify,
+}
+
+pub enum Received {
+    Command(String),
+    // Commands were dropped (`DropOldest`) or are piling up (`Queue`)
+    Lagged { dropped: u64, queued: usize },
+}
+
+impl ClientQueue {
+    pub async fn recv(&self) -> Received {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.dropped > 0 || state.backlogged {
+                    let dropped = std::mem::take(&mut state.dropped);
+                    state.backlogged = false;
+                    return Received::Lagged {
+                        dropped,
+                        queued: state.commands.len(),
+                    };
+                }
+                if let Some(command) = state.commands.pop_front() {
+                    self.space.notify_one();
+                    return Received::Command(command);
+                }
+            }
+            self.available.notified().await;
+        }
+    }
+}
+
+// Fan-out of commands from the UI to every connected client, replacing a
+// fixed-size broadcast channel that silently dropped for slow receivers
+#[derive(Debug, Default)]
+pub struct CommandChannel {
+    config: Mutex<CommandChannelConfig>,
+    queues: Mutex<HashMap<String, Arc<ClientQueue>>>,
+}
+
+pub type CommandBus = Arc<CommandChannel>;
+
+impl CommandChannel {
+    pub fn subscribe(&self, client: &Client) -> Arc<ClientQueue> {
+        let queue = Arc::new(ClientQueue::default());
+        self.queues
+            .lock()
+            .unwrap()
+            .insert(client.id.clone(), queue.clone());
+        queue
+    }
+
+    pub fn unsubscribe(&self, client: &Client) {
+        if let Some(queue) = self.queues.lock().unwrap().remove(&client.id) {
+            // Release a `Block` sender waiting on this client
+            queue.state.lock().unwrap().closed = true;
+            queue.space.notify_waiters();
+        }
+    }
+
+    // Queues `command` for every client, returning how many it was queued for
+    pub async fn send(&self, command: String) -> usize {
+        let config = *self.config.lock().unwrap();
+        let queues: Vec<Arc<ClientQueue>> = self.queues.lock().unwrap().values().cloned().collect();
+
+        for queue in &queues {
+            loop {
+                let space = queue.space.notified();
+                {
+                    let mut state = queue.state.lock().unwrap();
+                    if state.closed {
+                        break;
+                    }
+                    let full = state.commands.len() >= config.capacity;
+                    match config.overflow {
+                        OverflowPolicy::Block if full => {}
+                        OverflowPolicy::DropOldest if full => {
+                            state.commands.pop_front();
+                            state.dropped += 1;
+                            state.commands.push_back(command.clone());
+                            break;
+                        }
+                        OverflowPolicy::Queue if full => {
+                            // Warn once per stretch over capacity, not per command
+                            if state.commands.len() == config.capacity {
+                                state.backlogged = true;
+                            }
+                            state.commands.push_back(command.clone());
+                            break;
+                        }
+                        _ => {
+                            state.commands.push_back(command.clone());
+                            break;
+                        }
+                    }
+                }
+                space.await;
+            }
+            queue.available.notify_one();
+        }
+        queues.len()
+    }
+}
+
+#[tauri::command]
+pub async fn send_command(
+    bus: tauri::State<'_, CommandBus>,
+    command: serde_json::Value,
+) -> Result<usize, String> {
+    let command = serde_json::to_string(&command).map_err(|e| e.to_string())?;
+    Ok(bus.send(command).await)
+}
+
+#[tauri::command]
+pub async fn set_command_channel_config(
+    bus: tauri::State<'_, CommandBus>,
+    config: CommandChannelConfig,
+) -> Result<(), String> {
+    if config.capacity == 0 {
+        return Err("capacity must be at least 1".to_string());
+    }
+    *bus.config.lock().map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_command_channel_config(
+    bus: tauri::State<'_, CommandBus>,
+) -> Result<CommandChannelConfig, String> {
+    Ok(*bus.config.lock().map_err(|e| e.to_string())?)
+}
@@ -0,0 +1,238 @@
+// Settings that can be tuned while a capture is running, from `config.json` in
+// the data directory (or the file NETWORK_OBSERVER_CONFIG names) and from
+// `set_config`. The file is checked every second and applied as soon as it
+// changes, so adjusting a rule doesn't mean restarting and losing the capture.
+// The ingest port is not among them; it is set at startup, see
+// `server::ingest_addr`.
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use ts_rs::TS;
+
+use crate::bulk::BulkResult;
+use crate::events::{self, AppEvent, EventSink, Events};
+use crate::filter::RequestFilter;
+use crate::limits::{self, StoreLimitState, StoreLimits};
+use crate::rulepacks;
+use crate::storage::{self, StorageState};
+use crate::{NetworkRequest, RequestStore};
+
+pub const CONFIG_ENV: &str = "NETWORK_OBSERVER_CONFIG";
+const CONFIG_FILE: &str = "config.json";
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ObserverConfig {
+    // Header, query parameter and JSON body keys whose values are masked
+    #[serde(default)]
+    pub redact: Vec<String>,
+    // Requests matching any of these aren't stored
+    #[serde(default)]
+    pub ignore: Vec<RequestFilter>,
+    // Replaces the store limits when set; unset leaves them as they are
+    #[serde(default)]
+    pub limits: Option<StoreLimits>,
+    // Identical requests within this many seconds are merged
+    #[serde(default = "default_dedup_window")]
+    #[ts(type = "number")]
+    pub dedup_window_secs: u64,
+    // Identical requests after the dedup window are counted on the first
+    // one instead of stored again
+    #[serde(default)]
+    pub collapse_repeats: bool,
+    // Captures dropped in this directory are imported as new sessions
+    #[serde(default)]
+    pub import_dir: Option<String>,
+}
+
+fn default_dedup_window() -> u64 {
+    2
+}
+
+impl Default for ObserverConfig {
+    fn default() -> Self {
+        ObserverConfig {
+            redact: Vec::new(),
+            ignore: Vec::new(),
+            limits: None,
+            dedup_window_secs: default_dedup_window(),
+            collapse_repeats: false,
+            import_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ConfigMode {
+    pub config: ObserverConfig,
+    path: Option<PathBuf>,
+    // Of the file when it was last read or written
+    modified: Option<SystemTime>,
+}
+
+pub type ConfigState = Arc<Mutex<ConfigMode>>;
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn read(path: &Path) -> Result<ObserverConfig, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+}
+
+pub(crate) fn dedup_window(state: &ConfigState) -> u64 {
+    state.lock().unwrap().config.dedup_window_secs
+}
+
+pub(crate) fn collapse_repeats(state: &ConfigState) -> bool {
+    state.lock().unwrap().config.collapse_repeats
+}
+
+pub(crate) fn import_dir(state: &ConfigState) -> Option<PathBuf> {
+    let dir = state.lock().unwrap().config.import_dir.clone()?;
+    Some(PathBuf::from(dir))
+}
+
+pub(crate) fn ignored(state: &ConfigState, request: &NetworkRequest) -> bool {
+    let mode = state.lock().unwrap();
+    mode.config
+        .ignore
+        .iter()
+        .any(|filter| filter.matches(request))
+}
+
+pub(crate) fn redact(state: &ConfigState, request: &mut NetworkRequest) {
+    let names = state.lock().unwrap().config.redact.clone();
+    rulepacks::redact_names(request, &names);
+}
+
+// Makes `config` current: rules apply to the next request, new limits to the
+// store straight away
+pub fn apply(
+    state: &ConfigState,
+    store_limits: &StoreLimitState,
+    store: &RequestStore,
+    storage: &StorageState,
+    sink: &dyn EventSink,
+    config: ObserverConfig,
+) -> Result<(), String> {
+    if config.limits.as_ref().and_then(|l| l.max_requests) == Some(0) {
+        return Err("max_requests must be at least 1".to_string());
+    }
+    if let Some(new_limits) = &config.limits {
+        store_limits.lock().map_err(|e| e.to_string())?.limits = new_limits.clone();
+        let ids = {
+            let mut requests = store.lock().map_err(|e| e.to_string())?;
+            limits::enforce(store_limits, &mut requests)
+        };
+        storage::remove(storage, &ids);
+        if !ids.is_empty() {
+            events::emit(sink, AppEvent::RequestsDeleted(&BulkResult { ids }))?;
+        }
+    }
+    state.lock().map_err(|e| e.to_string())?.config = config.clone();
+    events::emit(sink, AppEvent::ConfigChanged(&config))
+}
+
+// Reads the config file, if there is one, before any traffic is processed
+pub fn load(
+    state: &ConfigState,
+    store_limits: &StoreLimitState,
+    store: &RequestStore,
+    storage: &StorageState,
+    sink: &dyn EventSink,
+    data_dir: &Path,
+) {
+    let path = std::env::var(CONFIG_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| data_dir.join(CONFIG_FILE));
+    {
+        let mut mode = state.lock().unwrap();
+        mode.path = Some(path.clone());
+        mode.modified = modified(&path);
+    }
+    if !path.exists() {
+        return;
+    }
+    match read(&path).and_then(|config| apply(state, store_limits, store, storage, sink, config)) {
+        Ok(()) => println!("Loaded config from {}", path.display()),
+        Err(e) => println!("Config not applied: {}", e),
+    }
+}
+
+// Applies the config file whenever it changes. A file that doesn't parse is
+// reported and the previous config stays in effect.
+pub async fn watch(
+    state: ConfigState,
+    store_limits: StoreLimitState,
+    store: RequestStore,
+    storage: StorageState,
+    sink: Events,
+) {
+    let mut interval = tokio::time::interval(WATCH_INTERVAL);
+    loop {
+        interval.tick().await;
+        let (path, changed) = {
+            let mut mode = state.lock().unwrap();
+            let Some(path) = mode.path.clone() else {
+                continue;
+            };
+            let current = modified(&path);
+            let changed = current.is_some() && current != mode.modified;
+            mode.modified = current;
+            (path, changed)
+        };
+        if !changed {
+            continue;
+        }
+        match read(&path)
+            .and_then(|config| apply(&state, &store_limits, &store, &storage, &*sink, config))
+        {
+            Ok(()) => println!("Reloaded config from {}", path.display()),
+            Err(e) => println!("Config not applied: {}", e),
+        }
+    }
+}
+
+// Applies the config and writes it to the config file, so it survives a restart
+#[tauri::command]
+pub async fn set_config(
+    state: tauri::State<'_, ConfigState>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    app_handle: tauri::AppHandle,
+    config: ObserverConfig,
+) -> Result<(), String> {
+    apply(
+        &state,
+        &store_limits,
+        &store,
+        &storage,
+        &app_handle,
+        config.clone(),
+    )?;
+
+    let mut mode = state.lock().map_err(|e| e.to_string())?;
+    let Some(path) = mode.path.clone() else {
+        return Ok(());
+    };
+    let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, json)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    // Already applied; the watcher needn't do it again
+    mode.modified = modified(&path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_config(state: tauri::State<'_, ConfigState>) -> Result<ObserverConfig, String> {
+    Ok(state.lock().map_err(|e| e.to_string())?.config.clone())
+}
@@ -0,0 +1,60 @@
+use serde_json::{Map, Value};
+
+use crate::limits::{self, StoreLimitState};
+use crate::stats::{group_stats, GroupStats};
+use crate::{NetworkRequest, RequestStore};
+
+// App-defined context attached by the client to each request, e.g.
+// `{ "route": "Checkout", "screen": "PaymentSheet", "flags": { "newCart": true } }`.
+// Keys are free-form; nested objects are addressed with dotted keys (`flags.newCart`).
+pub type RequestContext = Map<String, Value>;
+
+fn lookup<'a>(context: &'a RequestContext, key: &str) -> Option<&'a Value> {
+    let mut parts = key.split('.');
+    let mut value = context.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_object()?.get(part)?;
+    }
+    Some(value)
+}
+
+// Strings compare by their content, everything else by its JSON text
+fn value_label(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+pub fn context_value(request: &NetworkRequest, key: &str) -> Option<String> {
+    request
+        .context
+        .as_ref()
+        .and_then(|context| lookup(context, key))
+        .map(value_label)
+}
+
+#[tauri::command]
+pub async fn get_requests_by_context(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    key: String,
+    value: String,
+) -> Result<Vec<NetworkRequest>, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    Ok(requests
+        .iter()
+        .filter(|r| context_value(r, &key).as_deref() == Some(value.as_str()))
+        .cloned()
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_context_stats(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    key: String,
+) -> Result<Vec<GroupStats>, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    Ok(group_stats(&requests, |r| context_value(r, &key)))
+}
@@ -0,0 +1,213 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+use crate::jsonpath::{self, Segment};
+use crate::limits::{self, StoreLimitState};
+use crate::stats::{group_stats, GroupStats};
+use crate::storage::{self, StorageState};
+use crate::{Headers, NetworkRequest, RequestStore};
+
+// Longest value kept per derived field; they're meant to be short ids and codes
+const MAX_DERIVED_VALUE_LEN: usize = 256;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum DerivedSource {
+    #[default]
+    Url,
+    RequestBody,
+    ResponseBody,
+    // The value of `header`, joined with ", " when it is repeated
+    RequestHeader,
+    ResponseHeader,
+}
+
+// `group` picks a capture group by name or number; without it the first group
+// is used, or the whole match when the pattern has no groups
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum Extractor {
+    Regex {
+        pattern: String,
+        group: Option<String>,
+    },
+    JsonPath {
+        path: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum Compiled {
+    Regex(Regex),
+    JsonPath(Vec<Segment>),
+}
+
+// A user-defined column pulled out of each request on ingest, e.g.
+// `{ "name": "orderId", "source": "url", "extractor": { "kind": "regex", "pattern": "/orders/(\\d+)" } }`
+// or, promoting a header as is,
+// `{ "name": "cache", "source": "response_header", "header": "X-Cache" }`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct DerivedField {
+    pub name: String,
+    #[serde(default)]
+    pub source: DerivedSource,
+    // Which header the header sources read
+    #[serde(default)]
+    pub header: Option<String>,
+    // Without one the whole source value is taken
+    #[serde(default)]
+    pub extractor: Option<Extractor>,
+    #[serde(skip)]
+    compiled: Option<Compiled>,
+}
+
+pub type DerivedFields = Arc<Mutex<Vec<DerivedField>>>;
+
+// Field name -> extracted value
+pub type DerivedValues = BTreeMap<String, String>;
+
+impl DerivedField {
+    fn compile(&mut self) -> Result<(), String> {
+        let reads_header = matches!(
+            self.source,
+            DerivedSource::RequestHeader | DerivedSource::ResponseHeader
+        );
+        match &self.header {
+            Some(header) if header.trim().is_empty() => {
+                return Err(format!("{}: header name is empty", self.name));
+            }
+            None if reads_header => {
+                return Err(format!("{}: header sources need a header name", self.name));
+            }
+            _ => {}
+        }
+
+        self.compiled = match &self.extractor {
+            Some(Extractor::Regex { pattern, .. }) => Some(Compiled::Regex(
+                Regex::new(pattern).map_err(|e| format!("{}: {}", self.name, e))?,
+            )),
+            Some(Extractor::JsonPath { path }) => Some(Compiled::JsonPath(
+                jsonpath::parse(path).map_err(|e| format!("{}: {}", self.name, e))?,
+            )),
+            None => None,
+        };
+        Ok(())
+    }
+
+    fn header_value(&self, headers: &Headers) -> Option<String> {
+        let values: Vec<&str> = headers
+            .get_all(self.header.as_deref()?.trim())
+            .map(str::trim)
+            .collect();
+        (!values.is_empty()).then(|| values.join(", "))
+    }
+
+    fn extract(&self, request: &NetworkRequest) -> Option<String> {
+        let header;
+        let input = match self.source {
+            DerivedSource::Url => Some(request.url.as_str()),
+            DerivedSource::RequestBody => request.body.as_deref(),
+            DerivedSource::ResponseBody => request.response.as_ref()?.body.as_deref(),
+            DerivedSource::RequestHeader => {
+                header = self.header_value(&request.headers)?;
+                Some(header.as_str())
+            }
+            DerivedSource::ResponseHeader => {
+                header = self.header_value(&request.response.as_ref()?.headers)?;
+                Some(header.as_str())
+            }
+        }?;
+
+        let value = match (&self.compiled, &self.extractor) {
+            (None, None) => input.to_string(),
+            (Some(Compiled::Regex(regex)), Some(Extractor::Regex { group, .. })) => {
+                let captures = regex.captures(input)?;
+                let matched = match group {
+                    Some(group) => match group.parse::<usize>() {
+                        Ok(index) => captures.get(index),
+                        Err(_) => captures.name(group),
+                    },
+                    None => captures.get(1).or_else(|| captures.get(0)),
+                }?;
+                matched.as_str().to_string()
+            }
+            (Some(Compiled::JsonPath(segments)), _) => {
+                let body: Value = serde_json::from_str(input).ok()?;
+                match jsonpath::select(&body, segments).into_iter().next()? {
+                    Value::String(s) => s.clone(),
+                    Value::Null => return None,
+                    other => other.to_string(),
+                }
+            }
+            _ => return None,
+        };
+
+        (!value.is_empty() && value.len() <= MAX_DERIVED_VALUE_LEN).then_some(value)
+    }
+}
+
+pub fn derive_fields(request: &mut NetworkRequest, fields: &[DerivedField]) {
+    let values: DerivedValues = fields
+        .iter()
+        .filter_map(|field| Some((field.name.clone(), field.extract(request)?)))
+        .collect();
+    request.derived = values;
+}
+
+#[tauri::command]
+pub async fn set_derived_fields(
+    fields_state: tauri::State<'_, DerivedFields>,
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    fields: Vec<DerivedField>,
+) -> Result<(), String> {
+    let mut fields = fields;
+    for field in fields.iter_mut() {
+        field.compile()?;
+    }
+
+    // Recompute for what's already captured so columns fill in straight away
+    let changed: Vec<NetworkRequest> = {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        requests
+            .iter_mut()
+            .filter_map(|request| {
+                let before = std::mem::take(&mut request.derived);
+                derive_fields(request, &fields);
+                (request.derived != before).then(|| request.clone())
+            })
+            .collect()
+    };
+    for request in &changed {
+        storage::put(&storage, request);
+    }
+    let mut current = fields_state.lock().map_err(|e| e.to_string())?;
+    *current = fields;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_derived_fields(
+    fields_state: tauri::State<'_, DerivedFields>,
+) -> Result<Vec<DerivedField>, String> {
+    let fields = fields_state.lock().map_err(|e| e.to_string())?;
+    Ok(fields.clone())
+}
+
+// Stats grouped by one derived field; requests where it didn't match are grouped under `null`
+#[tauri::command]
+pub async fn get_derived_field_stats(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    name: String,
+) -> Result<Vec<GroupStats>, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    Ok(group_stats(&requests, |r| r.derived.get(&name).cloned()))
+}
@@ -0,0 +1,64 @@
+use crate::filter::url_host;
+use crate::NetworkRequest;
+
+fn is_uuid(segment: &str) -> bool {
+    segment.len() == 36
+        && segment.chars().enumerate().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+// Segments that identify a resource rather than name a route
+fn is_identifier(segment: &str) -> bool {
+    if segment.is_empty() {
+        return false;
+    }
+    if segment.chars().all(|c| c.is_ascii_digit()) || is_uuid(segment) {
+        return true;
+    }
+    // Hashes, object ids and opaque tokens: long and mixing digits into letters
+    segment.len() >= 16
+        && segment.chars().any(|c| c.is_ascii_digit())
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// Path with identifiers replaced by `{id}` and the query dropped:
+// `https://api.x.com/users/42/orders?page=2` -> `/users/{id}/orders`
+pub fn path_template(url: &str) -> String {
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let path = match rest.find('/') {
+        Some(start) => &rest[start..],
+        None => "/",
+    };
+    let path = path.split(['?', '#']).next().unwrap_or("/");
+
+    let template: Vec<&str> = path
+        .split('/')
+        .map(|segment| {
+            if is_identifier(segment) {
+                "{id}"
+            } else {
+                segment
+            }
+        })
+        .collect();
+    let template = template.join("/");
+    if template.is_empty() {
+        "/".to_string()
+    } else {
+        template
+    }
+}
+
+// Stable name for "the same endpoint": `GET api.x.com/users/{id}`
+pub fn endpoint_key(request: &NetworkRequest) -> String {
+    format!(
+        "{} {}{}",
+        request.method.to_ascii_uppercase(),
+        url_host(&request.url).to_ascii_lowercase(),
+        path_template(&request.url)
+    )
+}
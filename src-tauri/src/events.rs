@@ -0,0 +1,84 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+use tauri::Emitter;
+use ts_rs::TS;
+
+use crate::alerts::AlertRecord;
+use crate::baselines::Anomaly;
+use crate::bulk::BulkResult;
+use crate::capture::CaptureStatus;
+use crate::clients::ClientLagWarning;
+use crate::config::ObserverConfig;
+use crate::export::chunks::StoreChunk;
+use crate::export::stream::ExportProgress;
+use crate::lifecycle::StateChange;
+use crate::markers::Marker;
+use crate::quarantine::UnparsedMessage;
+use crate::replay::ReplayOutcome;
+use crate::session::LoadedSession;
+use crate::sessions::SessionInfo;
+use crate::watches::WatchHit;
+use crate::NetworkRequest;
+
+// Every event the backend emits to the frontend. Going through this enum keeps
+// event names and payloads in one place and in the generated `AppEvent` type.
+#[derive(Debug, Serialize, TS)]
+#[serde(tag = "event", content = "payload", rename_all = "kebab-case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum AppEvent<'a> {
+    // Peer address of a new ingest connection
+    WebsocketConnected(&'a str),
+    NewRequest(&'a NetworkRequest),
+    // A pending request completed by `request-end` or moved on by `request-state`
+    RequestUpdated(&'a NetworkRequest),
+    RequestStateChanged(&'a StateChange),
+    RequestsImported(&'a Vec<NetworkRequest>),
+    RequestsUpdated(&'a BulkResult),
+    RequestsDeleted(&'a BulkResult),
+    // Dropped by the retention policy
+    RequestsPruned(&'a BulkResult),
+    // Brought back by `undo_clear`
+    RequestsRestored(&'a BulkResult),
+    // Replaced or merged with a file by `load_session`
+    StoreReplaced(&'a LoadedSession),
+    MarkerAdded(&'a Marker),
+    AlertFired(&'a AlertRecord),
+    AlertUpdated(&'a AlertRecord),
+    // A request far off its endpoint's learned baseline
+    AnomalyDetected(&'a Anomaly),
+    WatchHit(&'a WatchHit),
+    CapturePaused(&'a CaptureStatus),
+    CaptureResumed(&'a CaptureStatus),
+    ReplayProgress(&'a ReplayOutcome),
+    ExportProgress(&'a ExportProgress),
+    // Part of a `start_store_stream` stream
+    StoreChunk(&'a StoreChunk),
+    UnparsedMessage(&'a UnparsedMessage),
+    SessionSwitched(&'a SessionInfo),
+    // A capture from the import directory, loaded as a new session
+    SessionImported(&'a SessionInfo),
+    // A client's command channel overflowed and dropped commands
+    ClientLagging(&'a ClientLagWarning),
+    // Applied from the config file or `set_config`
+    ConfigChanged(&'a ObserverConfig),
+}
+
+// Where events go: the app handle in the app, something that records them in tests
+pub trait EventSink: Send + Sync {
+    fn emit_event(&self, name: &str, payload: Value) -> Result<(), String>;
+}
+
+impl EventSink for tauri::AppHandle {
+    fn emit_event(&self, name: &str, payload: Value) -> Result<(), String> {
+        self.emit(name, payload).map_err(|e| e.to_string())
+    }
+}
+
+pub type Events = Arc<dyn EventSink>;
+
+pub fn emit(sink: &dyn EventSink, event: AppEvent) -> Result<(), String> {
+    let mut value = serde_json::to_value(&event).map_err(|e| e.to_string())?;
+    let name = value["event"].as_str().unwrap_or_default().to_string();
+    sink.emit_event(&name, value["payload"].take())
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+use crate::limits::{self, StoreLimitState};
+use crate::stats::{group_stats, GroupStats};
+use crate::storage::{self, StorageState};
+use crate::{NetworkRequest, RequestStore};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum ExperimentSource {
+    Header,
+    Cookie,
+}
+
+// Where to read the variant of one experiment from, e.g.
+// `{ "experiment": "checkout_v2", "source": "cookie", "key": "exp_checkout" }`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ExperimentRule {
+    pub experiment: String,
+    pub source: ExperimentSource,
+    pub key: String,
+}
+
+pub type ExperimentRules = Arc<Mutex<Vec<ExperimentRule>>>;
+
+// Experiment name -> variant label
+pub type ExperimentVariants = BTreeMap<String, String>;
+
+fn cookie_value<'a>(cookies: &'a str, name: &str) -> Option<&'a str> {
+    cookies.split(';').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key.trim() == name).then(|| value.trim())
+    })
+}
+
+fn find_variant(request: &NetworkRequest, rule: &ExperimentRule) -> Option<String> {
+    let value = match rule.source {
+        ExperimentSource::Header => request.headers.get(&rule.key).or_else(|| {
+            request
+                .response
+                .as_ref()
+                .and_then(|response| response.headers.get(&rule.key))
+        }),
+        // The variant is usually already in the Cookie header; on the first visit the
+        // server assigns it through Set-Cookie instead
+        ExperimentSource::Cookie => request
+            .headers
+            .get_all("cookie")
+            .find_map(|cookies| cookie_value(cookies, &rule.key))
+            .or_else(|| {
+                request.response.as_ref().and_then(|response| {
+                    response
+                        .headers
+                        .get_all("set-cookie")
+                        .find_map(|cookie| cookie_value(cookie.split(';').next()?, &rule.key))
+                })
+            }),
+    }?;
+
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+pub fn label_request(request: &mut NetworkRequest, rules: &[ExperimentRule]) {
+    let variants: ExperimentVariants = rules
+        .iter()
+        .filter_map(|rule| Some((rule.experiment.clone(), find_variant(request, rule)?)))
+        .collect();
+    request.experiments = variants;
+}
+
+#[tauri::command]
+pub async fn set_experiment_rules(
+    rules_state: tauri::State<'_, ExperimentRules>,
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    rules: Vec<ExperimentRule>,
+) -> Result<(), String> {
+    // Relabel what's already captured so stats reflect the new rules straight away
+    let changed: Vec<NetworkRequest> = {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        requests
+            .iter_mut()
+            .filter_map(|request| {
+                let before = std::mem::take(&mut request.experiments);
+                label_request(request, &rules);
+                (request.experiments != before).then(|| request.clone())
+            })
+            .collect()
+    };
+    for request in &changed {
+        storage::put(&storage, request);
+    }
+    let mut current = rules_state.lock().map_err(|e| e.to_string())?;
+    *current = rules;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_experiment_rules(
+    rules_state: tauri::State<'_, ExperimentRules>,
+) -> Result<Vec<ExperimentRule>, String> {
+    let rules = rules_state.lock().map_err(|e| e.to_string())?;
+    Ok(rules.clone())
+}
+
+// Per-variant stats for one experiment; requests without a variant are grouped under `null`
+#[tauri::command]
+pub async fn get_experiment_stats(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    experiment: String,
+) -> Result<Vec<GroupStats>, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    Ok(group_stats(&requests, |r| {
+        r.experiments.get(&experiment).cloned()
+    }))
+}
@@ -0,0 +1,10 @@
+// Exporters that render captured traffic into other formats.
+
+pub mod aggregate;
+pub mod anonymize;
+pub mod chunks;
+pub mod compare;
+pub mod har;
+pub mod snippet;
+pub mod stream;
+pub mod window;
@@ -0,0 +1,193 @@
+use rand::Rng;
+use serde::Deserialize;
+use std::path::Path;
+use ts_rs::TS;
+
+use super::compare::SessionComparison;
+use crate::stats::GroupStats;
+
+// Turns a report into one that can be shared outside the team: only endpoint
+// templates and numbers, with rare endpoints left out and, optionally, counts
+// blurred with Laplace noise so single requests can't be picked out.
+#[derive(Debug, Clone, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct AggregateProfile {
+    // Endpoints with fewer requests than this in either session are dropped
+    pub min_group_size: usize,
+    // Privacy budget per count; smaller is noisier. None adds no noise.
+    pub epsilon: Option<f64>,
+}
+
+impl Default for AggregateProfile {
+    fn default() -> Self {
+        AggregateProfile {
+            min_group_size: 5,
+            epsilon: None,
+        }
+    }
+}
+
+// Laplace noise for a count, which one request changes by at most 1
+fn laplace(rng: &mut impl Rng, epsilon: f64) -> f64 {
+    let u: f64 = rng.gen_range(-0.5..0.5);
+    -(1.0 / epsilon) * u.signum() * (1.0 - 2.0 * u.abs()).ln()
+}
+
+fn noisy(rng: &mut impl Rng, epsilon: Option<f64>, count: usize) -> usize {
+    match epsilon {
+        Some(epsilon) => (count as f64 + laplace(rng, epsilon)).round().max(0.0) as usize,
+        None => count,
+    }
+}
+
+fn blur(rng: &mut impl Rng, epsilon: Option<f64>, stats: &mut GroupStats) {
+    stats.count = noisy(rng, epsilon, stats.count);
+    stats.errors = noisy(rng, epsilon, stats.errors).min(stats.count);
+    stats.pending = noisy(rng, epsilon, stats.pending).min(stats.count);
+    stats.error_rate = match stats.count {
+        0 => 0.0,
+        count => stats.errors as f64 / count as f64,
+    };
+}
+
+// Session paths tend to include user names; the file name is enough to tell
+// the two apart
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+pub fn aggregate_only(report: &mut SessionComparison, profile: &AggregateProfile) {
+    let min = profile.min_group_size;
+    let before = report.changed.len() + report.new_endpoints.len() + report.removed_endpoints.len();
+    report
+        .changed
+        .retain(|delta| delta.baseline.count >= min && delta.candidate.count >= min);
+    report.new_endpoints.retain(|stats| stats.count >= min);
+    report.removed_endpoints.retain(|stats| stats.count >= min);
+    let after = report.changed.len() + report.new_endpoints.len() + report.removed_endpoints.len();
+    report.suppressed_endpoints += before - after;
+
+    let mut rng = rand::thread_rng();
+    let epsilon = profile.epsilon;
+    for delta in report.changed.iter_mut() {
+        blur(&mut rng, epsilon, &mut delta.baseline);
+        blur(&mut rng, epsilon, &mut delta.candidate);
+        delta.error_rate_delta = delta.candidate.error_rate - delta.baseline.error_rate;
+    }
+    for stats in report
+        .new_endpoints
+        .iter_mut()
+        .chain(report.removed_endpoints.iter_mut())
+    {
+        blur(&mut rng, epsilon, stats);
+    }
+    report.baseline_requests = noisy(&mut rng, epsilon, report.baseline_requests);
+    report.candidate_requests = noisy(&mut rng, epsilon, report.candidate_requests);
+    report.baseline = file_name(&report.baseline);
+    report.candidate = file_name(&report.candidate);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::compare::EndpointDelta;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn stats(endpoint: &str, count: usize, errors: usize) -> GroupStats {
+        GroupStats {
+            value: Some(endpoint.to_string()),
+            count,
+            errors,
+            pending: 0,
+            error_rate: errors as f64 / count.max(1) as f64,
+            avg_duration_ms: None,
+            p95_duration_ms: None,
+            total_response_bytes: 0,
+        }
+    }
+
+    fn report() -> SessionComparison {
+        SessionComparison {
+            baseline: "/home/alice/captures/before.session".to_string(),
+            candidate: "/home/alice/captures/after.session".to_string(),
+            generated_at: 0,
+            generated_at_text: String::new(),
+            baseline_requests: 20,
+            candidate_requests: 21,
+            changed: vec![
+                EndpointDelta {
+                    endpoint: "GET /users".to_string(),
+                    baseline: stats("GET /users", 10, 1),
+                    candidate: stats("GET /users", 10, 5),
+                    avg_duration_delta_ms: None,
+                    p95_duration_delta_ms: None,
+                    error_rate_delta: 0.4,
+                },
+                EndpointDelta {
+                    endpoint: "GET /rare".to_string(),
+                    baseline: stats("GET /rare", 10, 0),
+                    candidate: stats("GET /rare", 1, 0),
+                    avg_duration_delta_ms: None,
+                    p95_duration_delta_ms: None,
+                    error_rate_delta: 0.0,
+                },
+            ],
+            new_endpoints: vec![stats("POST /new", 5, 0), stats("POST /once", 1, 1)],
+            removed_endpoints: vec![stats("GET /old", 4, 0)],
+            suppressed_endpoints: 0,
+        }
+    }
+
+    #[test]
+    fn drops_rare_endpoints_and_paths() {
+        let mut report = report();
+        aggregate_only(&mut report, &AggregateProfile::default());
+        let changed: Vec<&str> = report.changed.iter().map(|d| d.endpoint.as_str()).collect();
+        assert_eq!(changed, ["GET /users"]);
+        assert_eq!(report.new_endpoints.len(), 1);
+        assert!(report.removed_endpoints.is_empty());
+        assert_eq!(report.suppressed_endpoints, 3);
+        // Without noise the numbers stay as they were
+        assert_eq!(report.changed[0].candidate.count, 10);
+        assert_eq!(report.baseline_requests, 20);
+        assert_eq!(report.baseline, "before.session");
+        assert_eq!(report.candidate, "after.session");
+    }
+
+    #[test]
+    fn keeps_blurred_counts_consistent() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..1_000 {
+            let mut blurred = stats("GET /users", 3, 3);
+            blur(&mut rng, Some(0.5), &mut blurred);
+            assert!(blurred.errors <= blurred.count);
+            let expected = match blurred.count {
+                0 => 0.0,
+                count => blurred.errors as f64 / count as f64,
+            };
+            assert_eq!(blurred.error_rate, expected);
+        }
+    }
+
+    #[test]
+    fn adds_noise_centred_on_the_count() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let samples = 20_000;
+        let total: f64 = (0..samples).map(|_| laplace(&mut rng, 1.0)).sum();
+        assert!((total / samples as f64).abs() < 0.05);
+        // A smaller budget spreads it wider
+        let spread = |rng: &mut StdRng, epsilon| {
+            (0..samples)
+                .map(|_| laplace(rng, epsilon).abs())
+                .sum::<f64>()
+                / samples as f64
+        };
+        let (tight, loose) = (spread(&mut rng, 2.0), spread(&mut rng, 0.5));
+        assert!((tight - 0.5).abs() < 0.05 && (loose - 2.0).abs() < 0.1);
+    }
+}
@@ -0,0 +1,227 @@
+// The store sent to the frontend as a series of `store-chunk` events instead
+// of one `get_requests` reply, so a large capture renders as it arrives and
+// no command holds the store while the whole list is cloned. The frontend
+// acknowledges each chunk with `ack_store_chunk`; no more than
+// `MAX_IN_FLIGHT` chunks go out ahead of the last one acknowledged, so a busy
+// UI slows the stream down rather than queueing events it can't keep up with.
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::bulk::Selection;
+use crate::events::{self, AppEvent, EventSink, Events};
+use crate::export::stream::fetch;
+use crate::limits::{self, StoreLimitState};
+use crate::{NetworkRequest, RequestStore};
+
+const DEFAULT_CHUNK_SIZE: usize = 200;
+const MAX_IN_FLIGHT: usize = 2;
+// A stream nobody acknowledges, say because the window was reloaded, is
+// dropped after this long
+const ACK_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct StoreChunk {
+    pub stream_id: String,
+    // From 0; acknowledge it with this number
+    pub seq: usize,
+    pub requests: Vec<NetworkRequest>,
+    // Requests sent so far, this chunk included; ones deleted from the store
+    // meanwhile are skipped
+    pub sent: usize,
+    pub total: usize,
+    // The last chunk of the stream
+    pub done: bool,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct StoreStream {
+    pub stream_id: String,
+    pub total: usize,
+    pub chunk_size: usize,
+}
+
+#[derive(Default)]
+pub struct StreamControl {
+    // Chunks acknowledged so far
+    acked: Mutex<usize>,
+    changed: Condvar,
+    cancelled: AtomicBool,
+}
+
+// Streams still sending
+pub type StoreStreams = Arc<Mutex<HashMap<String, Arc<StreamControl>>>>;
+
+enum Wait {
+    Ready,
+    Cancelled,
+    TimedOut,
+}
+
+// Blocks until chunk `seq` may be sent
+fn wait_for_ack(control: &StreamControl, seq: usize) -> Wait {
+    let deadline = Instant::now() + ACK_TIMEOUT;
+    let mut acked = control.acked.lock().unwrap();
+    loop {
+        if control.cancelled.load(Ordering::Relaxed) {
+            return Wait::Cancelled;
+        }
+        if seq < *acked + MAX_IN_FLIGHT {
+            return Wait::Ready;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Wait::TimedOut;
+        }
+        acked = control
+            .changed
+            .wait_timeout(acked, deadline - now)
+            .unwrap()
+            .0;
+    }
+}
+
+fn run(
+    stream_id: &str,
+    ids: &[String],
+    chunk_size: usize,
+    control: &StreamControl,
+    store: &RequestStore,
+    store_limits: &StoreLimitState,
+    sink: &dyn EventSink,
+) -> Result<(), String> {
+    let total = ids.len();
+    let mut chunks = ids.chunks(chunk_size).peekable();
+    let mut sent = 0;
+    let mut seq = 0;
+    // An empty store still gets its one, final chunk
+    let empty: &[String] = &[];
+    while let Some(chunk_ids) = chunks.next().or((seq == 0).then_some(empty)) {
+        match wait_for_ack(control, seq) {
+            Wait::Ready => {}
+            Wait::Cancelled => return Ok(()),
+            Wait::TimedOut => {
+                return Err(format!("Store stream {} was not acknowledged", stream_id))
+            }
+        }
+        let requests = fetch(store, store_limits, chunk_ids)?;
+        sent += requests.len();
+        let chunk = StoreChunk {
+            stream_id: stream_id.to_string(),
+            seq,
+            requests,
+            sent,
+            total,
+            done: chunks.peek().is_none(),
+        };
+        events::emit(sink, AppEvent::StoreChunk(&chunk))?;
+        if chunk.done {
+            break;
+        }
+        seq += 1;
+    }
+    Ok(())
+}
+
+// Starts streaming the selected requests (all of them without a selection)
+// in store order. Only their ids are taken now.
+#[tauri::command]
+pub async fn start_store_stream(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    streams: tauri::State<'_, StoreStreams>,
+    app_handle: tauri::AppHandle,
+    selection: Option<Selection>,
+    chunk_size: Option<usize>,
+) -> Result<StoreStream, String> {
+    let chunk_size = chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+    if chunk_size == 0 {
+        return Err("chunk_size must be at least 1".to_string());
+    }
+    let ids: Vec<String> = {
+        let requests = limits::captured(&store_limits, &store)?;
+        let matcher = selection.as_ref().map(Selection::matcher);
+        requests
+            .iter()
+            .filter(|r| matcher.as_ref().is_none_or(|m| m.matches(r)))
+            .map(|r| r.id.clone())
+            .collect()
+    };
+    let started = StoreStream {
+        stream_id: Uuid::new_v4().to_string(),
+        total: ids.len(),
+        chunk_size,
+    };
+    let control = Arc::new(StreamControl::default());
+    streams
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(started.stream_id.clone(), control.clone());
+
+    let stream_id = started.stream_id.clone();
+    let (store, store_limits) = (store.inner().clone(), store_limits.inner().clone());
+    let streams = streams.inner().clone();
+    let sink: Events = Arc::new(app_handle);
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = run(
+            &stream_id,
+            &ids,
+            chunk_size,
+            &control,
+            &store,
+            &store_limits,
+            &*sink,
+        ) {
+            println!("Store stream stopped: {}", e);
+        }
+        if let Ok(mut streams) = streams.lock() {
+            streams.remove(&stream_id);
+        }
+    });
+    Ok(started)
+}
+
+// The frontend has handled chunk `seq`, making room for the next ones
+#[tauri::command]
+pub async fn ack_store_chunk(
+    streams: tauri::State<'_, StoreStreams>,
+    stream_id: String,
+    seq: usize,
+) -> Result<(), String> {
+    // The last chunks are acknowledged after their stream has finished
+    let Some(control) = streams
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&stream_id)
+        .cloned()
+    else {
+        return Ok(());
+    };
+    let mut acked = control.acked.lock().map_err(|e| e.to_string())?;
+    *acked = (*acked).max(seq + 1);
+    control.changed.notify_all();
+    Ok(())
+}
+
+// Stops a stream before its next chunk
+#[tauri::command]
+pub async fn cancel_store_stream(
+    streams: tauri::State<'_, StoreStreams>,
+    stream_id: String,
+) -> Result<(), String> {
+    let streams = streams.lock().map_err(|e| e.to_string())?;
+    let control = streams
+        .get(&stream_id)
+        .ok_or_else(|| format!("Store stream not found: {}", stream_id))?;
+    // Under the lock, so a sender about to wait can't miss it
+    let _acked = control.acked.lock().map_err(|e| e.to_string())?;
+    control.cancelled.store(true, Ordering::Relaxed);
+    control.changed.notify_all();
+    Ok(())
+}
@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use ts_rs::TS;
+
+use super::aggregate::{aggregate_only, AggregateProfile};
+use crate::endpoint::endpoint_key;
+use crate::har::{format_timestamp_in, TimestampZone};
+use crate::markers::now_millis;
+use crate::session::load_requests;
+use crate::stats::{group_stats, GroupStats};
+use crate::NetworkRequest;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum ReportFormat {
+    Json,
+    Markdown,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct EndpointDelta {
+    pub endpoint: String,
+    pub baseline: GroupStats,
+    pub candidate: GroupStats,
+    pub avg_duration_delta_ms: Option<f64>,
+    #[ts(type = "number | null")]
+    pub p95_duration_delta_ms: Option<i64>,
+    pub error_rate_delta: f64,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct SessionComparison {
+    pub baseline: String,
+    pub candidate: String,
+    #[ts(type = "number")]
+    pub generated_at: u64,
+    // `generated_at` written out in the zone the report was asked for
+    pub generated_at_text: String,
+    pub baseline_requests: usize,
+    pub candidate_requests: usize,
+    // Endpoints seen in both, slowest regression first
+    pub changed: Vec<EndpointDelta>,
+    pub new_endpoints: Vec<GroupStats>,
+    pub removed_endpoints: Vec<GroupStats>,
+    // Left out of an aggregate-only report for having too few requests
+    pub suppressed_endpoints: usize,
+}
+
+fn endpoint_stats(requests: &[NetworkRequest]) -> Vec<GroupStats> {
+    group_stats(requests, |r| Some(endpoint_key(r)))
+}
+
+fn delta(baseline: GroupStats, candidate: GroupStats) -> EndpointDelta {
+    EndpointDelta {
+        endpoint: baseline.value.clone().unwrap_or_default(),
+        avg_duration_delta_ms: baseline
+            .avg_duration_ms
+            .zip(candidate.avg_duration_ms)
+            .map(|(before, after)| after - before),
+        p95_duration_delta_ms: baseline
+            .p95_duration_ms
+            .zip(candidate.p95_duration_ms)
+            .map(|(before, after)| after as i64 - before as i64),
+        error_rate_delta: candidate.error_rate - baseline.error_rate,
+        baseline,
+        candidate,
+    }
+}
+
+pub fn compare(
+    baseline_name: &str,
+    baseline: &[NetworkRequest],
+    candidate_name: &str,
+    candidate: &[NetworkRequest],
+    zone: TimestampZone,
+) -> SessionComparison {
+    let mut before = endpoint_stats(baseline);
+    let mut after = endpoint_stats(candidate);
+    let before_keys: BTreeSet<Option<String>> = before.iter().map(|s| s.value.clone()).collect();
+    let after_keys: BTreeSet<Option<String>> = after.iter().map(|s| s.value.clone()).collect();
+
+    let removed_endpoints: Vec<GroupStats> = before
+        .iter()
+        .filter(|s| !after_keys.contains(&s.value))
+        .cloned()
+        .collect();
+    let new_endpoints: Vec<GroupStats> = after
+        .iter()
+        .filter(|s| !before_keys.contains(&s.value))
+        .cloned()
+        .collect();
+
+    before.retain(|s| after_keys.contains(&s.value));
+    after.retain(|s| before_keys.contains(&s.value));
+    before.sort_by(|a, b| a.value.cmp(&b.value));
+    after.sort_by(|a, b| a.value.cmp(&b.value));
+    let mut changed: Vec<EndpointDelta> = before
+        .into_iter()
+        .zip(after)
+        .map(|(before, after)| delta(before, after))
+        .collect();
+    changed.sort_by(|a, b| {
+        let a = a.avg_duration_delta_ms.unwrap_or(f64::MIN);
+        let b = b.avg_duration_delta_ms.unwrap_or(f64::MIN);
+        b.total_cmp(&a)
+    });
+
+    let generated_at = now_millis();
+    SessionComparison {
+        baseline: baseline_name.to_string(),
+        candidate: candidate_name.to_string(),
+        generated_at,
+        generated_at_text: format_timestamp_in(generated_at, zone),
+        baseline_requests: baseline.len(),
+        candidate_requests: candidate.len(),
+        changed,
+        new_endpoints,
+        removed_endpoints,
+        suppressed_endpoints: 0,
+    }
+}
+
+fn ms(value: Option<f64>) -> String {
+    value.map_or("-".to_string(), |v| format!("{:.0}", v))
+}
+
+fn signed(value: Option<f64>) -> String {
+    value.map_or("-".to_string(), |v| format!("{:+.0}", v))
+}
+
+fn percent(rate: f64) -> String {
+    format!("{:.1}%", rate * 100.0)
+}
+
+// Writing to a String can't fail, so the fmt results are ignored throughout
+pub fn render_markdown(report: &SessionComparison) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Session comparison\n");
+    let _ = writeln!(out, "- Generated: {}", report.generated_at_text);
+    let _ = writeln!(
+        out,
+        "- Baseline: `{}` ({} requests)",
+        report.baseline, report.baseline_requests
+    );
+    let _ = writeln!(
+        out,
+        "- Candidate: `{}` ({} requests)\n",
+        report.candidate, report.candidate_requests
+    );
+
+    let _ = writeln!(out, "## Changed endpoints\n");
+    if report.changed.is_empty() {
+        let _ = writeln!(out, "None.\n");
+    } else {
+        let _ = writeln!(
+            out,
+            "| Endpoint | Requests | Avg ms | Δ avg ms | P95 Δ ms | Error rate | Δ errors |"
+        );
+        let _ = writeln!(out, "|---|---|---|---|---|---|---|");
+        for delta in &report.changed {
+            let _ = writeln!(
+                out,
+                "| `{}` | {} → {} | {} → {} | {} | {} | {} → {} | {:+.1}pp |",
+                delta.endpoint,
+                delta.baseline.count,
+                delta.candidate.count,
+                ms(delta.baseline.avg_duration_ms),
+                ms(delta.candidate.avg_duration_ms),
+                signed(delta.avg_duration_delta_ms),
+                signed(delta.p95_duration_delta_ms.map(|v| v as f64)),
+                percent(delta.baseline.error_rate),
+                percent(delta.candidate.error_rate),
+                delta.error_rate_delta * 100.0,
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    for (title, endpoints) in [
+        ("New endpoints", &report.new_endpoints),
+        ("Removed endpoints", &report.removed_endpoints),
+    ] {
+        let _ = writeln!(out, "## {}\n", title);
+        if endpoints.is_empty() {
+            let _ = writeln!(out, "None.\n");
+            continue;
+        }
+        for stats in endpoints {
+            let _ = writeln!(
+                out,
+                "- `{}`: {} requests, avg {} ms, {} errors",
+                stats.value.as_deref().unwrap_or_default(),
+                stats.count,
+                ms(stats.avg_duration_ms),
+                percent(stats.error_rate),
+            );
+        }
+        let _ = writeln!(out);
+    }
+    if report.suppressed_endpoints > 0 {
+        let _ = writeln!(
+            out,
+            "{} endpoints with too few requests to share were left out.",
+            report.suppressed_endpoints
+        );
+    }
+    out
+}
+
+// Compares two saved sessions (any file `session::load_requests` reads) and
+// writes the report to `path`. Without a format, `.md` paths get Markdown.
+// With `aggregate_only` the report keeps nothing that identifies a customer.
+#[tauri::command]
+pub async fn export_session_comparison(
+    baseline: String,
+    candidate: String,
+    path: String,
+    format: Option<ReportFormat>,
+    aggregate_only_with: Option<AggregateProfile>,
+    timestamps: Option<TimestampZone>,
+) -> Result<SessionComparison, String> {
+    let zone = timestamps.unwrap_or_default().check()?;
+    let epsilon = aggregate_only_with.as_ref().and_then(|p| p.epsilon);
+    if epsilon.is_some_and(|epsilon| epsilon.is_nan() || epsilon <= 0.0) {
+        return Err("epsilon must be positive".to_string());
+    }
+    let baseline_requests = load_requests(&baseline)?;
+    let candidate_requests = load_requests(&candidate)?;
+    let mut report = compare(
+        &baseline,
+        &baseline_requests,
+        &candidate,
+        &candidate_requests,
+        zone,
+    );
+    if let Some(profile) = &aggregate_only_with {
+        aggregate_only(&mut report, profile);
+    }
+
+    let format = format.unwrap_or(if path.ends_with(".md") {
+        ReportFormat::Markdown
+    } else {
+        ReportFormat::Json
+    });
+    let contents = match format {
+        ReportFormat::Json => serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?,
+        ReportFormat::Markdown => render_markdown(&report),
+    };
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    Ok(report)
+}
@@ -0,0 +1,32 @@
+use crate::blobs;
+use crate::bulk::Selection;
+use crate::export::anonymize::{anonymize, AnonymizeProfile};
+use crate::har::{self, TimestampZone};
+use crate::limits::{self, StoreLimitState};
+use crate::RequestStore;
+
+// Writes the selected requests (all of them without a selection) as a HAR 1.2
+// archive and returns how many were exported
+#[tauri::command]
+pub async fn export_har(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    selection: Option<Selection>,
+    path: String,
+    anonymize_with: Option<AnonymizeProfile>,
+    timestamps: Option<TimestampZone>,
+) -> Result<usize, String> {
+    let zone = timestamps.unwrap_or_default().check()?;
+    let mut selected = limits::captured(&store_limits, &store)?;
+    if let Some(selection) = &selection {
+        let matcher = selection.matcher();
+        selected.retain(|r| matcher.matches(r));
+    }
+    blobs::restore(&mut selected);
+    anonymize(&mut selected, anonymize_with);
+
+    let json =
+        serde_json::to_string_pretty(&har::to_har(&selected, zone)).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    Ok(selected.len())
+}
@@ -0,0 +1,216 @@
+use serde::Deserialize;
+use serde_json::Value;
+use ts_rs::TS;
+
+use crate::blobs;
+use crate::export::anonymize::{anonymize, AnonymizeProfile};
+use crate::headers::canonical_name;
+use crate::limits::{self, StoreLimitState};
+use crate::{NetworkRequest, RequestStore};
+
+// Headers the HTTP client computes itself; copying them into a snippet only causes trouble
+const SKIPPED_HEADERS: &[&str] = &["content-length", "host", "connection", "accept-encoding"];
+
+#[derive(Debug, Clone, Copy, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum SnippetLanguage {
+    RustReqwest,
+    JsFetch,
+    PythonRequests,
+}
+
+fn snippet_headers(request: &NetworkRequest) -> Vec<(&str, &str)> {
+    let headers: Vec<(&str, &str)> = request
+        .headers
+        .iter()
+        .filter(|(name, _)| !SKIPPED_HEADERS.contains(&canonical_name(name).as_str()))
+        .collect();
+    headers
+}
+
+// Structured JSON bodies render as native literals instead of opaque strings
+fn json_body(request: &NetworkRequest) -> Option<Value> {
+    let body = request.body.as_ref()?;
+    match serde_json::from_str::<Value>(body) {
+        Ok(value @ (Value::Object(_) | Value::Array(_))) => Some(value),
+        _ => None,
+    }
+}
+
+fn is_json_content_type(name: &str, value: &str) -> bool {
+    name.eq_ignore_ascii_case("content-type") && value.contains("json")
+}
+
+fn rust_raw_string(text: &str) -> String {
+    let mut hashes = String::from("#");
+    while text.contains(&format!("\"{}", hashes)) {
+        hashes.push('#');
+    }
+    format!("r{hashes}\"{text}\"{hashes}")
+}
+
+pub fn render_rust_reqwest(request: &NetworkRequest) -> String {
+    let json = json_body(request);
+    let method = request.method.to_ascii_uppercase();
+    let builder = match method.as_str() {
+        "GET" | "POST" | "PUT" | "PATCH" | "DELETE" | "HEAD" => {
+            format!(
+                "client\n        .{}({:?})",
+                method.to_ascii_lowercase(),
+                request.url
+            )
+        }
+        _ => format!(
+            "client\n        .request(reqwest::Method::from_bytes(b{:?})?, {:?})",
+            method, request.url
+        ),
+    };
+
+    let mut lines = vec![
+        "let client = reqwest::Client::new();".to_string(),
+        format!("let response = {}", builder),
+    ];
+    for (name, value) in snippet_headers(request) {
+        // `.json()` sets the content type itself
+        if json.is_some() && is_json_content_type(name, value) {
+            continue;
+        }
+        lines.push(format!("        .header({:?}, {:?})", name, value));
+    }
+    if let Some(value) = &json {
+        let pretty = serde_json::to_string_pretty(value).unwrap_or_default();
+        lines.push(format!(
+            "        .json(&serde_json::json!({}))",
+            pretty.replace('\n', "\n        ")
+        ));
+    } else if let Some(body) = &request.body {
+        lines.push(format!("        .body({})", rust_raw_string(body)));
+    }
+    lines.push("        .send()".to_string());
+    lines.push("        .await?;".to_string());
+    lines.join("\n")
+}
+
+pub fn render_js_fetch(request: &NetworkRequest) -> String {
+    let quote = |text: &str| serde_json::to_string(text).unwrap_or_default();
+    let mut options = vec![format!(
+        "  method: {},",
+        quote(&request.method.to_ascii_uppercase())
+    )];
+
+    let headers = snippet_headers(request);
+    if !headers.is_empty() {
+        options.push("  headers: {".to_string());
+        for (name, value) in headers {
+            options.push(format!("    {}: {},", quote(name), quote(value)));
+        }
+        options.push("  },".to_string());
+    }
+    if let Some(value) = json_body(request) {
+        let pretty = serde_json::to_string_pretty(&value).unwrap_or_default();
+        options.push(format!(
+            "  body: JSON.stringify({}),",
+            pretty.replace('\n', "\n  ")
+        ));
+    } else if let Some(body) = &request.body {
+        options.push(format!("  body: {},", quote(body)));
+    }
+
+    format!(
+        "const response = await fetch({}, {{\n{}\n}});",
+        quote(&request.url),
+        options.join("\n")
+    )
+}
+
+fn python_literal(value: &Value, indent: usize) -> String {
+    let pad = "    ".repeat(indent + 1);
+    let close = "    ".repeat(indent);
+    match value {
+        Value::Null => "None".to_string(),
+        Value::Bool(true) => "True".to_string(),
+        Value::Bool(false) => "False".to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => serde_json::to_string(s).unwrap_or_default(),
+        Value::Array(items) if items.is_empty() => "[]".to_string(),
+        Value::Array(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| format!("{}{}", pad, python_literal(item, indent + 1)))
+                .collect();
+            format!("[\n{},\n{}]", items.join(",\n"), close)
+        }
+        Value::Object(map) if map.is_empty() => "{}".to_string(),
+        Value::Object(map) => {
+            let entries: Vec<String> = map
+                .iter()
+                .map(|(key, item)| {
+                    format!(
+                        "{}{}: {}",
+                        pad,
+                        serde_json::to_string(key).unwrap_or_default(),
+                        python_literal(item, indent + 1)
+                    )
+                })
+                .collect();
+            format!("{{\n{},\n{}}}", entries.join(",\n"), close)
+        }
+    }
+}
+
+pub fn render_python_requests(request: &NetworkRequest) -> String {
+    let quote = |text: &str| serde_json::to_string(text).unwrap_or_default();
+    let json = json_body(request);
+    let mut args = vec![
+        format!("    {},", quote(&request.method.to_ascii_uppercase())),
+        format!("    {},", quote(&request.url)),
+    ];
+
+    let headers: Vec<(&str, &str)> = snippet_headers(request)
+        .into_iter()
+        .filter(|(name, value)| json.is_none() || !is_json_content_type(name, value))
+        .collect();
+    if !headers.is_empty() {
+        args.push("    headers={".to_string());
+        for (name, value) in headers {
+            args.push(format!("        {}: {},", quote(name), quote(value)));
+        }
+        args.push("    },".to_string());
+    }
+    if let Some(value) = &json {
+        args.push(format!("    json={},", python_literal(value, 1)));
+    } else if let Some(body) = &request.body {
+        args.push(format!("    data={},", quote(body)));
+    }
+
+    format!(
+        "import requests\n\nresponse = requests.request(\n{}\n)",
+        args.join("\n")
+    )
+}
+
+pub fn render_snippet(request: &NetworkRequest, language: SnippetLanguage) -> String {
+    match language {
+        SnippetLanguage::RustReqwest => render_rust_reqwest(request),
+        SnippetLanguage::JsFetch => render_js_fetch(request),
+        SnippetLanguage::PythonRequests => render_python_requests(request),
+    }
+}
+
+#[tauri::command]
+pub async fn export_snippet(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    id: String,
+    language: Option<SnippetLanguage>,
+    anonymize_with: Option<AnonymizeProfile>,
+) -> Result<String, String> {
+    let mut request = limits::find_request(&store_limits, &store, &id)?;
+    blobs::restore(std::slice::from_mut(&mut request));
+    anonymize(std::slice::from_mut(&mut request), anonymize_with);
+    Ok(render_snippet(
+        &request,
+        language.unwrap_or(SnippetLanguage::RustReqwest),
+    ))
+}
@@ -0,0 +1,293 @@
+// Exports for sessions too big to build in memory: the selection is copied out
+// of the store a chunk at a time, serialized on worker threads and written to
+// disk in order, with `export-progress` events along the way. The file only
+// appears at `path` once complete; a cancelled or failed export leaves nothing.
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::blobs;
+use crate::bulk::Selection;
+use crate::events::{self, AppEvent};
+use crate::export::anonymize::{AnonymizeProfile, Anonymizer};
+use crate::har::{self, TimestampZone};
+use crate::limits::{self, StoreLimitState};
+use crate::overflow;
+use crate::{NetworkRequest, RequestStore};
+
+const CHUNK_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Copy, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum ExportFormat {
+    Har,
+    // One request's JSON per line
+    Jsonl,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum ExportStatus {
+    Running,
+    Done,
+    Cancelled,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ExportProgress {
+    pub job_id: String,
+    pub path: String,
+    pub status: ExportStatus,
+    // Requests written so far; ones deleted from the store meanwhile are skipped
+    pub written: usize,
+    pub total: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ExportJob {
+    pub job_id: String,
+    pub total: usize,
+}
+
+// Cancellation flags of the exports still running
+pub type ExportJobs = Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>;
+
+// Copies the requests with these ids out of the store, or its spill segment
+// for the ones overflowed to disk, in the given order
+pub(crate) fn fetch(
+    store: &RequestStore,
+    store_limits: &StoreLimitState,
+    ids: &[String],
+) -> Result<Vec<NetworkRequest>, String> {
+    let wanted: HashSet<&str> = ids.iter().map(String::as_str).collect();
+    let mut found: HashMap<String, NetworkRequest> = {
+        let requests = store.lock().map_err(|e| e.to_string())?;
+        requests
+            .iter()
+            .filter(|r| wanted.contains(r.id.as_str()))
+            .map(|r| (r.id.clone(), r.clone()))
+            .collect()
+    };
+    let overflow = limits::overflow(store_limits);
+    Ok(ids
+        .iter()
+        .filter_map(|id| found.remove(id).or_else(|| overflow::get(&overflow, id)))
+        .collect())
+}
+
+// Full bodies back in, anonymized, one serialized record per request
+fn render(
+    mut chunk: Vec<NetworkRequest>,
+    format: ExportFormat,
+    anonymizer: Option<&Anonymizer>,
+    zone: TimestampZone,
+) -> Result<Vec<String>, String> {
+    blobs::restore(&mut chunk);
+    if let Some(anonymizer) = anonymizer {
+        chunk
+            .iter_mut()
+            .for_each(|request| anonymizer.request(request));
+    }
+    chunk
+        .iter()
+        .map(|request| match format {
+            ExportFormat::Har => serde_json::to_string(&har::request_to_entry(request, zone)),
+            ExportFormat::Jsonl => serde_json::to_string(request),
+        })
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+struct Job {
+    id: String,
+    path: String,
+    ids: Vec<String>,
+    format: ExportFormat,
+    anonymize_with: Option<AnonymizeProfile>,
+    zone: TimestampZone,
+    cancelled: Arc<AtomicBool>,
+}
+
+// Writes the export to `temp` and returns what it ended as and how many
+// requests were written
+fn write(
+    job: &Job,
+    temp: &str,
+    store: &RequestStore,
+    store_limits: &StoreLimitState,
+    report: &dyn Fn(usize),
+) -> Result<(ExportStatus, usize), String> {
+    let file = File::create(temp).map_err(|e| format!("Failed to write {}: {}", temp, e))?;
+    let mut out = BufWriter::new(file);
+    let io = |e: std::io::Error| format!("Failed to write {}: {}", temp, e);
+
+    // The archive around the entries; `entries` is the last field of the log
+    let shell = serde_json::to_string(&har::to_har(&[], job.zone)).map_err(|e| e.to_string())?;
+    let (head, tail) = shell.rsplit_once("[]").unwrap_or((&shell, ""));
+    if let ExportFormat::Har = job.format {
+        write!(out, "{}[", head).map_err(io)?;
+    }
+
+    let anonymizer = job.anonymize_with.clone().map(Anonymizer::new);
+    let workers = std::thread::available_parallelism().map_or(1, usize::from);
+    let mut written = 0;
+    for batch in job.ids.chunks(CHUNK_SIZE * workers) {
+        if job.cancelled.load(Ordering::Relaxed) {
+            return Ok((ExportStatus::Cancelled, written));
+        }
+        let chunks = batch
+            .chunks(CHUNK_SIZE)
+            .map(|ids| fetch(store, store_limits, ids))
+            .collect::<Result<Vec<_>, _>>()?;
+        let rendered = std::thread::scope(|scope| {
+            let handles: Vec<_> = chunks
+                .into_iter()
+                .map(|chunk| {
+                    scope.spawn(|| render(chunk, job.format, anonymizer.as_ref(), job.zone))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|_| Err("Export worker failed".to_string()))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+        for record in rendered.iter().flatten() {
+            match job.format {
+                ExportFormat::Har if written > 0 => write!(out, ",{}", record),
+                ExportFormat::Har => write!(out, "{}", record),
+                ExportFormat::Jsonl => writeln!(out, "{}", record),
+            }
+            .map_err(io)?;
+            written += 1;
+        }
+        report(written);
+    }
+
+    if let ExportFormat::Har = job.format {
+        write!(out, "]{}", tail).map_err(io)?;
+    }
+    out.flush().map_err(io)?;
+    Ok((ExportStatus::Done, written))
+}
+
+fn run(
+    job: Job,
+    store: RequestStore,
+    store_limits: StoreLimitState,
+    jobs: ExportJobs,
+    app_handle: tauri::AppHandle,
+) {
+    let temp = format!("{}.partial", job.path);
+    let progress = |status, written, error| ExportProgress {
+        job_id: job.id.clone(),
+        path: job.path.clone(),
+        status,
+        written,
+        total: job.ids.len(),
+        error,
+    };
+    let emit = |progress: ExportProgress| {
+        if let Err(e) = events::emit(&app_handle, AppEvent::ExportProgress(&progress)) {
+            println!("Failed to emit export-progress event: {}", e);
+        }
+    };
+
+    let report = |written| emit(progress(ExportStatus::Running, written, None));
+    let finished = match write(&job, &temp, &store, &store_limits, &report) {
+        Ok((ExportStatus::Done, written)) => match std::fs::rename(&temp, &job.path) {
+            Ok(()) => progress(ExportStatus::Done, written, None),
+            Err(e) => {
+                let error = format!("Failed to write {}: {}", job.path, e);
+                progress(ExportStatus::Failed, written, Some(error))
+            }
+        },
+        Ok((status, written)) => progress(status, written, None),
+        Err(e) => progress(ExportStatus::Failed, 0, Some(e)),
+    };
+    if finished.status != ExportStatus::Done {
+        std::fs::remove_file(&temp).ok();
+    }
+    if let Ok(mut jobs) = jobs.lock() {
+        jobs.remove(&job.id);
+    }
+    emit(finished);
+}
+
+// Starts exporting the selected requests (all of them without a selection)
+// in the background. Only their ids are taken now; requests deleted before
+// their turn are left out.
+// Each argument is a named field of the one object the frontend passes
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn start_export(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    jobs: tauri::State<'_, ExportJobs>,
+    app_handle: tauri::AppHandle,
+    selection: Option<Selection>,
+    format: ExportFormat,
+    path: String,
+    anonymize_with: Option<AnonymizeProfile>,
+    timestamps: Option<TimestampZone>,
+) -> Result<ExportJob, String> {
+    let zone = timestamps.unwrap_or_default().check()?;
+    let ids: Vec<String> = {
+        let requests = limits::captured(&store_limits, &store)?;
+        let matcher = selection.as_ref().map(Selection::matcher);
+        requests
+            .iter()
+            .filter(|r| matcher.as_ref().is_none_or(|m| m.matches(r)))
+            .map(|r| r.id.clone())
+            .collect()
+    };
+    let job = Job {
+        id: Uuid::new_v4().to_string(),
+        path,
+        ids,
+        format,
+        anonymize_with,
+        zone,
+        cancelled: Arc::default(),
+    };
+    let started = ExportJob {
+        job_id: job.id.clone(),
+        total: job.ids.len(),
+    };
+    jobs.lock()
+        .map_err(|e| e.to_string())?
+        .insert(job.id.clone(), job.cancelled.clone());
+
+    let (store, store_limits) = (store.inner().clone(), store_limits.inner().clone());
+    let jobs = jobs.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || run(job, store, store_limits, jobs, app_handle));
+    Ok(started)
+}
+
+// Stops an export at the next chunk; its partial file is removed
+#[tauri::command]
+pub async fn cancel_export(
+    jobs: tauri::State<'_, ExportJobs>,
+    job_id: String,
+) -> Result<(), String> {
+    let jobs = jobs.lock().map_err(|e| e.to_string())?;
+    let cancelled = jobs
+        .get(&job_id)
+        .ok_or_else(|| format!("Export not found: {}", job_id))?;
+    cancelled.store(true, Ordering::Relaxed);
+    Ok(())
+}
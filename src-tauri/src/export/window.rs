@@ -0,0 +1,84 @@
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::blobs;
+use crate::export::anonymize::{anonymize, AnonymizeProfile};
+use crate::limits::{self, StoreLimitState};
+use crate::markers::{now_millis, Marker, MarkerStore};
+use crate::migrate::REQUEST_VERSION;
+use crate::{NetworkRequest, RequestStore};
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct WindowExport {
+    #[ts(type = "number")]
+    pub start_ts: u64,
+    #[ts(type = "number")]
+    pub end_ts: u64,
+    #[ts(type = "number")]
+    pub exported_at: u64,
+    // `migrate::REQUEST_VERSION` the requests are written in
+    pub request_version: u32,
+    pub markers: Vec<Marker>,
+    pub requests: Vec<NetworkRequest>,
+}
+
+// A request belongs to the window if any part of it was in flight during it,
+// so calls that started just before the recording are not cut off
+fn overlaps(request: &NetworkRequest, start_ts: u64, end_ts: u64) -> bool {
+    let finished = request.timestamp + request.duration.unwrap_or(0);
+    request.timestamp <= end_ts && finished >= start_ts
+}
+
+pub fn build_window_export(
+    requests: &[NetworkRequest],
+    markers: &[Marker],
+    start_ts: u64,
+    end_ts: u64,
+) -> WindowExport {
+    WindowExport {
+        start_ts,
+        end_ts,
+        exported_at: now_millis(),
+        request_version: REQUEST_VERSION,
+        markers: markers
+            .iter()
+            .filter(|m| m.timestamp >= start_ts && m.timestamp <= end_ts)
+            .cloned()
+            .collect(),
+        requests: requests
+            .iter()
+            .filter(|r| overlaps(r, start_ts, end_ts))
+            .cloned()
+            .collect(),
+    }
+}
+
+#[tauri::command]
+pub async fn export_window(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    markers: tauri::State<'_, MarkerStore>,
+    start_ts: u64,
+    end_ts: u64,
+    path: Option<String>,
+    anonymize_with: Option<AnonymizeProfile>,
+) -> Result<WindowExport, String> {
+    if end_ts < start_ts {
+        return Err("end_ts must not be before start_ts".to_string());
+    }
+
+    let mut export = {
+        let requests = limits::captured(&store_limits, &store)?;
+        let markers = markers.lock().map_err(|e| e.to_string())?;
+        build_window_export(&requests, &markers, start_ts, end_ts)
+    };
+    blobs::restore(&mut export.requests);
+    anonymize(&mut export.requests, anonymize_with);
+
+    if let Some(path) = path {
+        let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    }
+    Ok(export)
+}
@@ -0,0 +1,135 @@
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::NetworkRequest;
+
+// Caps so one huge payload can't blow up memory on ingest
+const MAX_PATHS: usize = 5_000;
+const MAX_VALUES_PER_PATH: usize = 64;
+const MAX_VALUE_LEN: usize = 256;
+
+// Key paths present in a JSON response body, with array indices collapsed to
+// `[]`: `{"data":{"items":[{"price":3}]}}` indexes `data`, `data.items`,
+// `data.items[]` and `data.items[].price = "3"`. Built once on ingest so
+// `has_field` / `field` filters don't re-parse bodies.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonIndex {
+    paths: HashMap<String, Vec<String>>,
+}
+
+fn scalar_text(value: &Value) -> Option<String> {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Null => "null".to_string(),
+        Value::Array(_) | Value::Object(_) => return None,
+    };
+    (text.len() <= MAX_VALUE_LEN).then_some(text)
+}
+
+impl JsonIndex {
+    pub fn build(value: &Value) -> Self {
+        let mut index = JsonIndex::default();
+        index.visit(String::new(), value);
+        index
+    }
+
+    fn record(&mut self, path: &str, value: &Value) {
+        if path.is_empty() {
+            return;
+        }
+        if !self.paths.contains_key(path) && self.paths.len() >= MAX_PATHS {
+            return;
+        }
+        let values = self.paths.entry(path.to_string()).or_default();
+        if let Some(text) = scalar_text(value) {
+            if values.len() < MAX_VALUES_PER_PATH && !values.contains(&text) {
+                values.push(text);
+            }
+        }
+    }
+
+    fn visit(&mut self, path: String, value: &Value) {
+        self.record(&path, value);
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map {
+                    let child_path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+                    self.visit(child_path, child);
+                }
+            }
+            Value::Array(items) => {
+                let child_path = format!("{}[]", path);
+                for item in items {
+                    self.visit(child_path.clone(), item);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn has_path(&self, path: &str) -> bool {
+        self.paths.contains_key(normalize_path(path).as_str())
+    }
+
+    // Scalars compare as text, so `error.code=429` matches both 429 and "429"
+    pub fn has_value(&self, path: &str, expected: &str) -> bool {
+        self.paths
+            .get(normalize_path(path).as_str())
+            .is_some_and(|values| values.iter().any(|v| v == expected))
+    }
+}
+
+// Accept `$.a[0].b`, `a[*].b` and `a[].b` as the same indexed path
+fn normalize_path(path: &str) -> String {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let path = path.strip_prefix('.').unwrap_or(path);
+
+    let mut normalized = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c == '[' {
+            for inner in chars.by_ref() {
+                if inner == ']' {
+                    break;
+                }
+            }
+            normalized.push_str("[]");
+        } else {
+            normalized.push(c);
+        }
+    }
+    normalized
+}
+
+pub fn index_body(body: &str) -> Option<JsonIndex> {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .map(|value| JsonIndex::build(&value))
+}
+
+pub fn index_request(request: &mut NetworkRequest) {
+    if let Some(response) = request.response.as_mut() {
+        response.json_index = response.body.as_deref().and_then(index_body).map(Box::new);
+    }
+}
+
+// Uses the ingest-time index when present, otherwise indexes on the fly
+// (requests loaded from exports or edited after ingest)
+pub fn with_index<T>(request: &NetworkRequest, f: impl FnOnce(&JsonIndex) -> T) -> Option<T> {
+    let response = request.response.as_ref()?;
+    match &response.json_index {
+        Some(index) => Some(f(index)),
+        None => response
+            .body
+            .as_deref()
+            .and_then(index_body)
+            .map(|index| f(&index)),
+    }
+}
@@ -0,0 +1,316 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use ts_rs::TS;
+
+use crate::classify::ContentClass;
+use crate::fieldindex::with_index;
+use crate::forms;
+use crate::NetworkRequest;
+
+// Structured request predicate shared by rules and bulk commands. Every field
+// that is set must match; an empty filter matches everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct RequestFilter {
+    #[serde(default)]
+    pub url_contains: Option<String>,
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub status_min: Option<u16>,
+    #[serde(default)]
+    pub status_max: Option<u16>,
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub min_duration_ms: Option<u64>,
+    #[serde(default)]
+    pub content_class: Option<ContentClass>,
+    // `Some(true)` only matches requests still waiting for a response
+    #[serde(default)]
+    pub pending: Option<bool>,
+    // JSON key path that must exist in the response body, e.g. `data.items[].price`
+    #[serde(default)]
+    pub has_field: Option<String>,
+    // `path=value` on a response body scalar, e.g. `error.code=429`
+    #[serde(default)]
+    pub field: Option<String>,
+    // `name=value` on a derived field, or just `name` to require that it matched
+    #[serde(default)]
+    pub derived: Option<String>,
+    // `name=value` on a field of a form-encoded request body, or just `name`
+    #[serde(default)]
+    pub form_field: Option<String>,
+    #[serde(default)]
+    pub filter_regex: Option<RegexFilter>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum RegexField {
+    Url,
+    // Each request and response header name and value, searched separately
+    Headers,
+    // The request and response bodies as kept in memory
+    Body,
+}
+
+// `{ "field": "url", "pattern": "/v[0-9]+/users/\\d+" }`. Rust regex syntax,
+// case-sensitive unless the pattern starts with `(?i)`.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct RegexFilter {
+    pub field: RegexField,
+    pub pattern: String,
+    // Compiled on first use and kept with the filter, failures included so a
+    // bad rule isn't recompiled for every request
+    #[serde(skip)]
+    #[ts(skip)]
+    compiled: OnceLock<Result<Regex, String>>,
+}
+
+// The regex engine runs in time linear in the text, so no pattern can hang a
+// filter; these keep the compiled pattern and the text searched bounded too
+const MAX_PATTERN_LEN: usize = 1000;
+const COMPILED_SIZE_LIMIT: usize = 1 << 20;
+const MAX_BODY_BYTES: usize = 1 << 20;
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    if pattern.len() > MAX_PATTERN_LEN {
+        Err(format!(
+            "Regex is too long: {} bytes, at most {}",
+            pattern.len(),
+            MAX_PATTERN_LEN
+        ))
+    } else {
+        RegexBuilder::new(pattern)
+            .size_limit(COMPILED_SIZE_LIMIT)
+            .dfa_size_limit(COMPILED_SIZE_LIMIT)
+            .build()
+            .map_err(|e| format!("Invalid regex: {}", e))
+    }
+}
+
+// At most the first `MAX_BODY_BYTES`, cut at a character boundary
+fn searched(body: &str) -> &str {
+    let mut end = body.len().min(MAX_BODY_BYTES);
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    &body[..end]
+}
+
+impl RegexFilter {
+    fn regex(&self) -> &Result<Regex, String> {
+        self.compiled.get_or_init(|| compile(&self.pattern))
+    }
+
+    fn matches(&self, request: &NetworkRequest) -> bool {
+        // Reported by `RequestFilter::validate`; an invalid pattern matches nothing
+        let Ok(regex) = self.regex() else {
+            return false;
+        };
+        let response = request.response.as_ref();
+        match self.field {
+            RegexField::Url => regex.is_match(&request.url),
+            RegexField::Headers => request
+                .headers
+                .iter()
+                .chain(response.into_iter().flat_map(|r| r.headers.iter()))
+                .any(|(name, value)| regex.is_match(name) || regex.is_match(value)),
+            RegexField::Body => request
+                .body
+                .iter()
+                .chain(response.and_then(|r| r.body.as_ref()))
+                .any(|body| regex.is_match(searched(body))),
+        }
+    }
+}
+
+// Host part of a URL without scheme, credentials or port
+pub fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    if host.starts_with('[') {
+        return host.split(']').next().map(|h| &h[1..]).unwrap_or(host);
+    }
+    host.split(':').next().unwrap_or(host)
+}
+
+impl RequestFilter {
+    // For commands taking a filter, so a bad pattern is reported instead of
+    // quietly matching nothing
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.filter_regex {
+            Some(filter) => filter.regex().as_ref().map(|_| ()).map_err(Clone::clone),
+            None => Ok(()),
+        }
+    }
+
+    pub fn matches(&self, request: &NetworkRequest) -> bool {
+        let status = request.response.as_ref().map(|r| r.status);
+
+        if let Some(needle) = &self.url_contains {
+            if !request.url.to_lowercase().contains(&needle.to_lowercase()) {
+                return false;
+            }
+        }
+        if let Some(host) = &self.host {
+            if !url_host(&request.url).eq_ignore_ascii_case(host) {
+                return false;
+            }
+        }
+        if let Some(method) = &self.method {
+            if !request.method.eq_ignore_ascii_case(method) {
+                return false;
+            }
+        }
+        if let Some(min) = self.status_min {
+            if status.is_none_or(|s| s < min) {
+                return false;
+            }
+        }
+        if let Some(max) = self.status_max {
+            if status.is_none_or(|s| s > max) {
+                return false;
+            }
+        }
+        if let Some(min) = self.min_duration_ms {
+            if request.duration.is_none_or(|d| d < min) {
+                return false;
+            }
+        }
+        if let Some(class) = self.content_class {
+            if request.response.as_ref().and_then(|r| r.content_class) != Some(class) {
+                return false;
+            }
+        }
+        if let Some(pending) = self.pending {
+            if request.state.is_final() == pending {
+                return false;
+            }
+        }
+        if let Some(path) = &self.has_field {
+            if with_index(request, |index| index.has_path(path)) != Some(true) {
+                return false;
+            }
+        }
+        if let Some(field) = &self.field {
+            let (path, expected) = field.split_once('=').unwrap_or((field, ""));
+            let (path, expected) = (path.trim(), expected.trim());
+            if with_index(request, |index| index.has_value(path, expected)) != Some(true) {
+                return false;
+            }
+        }
+        if let Some(derived) = &self.derived {
+            let matched = match derived.split_once('=') {
+                Some((name, expected)) => request
+                    .derived
+                    .get(name.trim())
+                    .is_some_and(|value| value == expected.trim()),
+                None => request.derived.contains_key(derived.trim()),
+            };
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(form_field) = &self.form_field {
+            let (name, expected) = match form_field.split_once('=') {
+                Some((name, expected)) => (name.trim(), Some(expected.trim())),
+                None => (form_field.trim(), None),
+            };
+            let matched = forms::fields(request).is_some_and(|fields| {
+                fields
+                    .iter()
+                    .any(|f| f.name == name && expected.is_none_or(|e| f.value == e))
+            });
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(filter) = &self.filter_regex {
+            if !filter.matches(request) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bodies::Body;
+    use crate::testing::request;
+
+    fn regex_filter(field: RegexField, pattern: &str) -> RequestFilter {
+        let filter: RegexFilter = serde_json::from_value(serde_json::json!({
+            "field": field,
+            "pattern": pattern,
+        }))
+        .unwrap();
+        RequestFilter {
+            filter_regex: Some(filter),
+            ..RequestFilter::default()
+        }
+    }
+
+    #[test]
+    fn reports_bad_patterns_and_matches_nothing_with_them() {
+        let invalid = regex_filter(RegexField::Url, "users/(\\d+");
+        assert!(invalid.validate().unwrap_err().starts_with("Invalid regex"));
+        assert!(!invalid.matches(&request("a", "https://api.example.com/users/(1", 200)));
+
+        let long = regex_filter(RegexField::Url, &"a".repeat(MAX_PATTERN_LEN + 1));
+        assert!(long
+            .validate()
+            .unwrap_err()
+            .starts_with("Regex is too long"));
+    }
+
+    #[test]
+    fn matches_urls_case_sensitively_unless_asked() {
+        let users = request("a", "https://api.example.com/v2/Users/42", 200);
+        assert!(regex_filter(RegexField::Url, "/v[0-9]+/Users/\\d+$").matches(&users));
+        assert!(!regex_filter(RegexField::Url, "/users/").matches(&users));
+        assert!(regex_filter(RegexField::Url, "(?i)/users/").matches(&users));
+    }
+
+    #[test]
+    fn matches_header_names_and_values_separately() {
+        let mut traced = request("a", "https://api.example.com/", 200);
+        traced.headers.push("x-trace-id", "abc123");
+        let response = traced.response.as_mut().unwrap();
+        response.headers.push("content-type", "application/json");
+        assert!(regex_filter(RegexField::Headers, "^x-trace-").matches(&traced));
+        assert!(regex_filter(RegexField::Headers, "^application/json$").matches(&traced));
+        // Not a `name: value` line any more
+        assert!(!regex_filter(RegexField::Headers, "content-type: application").matches(&traced));
+    }
+
+    #[test]
+    fn searches_only_the_start_of_large_bodies() {
+        let mut large = request("a", "https://api.example.com/", 200);
+        // A multi-byte character across the cut
+        let body = format!("{}é{}", "a".repeat(MAX_BODY_BYTES - 1), "needle");
+        assert_eq!(searched(&body).len(), MAX_BODY_BYTES - 1);
+        large.response.as_mut().unwrap().body = Some(Body::from(body));
+        assert!(!regex_filter(RegexField::Body, "needle").matches(&large));
+        assert!(regex_filter(RegexField::Body, "^a+$").matches(&large));
+    }
+
+    #[test]
+    fn finds_the_host_of_any_url() {
+        assert_eq!(
+            url_host("https://user:pw@api.example.com:8443/a?b"),
+            "api.example.com"
+        );
+        assert_eq!(url_host("http://[::1]:8080/health"), "::1");
+        assert_eq!(url_host("example.com/path"), "example.com");
+        assert_eq!(url_host("https://example.com#top"), "example.com");
+    }
+}
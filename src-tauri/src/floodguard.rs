@@ -0,0 +1,181 @@
+// Protects the ingest server from instrumentation that reconnects in a tight
+// loop. Connections are counted per source, the page's Origin when the client
+// sends one and the peer IP otherwise. A source over its concurrent limit or
+// its connect rate is closed right after the handshake with a close frame
+// saying why, so the socket is released instead of piling up.
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ConnectionLimits {
+    // Open connections at once per source
+    pub max_connections: usize,
+    // New connections per source within `window_secs`
+    pub max_connects: usize,
+    #[ts(type = "number")]
+    pub window_secs: u64,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        ConnectionLimits {
+            max_connections: 32,
+            max_connects: 30,
+            window_secs: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ConnectionGuardStatus {
+    pub limits: ConnectionLimits,
+    // Source -> open connections
+    pub open: HashMap<String, usize>,
+    #[ts(type = "number")]
+    pub rejected: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct FloodGuard {
+    limits: ConnectionLimits,
+    open: HashMap<String, usize>,
+    // Source -> when its recent connects were accepted, oldest first
+    connects: HashMap<String, VecDeque<Instant>>,
+    rejected: u64,
+}
+
+pub type FloodGuardState = Arc<Mutex<FloodGuard>>;
+
+// Why a connection was turned away, sent as its close frame
+#[derive(Debug)]
+pub struct Rejection {
+    pub code: CloseCode,
+    pub reason: String,
+}
+
+// Held for as long as the connection is open
+pub struct Slot {
+    state: FloodGuardState,
+    source: String,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut guard = self.state.lock().unwrap();
+        if let Some(open) = guard.open.get_mut(&self.source) {
+            *open -= 1;
+            if *open == 0 {
+                guard.open.remove(&self.source);
+            }
+        }
+    }
+}
+
+pub fn source(origin: Option<&str>, ip: &str) -> String {
+    match origin {
+        Some(origin) if !origin.is_empty() && origin != "null" => origin.to_string(),
+        _ => ip.to_string(),
+    }
+}
+
+// Close frame reasons are limited to 123 bytes
+fn reason(text: String) -> String {
+    if text.len() <= 123 {
+        return text;
+    }
+    let mut end = 120;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...", &text[..end])
+}
+
+pub fn admit(state: &FloodGuardState, source: &str) -> Result<Slot, Rejection> {
+    let mut guard = state.lock().unwrap();
+    let limits = guard.limits.clone();
+    let now = Instant::now();
+    let window = Duration::from_secs(limits.window_secs);
+
+    let open = guard.open.get(source).copied().unwrap_or(0);
+    let connects = guard.connects.entry(source.to_string()).or_default();
+    while connects
+        .front()
+        .is_some_and(|at| now.duration_since(*at) >= window)
+    {
+        connects.pop_front();
+    }
+
+    let rejection = if open >= limits.max_connections {
+        Some(Rejection {
+            code: CloseCode::Policy,
+            reason: reason(format!(
+                "Too many open connections from {} (limit {})",
+                source, limits.max_connections
+            )),
+        })
+    } else if connects.len() >= limits.max_connects {
+        Some(Rejection {
+            code: CloseCode::Again,
+            reason: reason(format!(
+                "Reconnecting too fast from {} (limit {} per {}s)",
+                source, limits.max_connects, limits.window_secs
+            )),
+        })
+    } else {
+        connects.push_back(now);
+        None
+    };
+    if let Some(rejection) = rejection {
+        guard.rejected += 1;
+        return Err(rejection);
+    }
+
+    *guard.open.entry(source.to_string()).or_insert(0) += 1;
+    // Forget sources that went quiet
+    if guard.connects.len() > 1024 {
+        guard.connects.retain(|_, connects| {
+            connects
+                .back()
+                .is_some_and(|at| now.duration_since(*at) < window)
+        });
+    }
+    Ok(Slot {
+        state: state.clone(),
+        source: source.to_string(),
+    })
+}
+
+pub fn set_limits(state: &FloodGuardState, limits: ConnectionLimits) -> Result<(), String> {
+    if limits.max_connections == 0 || limits.max_connects == 0 {
+        return Err("Connection limits must be at least 1".to_string());
+    }
+    state.lock().map_err(|e| e.to_string())?.limits = limits;
+    Ok(())
+}
+
+// Applies to new connections; open ones are left alone
+#[tauri::command]
+pub async fn set_connection_limits(
+    state: tauri::State<'_, FloodGuardState>,
+    limits: ConnectionLimits,
+) -> Result<(), String> {
+    set_limits(&state, limits)
+}
+
+#[tauri::command]
+pub async fn get_connection_guard_status(
+    state: tauri::State<'_, FloodGuardState>,
+) -> Result<ConnectionGuardStatus, String> {
+    let guard = state.lock().map_err(|e| e.to_string())?;
+    Ok(ConnectionGuardStatus {
+        limits: guard.limits.clone(),
+        open: guard.open.clone(),
+        rejected: guard.rejected,
+    })
+}
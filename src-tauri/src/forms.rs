@@ -0,0 +1,120 @@
+// `application/x-www-form-urlencoded` request bodies as name/value pairs, for
+// the details view, the `form_field` filter and `resend_request`, which takes
+// edited fields and encodes them back into the body. Pairs keep the order they
+// were sent in, repeated names included.
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::blobs::{self, BodyPart};
+use crate::headers::Headers;
+use crate::limits::{self, StoreLimitState};
+use crate::{NetworkRequest, RequestStore};
+
+pub const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct FormField {
+    pub name: String,
+    pub value: String,
+}
+
+pub(crate) fn is_form(headers: &Headers) -> bool {
+    headers.get("content-type").is_some_and(|content_type| {
+        let mime = content_type.split(';').next().unwrap_or_default();
+        mime.trim().eq_ignore_ascii_case(FORM_CONTENT_TYPE)
+    })
+}
+
+fn hex(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
+
+// `+` is a space; a `%` not followed by two hex digits is kept as it is
+fn decode(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i..i + 3) {
+            Some([b'%', high, low]) => hex(*high).zip(hex(*low)),
+            _ => None,
+        };
+        match (escaped, bytes[i]) {
+            (Some((high, low)), _) => {
+                out.push(high << 4 | low);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => out.push(b' '),
+            (None, byte) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// What browsers send: alphanumerics and `*-._` as they are, spaces as `+`
+pub(crate) fn encode_component(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                out.push(byte as char)
+            }
+            b' ' => out.push('+'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+pub fn parse(body: &str) -> Vec<FormField> {
+    body.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            FormField {
+                name: decode(name),
+                value: decode(value),
+            }
+        })
+        .collect()
+}
+
+pub fn encode(fields: &[FormField]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            format!(
+                "{}={}",
+                encode_component(&field.name),
+                encode_component(&field.value)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+// Fields of the body kept in memory, when it is form-encoded
+pub(crate) fn fields(request: &NetworkRequest) -> Option<Vec<FormField>> {
+    if !is_form(&request.headers) {
+        return None;
+    }
+    request.body.as_deref().map(parse)
+}
+
+#[tauri::command]
+pub async fn get_form_fields(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    id: String,
+) -> Result<Vec<FormField>, String> {
+    let request = limits::find_request(&store_limits, &store, &id)?;
+    if !is_form(&request.headers) {
+        return Err(format!("Request body is not form-encoded: {}", id));
+    }
+    // A spilled body is read back whole
+    let body = blobs::body_of(&request, Some(BodyPart::Request))?;
+    Ok(body.as_deref().map(parse).unwrap_or_default())
+}
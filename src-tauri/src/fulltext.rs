@@ -0,0 +1,320 @@
+// Full-text search over request and response bodies and headers. An in-memory
+// SQLite FTS5 index, contentless so texts aren't kept twice, finds the
+// requests containing the term's words in order; the term is then looked for
+// in those requests to say exactly where it is. Like the request index, it
+// catches up with the store on each search: new requests and responses are
+// indexed, removed ones dropped. The first search over a large capture takes
+// longest; later ones only index what arrived since. Spilled bodies are
+// searched in full, as are requests overflowed to disk.
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+use crate::blobs;
+use crate::headers::Headers;
+use crate::limits::{self, StoreLimitState};
+use crate::{NetworkRequest, RequestStore};
+
+const DEFAULT_LIMIT: usize = 200;
+const MAX_LIMIT: usize = 1000;
+// Per part; `count` still has them all
+const MAX_MATCHES: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum SearchPart {
+    RequestHeaders,
+    RequestBody,
+    ResponseHeaders,
+    ResponseBody,
+}
+
+// Where the term is, in UTF-16 code units like the webview's strings
+#[derive(Debug, Clone, Copy, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct TextMatch {
+    pub start: u32,
+    pub length: u32,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct PartMatches {
+    pub part: SearchPart,
+    // For header parts, the header's position in the list; offsets count in
+    // its `name: value` line
+    pub header: Option<usize>,
+    pub count: usize,
+    pub matches: Vec<TextMatch>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct BodySearchHit {
+    pub request_id: String,
+    pub parts: Vec<PartMatches>,
+}
+
+#[derive(Debug, Default)]
+struct Indexed {
+    rows: Vec<i64>,
+    response: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct TextIndex {
+    // Opened on the first search
+    db: Option<Connection>,
+    // FTS row -> request id
+    rows: HashMap<i64, String>,
+    requests: HashMap<String, Indexed>,
+    next_row: i64,
+}
+
+pub type TextIndexState = Arc<Mutex<TextIndex>>;
+
+fn open() -> Result<Connection, String> {
+    let db = Connection::open_in_memory().map_err(|e| e.to_string())?;
+    db.execute_batch(
+        "CREATE VIRTUAL TABLE documents USING fts5(content, content = '', contentless_delete = 1);",
+    )
+    .map_err(|e| format!("Failed to create the search index: {}", e))?;
+    Ok(db)
+}
+
+fn header_line(name: &str, value: &str) -> String {
+    format!("{}: {}", name, value)
+}
+
+fn header_text(headers: &Headers) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| header_line(name, value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn request_texts(request: &NetworkRequest) -> Vec<String> {
+    let body = blobs::full_text(request.body.as_ref(), request.body_file.as_ref());
+    std::iter::once(header_text(&request.headers))
+        .chain(body)
+        .collect()
+}
+
+fn response_texts(request: &NetworkRequest) -> Vec<String> {
+    let Some(response) = &request.response else {
+        return Vec::new();
+    };
+    let body = blobs::full_text(response.body.as_ref(), response.body_file.as_ref());
+    std::iter::once(header_text(&response.headers))
+        .chain(body)
+        .collect()
+}
+
+impl TextIndex {
+    fn sync(&mut self, requests: &[NetworkRequest]) -> Result<(), String> {
+        if self.db.is_none() {
+            self.db = Some(open()?);
+        }
+        let TextIndex {
+            db,
+            rows,
+            requests: indexed,
+            next_row,
+        } = self;
+        let Some(db) = db.as_mut() else {
+            return Ok(());
+        };
+        let tx = db.transaction().map_err(|e| e.to_string())?;
+        {
+            let mut insert = tx
+                .prepare("INSERT INTO documents (rowid, content) VALUES (?1, ?2)")
+                .map_err(|e| e.to_string())?;
+            let mut delete = tx
+                .prepare("DELETE FROM documents WHERE rowid = ?1")
+                .map_err(|e| e.to_string())?;
+
+            let live: HashSet<&str> = requests.iter().map(|r| r.id.as_str()).collect();
+            let gone: Vec<String> = indexed
+                .keys()
+                .filter(|id| !live.contains(id.as_str()))
+                .cloned()
+                .collect();
+            for id in gone {
+                for row in indexed.remove(&id).unwrap_or_default().rows {
+                    delete.execute(params![row]).map_err(|e| e.to_string())?;
+                    rows.remove(&row);
+                }
+            }
+
+            for request in requests {
+                let entry = indexed.entry(request.id.clone()).or_default();
+                let mut texts = Vec::new();
+                if entry.rows.is_empty() {
+                    texts.extend(request_texts(request));
+                }
+                if !entry.response && request.response.is_some() {
+                    texts.extend(response_texts(request));
+                    entry.response = true;
+                }
+                for text in texts {
+                    *next_row += 1;
+                    insert
+                        .execute(params![*next_row, text])
+                        .map_err(|e| e.to_string())?;
+                    entry.rows.push(*next_row);
+                    rows.insert(*next_row, request.id.clone());
+                }
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    // Ids of requests with a part holding `words` in order
+    fn candidates(&self, words: &[&str]) -> Result<HashSet<String>, String> {
+        let Some(db) = &self.db else {
+            return Ok(HashSet::new());
+        };
+        let phrase = format!("\"{}\"", words.join(" "));
+        let mut select = db
+            .prepare("SELECT rowid FROM documents WHERE documents MATCH ?1")
+            .map_err(|e| e.to_string())?;
+        let found = select
+            .query_map(params![phrase], |row| row.get::<_, i64>(0))
+            .map_err(|e| e.to_string())?;
+        let mut ids = HashSet::new();
+        for row in found {
+            let row = row.map_err(|e| e.to_string())?;
+            if let Some(id) = self.rows.get(&row) {
+                ids.insert(id.clone());
+            }
+        }
+        Ok(ids)
+    }
+}
+
+// Lowercased a char at a time, for the text and the needle alike, folding
+// Unicode case as the index's tokenizer does
+fn fold(text: &str) -> String {
+    text.chars().flat_map(char::to_lowercase).collect()
+}
+
+// Occurrences of `needle`, already folded, ignoring case
+fn find(text: &str, needle: &str) -> (usize, Vec<TextMatch>) {
+    let starts: Vec<usize> = fold(text).match_indices(needle).map(|(i, _)| i).collect();
+    // Folded byte offsets to UTF-16 in `text` in one pass, in text order;
+    // folding can change a char's length, so both are counted
+    let mut chars = text.chars().peekable();
+    let mut folded = 0usize;
+    let mut units = 0u32;
+    // Stops at the char whose folded form holds byte `to`
+    let mut advance = |to: usize| {
+        while let Some(&c) = chars.peek() {
+            let len: usize = c.to_lowercase().map(char::len_utf8).sum();
+            if folded + len > to {
+                break;
+            }
+            folded += len;
+            units += c.len_utf16() as u32;
+            chars.next();
+        }
+        (units, chars.peek().map_or(0, |c| c.len_utf16() as u32))
+    };
+    let mut matches = Vec::new();
+    for &start in starts.iter().take(MAX_MATCHES) {
+        let (from, _) = advance(start);
+        let (last, width) = advance(start + needle.len() - 1);
+        matches.push(TextMatch {
+            start: from,
+            length: last + width - from,
+        });
+    }
+    (starts.len(), matches)
+}
+
+fn part_matches(
+    part: SearchPart,
+    header: Option<usize>,
+    text: &str,
+    needle: &str,
+) -> Option<PartMatches> {
+    let (count, matches) = find(text, needle);
+    (count > 0).then_some(PartMatches {
+        part,
+        header,
+        count,
+        matches,
+    })
+}
+
+fn header_matches(part: SearchPart, headers: &Headers, needle: &str) -> Vec<PartMatches> {
+    headers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (name, value))| {
+            part_matches(part, Some(i), &header_line(name, value), needle)
+        })
+        .collect()
+}
+
+fn matches_in(request: &NetworkRequest, needle: &str) -> Vec<PartMatches> {
+    let mut parts = header_matches(SearchPart::RequestHeaders, &request.headers, needle);
+    let body = blobs::full_text(request.body.as_ref(), request.body_file.as_ref());
+    parts.extend(body.and_then(|body| part_matches(SearchPart::RequestBody, None, &body, needle)));
+    if let Some(response) = &request.response {
+        parts.extend(header_matches(
+            SearchPart::ResponseHeaders,
+            &response.headers,
+            needle,
+        ));
+        let body = blobs::full_text(response.body.as_ref(), response.body_file.as_ref());
+        parts.extend(
+            body.and_then(|body| part_matches(SearchPart::ResponseBody, None, &body, needle)),
+        );
+    }
+    parts
+}
+
+// Requests whose bodies or headers contain `term`, ignoring case, in store
+// order, with where it was found
+#[tauri::command]
+pub async fn search_bodies(
+    state: tauri::State<'_, TextIndexState>,
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    term: String,
+    limit: Option<usize>,
+) -> Result<Vec<BodySearchHit>, String> {
+    let needle = fold(term.trim());
+    let words: Vec<&str> = needle
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.is_empty() {
+        return Err("Search term needs a letter or digit".to_string());
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    // A copy, so spilled bodies are read with the store unlocked
+    let requests = limits::captured(&store_limits, &store)?;
+    let mut index = state.lock().map_err(|e| e.to_string())?;
+    index.sync(&requests)?;
+    let candidates = index.candidates(&words)?;
+    drop(index);
+
+    Ok(requests
+        .iter()
+        .filter(|request| candidates.contains(&request.id))
+        .filter_map(|request| {
+            let parts = matches_in(request, &needle);
+            (!parts.is_empty()).then(|| BodySearchHit {
+                request_id: request.id.clone(),
+                parts,
+            })
+        })
+        .take(limit)
+        .collect())
+}
@@ -0,0 +1,126 @@
+// Entry points for the cargo-fuzz targets in `fuzz/`. Only compiled under
+// `--cfg fuzzing`, which `cargo fuzz` sets, so none of this is public API.
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::bodies::Body;
+use crate::clients::Client;
+use crate::events::{EventSink, Events};
+use crate::headers::Headers;
+use crate::server::{self, IngestState};
+use crate::summaries::RequestSummary;
+use crate::{classify, endpoint, fieldindex, har, jsonpath, NetworkRequest, NetworkResponse};
+
+struct Discard;
+
+impl EventSink for Discard {
+    fn emit_event(&self, _name: &str, _payload: Value) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+// One text frame through the same path a live connection takes, replies included
+pub fn ingest_text(text: &str) {
+    let state = IngestState::new();
+    let client = Client {
+        id: "fuzz".to_string(),
+        peer: "127.0.0.1:0".to_string(),
+    };
+    let sink: Events = Arc::new(Discard);
+    for reply in server::handle_text(&state, &client, text, &sink) {
+        serde_json::to_string(&reply).unwrap();
+    }
+}
+
+fn request(
+    method: String,
+    url: String,
+    body: Option<String>,
+    response: Option<NetworkResponse>,
+) -> NetworkRequest {
+    NetworkRequest {
+        id: "fuzz".to_string(),
+        url,
+        method,
+        headers: Headers::new(),
+        body: body.map(Body::from),
+        body_file: None,
+        response,
+        timestamp: 0,
+        duration: None,
+        initiator: None,
+        context: None,
+        experiments: Default::default(),
+        notes: Vec::new(),
+        tags: Vec::new(),
+        derived: Default::default(),
+        aliases: Vec::new(),
+        state: Default::default(),
+        transitions: Vec::new(),
+        provenance: Vec::new(),
+        pinned: false,
+        hints: Vec::new(),
+        client_id: None,
+        occurrences: None,
+    }
+}
+
+pub fn request_signature(
+    method: String,
+    url: String,
+    body: Option<String>,
+    status: Option<u16>,
+) -> String {
+    let response = status.map(|status| NetworkResponse {
+        status,
+        status_text: String::new(),
+        headers: Headers::new(),
+        body: None,
+        body_file: None,
+        content_class: None,
+        received_bytes: None,
+        server_timing: Vec::new(),
+        json_index: None,
+    });
+    server::create_request_signature(&request(method, url, body, response))
+}
+
+// Content sniffing, JSON indexing and the summary/endpoint derivations that
+// slice bodies and URLs
+pub fn decode_body(url: String, content_type: Option<String>, body: String, path: &str) {
+    let mut headers = Headers::new();
+    if let Some(content_type) = content_type {
+        headers.push("content-type".to_string(), content_type);
+    }
+    let response = NetworkResponse {
+        status: 200,
+        status_text: "OK".to_string(),
+        headers,
+        body: Some(body.as_str().into()),
+        body_file: None,
+        content_class: None,
+        received_bytes: None,
+        server_timing: Vec::new(),
+        json_index: None,
+    };
+    let mut request = request("GET".to_string(), url, Some(body), Some(response));
+
+    classify::classify_request(&mut request);
+    fieldindex::index_request(&mut request);
+    RequestSummary::from_request(&request);
+    endpoint::endpoint_key(&request);
+
+    if let (Ok(segments), Some(value)) = (
+        jsonpath::parse(path),
+        request
+            .body
+            .as_deref()
+            .and_then(|b| serde_json::from_str::<Value>(b).ok()),
+    ) {
+        jsonpath::select(&value, &segments);
+    }
+}
+
+pub fn parse_timestamp(text: &str) -> Option<u64> {
+    har::parse_timestamp(text)
+}
@@ -0,0 +1,472 @@
+use chrono::{Local, Offset, TimeZone};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::bodies::Body;
+use crate::lifecycle::{self, RequestState};
+use crate::{Headers, NetworkRequest, NetworkResponse};
+
+// The subset of HAR 1.2 the observer reads and writes. Unknown fields are
+// ignored so archives from browsers and proxies load as-is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Har {
+    pub log: HarLog,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarLog {
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub creator: HarCreator,
+    #[serde(default)]
+    pub entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HarCreator {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+}
+
+// Fields HAR requires that the observer has nothing to put in write as empty
+// objects and lists
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HarEmpty {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    pub started_date_time: String,
+    #[serde(default)]
+    pub time: f64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    #[serde(default)]
+    pub cache: HarEmpty,
+    #[serde(default)]
+    pub timings: HarTimings,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HarTimings {
+    #[serde(default)]
+    pub send: f64,
+    #[serde(default)]
+    pub wait: f64,
+    #[serde(default)]
+    pub receive: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarHeader {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub http_version: String,
+    #[serde(default)]
+    pub headers: Vec<HarHeader>,
+    #[serde(default)]
+    pub query_string: Vec<HarHeader>,
+    #[serde(default)]
+    pub cookies: Vec<HarEmpty>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+    #[serde(default)]
+    pub headers_size: i64,
+    #[serde(default)]
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPostData {
+    #[serde(default)]
+    pub mime_type: String,
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub status_text: String,
+    #[serde(default)]
+    pub http_version: String,
+    #[serde(default)]
+    pub headers: Vec<HarHeader>,
+    #[serde(default)]
+    pub cookies: Vec<HarEmpty>,
+    #[serde(default)]
+    pub content: Option<HarContent>,
+    #[serde(default)]
+    pub redirect_url: String,
+    #[serde(default)]
+    pub headers_size: i64,
+    #[serde(default)]
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    #[serde(default)]
+    pub size: i64,
+    #[serde(default)]
+    pub mime_type: String,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+pub fn read_har(path: &str) -> Result<Har, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    serde_json::from_str(&text).map_err(|e| format!("{}: not a HAR file: {}", path, e))
+}
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Inverse of `days_from_civil`: days since the epoch -> (year, month, day)
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+// ms since epoch -> `2024-05-01T12:30:00.123Z`
+pub fn format_timestamp(millis: u64) -> String {
+    let millis = millis as i64;
+    let (year, month, day) = civil_from_days(millis.div_euclid(86_400_000));
+    let of_day = millis.rem_euclid(86_400_000);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        of_day / 3_600_000,
+        of_day / 60_000 % 60,
+        of_day / 1_000 % 60,
+        of_day % 1_000
+    )
+}
+
+// How exported timestamps are written: in UTC (`...Z`), in this machine's zone
+// with the offset it had at that moment, or at a fixed offset in minutes east
+// of UTC. Whichever it is, the offset is in the text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum TimestampZone {
+    #[default]
+    Utc,
+    Local,
+    Offset(i32),
+}
+
+impl TimestampZone {
+    pub fn check(self) -> Result<Self, String> {
+        match self {
+            TimestampZone::Offset(minutes) if minutes.abs() >= 24 * 60 => Err(format!(
+                "Timestamp offset must be within a day: {} minutes",
+                minutes
+            )),
+            zone => Ok(zone),
+        }
+    }
+
+    fn offset_minutes(self, millis: u64) -> i32 {
+        match self {
+            TimestampZone::Utc => 0,
+            TimestampZone::Local => Local
+                .timestamp_millis_opt(millis as i64)
+                .single()
+                .map_or(0, |time| time.offset().fix().local_minus_utc() / 60),
+            TimestampZone::Offset(minutes) => minutes,
+        }
+    }
+}
+
+// ms since epoch -> `2024-05-01T14:30:00.123+02:00`, or `...Z` in UTC
+pub fn format_timestamp_in(millis: u64, zone: TimestampZone) -> String {
+    if zone == TimestampZone::Utc {
+        return format_timestamp(millis);
+    }
+    let offset = zone.offset_minutes(millis);
+    let shifted = (millis as i64 + offset as i64 * 60_000).max(0) as u64;
+    let utc = format_timestamp(shifted);
+    format!(
+        "{}{}{:02}:{:02}",
+        utc.trim_end_matches('Z'),
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+// `2024-05-01T12:30:00.123Z` / `...+02:00` -> ms since epoch
+pub fn parse_timestamp(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let (date, time) = text.split_once(['T', ' '])?;
+
+    let mut date_parts = date.split('-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: i64 = date_parts.next()?.parse().ok()?;
+    let day: i64 = date_parts.next()?.parse().ok()?;
+
+    let (clock, offset_minutes) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+        (clock, 0)
+    } else if let Some(split) = time.rfind(['+', '-']) {
+        let (clock, offset) = time.split_at(split);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let offset = offset[1..].replace(':', "");
+        let hours: i64 = offset.get(..2)?.parse().ok()?;
+        let minutes: i64 = offset
+            .get(2..)
+            .filter(|m| !m.is_empty())
+            .map_or(Ok(0), str::parse)
+            .ok()?;
+        (clock, sign * (hours * 60 + minutes))
+    } else {
+        (time, 0)
+    };
+
+    let mut clock_parts = clock.split(':');
+    let hour: i64 = clock_parts.next()?.parse().ok()?;
+    let minute: i64 = clock_parts.next()?.parse().ok()?;
+    let seconds: f64 = clock_parts.next().unwrap_or("0").parse().ok()?;
+
+    // Out-of-range parts would overflow the arithmetic below rather than just
+    // give a wrong date
+    let valid = (0..=9999).contains(&year)
+        && (1..=12).contains(&month)
+        && (1..=31).contains(&day)
+        && (0..=23).contains(&hour)
+        && (0..=59).contains(&minute)
+        && (0.0..61.0).contains(&seconds)
+        && offset_minutes.abs() < 24 * 60;
+    if !valid {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    let millis = ((days * 86_400 + hour * 3_600 + minute * 60 - offset_minutes * 60) * 1_000)
+        as f64
+        + seconds * 1_000.0;
+    (millis >= 0.0).then_some(millis.round() as u64)
+}
+
+fn to_headers(headers: &[HarHeader]) -> Headers {
+    headers
+        .iter()
+        .map(|header| (header.name.clone(), header.value.clone()))
+        .collect()
+}
+
+// Converts an entry into a request as if the client had reported it. Base64
+// bodies are left out, matching how binary responses are reported.
+pub fn entry_to_request(entry: &HarEntry) -> NetworkRequest {
+    let body = entry
+        .response
+        .content
+        .as_ref()
+        .filter(|content| content.encoding.as_deref() != Some("base64"))
+        .and_then(|content| content.text.as_deref())
+        .map(Body::from);
+
+    let mut request = NetworkRequest {
+        id: uuid::Uuid::new_v4().to_string(),
+        url: entry.request.url.clone(),
+        method: entry.request.method.clone(),
+        headers: to_headers(&entry.request.headers),
+        body: entry
+            .request
+            .post_data
+            .as_ref()
+            .and_then(|post| post.text.as_deref())
+            .map(Body::from),
+        body_file: None,
+        // HAR uses status 0 for requests that never got a response
+        response: (entry.response.status != 0).then(|| NetworkResponse {
+            status: entry.response.status,
+            status_text: entry.response.status_text.clone(),
+            headers: to_headers(&entry.response.headers),
+            body,
+            body_file: None,
+            content_class: None,
+            received_bytes: None,
+            server_timing: Vec::new(),
+            json_index: None,
+        }),
+        timestamp: parse_timestamp(&entry.started_date_time).unwrap_or(0),
+        duration: (entry.time >= 0.0).then_some(entry.time.round() as u64),
+        initiator: None,
+        context: None,
+        experiments: Default::default(),
+        notes: Vec::new(),
+        tags: Vec::new(),
+        derived: Default::default(),
+        aliases: Vec::new(),
+        // Inferred from the response by `lifecycle::initialize`, except here
+        // where a missing response means the request failed
+        state: if entry.response.status == 0 {
+            RequestState::Failed
+        } else {
+            RequestState::Complete
+        },
+        transitions: Vec::new(),
+        provenance: Vec::new(),
+        pinned: false,
+        hints: Vec::new(),
+        client_id: None,
+        occurrences: None,
+    };
+    lifecycle::initialize(&mut request);
+    request
+}
+
+fn to_har_headers(headers: &Headers) -> Vec<HarHeader> {
+    headers
+        .iter()
+        .map(|(name, value)| HarHeader {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+        .collect()
+}
+
+fn query_string(url: &str) -> Vec<HarHeader> {
+    let query = url
+        .split('#')
+        .next()
+        .and_then(|url| url.split_once('?'))
+        .map(|(_, query)| query)
+        .unwrap_or_default();
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            HarHeader {
+                name: name.to_string(),
+                value: value.to_string(),
+            }
+        })
+        .collect()
+}
+
+fn mime_type(headers: &Headers) -> String {
+    headers.get("content-type").unwrap_or_default().to_string()
+}
+
+// The reverse of `entry_to_request`. Unknown sizes are -1, as HAR specifies.
+pub fn request_to_entry(request: &NetworkRequest, zone: TimestampZone) -> HarEntry {
+    let time = request.duration.unwrap_or(0) as f64;
+    let response = match &request.response {
+        Some(response) => HarResponse {
+            status: response.status,
+            status_text: response.status_text.clone(),
+            http_version: "HTTP/1.1".to_string(),
+            headers: to_har_headers(&response.headers),
+            cookies: Vec::new(),
+            content: Some(HarContent {
+                size: response.body.as_ref().map_or(0, |body| body.len() as i64),
+                mime_type: mime_type(&response.headers),
+                text: response.body.as_deref().map(str::to_string),
+                encoding: None,
+            }),
+            redirect_url: response
+                .headers
+                .get("location")
+                .unwrap_or_default()
+                .to_string(),
+            headers_size: -1,
+            body_size: response.received_bytes.map_or(-1, |bytes| bytes as i64),
+        },
+        // What browsers write for requests that never got one
+        None => HarResponse {
+            status: 0,
+            status_text: String::new(),
+            http_version: String::new(),
+            headers: Vec::new(),
+            cookies: Vec::new(),
+            content: Some(HarContent {
+                size: 0,
+                mime_type: String::new(),
+                text: None,
+                encoding: None,
+            }),
+            redirect_url: String::new(),
+            headers_size: -1,
+            body_size: -1,
+        },
+    };
+
+    HarEntry {
+        started_date_time: format_timestamp_in(request.timestamp, zone),
+        time,
+        request: HarRequest {
+            method: request.method.clone(),
+            url: request.url.clone(),
+            http_version: "HTTP/1.1".to_string(),
+            headers: to_har_headers(&request.headers),
+            query_string: query_string(&request.url),
+            cookies: Vec::new(),
+            post_data: request.body.as_ref().map(|body| HarPostData {
+                mime_type: mime_type(&request.headers),
+                text: Some(body.to_string()),
+            }),
+            headers_size: -1,
+            body_size: request.body.as_ref().map_or(0, |body| body.len() as i64),
+        },
+        response,
+        cache: HarEmpty {},
+        // Only the total is known; HAR wants the phases to add up to it
+        timings: HarTimings {
+            send: 0.0,
+            wait: time,
+            receive: 0.0,
+        },
+    }
+}
+
+pub fn to_har(requests: &[NetworkRequest], zone: TimestampZone) -> Har {
+    Har {
+        log: HarLog {
+            version: "1.2".to_string(),
+            creator: HarCreator {
+                name: "Network Observer".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            entries: requests
+                .iter()
+                .map(|request| request_to_entry(request, zone))
+                .collect(),
+        },
+    }
+}
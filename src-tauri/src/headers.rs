@@ -0,0 +1,152 @@
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use ts_rs::TS;
+
+// Ordered header multimap. Names keep the casing and order the client sent, and
+// repeated headers (Set-Cookie, Vary, ...) are kept as separate entries instead of
+// being collapsed by a map. Lookups are case-insensitive.
+#[derive(Debug, Clone, Default, PartialEq, Eq, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct Headers(Vec<(String, String)>);
+
+// Header names compare case-insensitively; this is the form used for lookups and grouping
+pub fn canonical_name(name: &str) -> String {
+    name.trim().to_ascii_lowercase()
+}
+
+impl Headers {
+    pub fn new() -> Self {
+        Headers(Vec::new())
+    }
+
+    pub fn push(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.0.push((name.into(), value.into()));
+    }
+
+    // First value for `name`, matching case-insensitively
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.0
+            .iter()
+            .filter(move |(key, _)| key.trim().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.0
+            .retain(|(key, _)| !key.trim().eq_ignore_ascii_case(name));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut String)> {
+        self.0.iter_mut().map(|(name, value)| (&*name, value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Headers {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Headers(
+            iter.into_iter()
+                .map(|(name, value)| (name.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+// Serialized as `[["name", "value"], ...]` so order and duplicates survive the round trip
+impl Serialize for Headers {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for (name, value) in &self.0 {
+            seq.serialize_element(&(name, value))?;
+        }
+        seq.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HeaderEntry {
+    Pair(String, String),
+    Named { name: String, value: String },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HeaderValue {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+struct HeadersVisitor;
+
+impl<'de> Visitor<'de> for HeadersVisitor {
+    type Value = Headers;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a header object or a list of [name, value] pairs")
+    }
+
+    // Legacy clients send a plain object; entries are visited in document order
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Headers, A::Error> {
+        let mut headers = Headers::new();
+        while let Some((name, value)) = map.next_entry::<String, HeaderValue>()? {
+            match value {
+                HeaderValue::Single(value) => headers.push(name, value),
+                HeaderValue::Multiple(values) => {
+                    for value in values {
+                        headers.push(name.clone(), value);
+                    }
+                }
+            }
+        }
+        Ok(headers)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Headers, A::Error> {
+        let mut headers = Headers::new();
+        while let Some(entry) = seq.next_element::<HeaderEntry>()? {
+            match entry {
+                HeaderEntry::Pair(name, value) | HeaderEntry::Named { name, value } => {
+                    headers.push(name, value)
+                }
+            }
+        }
+        Ok(headers)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Headers, E> {
+        Ok(Headers::new())
+    }
+}
+
+impl<'de> Deserialize<'de> for Headers {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Headers, D::Error> {
+        deserializer.deserialize_any(HeadersVisitor)
+    }
+}
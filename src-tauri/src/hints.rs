@@ -0,0 +1,184 @@
+// Plain-language explanations for common failure shapes, attached to the entry
+// at ingest so the likely cause and fix are right next to the error. Each hint
+// only fires on evidence in the entry itself; most requests get none.
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ts_rs::TS;
+
+use crate::filter::url_host;
+use crate::lifecycle::RequestState;
+use crate::NetworkRequest;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "kebab-case")]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub enum HintKind {
+    Cors,
+    ExpiredToken,
+    PayloadTooLarge,
+    Tls,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct ErrorHint {
+    pub kind: HintKind,
+    // What most likely went wrong
+    pub cause: String,
+    // What usually fixes it
+    pub fix: String,
+}
+
+fn hint(kind: HintKind, cause: impl Into<String>, fix: impl Into<String>) -> ErrorHint {
+    ErrorHint {
+        kind,
+        cause: cause.into(),
+        fix: fix.into(),
+    }
+}
+
+// Why the request failed or was aborted, lowercased, when the client said
+fn failure_detail(request: &NetworkRequest) -> Option<String> {
+    if !matches!(request.state, RequestState::Failed | RequestState::Aborted) {
+        return None;
+    }
+    request
+        .transitions
+        .iter()
+        .rev()
+        .find_map(|t| t.detail.as_ref())
+        .map(|detail| detail.to_lowercase())
+}
+
+fn cors(request: &NetworkRequest, detail: Option<&str>) -> Option<ErrorHint> {
+    if detail.is_some_and(|d| d.contains("cors") || d.contains("access-control")) {
+        return Some(hint(
+            HintKind::Cors,
+            "The browser blocked the response because the server didn't allow this origin.",
+            "Have the server answer with Access-Control-Allow-Origin for this origin, and \
+             handle the OPTIONS preflight for non-simple requests.",
+        ));
+    }
+    // Seen by the observer, but a browser would have hidden it from the page
+    let origin = request.headers.get("origin")?;
+    let response = request.response.as_ref()?;
+    let cross_origin = !url_host(origin).eq_ignore_ascii_case(url_host(&request.url));
+    if !cross_origin || response.headers.contains("access-control-allow-origin") {
+        return None;
+    }
+    Some(hint(
+        HintKind::Cors,
+        format!(
+            "The response has no Access-Control-Allow-Origin header, so a browser on {} \
+             can't read it.",
+            origin
+        ),
+        "Have the server allow this origin with Access-Control-Allow-Origin, answering \
+         the OPTIONS preflight too.",
+    ))
+}
+
+// Seconds since the epoch the bearer JWT expired at, if it had
+fn expired_at(request: &NetworkRequest) -> Option<u64> {
+    let authorization = request.headers.get("authorization")?.trim();
+    let (scheme, token) = authorization.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+    let payload = token.trim().split('.').nth(1)?;
+    let claims: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    let exp = claims.get("exp")?.as_u64()?;
+    (exp.saturating_mul(1000) <= request.timestamp).then_some(exp)
+}
+
+fn expired_token(request: &NetworkRequest) -> Option<ErrorHint> {
+    let response = request.response.as_ref()?;
+    if response.status != 401 {
+        return None;
+    }
+    let cause = match expired_at(request) {
+        Some(exp) => match (request.timestamp / 1000).saturating_sub(exp) / 60 {
+            0 => "The bearer token expired just before this request was sent.".to_string(),
+            minutes => format!(
+                "The bearer token expired {} minutes before this request was sent.",
+                minutes
+            ),
+        },
+        None if response
+            .headers
+            .get("www-authenticate")
+            .is_some_and(|v| v.to_lowercase().contains("expired")) =>
+        {
+            "The server reports that the access token has expired.".to_string()
+        }
+        None => return None,
+    };
+    Some(hint(
+        HintKind::ExpiredToken,
+        cause,
+        "Refresh the token before it expires, or retry once with a new token after a 401.",
+    ))
+}
+
+fn payload_too_large(request: &NetworkRequest) -> Option<ErrorHint> {
+    if request.response.as_ref()?.status != 413 {
+        return None;
+    }
+    let size = request
+        .body_file
+        .as_ref()
+        .map(|spilled| spilled.size)
+        .or_else(|| request.body.as_ref().map(|body| body.len() as u64))
+        .or_else(|| request.headers.get("content-length")?.trim().parse().ok());
+    let cause = match size {
+        Some(size) => format!(
+            "The server rejected the {} byte request body as too large.",
+            size
+        ),
+        None => "The server rejected the request body as too large.".to_string(),
+    };
+    Some(hint(
+        HintKind::PayloadTooLarge,
+        cause,
+        "Compress or split the upload, or raise the body size limit on the server or the \
+         proxy in front of it (nginx `client_max_body_size`, for example).",
+    ))
+}
+
+fn tls(detail: Option<&str>) -> Option<ErrorHint> {
+    const SIGNS: &[&str] = &[
+        "certificate",
+        "ssl",
+        "tls",
+        "handshake",
+        "cert_",
+        "self signed",
+    ];
+    let detail = detail?;
+    if !SIGNS.iter().any(|sign| detail.contains(sign)) {
+        return None;
+    }
+    Some(hint(
+        HintKind::Tls,
+        "The secure connection couldn't be set up; the certificate wasn't trusted or the \
+         TLS handshake failed.",
+        "If traffic goes through a debugging or corporate proxy, install and trust its CA \
+         certificate on the device. Otherwise check that the server's certificate is valid \
+         and matches the hostname.",
+    ))
+}
+
+pub fn annotate_request(request: &mut NetworkRequest) {
+    let detail = failure_detail(request);
+    let detail = detail.as_deref();
+    request.hints = [
+        cors(request, detail),
+        expired_token(request),
+        payload_too_large(request),
+        tls(detail),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+}
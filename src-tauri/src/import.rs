@@ -0,0 +1,89 @@
+// Importers for captures made with other tools, for users switching over or
+// receiving files from teammates who use them. Each turns its format into HAR
+// entries, so imported traffic looks exactly like the same traffic loaded from
+// a HAR file.
+use std::path::Path;
+
+use crate::blobs::BlobStore;
+use crate::derived::DerivedFields;
+use crate::experiments::ExperimentRules;
+use crate::har::{self, HarEntry};
+use crate::index::IndexState;
+use crate::limits::StoreLimitState;
+use crate::server;
+use crate::session::{self, LoadMode};
+use crate::storage::{self, StorageState};
+use crate::{NetworkRequest, RequestStore};
+
+pub mod charles;
+pub mod proxyman;
+pub mod saz;
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+fn charles_json(data: &[u8]) -> Result<Vec<HarEntry>, String> {
+    charles::read(&String::from_utf8_lossy(data))
+}
+
+fn charles_binary(_: &[u8]) -> Result<Vec<HarEntry>, String> {
+    Err(
+        "Charles binary sessions can't be read; in Charles, export the session \
+         as a JSON Session File (.chlsj) or HAR instead"
+            .to_string(),
+    )
+}
+
+// Requests from a file in one of the formats above, or None when `path`
+// isn't one of them
+pub fn read(path: &Path) -> Option<Result<Vec<NetworkRequest>, String>> {
+    let parse: fn(&[u8]) -> Result<Vec<HarEntry>, String> = match extension(path).as_str() {
+        "chlsj" => charles_json,
+        "chls" => charles_binary,
+        "proxymanlog" | "proxymanlogv2" => proxyman::read,
+        "saz" => saz::read,
+        _ => return None,
+    };
+    let entries = std::fs::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+        .and_then(|data| parse(&data).map_err(|e| format!("{}: {}", path.display(), e)));
+    Some(entries.map(|entries| entries.iter().map(har::entry_to_request).collect()))
+}
+
+// Adds the requests in a capture file (anything `session::load_requests`
+// reads, including the formats above) to the store the way offline queues are,
+// or replaces it, and returns the store as it is afterwards
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn import_capture(
+    store: tauri::State<'_, RequestStore>,
+    experiment_rules: tauri::State<'_, ExperimentRules>,
+    derived_fields: tauri::State<'_, DerivedFields>,
+    blob_store: tauri::State<'_, BlobStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    storage: tauri::State<'_, StorageState>,
+    index: tauri::State<'_, IndexState>,
+    path: String,
+    mode: Option<LoadMode>,
+) -> Result<Vec<NetworkRequest>, String> {
+    let loaded = session::load_requests(&path)?;
+    if mode.unwrap_or_default() == LoadMode::Replace {
+        // Emptied first, so nothing in the file is skipped as already stored
+        store.lock().map_err(|e| e.to_string())?.clear();
+        storage::replace_all(&storage, &[]);
+    }
+    let imported = server::import_requests(
+        &store,
+        &experiment_rules,
+        &derived_fields,
+        &blob_store,
+        loaded,
+        Some(path),
+    );
+    server::store_imported(&store, &store_limits, &storage, &index, &imported);
+    Ok(store.lock().map_err(|e| e.to_string())?.clone())
+}
@@ -0,0 +1,246 @@
+// Charles sessions exported as JSON (File > Export Session > JSON Session
+// File, `.chlsj`): a list of transactions with the URL split into parts and
+// the headers next to each message's first line. Native `.chls` sessions are
+// serialized Java objects and can't be read outside Charles.
+use serde::Deserialize;
+
+use crate::har::{HarContent, HarEntry, HarHeader, HarPostData, HarRequest, HarResponse};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Transaction {
+    #[serde(default)]
+    method: String,
+    #[serde(default)]
+    protocol_version: String,
+    #[serde(default)]
+    scheme: Option<String>,
+    #[serde(default)]
+    host: String,
+    #[serde(default, alias = "port")]
+    actual_port: Option<u16>,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    query: Option<String>,
+    #[serde(default)]
+    times: Times,
+    #[serde(default)]
+    durations: Durations,
+    #[serde(default)]
+    request: Option<Message>,
+    #[serde(default)]
+    response: Option<Message>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Times {
+    #[serde(default)]
+    start: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Durations {
+    #[serde(default)]
+    total: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Message {
+    #[serde(default)]
+    status: Option<u16>,
+    #[serde(default)]
+    mime_type: Option<String>,
+    #[serde(default)]
+    header: Option<Header>,
+    #[serde(default)]
+    body: Option<MessageBody>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Header {
+    #[serde(default)]
+    first_line: Option<String>,
+    #[serde(default)]
+    headers: Vec<HarHeader>,
+}
+
+// Text bodies come as `text`, others base64 in `encoded`
+#[derive(Debug, Deserialize)]
+struct MessageBody {
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    encoded: Option<String>,
+}
+
+fn url(transaction: &Transaction) -> String {
+    let scheme = transaction.scheme.as_deref().unwrap_or("http");
+    let mut url = format!("{}://{}", scheme, transaction.host);
+    let default_port = if scheme == "https" { 443 } else { 80 };
+    if let Some(port) = transaction.actual_port.filter(|&p| p != default_port) {
+        url.push_str(&format!(":{}", port));
+    }
+    url.push_str(transaction.path.as_deref().unwrap_or(""));
+    if let Some(query) = transaction.query.as_deref().filter(|q| !q.is_empty()) {
+        url.push('?');
+        url.push_str(query);
+    }
+    url
+}
+
+fn headers(message: Option<&Message>) -> Vec<HarHeader> {
+    message
+        .and_then(|m| m.header.as_ref())
+        .map(|h| h.headers.clone())
+        .unwrap_or_default()
+}
+
+// `HTTP/1.1 404 Not Found` -> `Not Found`
+fn status_text(message: &Message) -> String {
+    message
+        .header
+        .as_ref()
+        .and_then(|h| h.first_line.as_deref())
+        .and_then(|line| line.splitn(3, ' ').nth(2))
+        .unwrap_or("")
+        .to_string()
+}
+
+fn to_entry(transaction: Transaction) -> HarEntry {
+    let request_body = transaction
+        .request
+        .as_ref()
+        .and_then(|m| m.body.as_ref())
+        .and_then(|b| b.text.clone());
+    let response = transaction.response.as_ref();
+    HarEntry {
+        started_date_time: transaction.times.start.clone().unwrap_or_default(),
+        time: transaction.durations.total.unwrap_or(-1.0),
+        request: HarRequest {
+            url: url(&transaction),
+            method: transaction.method.clone(),
+            http_version: transaction.protocol_version.clone(),
+            headers: headers(transaction.request.as_ref()),
+            query_string: Vec::new(),
+            cookies: Vec::new(),
+            post_data: request_body.map(|text| HarPostData {
+                mime_type: transaction
+                    .request
+                    .as_ref()
+                    .and_then(|m| m.mime_type.clone())
+                    .unwrap_or_default(),
+                text: Some(text),
+            }),
+            headers_size: -1,
+            body_size: -1,
+        },
+        response: HarResponse {
+            // Sessions that failed or were still open when exported have no
+            // response
+            status: response.and_then(|m| m.status).unwrap_or(0),
+            status_text: response.map(status_text).unwrap_or_default(),
+            http_version: transaction.protocol_version.clone(),
+            headers: headers(response),
+            cookies: Vec::new(),
+            content: response.and_then(|m| {
+                let body = m.body.as_ref()?;
+                let (text, encoding) = match (&body.text, &body.encoded) {
+                    (Some(text), _) => (text.clone(), None),
+                    (None, Some(encoded)) => (encoded.clone(), Some("base64".to_string())),
+                    (None, None) => return None,
+                };
+                Some(HarContent {
+                    size: text.len() as i64,
+                    mime_type: m.mime_type.clone().unwrap_or_default(),
+                    text: Some(text),
+                    encoding,
+                })
+            }),
+            redirect_url: String::new(),
+            headers_size: -1,
+            body_size: -1,
+        },
+        cache: Default::default(),
+        timings: Default::default(),
+    }
+}
+
+pub fn read(text: &str) -> Result<Vec<HarEntry>, String> {
+    let transactions: Vec<Transaction> =
+        serde_json::from_str(text).map_err(|e| format!("not a Charles JSON session: {}", e))?;
+    Ok(transactions.into_iter().map(to_entry).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebuilds_urls_and_status_lines() {
+        let entries = read(
+            r#"[{
+                "method": "POST",
+                "protocolVersion": "HTTP/1.1",
+                "scheme": "https",
+                "host": "api.example.com",
+                "actualPort": 8443,
+                "path": "/users",
+                "query": "page=2",
+                "times": { "start": "2024-01-02T03:04:05.000Z" },
+                "durations": { "total": 42 },
+                "request": {
+                    "mimeType": "application/json",
+                    "header": { "headers": [{ "name": "Accept", "value": "*/*" }] },
+                    "body": { "text": "{\"name\":\"a\"}" }
+                },
+                "response": {
+                    "status": 404,
+                    "header": { "firstLine": "HTTP/1.1 404 Not Found", "headers": [] },
+                    "body": { "encoded": "AAEC" }
+                }
+            }]"#,
+        )
+        .unwrap();
+        let entry = &entries[0];
+        assert_eq!(
+            entry.request.url,
+            "https://api.example.com:8443/users?page=2"
+        );
+        assert_eq!(entry.request.headers[0].name, "Accept");
+        let post = entry.request.post_data.as_ref().unwrap();
+        assert_eq!(
+            (post.mime_type.as_str(), post.text.as_deref()),
+            ("application/json", Some("{\"name\":\"a\"}"))
+        );
+        assert_eq!(
+            (entry.response.status, entry.response.status_text.as_str()),
+            (404, "Not Found")
+        );
+        let content = entry.response.content.as_ref().unwrap();
+        assert_eq!(content.text.as_deref(), Some("AAEC"));
+        assert_eq!(content.encoding.as_deref(), Some("base64"));
+        assert_eq!(entry.time, 42.0);
+    }
+
+    #[test]
+    fn leaves_out_default_ports_and_missing_responses() {
+        let entries = read(
+            r#"[{ "method": "GET", "host": "example.com", "port": 80, "path": "/", "query": "" }]"#,
+        )
+        .unwrap();
+        assert_eq!(entries[0].request.url, "http://example.com/");
+        assert_eq!(entries[0].response.status, 0);
+        assert!(entries[0].response.content.is_none());
+        assert_eq!(entries[0].time, -1.0);
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_session() {
+        assert!(read(r#"{ "log": {} }"#)
+            .unwrap_err()
+            .starts_with("not a Charles JSON session"));
+    }
+}
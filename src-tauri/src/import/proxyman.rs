@@ -0,0 +1,305 @@
+// Proxyman logs (File > Export > Proxyman Log, `.proxymanlogv2`): a zip with
+// one JSON document per flow. The layout isn't documented and has shifted
+// between releases, so flows are read field by field from what the exports
+// seen so far contain, and anything missing is left empty rather than failing
+// the whole file. Proxyman's HAR export loads as a HAR file.
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+use std::io::{Cursor, Read};
+
+use crate::har::{self, HarContent, HarEntry, HarHeader, HarPostData, HarRequest, HarResponse};
+
+// Swift encodes dates as seconds since 2001-01-01 unless told otherwise
+const APPLE_EPOCH_SECS: f64 = 978_307_200.0;
+
+// A string, or an object naming it: `"GET"` or `{"name": "GET"}`
+fn name(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(text) => Some(text.clone()),
+        Value::Object(object) => object
+            .get("name")
+            .or_else(|| object.get("rawValue"))
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        _ => None,
+    }
+}
+
+fn headers(message: &Value) -> Vec<HarHeader> {
+    let header = message.get("header").or_else(|| message.get("headers"));
+    let entries = header
+        .and_then(|h| h.get("entries").or(Some(h)))
+        .and_then(Value::as_array);
+    entries
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.get("isEnabled").and_then(Value::as_bool) != Some(false))
+        .filter_map(|entry| {
+            Some(HarHeader {
+                name: name(entry.get("key").or_else(|| entry.get("name")))?,
+                value: entry.get("value")?.as_str()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+// Decoded when it's text; binary bodies come back as base64
+fn body(message: &Value) -> Option<(String, Option<String>)> {
+    if let Some(text) = message.get("body").and_then(Value::as_str) {
+        return Some((text.to_string(), None));
+    }
+    let encoded = message.get("bodyData")?.as_str()?;
+    let bytes = STANDARD.decode(encoded).ok()?;
+    match String::from_utf8(bytes) {
+        Ok(text) => Some((text, None)),
+        Err(_) => Some((encoded.to_string(), Some("base64".to_string()))),
+    }
+}
+
+fn url(request: &Value) -> String {
+    let full = request
+        .get("fullPath")
+        .or_else(|| request.pointer("/uri/fullPath"))
+        .or_else(|| request.get("url"))
+        .and_then(Value::as_str);
+    if let Some(full) = full.filter(|url| url.contains("://")) {
+        return full.to_string();
+    }
+    let secure = request
+        .get("isSSL")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let host = request.get("host").and_then(Value::as_str).unwrap_or("");
+    let port = request.get("port").and_then(Value::as_u64);
+    let mut url = format!("{}://{}", if secure { "https" } else { "http" }, host);
+    if let Some(port) = port.filter(|&p| p != if secure { 443 } else { 80 }) {
+        url.push_str(&format!(":{}", port));
+    }
+    url.push_str(
+        full.or_else(|| request.get("path").and_then(Value::as_str))
+            .unwrap_or(""),
+    );
+    url
+}
+
+// Seconds as either a Unix or a Swift reference date; a Unix time in seconds
+// is past 1e9 for anything captured since 2001
+fn millis(value: Option<&Value>) -> Option<u64> {
+    let seconds = value?.as_f64()?;
+    let unix = if seconds < 1e9 {
+        seconds + APPLE_EPOCH_SECS
+    } else {
+        seconds
+    };
+    (unix >= 0.0).then(|| (unix * 1_000.0).round() as u64)
+}
+
+fn to_entry(flow: &Value) -> Option<HarEntry> {
+    let request = flow.get("request")?;
+    let response = flow.get("response").filter(|r| !r.is_null());
+    let timing = flow.get("timing");
+    let started = millis(timing.and_then(|t| t.get("requestStartedAt")))
+        .or_else(|| millis(flow.get("createdAt")))
+        .unwrap_or(0);
+    let ended = millis(timing.and_then(|t| t.get("responseEndedAt")));
+
+    let status = response.and_then(|r| r.get("status"));
+    let code = status
+        .and_then(|s| s.as_u64().or_else(|| s.get("code")?.as_u64()))
+        .unwrap_or(0) as u16;
+    let status_text = status
+        .and_then(|s| s.get("phrase"))
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .to_string();
+
+    Some(HarEntry {
+        started_date_time: har::format_timestamp(started),
+        time: ended.map_or(-1.0, |ended| ended.saturating_sub(started) as f64),
+        request: HarRequest {
+            url: url(request),
+            method: name(request.get("method")).unwrap_or_else(|| "GET".to_string()),
+            http_version: String::new(),
+            headers: headers(request),
+            query_string: Vec::new(),
+            cookies: Vec::new(),
+            post_data: body(request)
+                .filter(|(_, encoding)| encoding.is_none())
+                .map(|(text, _)| HarPostData {
+                    mime_type: String::new(),
+                    text: Some(text),
+                }),
+            headers_size: -1,
+            body_size: -1,
+        },
+        response: HarResponse {
+            status: code,
+            status_text,
+            http_version: String::new(),
+            headers: response.map(headers).unwrap_or_default(),
+            cookies: Vec::new(),
+            content: response.and_then(body).map(|(text, encoding)| HarContent {
+                size: text.len() as i64,
+                mime_type: String::new(),
+                text: Some(text),
+                encoding,
+            }),
+            redirect_url: String::new(),
+            headers_size: -1,
+            body_size: -1,
+        },
+        cache: Default::default(),
+        timings: Default::default(),
+    })
+}
+
+// Flows from a document that is one flow or a list of them
+fn flows(document: Value, entries: &mut Vec<HarEntry>) {
+    match document {
+        Value::Array(items) => entries.extend(items.iter().filter_map(to_entry)),
+        flow => entries.extend(to_entry(&flow)),
+    }
+}
+
+// A zipped log, or its flows as plain JSON
+pub fn read(data: &[u8]) -> Result<Vec<HarEntry>, String> {
+    let mut entries = Vec::new();
+    if !data.starts_with(b"PK") {
+        let document: Value =
+            serde_json::from_slice(data).map_err(|e| format!("not a Proxyman log: {}", e))?;
+        flows(document, &mut entries);
+        return Ok(entries);
+    }
+
+    let mut zip = zip::ZipArchive::new(Cursor::new(data))
+        .map_err(|e| format!("not a Proxyman log: {}", e))?;
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i).map_err(|e| e.to_string())?;
+        if file.is_dir() {
+            continue;
+        }
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents).map_err(|e| e.to_string())?;
+        // Logs also carry metadata and attachments
+        if let Ok(document) = serde_json::from_slice::<Value>(&contents) {
+            flows(document, &mut entries);
+        }
+    }
+    // Zip order isn't capture order
+    entries.sort_by_key(|entry| har::parse_timestamp(&entry.started_date_time));
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+
+    fn flow(host: &str, started: f64) -> Value {
+        json!({
+            "request": {
+                "method": { "name": "POST" },
+                "isSSL": true,
+                "host": host,
+                "port": 8443,
+                "path": "/upload",
+                "header": { "entries": [
+                    { "key": { "name": "Accept" }, "value": "*/*", "isEnabled": true },
+                    { "key": "X-Disabled", "value": "1", "isEnabled": false }
+                ] },
+                "body": "payload"
+            },
+            "response": {
+                "status": { "code": 201, "phrase": "Created" },
+                "headers": [{ "name": "Content-Type", "value": "image/png" }],
+                "bodyData": STANDARD.encode([0x89, 0x50, 0xff, 0x00])
+            },
+            "timing": { "requestStartedAt": started, "responseEndedAt": started + 0.25 }
+        })
+    }
+
+    #[test]
+    fn reads_flows_field_by_field() {
+        let entries = read(
+            flow("api.example.com", 1_700_000_000.0)
+                .to_string()
+                .as_bytes(),
+        )
+        .unwrap();
+        let entry = &entries[0];
+        assert_eq!(entry.request.url, "https://api.example.com:8443/upload");
+        assert_eq!(entry.request.method, "POST");
+        let names: Vec<&str> = entry
+            .request
+            .headers
+            .iter()
+            .map(|h| h.name.as_str())
+            .collect();
+        assert_eq!(names, ["Accept"]);
+        assert_eq!(
+            entry.request.post_data.as_ref().unwrap().text.as_deref(),
+            Some("payload")
+        );
+        assert_eq!(
+            (entry.response.status, entry.response.status_text.as_str()),
+            (201, "Created")
+        );
+        // Not UTF-8, so kept as base64
+        let content = entry.response.content.as_ref().unwrap();
+        assert_eq!(content.encoding.as_deref(), Some("base64"));
+        assert_eq!(entry.time, 250.0);
+    }
+
+    #[test]
+    fn reads_swift_and_unix_dates() {
+        assert_eq!(millis(Some(&json!(0.5))), Some(978_307_200_500));
+        assert_eq!(millis(Some(&json!(1_700_000_000))), Some(1_700_000_000_000));
+        assert_eq!(millis(Some(&json!(-978_307_201.0))), None);
+        assert_eq!(millis(Some(&json!("yesterday"))), None);
+    }
+
+    #[test]
+    fn prefers_a_full_url_when_there_is_one() {
+        let request = json!({ "fullPath": "http://example.com:8080/a?b=1", "host": "ignored" });
+        assert_eq!(url(&request), "http://example.com:8080/a?b=1");
+        let request = json!({ "uri": { "fullPath": "/a" }, "host": "example.com", "port": 80 });
+        assert_eq!(url(&request), "http://example.com/a");
+    }
+
+    #[test]
+    fn reads_zipped_logs_in_capture_order() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, content) in [
+            (
+                "later.json",
+                flow("later.example.com", 1_700_000_100.0).to_string(),
+            ),
+            ("meta.plist", "<plist/>".to_string()),
+            (
+                "earlier.json",
+                json!([flow("earlier.example.com", 1_700_000_000.0)]).to_string(),
+            ),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        let data = zip.finish().unwrap().into_inner();
+        let hosts: Vec<String> = read(&data)
+            .unwrap()
+            .iter()
+            .map(|entry| entry.request.url.clone())
+            .collect();
+        assert_eq!(
+            hosts,
+            [
+                "https://earlier.example.com:8443/upload",
+                "https://later.example.com:8443/upload"
+            ]
+        );
+        assert!(read(b"{ not json")
+            .unwrap_err()
+            .starts_with("not a Proxyman log"));
+    }
+}
@@ -0,0 +1,367 @@
+// Fiddler session archives (`.saz`): a zip with three files per session under
+// `raw/`, `NN_c.txt` holding the request as it went over the wire,
+// `NN_s.txt` the response and `NN_m.xml` the session's timers and flags.
+// Messages are stored undecoded, so chunked and compressed bodies are undone
+// here the way the client would have.
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+
+use crate::har::{self, HarContent, HarEntry, HarHeader, HarPostData, HarRequest, HarResponse};
+
+#[derive(Default)]
+struct Session {
+    request: Option<Vec<u8>>,
+    response: Option<Vec<u8>>,
+    metadata: Option<String>,
+}
+
+// An HTTP message split into its start line, headers and body
+struct Message {
+    start_line: String,
+    headers: Vec<HarHeader>,
+    body: Vec<u8>,
+}
+
+fn header<'a>(headers: &'a [HarHeader], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str())
+}
+
+fn dechunk(body: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut rest = body;
+    loop {
+        let line_end = rest.windows(2).position(|w| w == b"\r\n")?;
+        let size = std::str::from_utf8(&rest[..line_end]).ok()?;
+        // Chunk extensions follow a `;`
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        rest = &rest[line_end + 2..];
+        if size == 0 {
+            return Some(out);
+        }
+        out.extend_from_slice(rest.get(..size)?);
+        rest = rest.get(size + 2..)?;
+    }
+}
+
+fn decompress(encoding: &str, body: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let read = match encoding.trim().to_ascii_lowercase().as_str() {
+        "gzip" | "x-gzip" => GzDecoder::new(body).read_to_end(&mut out),
+        // Servers disagree on whether deflate means zlib-wrapped or raw
+        "deflate" => ZlibDecoder::new(body).read_to_end(&mut out).or_else(|_| {
+            out.clear();
+            DeflateDecoder::new(body).read_to_end(&mut out)
+        }),
+        "identity" | "" => return Some(body.to_vec()),
+        _ => return None,
+    };
+    read.ok().map(|_| out)
+}
+
+fn parse(raw: &[u8]) -> Option<Message> {
+    let head_end = raw.windows(4).position(|w| w == b"\r\n\r\n");
+    let (head, body) = match head_end {
+        Some(end) => (&raw[..end], &raw[end + 4..]),
+        None => (raw, &[][..]),
+    };
+    let head = String::from_utf8_lossy(head);
+    let mut lines = head.split("\r\n");
+    let start_line = lines.next()?.trim().to_string();
+    let headers: Vec<HarHeader> = lines
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            Some(HarHeader {
+                name: name.trim().to_string(),
+                value: value.trim().to_string(),
+            })
+        })
+        .collect();
+
+    let mut body = body.to_vec();
+    if header(&headers, "transfer-encoding").is_some_and(|e| e.contains("chunked")) {
+        body = dechunk(&body).unwrap_or(body);
+    }
+    // Listed in the order they were applied
+    if let Some(encodings) = header(&headers, "content-encoding") {
+        for encoding in encodings.rsplit(',') {
+            match decompress(encoding, &body) {
+                Some(decoded) => body = decoded,
+                None => break,
+            }
+        }
+    }
+    Some(Message {
+        start_line,
+        headers,
+        body,
+    })
+}
+
+// Text as is; anything else base64, which `entry_to_request` leaves out
+fn content(body: Vec<u8>) -> Option<(String, Option<String>)> {
+    if body.is_empty() {
+        return None;
+    }
+    match String::from_utf8(body) {
+        Ok(text) => Some((text, None)),
+        Err(e) => Some((STANDARD.encode(e.into_bytes()), Some("base64".to_string()))),
+    }
+}
+
+// `Name="value"` from the tag starting with `<tag`
+fn attribute(xml: &str, tag: &str, name: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}", tag))?;
+    let element = &xml[start..start + xml[start..].find('>')?];
+    let key = format!(" {}=\"", name);
+    let value_start = element.find(&key)? + key.len();
+    let value_end = value_start + element[value_start..].find('"')?;
+    Some(element[value_start..value_end].to_string())
+}
+
+fn to_entry(session: Session) -> Option<HarEntry> {
+    let request = parse(session.request.as_deref()?)?;
+    let response = session.response.as_deref().and_then(parse);
+    let metadata = session.metadata.unwrap_or_default();
+    let timer = |name| {
+        attribute(&metadata, "SessionTimers", name).and_then(|time| har::parse_timestamp(&time))
+    };
+    let started = timer("ClientBeginRequest");
+    let done = timer("ClientDoneResponse");
+
+    let mut parts = request.start_line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next().unwrap_or("/");
+    let http_version = parts.next().unwrap_or("").to_string();
+    // Fiddler writes absolute URLs, except for tunnels
+    let url = if target.contains("://") {
+        target.to_string()
+    } else if method.eq_ignore_ascii_case("CONNECT") {
+        format!("https://{}", target)
+    } else {
+        let host = header(&request.headers, "host").unwrap_or("");
+        format!("http://{}{}", host, target)
+    };
+
+    let (status, status_text) = response
+        .as_ref()
+        .and_then(|r| {
+            let mut parts = r.start_line.splitn(3, ' ');
+            parts.next();
+            let status = parts.next()?.parse().ok()?;
+            Some((status, parts.next().unwrap_or("").to_string()))
+        })
+        .unwrap_or((0, String::new()));
+    let mime_type =
+        |headers: &[HarHeader]| header(headers, "content-type").unwrap_or("").to_string();
+
+    Some(HarEntry {
+        started_date_time: har::format_timestamp(started.unwrap_or(0)),
+        time: match (started, done) {
+            (Some(started), Some(done)) => done.saturating_sub(started) as f64,
+            _ => -1.0,
+        },
+        request: HarRequest {
+            url,
+            method,
+            http_version,
+            post_data: content(request.body)
+                .filter(|(_, encoding)| encoding.is_none())
+                .map(|(text, _)| HarPostData {
+                    mime_type: mime_type(&request.headers),
+                    text: Some(text),
+                }),
+            headers: request.headers,
+            query_string: Vec::new(),
+            cookies: Vec::new(),
+            headers_size: -1,
+            body_size: -1,
+        },
+        response: match response {
+            Some(response) => HarResponse {
+                status,
+                status_text,
+                http_version: response
+                    .start_line
+                    .split(' ')
+                    .next()
+                    .unwrap_or("")
+                    .to_string(),
+                content: content(response.body).map(|(text, encoding)| HarContent {
+                    size: text.len() as i64,
+                    mime_type: mime_type(&response.headers),
+                    text: Some(text),
+                    encoding,
+                }),
+                headers: response.headers,
+                cookies: Vec::new(),
+                redirect_url: String::new(),
+                headers_size: -1,
+                body_size: -1,
+            },
+            // Aborted before the server answered
+            None => HarResponse {
+                status: 0,
+                status_text: String::new(),
+                http_version: String::new(),
+                headers: Vec::new(),
+                cookies: Vec::new(),
+                content: None,
+                redirect_url: String::new(),
+                headers_size: -1,
+                body_size: -1,
+            },
+        },
+        cache: Default::default(),
+        timings: Default::default(),
+    })
+}
+
+pub fn read(data: &[u8]) -> Result<Vec<HarEntry>, String> {
+    let mut zip =
+        zip::ZipArchive::new(Cursor::new(data)).map_err(|e| format!("not a SAZ archive: {}", e))?;
+    // By session number, which is capture order
+    let mut sessions: BTreeMap<u64, Session> = BTreeMap::new();
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i).map_err(|e| e.to_string())?;
+        let name = file.name().replace('\\', "/");
+        let Some(file_name) = name.strip_prefix("raw/") else {
+            continue;
+        };
+        let Some((number, kind)) = file_name.split_once('_') else {
+            continue;
+        };
+        let Ok(number) = number.parse() else {
+            continue;
+        };
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents).map_err(|e| e.to_string())?;
+        let session = sessions.entry(number).or_default();
+        match kind {
+            "c.txt" => session.request = Some(contents),
+            "s.txt" => session.response = Some(contents),
+            "m.xml" => session.metadata = Some(String::from_utf8_lossy(&contents).into_owned()),
+            _ => {}
+        }
+    }
+    if sessions.is_empty() {
+        return Err("not a SAZ archive: no sessions under raw/".to_string());
+    }
+    Ok(sessions.into_values().filter_map(to_entry).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn archive(files: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn dechunks_bodies() {
+        assert_eq!(
+            dechunk(b"4;ext=1\r\nWiki\r\n5\r\npedia\r\n0\r\n\r\n").unwrap(),
+            b"Wikipedia"
+        );
+        assert_eq!(dechunk(b"zz\r\nWiki\r\n"), None);
+        // Cut off inside a chunk
+        assert_eq!(dechunk(b"10\r\nWiki\r\n"), None);
+    }
+
+    #[test]
+    fn undoes_chunking_and_then_compression() {
+        let compressed = gzip(r#"{"ok":true}"#);
+        let mut raw =
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nContent-Encoding: gzip\r\n\r\n"
+                .to_vec();
+        raw.extend(format!("{:x}\r\n", compressed.len()).into_bytes());
+        raw.extend(&compressed);
+        raw.extend(b"\r\n0\r\n\r\n");
+        let message = parse(&raw).unwrap();
+        assert_eq!(message.start_line, "HTTP/1.1 200 OK");
+        assert_eq!(message.body, br#"{"ok":true}"#);
+        // An encoding it doesn't know leaves the body as it was
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Encoding: br\r\n\r\nbrotli";
+        assert_eq!(parse(raw).unwrap().body, b"brotli");
+    }
+
+    #[test]
+    fn reads_attributes_from_the_metadata() {
+        let xml = r#"<Session><SessionTimers ClientBeginRequest="2024-01-02T03:04:05Z" ClientDoneResponse="x" /></Session>"#;
+        assert_eq!(
+            attribute(xml, "SessionTimers", "ClientBeginRequest").as_deref(),
+            Some("2024-01-02T03:04:05Z")
+        );
+        assert_eq!(attribute(xml, "SessionTimers", "Missing"), None);
+        assert_eq!(attribute(xml, "SessionFlags", "ClientBeginRequest"), None);
+    }
+
+    #[test]
+    fn reads_sessions_in_number_order() {
+        let data = archive(&[
+            ("raw/2_c.txt", b"CONNECT api.example.com:443 HTTP/1.1\r\n\r\n".to_vec()),
+            ("raw/10_c.txt", b"GET /late HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec()),
+            ("raw/1_c.txt", b"POST http://example.com/form HTTP/1.1\r\nContent-Type: text/plain\r\n\r\nhello".to_vec()),
+            ("raw/1_s.txt", b"HTTP/1.1 404 Not Found\r\n\r\n".to_vec()),
+            (
+                "raw/1_m.xml",
+                br#"<Session><SessionTimers ClientBeginRequest="2024-01-02T03:04:05.000Z" ClientDoneResponse="2024-01-02T03:04:05.250Z" /></Session>"#.to_vec(),
+            ),
+            ("[Content_Types].xml", b"<Types/>".to_vec()),
+        ]);
+        let entries = read(&data).unwrap();
+        let urls: Vec<&str> = entries.iter().map(|e| e.request.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "http://example.com/form",
+                "https://api.example.com:443",
+                "http://example.com/late"
+            ]
+        );
+        let first = &entries[0];
+        assert_eq!(
+            (first.response.status, first.response.status_text.as_str()),
+            (404, "Not Found")
+        );
+        assert_eq!(
+            first.request.post_data.as_ref().unwrap().text.as_deref(),
+            Some("hello")
+        );
+        assert_eq!(first.time, 250.0);
+        // No response, so aborted
+        assert_eq!(entries[1].response.status, 0);
+    }
+
+    #[test]
+    fn rejects_archives_without_sessions() {
+        let data = archive(&[("notes.txt", b"hi".to_vec())]);
+        assert_eq!(
+            read(&data).unwrap_err(),
+            "not a SAZ archive: no sessions under raw/"
+        );
+        assert!(read(b"plain text")
+            .unwrap_err()
+            .starts_with("not a SAZ archive"));
+    }
+}
@@ -0,0 +1,125 @@
+// Captures dropped into the config's `import_dir` are each loaded into a new
+// named session, called after the file, so captures collected from teammates
+// don't need importing one by one. Saved sessions (`.session`) and HAR files
+// (`.har`) are picked up once their size stops changing, then moved into
+// `imported/` in the same directory so a restart doesn't load them again. A
+// file that fails to load, or to move, is left alone until it changes.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::config;
+use crate::events::{self, AppEvent, Events};
+use crate::lifecycle;
+use crate::provenance::{self, ProvenanceKind};
+use crate::server::{enrich_request, IngestState};
+use crate::session;
+use crate::sessions::{self, SessionInfo};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const EXTENSIONS: &[&str] = &["session", "har"];
+const IMPORTED_DIR: &str = "imported";
+
+#[derive(Default)]
+struct Inbox {
+    dir: Option<PathBuf>,
+    // Size at the last poll, for files that may still be being written
+    sizes: HashMap<PathBuf, u64>,
+    // Files not to touch again until their modified time changes
+    skipped: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+fn importable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+fn import(state: &IngestState, path: &Path) -> Result<SessionInfo, String> {
+    let source = path.to_string_lossy().into_owned();
+    let mut loaded = session::load_requests(&source)?;
+    for request in loaded.iter_mut() {
+        lifecycle::initialize(request);
+        enrich_request(request, &state.experiment_rules, &state.derived_fields);
+        provenance::record(request, ProvenanceKind::Imported, Some(source.clone()));
+        state.blob_store.store(request);
+    }
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| source.clone());
+    sessions::add_imported(&state.sessions, &state.store, &name, loaded)
+}
+
+fn poll(inbox: &mut Inbox, dir: &Path, state: &IngestState, sink: &Events) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() || !importable(&path) {
+            continue;
+        }
+        let modified = metadata.modified().ok();
+        if inbox.skipped.get(&path) == Some(&modified) {
+            continue;
+        }
+        if inbox.sizes.insert(path.clone(), metadata.len()) != Some(metadata.len()) {
+            continue;
+        }
+        inbox.sizes.remove(&path);
+
+        let info = match import(state, &path) {
+            Ok(info) => info,
+            Err(e) => {
+                println!("Not imported {}: {}", path.display(), e);
+                inbox.skipped.insert(path, modified);
+                continue;
+            }
+        };
+        println!(
+            "Imported {} as session {} ({} requests)",
+            path.display(),
+            info.name,
+            info.request_count
+        );
+        let moved = std::fs::create_dir_all(dir.join(IMPORTED_DIR))
+            .and_then(|_| std::fs::rename(&path, dir.join(IMPORTED_DIR).join(entry.file_name())));
+        if let Err(e) = moved {
+            println!("Failed to move {} out of the way: {}", path.display(), e);
+            inbox.skipped.insert(path, modified);
+        }
+        if let Err(e) = events::emit(&**sink, AppEvent::SessionImported(&info)) {
+            println!("Failed to emit session-imported event: {}", e);
+        }
+    }
+}
+
+// Runs for the life of the app, following `import_dir` as the config changes
+pub(crate) async fn watch(state: IngestState, sink: Events) {
+    let mut inbox = Inbox::default();
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        let dir = config::import_dir(&state.config);
+        if dir != inbox.dir {
+            inbox = Inbox {
+                dir: dir.clone(),
+                ..Default::default()
+            };
+        }
+        let Some(dir) = dir else {
+            continue;
+        };
+        let (state, sink) = (state.clone(), sink.clone());
+        inbox = tauri::async_runtime::spawn_blocking(move || {
+            poll(&mut inbox, &dir, &state, &sink);
+            inbox
+        })
+        .await
+        .unwrap_or_default();
+    }
+}
@@ -0,0 +1,412 @@
+// Secondary indexes over the store by host, method and status class, so a
+// filter on any of them only looks at the requests that can match instead of
+// the whole store. Requests are added as they arrive and dropped as the
+// limits evict them. Anything else that changes the store (imports, deletes,
+// switching sessions) leaves the index behind; that is noticed on the next
+// lookup, which rebuilds it first.
+// Candidates still go through `RequestFilter::matches`, so the index only has
+// to hold a superset of the right answer.
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use crate::filter::{url_host, RequestFilter};
+use crate::limits::{self, StoreLimitState};
+use crate::overflow;
+use crate::{NetworkRequest, RequestStore};
+
+#[derive(Debug, Default)]
+pub struct RequestIndex {
+    // Request ids in store order, including evicted ones until `compact`
+    by_host: HashMap<String, Vec<String>>,
+    by_method: HashMap<String, Vec<String>>,
+    // Hundreds digit of the status; requests without a response aren't listed
+    by_status: HashMap<u16, Vec<String>>,
+    // Each request gets the next sequence number as it is added. `order` holds
+    // them in store order, so a request's position is where its number sits,
+    // and evicting one doesn't move the numbers of the rest.
+    sequence: HashMap<String, u64>,
+    order: VecDeque<u64>,
+    next: u64,
+    // Ids evicted since the lists were last compacted
+    dropped: usize,
+    // The store as it was when the index last matched it
+    len: usize,
+    first: Option<String>,
+    last: Option<String>,
+    stale: bool,
+}
+
+pub type IndexState = Arc<Mutex<RequestIndex>>;
+
+fn host_key(request: &NetworkRequest) -> String {
+    url_host(&request.url).to_ascii_lowercase()
+}
+
+fn status_class(request: &NetworkRequest) -> Option<u16> {
+    request
+        .response
+        .as_ref()
+        .map(|response| response.status / 100)
+}
+
+impl RequestIndex {
+    fn matches_store(&self, requests: &[NetworkRequest]) -> bool {
+        !self.stale
+            && self.len == requests.len()
+            && self.first.as_deref() == requests.first().map(|r| r.id.as_str())
+            && self.last.as_deref() == requests.last().map(|r| r.id.as_str())
+    }
+
+    fn remember(&mut self, requests: &[NetworkRequest]) {
+        self.len = requests.len();
+        self.first = requests.first().map(|r| r.id.clone());
+        self.last = requests.last().map(|r| r.id.clone());
+        self.stale = false;
+    }
+
+    fn add(&mut self, request: &NetworkRequest) {
+        let id = &request.id;
+        self.by_host
+            .entry(host_key(request))
+            .or_default()
+            .push(id.clone());
+        self.by_method
+            .entry(request.method.to_ascii_uppercase())
+            .or_default()
+            .push(id.clone());
+        if let Some(class) = status_class(request) {
+            self.by_status.entry(class).or_default().push(id.clone());
+        }
+        self.sequence.insert(id.clone(), self.next);
+        self.order.push_back(self.next);
+        self.next += 1;
+    }
+
+    fn position(&self, id: &str) -> Option<usize> {
+        let sequence = self.sequence.get(id)?;
+        self.order.binary_search(sequence).ok()
+    }
+
+    fn drop_id(&mut self, id: &str) -> bool {
+        let Some(position) = self.position(id) else {
+            return false;
+        };
+        self.sequence.remove(id);
+        self.order.remove(position);
+        self.dropped += 1;
+        true
+    }
+
+    // Clears evicted ids out of the lists once they outnumber the live ones
+    fn compact(&mut self) {
+        if self.dropped <= self.order.len() {
+            return;
+        }
+        let sequence = &self.sequence;
+        for ids in self
+            .by_host
+            .values_mut()
+            .chain(self.by_method.values_mut())
+            .chain(self.by_status.values_mut())
+        {
+            ids.retain(|id| sequence.contains_key(id));
+        }
+        self.by_host.retain(|_, ids| !ids.is_empty());
+        self.by_method.retain(|_, ids| !ids.is_empty());
+        self.by_status.retain(|_, ids| !ids.is_empty());
+        self.dropped = 0;
+    }
+
+    fn rebuild(&mut self, requests: &[NetworkRequest]) {
+        *self = RequestIndex::default();
+        for request in requests {
+            self.add(request);
+        }
+        self.remember(requests);
+    }
+
+    // Ids that may match, from the shortest list the filter narrows to; None
+    // when it doesn't use an indexed field
+    fn candidates(&self, filter: &RequestFilter) -> Option<Vec<&String>> {
+        const NONE: &[String] = &[];
+        let mut lists: Vec<Vec<&String>> = Vec::new();
+        if let Some(host) = &filter.host {
+            let ids = self.by_host.get(&host.to_ascii_lowercase());
+            lists.push(ids.map_or(NONE, Vec::as_slice).iter().collect());
+        }
+        if let Some(method) = &filter.method {
+            let ids = self.by_method.get(&method.to_ascii_uppercase());
+            lists.push(ids.map_or(NONE, Vec::as_slice).iter().collect());
+        }
+        if filter.status_min.is_some() || filter.status_max.is_some() {
+            let min = filter.status_min.unwrap_or(0) / 100;
+            let max = filter.status_max.unwrap_or(999) / 100;
+            lists.push(
+                (min..=max)
+                    .filter_map(|class| self.by_status.get(&class))
+                    .flatten()
+                    .collect(),
+            );
+        }
+        lists.into_iter().min_by_key(Vec::len)
+    }
+}
+
+// Call with the store right after a request was pushed onto it
+pub(crate) fn insert(index: &IndexState, requests: &[NetworkRequest]) {
+    let Some((request, before)) = requests.split_last() else {
+        return;
+    };
+    let mut index = index.lock().unwrap();
+    if index.matches_store(before) {
+        index.add(request);
+        index.remember(requests);
+    } else {
+        index.stale = true;
+    }
+}
+
+// Call with the store right after `limits::enforce` took `evicted` out of it
+pub(crate) fn evict(index: &IndexState, requests: &[NetworkRequest], evicted: &[String]) {
+    if evicted.is_empty() {
+        return;
+    }
+    let mut index = index.lock().unwrap();
+    if index.stale || index.len != requests.len() + evicted.len() {
+        index.stale = true;
+        return;
+    }
+    if !evicted.iter().all(|id| index.drop_id(id)) {
+        index.stale = true;
+        return;
+    }
+    index.compact();
+    index.remember(requests);
+}
+
+// For changes the next lookup might not notice, like a store replaced by one
+// of the same size
+pub(crate) fn invalidate(index: &IndexState) {
+    index.lock().unwrap().stale = true;
+}
+
+// A request that got its response joins its status class. Its old entry, if
+// any, is left for `matches` to turn away.
+pub(crate) fn record_status(index: &IndexState, request: &NetworkRequest) {
+    let Some(class) = status_class(request) else {
+        return;
+    };
+    let mut index = index.lock().unwrap();
+    let ids = index.by_status.entry(class).or_default();
+    if ids.last() != Some(&request.id) {
+        ids.push(request.id.clone());
+    }
+}
+
+// Positions in `requests` of those matching `filter`, in store order
+pub(crate) fn matching(
+    index: &IndexState,
+    requests: &[NetworkRequest],
+    filter: &RequestFilter,
+) -> Vec<usize> {
+    let scan = || {
+        (0..requests.len())
+            .filter(|&i| filter.matches(&requests[i]))
+            .collect()
+    };
+    let mut index = index.lock().unwrap();
+    if !index.matches_store(requests) {
+        index.rebuild(requests);
+    }
+    let Some(candidates) = index.candidates(filter) else {
+        return scan();
+    };
+    let mut positions = Vec::with_capacity(candidates.len());
+    for id in candidates {
+        // Evicted, not yet compacted away
+        let Some(position) = index.position(id) else {
+            continue;
+        };
+        if requests.get(position).map(|r| &r.id) != Some(id) {
+            // Changed in a way the checks above missed
+            index.rebuild(requests);
+            return scan();
+        }
+        positions.push(position);
+    }
+    positions.sort_unstable();
+    positions.dedup();
+    positions.retain(|&i| filter.matches(&requests[i]));
+    positions
+}
+
+#[tauri::command]
+pub async fn find_requests(
+    index: tauri::State<'_, IndexState>,
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    filter: RequestFilter,
+) -> Result<Vec<NetworkRequest>, String> {
+    filter.validate()?;
+    let live: Vec<NetworkRequest> = {
+        let requests = store.lock().map_err(|e| e.to_string())?;
+        matching(&index, &requests, &filter)
+            .into_iter()
+            .map(|i| requests[i].clone())
+            .collect()
+    };
+    let overflow = limits::overflow(&store_limits);
+    Ok(overflow::with_matching(&overflow, live, |r| {
+        filter.matches(r)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::StoreLimits;
+    use crate::testing::{pending, request};
+
+    struct Store {
+        index: IndexState,
+        limits: StoreLimitState,
+        requests: Vec<NetworkRequest>,
+    }
+
+    impl Store {
+        fn new(max_requests: usize) -> Self {
+            let limits = StoreLimitState::default();
+            limits.lock().unwrap().limits = StoreLimits {
+                max_requests: Some(max_requests),
+                ..StoreLimits::default()
+            };
+            Store {
+                index: IndexState::default(),
+                limits,
+                requests: Vec::new(),
+            }
+        }
+
+        // As `ingest_request` does
+        fn ingest(&mut self, request: NetworkRequest) {
+            self.requests.push(request);
+            insert(&self.index, &self.requests);
+            let evicted = limits::enforce(&self.limits, &mut self.requests);
+            evict(&self.index, &self.requests, &evicted);
+        }
+
+        fn find(&self, filter: &RequestFilter) -> Vec<&str> {
+            matching(&self.index, &self.requests, filter)
+                .into_iter()
+                .map(|i| self.requests[i].id.as_str())
+                .collect()
+        }
+
+        // Sequence numbers start over when the index is rebuilt
+        fn next(&self) -> u64 {
+            self.index.lock().unwrap().next
+        }
+    }
+
+    fn host(host: &str) -> RequestFilter {
+        RequestFilter {
+            host: Some(host.to_string()),
+            ..RequestFilter::default()
+        }
+    }
+
+    fn on(id: &str, host: &str, status: u16) -> NetworkRequest {
+        request(id, &format!("https://{}/", host), status)
+    }
+
+    #[test]
+    fn keeps_up_with_evictions_from_the_front() {
+        let mut store = Store::new(3);
+        for i in 0..10 {
+            let on_host = if i % 2 == 0 {
+                "a.example.com"
+            } else {
+                "b.example.com"
+            };
+            store.ingest(on(&i.to_string(), on_host, 200));
+        }
+        assert_eq!(store.find(&host("A.example.com")), ["8"]);
+        assert_eq!(store.find(&host("b.example.com")), ["7", "9"]);
+        assert_eq!(store.next(), 10);
+        let index = store.index.lock().unwrap();
+        assert_eq!(index.order.len(), 3);
+        // Evicted ids leave the lists once they outnumber the live ones
+        let listed: usize = index.by_host.values().map(Vec::len).sum();
+        assert!(listed <= 2 * 3, "{listed} ids listed");
+    }
+
+    #[test]
+    fn keeps_up_with_evictions_behind_a_pinned_request() {
+        let mut store = Store::new(2);
+        let mut pinned = on("pinned", "a.example.com", 200);
+        pinned.pinned = true;
+        store.ingest(pinned);
+        for i in 0..5 {
+            store.ingest(on(&i.to_string(), "a.example.com", 500));
+        }
+        let failed = RequestFilter {
+            status_min: Some(500),
+            ..RequestFilter::default()
+        };
+        assert_eq!(store.find(&host("a.example.com")), ["pinned", "4"]);
+        assert_eq!(store.find(&failed), ["4"]);
+        assert_eq!(store.next(), 6);
+    }
+
+    #[test]
+    fn narrows_to_the_shortest_list() {
+        let mut store = Store::new(10);
+        store.ingest(on("a", "a.example.com", 200));
+        store.ingest(on("b", "a.example.com", 404));
+        store.ingest(on("c", "b.example.com", 404));
+        let filter = RequestFilter {
+            host: Some("a.example.com".to_string()),
+            method: Some("get".to_string()),
+            status_min: Some(400),
+            status_max: Some(499),
+            ..RequestFilter::default()
+        };
+        let index = store.index.lock().unwrap();
+        assert_eq!(index.candidates(&filter).unwrap().len(), 2);
+        assert!(index.candidates(&RequestFilter::default()).is_none());
+        drop(index);
+        assert_eq!(store.find(&filter), ["b"]);
+    }
+
+    #[test]
+    fn rebuilds_after_changes_it_wasnt_told_about() {
+        let mut store = Store::new(10);
+        for i in 0..4 {
+            store.ingest(on(&i.to_string(), "a.example.com", 200));
+        }
+        // A delete, say
+        store.requests.remove(1);
+        assert_eq!(store.find(&host("a.example.com")), ["0", "2", "3"]);
+        assert_eq!(store.next(), 3);
+        // A store replaced by one of the same size
+        store.requests[0] = on("x", "b.example.com", 200);
+        invalidate(&store.index);
+        assert_eq!(store.find(&host("b.example.com")), ["x"]);
+        assert_eq!(store.find(&host("a.example.com")), ["2", "3"]);
+    }
+
+    #[test]
+    fn lists_a_request_under_its_status_once_it_completes() {
+        let mut store = Store::new(10);
+        store.ingest(pending("a", "https://a.example.com/"));
+        let ok = RequestFilter {
+            status_min: Some(200),
+            status_max: Some(299),
+            ..RequestFilter::default()
+        };
+        assert!(store.find(&ok).is_empty());
+        store.requests[0] = on("a", "a.example.com", 204);
+        record_status(&store.index, &store.requests[0]);
+        assert_eq!(store.find(&ok), ["a"]);
+    }
+}
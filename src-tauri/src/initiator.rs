@@ -0,0 +1,160 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use ts_rs::TS;
+
+use crate::limits::{self, StoreLimitState};
+use crate::{NetworkRequest, RequestStore};
+
+// What fired a request, as reported by the client: a component name and/or the
+// call stack at the time the request was made
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct Initiator {
+    #[serde(default)]
+    pub component: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_stack")]
+    pub stack: Vec<StackFrame>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct StackFrame {
+    #[serde(default)]
+    pub function: Option<String>,
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub line: Option<u32>,
+    #[serde(default)]
+    pub column: Option<u32>,
+    // Location in the original source, for clients that resolve frames through
+    // source maps before reporting them (bundled RN code is unreadable otherwise)
+    #[serde(default)]
+    pub original: Option<SourceLocation>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct SourceLocation {
+    pub file: String,
+    #[serde(default)]
+    pub line: Option<u32>,
+    #[serde(default)]
+    pub column: Option<u32>,
+    #[serde(default)]
+    pub function: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StackInput {
+    Frames(Vec<StackFrame>),
+    Raw(String),
+}
+
+// Clients can send structured frames or just forward `new Error().stack`
+fn deserialize_stack<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<StackFrame>, D::Error> {
+    Ok(match Option::<StackInput>::deserialize(deserializer)? {
+        Some(StackInput::Frames(frames)) => frames,
+        Some(StackInput::Raw(raw)) => parse_stack_trace(&raw),
+        None => Vec::new(),
+    })
+}
+
+// Splits `file:line:column` from the right so URLs with ports survive
+fn parse_location(location: &str) -> (Option<String>, Option<u32>, Option<u32>) {
+    let location = location.trim();
+    let Some((rest, last)) = location.rsplit_once(':') else {
+        return (Some(location.to_string()), None, None);
+    };
+    let Ok(last) = last.parse::<u32>() else {
+        return (Some(location.to_string()), None, None);
+    };
+    match rest.rsplit_once(':') {
+        Some((file, line)) => match line.parse::<u32>() {
+            Ok(line) => (Some(file.to_string()), Some(line), Some(last)),
+            Err(_) => (Some(rest.to_string()), Some(last), None),
+        },
+        None => (Some(rest.to_string()), Some(last), None),
+    }
+}
+
+// Understands V8/Hermes (`at fn (file:1:2)`) and JSC/SpiderMonkey (`fn@file:1:2`) frames
+pub fn parse_stack_trace(raw: &str) -> Vec<StackFrame> {
+    raw.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() {
+                return None;
+            }
+
+            if let Some(frame) = line.strip_prefix("at ") {
+                let (function, location) = match frame.rsplit_once(" (") {
+                    Some((function, location)) => {
+                        (Some(function.to_string()), location.trim_end_matches(')'))
+                    }
+                    None => (None, frame),
+                };
+                let (file, line, column) = parse_location(location);
+                return Some(StackFrame {
+                    function,
+                    file,
+                    line,
+                    column,
+                    original: None,
+                });
+            }
+
+            if let Some((function, location)) = line.split_once('@') {
+                let (file, line, column) = parse_location(location);
+                return Some(StackFrame {
+                    function: (!function.is_empty()).then(|| function.to_string()),
+                    file,
+                    line,
+                    column,
+                    original: None,
+                });
+            }
+
+            // Error message lines ("Error: boom") carry no location
+            None
+        })
+        .collect()
+}
+
+impl Initiator {
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        let contains = |value: &Option<String>| {
+            value
+                .as_ref()
+                .map(|v| v.to_lowercase().contains(&query))
+                .unwrap_or(false)
+        };
+
+        contains(&self.component)
+            || self.stack.iter().any(|frame| {
+                contains(&frame.function)
+                    || contains(&frame.file)
+                    || frame.original.as_ref().is_some_and(|original| {
+                        original.file.to_lowercase().contains(&query)
+                            || contains(&original.function)
+                    })
+            })
+    }
+}
+
+#[tauri::command]
+pub async fn search_initiators(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    query: String,
+) -> Result<Vec<NetworkRequest>, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    Ok(requests
+        .iter()
+        .filter(|r| r.initiator.as_ref().is_some_and(|i| i.matches(&query)))
+        .cloned()
+        .collect())
+}
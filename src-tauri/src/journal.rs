@@ -0,0 +1,152 @@
+// Append-only log of every request as it is stored or updated, so a crash
+// doesn't lose the capture. Each line is the entry's latest JSON, or
+// `{ "removed": [...] }` for requests evicted since; the file is removed on a
+// clean exit, so finding one at startup means the last run died.
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::migrate::{decode_request, REQUEST_VERSION};
+use crate::storage::RequestStorage;
+use crate::NetworkRequest;
+
+pub(crate) const JOURNAL_FILE: &str = "journal.jsonl";
+const JOURNAL_FORMAT: &str = "network-observer-journal";
+
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+}
+
+fn append_file(path: &Path) -> Result<File, String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+// The first line says which `migrate::REQUEST_VERSION` the rest is in
+fn header() -> Value {
+    json!({ "format": JOURNAL_FORMAT, "request_version": REQUEST_VERSION })
+}
+
+// The last version of each request in a journal left behind, in the order
+// they were first written. A line cut short by the crash is skipped.
+fn read(path: &Path) -> Vec<NetworkRequest> {
+    let Ok(file) = File::open(path) else {
+        return Vec::new();
+    };
+    let mut version = 1;
+    let mut order = Vec::new();
+    let mut latest: HashMap<String, NetworkRequest> = HashMap::new();
+    for line in BufReader::new(file).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let Ok(value) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if value.get("format").and_then(Value::as_str) == Some(JOURNAL_FORMAT) {
+            version = value
+                .get("request_version")
+                .and_then(Value::as_u64)
+                .unwrap_or(1) as u32;
+            continue;
+        }
+        if let Some(removed) = value.get("removed").and_then(Value::as_array) {
+            for id in removed.iter().filter_map(Value::as_str) {
+                latest.remove(id);
+            }
+            continue;
+        }
+        let request = match decode_request(value, version) {
+            Ok(request) => request,
+            Err(e) => {
+                println!("Skipping unreadable journal entry: {}", e);
+                continue;
+            }
+        };
+        if !latest.contains_key(&request.id) {
+            order.push(request.id.clone());
+        }
+        latest.insert(request.id.clone(), request);
+    }
+    order
+        .into_iter()
+        .filter_map(|id| latest.remove(&id))
+        .collect()
+}
+
+fn write_line(file: &mut File, request: &impl Serialize) -> Result<(), String> {
+    let mut line = serde_json::to_vec(request).map_err(|e| e.to_string())?;
+    line.push(b'\n');
+    // One write per line, so a crash can only cut the last one short. The OS
+    // keeps what was written if the app dies, which is the crash this is for.
+    file.write_all(&line).map_err(|e| e.to_string())
+}
+
+impl Journal {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        Ok(Journal {
+            path: path.to_path_buf(),
+            file: append_file(path)?,
+        })
+    }
+}
+
+impl RequestStorage for Journal {
+    // Whatever a previous run left behind. It is written back straight away,
+    // compacted, so a second crash doesn't lose it either.
+    fn load(&mut self) -> Result<Vec<NetworkRequest>, String> {
+        let recovered = read(&self.path);
+        self.replace_all(&recovered)?;
+        Ok(recovered)
+    }
+
+    fn put(&mut self, request: &NetworkRequest) -> Result<(), String> {
+        write_line(&mut self.file, request)
+    }
+
+    // One write for the batch; a crash can still only cut the last line short
+    fn put_all(&mut self, requests: &[NetworkRequest]) -> Result<(), String> {
+        let mut lines = Vec::new();
+        for request in requests {
+            serde_json::to_writer(&mut lines, request).map_err(|e| e.to_string())?;
+            lines.push(b'\n');
+        }
+        self.file.write_all(&lines).map_err(|e| e.to_string())
+    }
+
+    fn remove(&mut self, ids: &[String]) -> Result<(), String> {
+        write_line(&mut self.file, &json!({ "removed": ids }))
+    }
+
+    // Replaces the journal with one line per request, through a temp file so
+    // a crash meanwhile leaves the old one
+    fn replace_all(&mut self, requests: &[NetworkRequest]) -> Result<(), String> {
+        let temp = self.path.with_extension("jsonl.tmp");
+        let mut file = File::create(&temp)
+            .map_err(|e| format!("Failed to write {}: {}", temp.display(), e))?;
+        write_line(&mut file, &header())?;
+        for request in requests {
+            write_line(&mut file, request)?;
+        }
+        std::fs::rename(&temp, &self.path)
+            .map_err(|e| format!("Failed to replace {}: {}", self.path.display(), e))?;
+        self.file = append_file(&self.path)?;
+        Ok(())
+    }
+
+    // On a clean exit there is nothing to recover
+    fn close(&mut self) {
+        std::fs::remove_file(&self.path).ok();
+    }
+}
@@ -0,0 +1,178 @@
+// One subtree of a JSON body at a time, so the UI can expand a 50 MB payload
+// lazily instead of rendering all of it. The body is parsed once and kept
+// until a different one is asked for, since expanding a tree means many calls
+// against the same body in a row.
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+use crate::blobs::{self, BodyPart};
+use crate::limits::StoreLimitState;
+use crate::RequestStore;
+
+// Children of one node per call; `offset` pages through the rest
+const MAX_CHILDREN: usize = 1000;
+// Longer string values are cut; the full text is in the body
+const MAX_STRING_CHARS: usize = 10_000;
+
+#[derive(Default)]
+pub struct ParsedBody {
+    key: Option<(String, BodyPart)>,
+    value: Arc<Value>,
+}
+
+pub type JsonTreeCache = Arc<Mutex<ParsedBody>>;
+
+#[derive(Debug, Clone, Copy, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum JsonKind {
+    Object,
+    Array,
+    String,
+    Number,
+    Bool,
+    Null,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct JsonNode {
+    // Member name or array index; empty for the root
+    pub key: String,
+    // JSON Pointer to this node, for expanding it later
+    pub pointer: String,
+    pub kind: JsonKind,
+    // Scalars only
+    #[ts(type = "string | number | boolean | null")]
+    pub value: Option<Value>,
+    // Members of an object or items of an array
+    pub size: usize,
+    // While within the requested depth; null below it
+    pub children: Option<Vec<JsonNode>>,
+    // The string value was cut, or not all children are listed
+    pub truncated: bool,
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn node(key: String, pointer: String, value: &Value, depth: usize, offset: usize) -> JsonNode {
+    let (kind, size, members): (_, _, Box<dyn Iterator<Item = (String, &Value)>>) = match value {
+        Value::Object(map) => (
+            JsonKind::Object,
+            map.len(),
+            Box::new(map.iter().skip(offset).map(|(k, v)| (k.clone(), v))),
+        ),
+        Value::Array(items) => (
+            JsonKind::Array,
+            items.len(),
+            Box::new(
+                items
+                    .iter()
+                    .enumerate()
+                    .skip(offset)
+                    .map(|(i, v)| (i.to_string(), v)),
+            ),
+        ),
+        _ => {
+            let kind = match value {
+                Value::String(_) => JsonKind::String,
+                Value::Number(_) => JsonKind::Number,
+                Value::Bool(_) => JsonKind::Bool,
+                _ => JsonKind::Null,
+            };
+            let (value, truncated) = match value {
+                Value::String(text) if text.chars().count() > MAX_STRING_CHARS => (
+                    Value::String(text.chars().take(MAX_STRING_CHARS).collect()),
+                    true,
+                ),
+                _ => (value.clone(), false),
+            };
+            return JsonNode {
+                key,
+                pointer,
+                kind,
+                value: Some(value),
+                size: 0,
+                children: None,
+                truncated,
+            };
+        }
+    };
+
+    let children = (depth > 0).then(|| {
+        members
+            .take(MAX_CHILDREN)
+            .map(|(k, v)| {
+                let child_pointer = format!("{}/{}", pointer, escape(&k));
+                node(k, child_pointer, v, depth - 1, 0)
+            })
+            .collect::<Vec<_>>()
+    });
+    let truncated = children
+        .as_ref()
+        .is_some_and(|children| offset + children.len() < size);
+    JsonNode {
+        key,
+        pointer,
+        kind,
+        value: None,
+        size,
+        children,
+        truncated,
+    }
+}
+
+fn parsed(
+    cache: &JsonTreeCache,
+    store: &RequestStore,
+    store_limits: &StoreLimitState,
+    id: &str,
+    part: BodyPart,
+) -> Result<Arc<Value>, String> {
+    let key = (id.to_string(), part);
+    {
+        let cached = cache.lock().map_err(|e| e.to_string())?;
+        if cached.key.as_ref() == Some(&key) {
+            return Ok(cached.value.clone());
+        }
+    }
+    let body =
+        blobs::full_body(store, store_limits, id, Some(part))?.ok_or("Request has no body")?;
+    let value: Value =
+        serde_json::from_str(&body).map_err(|e| format!("Body is not JSON: {}", e))?;
+    let value = Arc::new(value);
+    *cache.lock().map_err(|e| e.to_string())? = ParsedBody {
+        key: Some(key),
+        value: value.clone(),
+    };
+    Ok(value)
+}
+
+// The node at `pointer` ("" for the root) with `depth` levels of children.
+// Depth 0 returns just the node, with its size for the expander.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn get_json_node(
+    cache: tauri::State<'_, JsonTreeCache>,
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    id: String,
+    pointer: String,
+    depth: usize,
+    part: Option<BodyPart>,
+    offset: Option<usize>,
+) -> Result<JsonNode, String> {
+    let value = parsed(&cache, &store, &store_limits, &id, part.unwrap_or_default())?;
+    let target = value
+        .pointer(&pointer)
+        .ok_or_else(|| format!("JSON pointer not found: {}", pointer))?;
+    let key = match pointer.rsplit_once('/') {
+        Some((_, last)) => last.replace("~1", "/").replace("~0", "~"),
+        None => String::new(),
+    };
+    Ok(node(key, pointer, target, depth, offset.unwrap_or(0)))
+}
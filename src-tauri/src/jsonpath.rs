@@ -0,0 +1,140 @@
+use serde_json::Value;
+
+// The JSONPath subset the observer understands:
+//   $.data.items[0].price   keys and array indexes (negative counts from the end)
+//   $.items[*].price        every element (`[]` and `.*` work too)
+//   $..id                   any descendant key
+//   $['odd key']            quoted keys
+// The leading `$` is optional.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Index(i64),
+    Wildcard,
+    Descendant(String),
+}
+
+fn read_key(chars: &[char], mut i: usize) -> (String, usize) {
+    let mut key = String::new();
+    while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+        key.push(chars[i]);
+        i += 1;
+    }
+    (key, i)
+}
+
+pub fn parse(path: &str) -> Result<Vec<Segment>, String> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let chars: Vec<char> = path.chars().collect();
+    let mut segments = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '.' if chars.get(i + 1) == Some(&'.') => {
+                let (key, next) = read_key(&chars, i + 2);
+                if key.is_empty() {
+                    return Err(format!("Expected a key after '..' in {:?}", path));
+                }
+                segments.push(Segment::Descendant(key));
+                i = next;
+            }
+            '.' => {
+                let (key, next) = read_key(&chars, i + 1);
+                match key.as_str() {
+                    "" => return Err(format!("Empty key in {:?}", path)),
+                    "*" => segments.push(Segment::Wildcard),
+                    _ => segments.push(Segment::Key(key)),
+                }
+                i = next;
+            }
+            '[' => {
+                let close = chars[i..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .map(|p| p + i)
+                    .ok_or_else(|| format!("Unclosed '[' in {:?}", path))?;
+                let inner: String = chars[i + 1..close].iter().collect();
+                let inner = inner.trim();
+                if inner.is_empty() || inner == "*" {
+                    segments.push(Segment::Wildcard);
+                } else if inner.len() >= 2
+                    && ((inner.starts_with('\'') && inner.ends_with('\''))
+                        || (inner.starts_with('"') && inner.ends_with('"')))
+                {
+                    segments.push(Segment::Key(inner[1..inner.len() - 1].to_string()));
+                } else {
+                    let index = inner
+                        .parse::<i64>()
+                        .map_err(|_| format!("Invalid index [{}] in {:?}", inner, path))?;
+                    segments.push(Segment::Index(index));
+                }
+                i = close + 1;
+            }
+            _ => {
+                // A bare leading key, as in `data.items`
+                let (key, next) = read_key(&chars, i);
+                segments.push(Segment::Key(key));
+                i = next;
+            }
+        }
+    }
+
+    Ok(segments)
+}
+
+fn collect_descendants<'a>(value: &'a Value, key: &str, out: &mut Vec<&'a Value>) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                if k == key {
+                    out.push(v);
+                }
+                collect_descendants(v, key, out);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_descendants(item, key, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn select<'a>(value: &'a Value, segments: &[Segment]) -> Vec<&'a Value> {
+    let mut current = vec![value];
+    for segment in segments {
+        let mut next = Vec::new();
+        for value in current {
+            match segment {
+                Segment::Key(key) => {
+                    if let Some(v) = value.get(key) {
+                        next.push(v);
+                    }
+                }
+                Segment::Index(index) => {
+                    if let Some(items) = value.as_array() {
+                        let index = if *index < 0 {
+                            items.len() as i64 + index
+                        } else {
+                            *index
+                        };
+                        if let Some(v) = usize::try_from(index).ok().and_then(|i| items.get(i)) {
+                            next.push(v);
+                        }
+                    }
+                }
+                Segment::Wildcard => match value {
+                    Value::Array(items) => next.extend(items.iter()),
+                    Value::Object(map) => next.extend(map.values()),
+                    _ => {}
+                },
+                Segment::Descendant(key) => collect_descendants(value, key, &mut next),
+            }
+        }
+        current = next;
+    }
+    current
+}
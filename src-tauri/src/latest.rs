@@ -0,0 +1,133 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+use crate::bulk::BulkResult;
+use crate::endpoint::endpoint_key;
+use crate::events::{self, AppEvent};
+use crate::{NetworkRequest, RequestStore};
+
+// Superseded entries kept per endpoint when history is on
+const MAX_HISTORY_PER_ENDPOINT: usize = 50;
+
+// "Latest state" mode keeps only the newest request per endpoint in the live
+// store, giving a compact view of the current API state for polling apps
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct LatestStateMode {
+    pub enabled: bool,
+    pub keep_history: bool,
+    #[serde(skip)]
+    pub history: HashMap<String, Vec<NetworkRequest>>,
+}
+
+pub type LatestState = Arc<Mutex<LatestStateMode>>;
+
+fn archive(mode: &mut LatestStateMode, endpoint: String, request: NetworkRequest) {
+    if !mode.keep_history {
+        return;
+    }
+    let history = mode.history.entry(endpoint).or_default();
+    history.push(request);
+    let overflow = history.len().saturating_sub(MAX_HISTORY_PER_ENDPOINT);
+    history.drain(..overflow);
+}
+
+// Removes the entries `request` replaces from the store and returns their ids
+pub fn supersede(
+    latest: &LatestState,
+    requests: &mut Vec<NetworkRequest>,
+    request: &NetworkRequest,
+) -> Vec<String> {
+    let mut mode = latest.lock().unwrap();
+    if !mode.enabled {
+        return Vec::new();
+    }
+
+    let endpoint = endpoint_key(request);
+    let mut replaced = Vec::new();
+    let mut kept = Vec::with_capacity(requests.len());
+    for existing in requests.drain(..) {
+        if endpoint_key(&existing) == endpoint {
+            replaced.push(existing.id.clone());
+            archive(&mut mode, endpoint.clone(), existing);
+        } else {
+            kept.push(existing);
+        }
+    }
+    *requests = kept;
+    replaced
+}
+
+// Collapses the store down to the newest entry per endpoint
+fn compact(mode: &mut LatestStateMode, requests: &mut Vec<NetworkRequest>) -> Vec<String> {
+    let mut newest: HashMap<String, usize> = HashMap::new();
+    for (index, request) in requests.iter().enumerate() {
+        let endpoint = endpoint_key(request);
+        match newest.get(&endpoint) {
+            Some(&current) if requests[current].timestamp > request.timestamp => {}
+            _ => {
+                newest.insert(endpoint, index);
+            }
+        }
+    }
+
+    let mut replaced = Vec::new();
+    let mut kept = Vec::new();
+    for (index, request) in requests.drain(..).enumerate() {
+        let endpoint = endpoint_key(&request);
+        if newest.get(&endpoint) == Some(&index) {
+            kept.push(request);
+        } else {
+            replaced.push(request.id.clone());
+            archive(mode, endpoint, request);
+        }
+    }
+    *requests = kept;
+    replaced
+}
+
+#[tauri::command]
+pub async fn set_latest_state_mode(
+    latest: tauri::State<'_, LatestState>,
+    store: tauri::State<'_, RequestStore>,
+    app_handle: tauri::AppHandle,
+    enabled: bool,
+    keep_history: Option<bool>,
+) -> Result<LatestStateMode, String> {
+    let (mode, replaced) = {
+        let mut mode = latest.lock().map_err(|e| e.to_string())?;
+        mode.enabled = enabled;
+        mode.keep_history = keep_history.unwrap_or(mode.keep_history);
+        if !mode.keep_history {
+            mode.history.clear();
+        }
+
+        let replaced = if enabled {
+            let mut requests = store.lock().map_err(|e| e.to_string())?;
+            compact(&mut mode, &mut requests)
+        } else {
+            Vec::new()
+        };
+        (mode.clone(), replaced)
+    };
+
+    if !replaced.is_empty() {
+        events::emit(
+            &app_handle,
+            AppEvent::RequestsDeleted(&BulkResult { ids: replaced }),
+        )?;
+    }
+    Ok(mode)
+}
+
+// Older entries for an endpoint key as returned by `endpoint_key`, oldest first
+#[tauri::command]
+pub async fn get_endpoint_history(
+    latest: tauri::State<'_, LatestState>,
+    endpoint: String,
+) -> Result<Vec<NetworkRequest>, String> {
+    let mode = latest.lock().map_err(|e| e.to_string())?;
+    Ok(mode.history.get(&endpoint).cloned().unwrap_or_default())
+}
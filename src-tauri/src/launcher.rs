@@ -0,0 +1,163 @@
+// Starting the app under test only once the observer can take its traffic.
+// Requests sent before the ingest server is bound go nowhere, so launching
+// through the observer waits for the port instead of racing its startup.
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use ts_rs::TS;
+
+use crate::agent;
+use crate::markers::now_millis;
+use crate::server;
+
+pub const LAUNCH_FLAG: &str = "--launch";
+// Where the launched app should report to; read by `packages/client-js`
+pub const URL_ENV: &str = "NETWORK_OBSERVER_URL";
+
+const READY_TIMEOUT: Duration = Duration::from_secs(10);
+const PROBE_INTERVAL: Duration = Duration::from_millis(100);
+
+// The ingest server's address, once it is bound
+#[derive(Clone)]
+pub struct Readiness(Arc<watch::Sender<Option<SocketAddr>>>);
+
+impl Default for Readiness {
+    fn default() -> Self {
+        Readiness(Arc::new(watch::channel(None).0))
+    }
+}
+
+impl Readiness {
+    pub(crate) fn mark_bound(&self, addr: SocketAddr) {
+        self.0.send_replace(Some(addr));
+    }
+
+    pub(crate) async fn wait(&self, timeout: Duration) -> Result<SocketAddr, String> {
+        let mut bound = self.0.subscribe();
+        let waited = tokio::time::timeout(timeout, bound.wait_for(Option::is_some)).await;
+        let addr = match waited {
+            Ok(Ok(addr)) => *addr,
+            _ => None,
+        };
+        addr.ok_or_else(|| format!("Ingest server not listening after {}s", timeout.as_secs()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct LaunchedApp {
+    pub pid: Option<u32>,
+    pub program: String,
+    // Passed to the app in NETWORK_OBSERVER_URL
+    pub url: String,
+    #[ts(type = "number")]
+    pub launched_at: u64,
+}
+
+// Starts `program` once the ingest server is bound, with NETWORK_OBSERVER_URL
+// pointing at it. The app keeps running when the observer quits.
+#[tauri::command]
+pub async fn launch_app(
+    readiness: tauri::State<'_, Readiness>,
+    program: String,
+    args: Vec<String>,
+    cwd: Option<String>,
+) -> Result<LaunchedApp, String> {
+    let addr = readiness.wait(READY_TIMEOUT).await?;
+    let url = format!("ws://{}", addr);
+
+    let mut command = tokio::process::Command::new(&program);
+    command.args(&args).env(URL_ENV, &url);
+    if let Some(cwd) = &cwd {
+        command.current_dir(cwd);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+
+    let launched = LaunchedApp {
+        pid: child.id(),
+        program,
+        url,
+        launched_at: now_millis(),
+    };
+    let name = launched.program.clone();
+    // Reaps the process so it doesn't linger as a zombie
+    tokio::spawn(async move {
+        match child.wait().await {
+            Ok(status) => println!("Launched app {} exited: {}", name, status),
+            Err(e) => println!("Failed to wait for launched app {}: {}", name, e),
+        }
+    });
+    Ok(launched)
+}
+
+// Whether something accepts connections on the ingest port. The probe shows
+// up as a connection that never completes the WebSocket handshake.
+fn ingest_listening(addr: &SocketAddr) -> bool {
+    std::net::TcpStream::connect_timeout(addr, PROBE_INTERVAL).is_ok()
+}
+
+fn wait_for_ingest(addr: &SocketAddr, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if ingest_listening(addr) {
+            return true;
+        }
+        std::thread::sleep(PROBE_INTERVAL);
+    }
+    false
+}
+
+// Entry point for `--launch <program> [args...]`: makes sure the UI or a
+// background agent is listening, starting the agent if neither is, then runs
+// the program and exits with its status. What the agent captured is handed to
+// the UI when it opens.
+pub fn run(command: &[String]) -> i32 {
+    let Some((program, args)) = command.split_first() else {
+        eprintln!(
+            "Usage: network-observer {} <program> [args...]",
+            LAUNCH_FLAG
+        );
+        return 2;
+    };
+
+    let addr = server::ingest_addr();
+    if !ingest_listening(&addr) {
+        let started = std::env::current_exe().and_then(|exe| {
+            std::process::Command::new(exe)
+                .arg(agent::AGENT_FLAG)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+        });
+        if let Err(e) = started {
+            eprintln!("Failed to start the capture agent: {}", e);
+            return 1;
+        }
+        if !wait_for_ingest(&addr, READY_TIMEOUT) {
+            eprintln!(
+                "Nothing listening on {} after {}s",
+                addr,
+                READY_TIMEOUT.as_secs()
+            );
+            return 1;
+        }
+    }
+
+    let status = std::process::Command::new(program)
+        .args(args)
+        .env(URL_ENV, format!("ws://{}", addr))
+        .status();
+    match status {
+        Ok(status) => status.code().unwrap_or(1),
+        Err(e) => {
+            eprintln!("Failed to start {}: {}", program, e);
+            1
+        }
+    }
+}
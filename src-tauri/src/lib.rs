@@ -1,276 +1,577 @@
-use futures_util::{StreamExt, SinkExt};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use tauri::Emitter;
-use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::broadcast;
-use tokio_tungstenite::{accept_async, tungstenite::Message};
-use uuid::Uuid;
+use tauri::Manager;
+use ts_rs::TS;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use replay::ReplayStore;
+
+pub mod agent;
+mod alerts;
+mod aliases;
+mod analysis;
+mod archive;
+mod assets;
+mod baselines;
+mod blobs;
+mod bodies;
+mod bulk;
+mod capture;
+mod classify;
+mod clients;
+mod commands;
+pub mod config;
+mod context;
+mod derived;
+mod endpoint;
+pub mod events;
+mod experiments;
+mod export;
+mod fieldindex;
+mod filter;
+pub mod floodguard;
+mod forms;
+mod fulltext;
+#[cfg(fuzzing)]
+pub mod fuzzing;
+mod har;
+mod headers;
+mod hints;
+mod import;
+mod inbox;
+mod index;
+mod initiator;
+mod journal;
+mod json_tree;
+mod jsonpath;
+mod latest;
+mod livestats;
+pub mod launcher;
+pub mod lifecycle;
+pub mod limits;
+mod markers;
+mod migrate;
+mod notes;
+mod occurrences;
+mod overflow;
+mod pages;
+mod paginate;
+pub mod perf;
+mod persist;
+mod pins;
+mod preflight;
+mod protocol;
+mod provenance;
+mod quarantine;
+mod query;
+mod replay;
+mod retention;
+mod rulepacks;
+mod sampling;
+mod screenshot;
+mod secrets;
+pub mod server;
+mod servertiming;
+mod session;
+mod sessions;
+mod snapshots;
+mod stats;
+pub mod storage;
+mod summaries;
+mod syntax;
+mod tags;
+mod telemetry;
+#[cfg(test)]
+mod testing;
+mod text;
+mod tombstones;
+mod triage;
+mod upstream;
+mod watches;
+
+pub use headers::Headers;
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
 pub struct NetworkRequest {
     pub id: String,
     pub url: String,
     pub method: String,
-    pub headers: HashMap<String, String>,
-    pub body: Option<String>,
+    pub headers: Headers,
+    #[ts(type = "string | null")]
+    pub body: Option<bodies::Body>,
+    // Set when the body was too big to keep in memory; `body` is then a preview
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_file: Option<blobs::SpilledBody>,
     pub response: Option<NetworkResponse>,
+    #[ts(type = "number")]
     pub timestamp: u64,
+    #[ts(type = "number | null")]
     pub duration: Option<u64>,
+    #[serde(default)]
+    pub initiator: Option<initiator::Initiator>,
+    #[serde(default)]
+    #[ts(type = "Record<string, unknown> | null")]
+    pub context: Option<context::RequestContext>,
+    #[serde(default)]
+    pub experiments: experiments::ExperimentVariants,
+    #[serde(default)]
+    pub notes: Vec<notes::RequestNote>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub derived: derived::DerivedValues,
+    // Client ids of duplicates that were merged into this entry
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub state: lifecycle::RequestState,
+    #[serde(default)]
+    pub transitions: Vec<lifecycle::StateTransition>,
+    // What the observer changed about the entry since it arrived
+    #[serde(default)]
+    pub provenance: Vec<provenance::ProvenanceEntry>,
+    // Kept when the store is cleared or pruned
+    #[serde(default)]
+    pub pinned: bool,
+    // Likely causes and fixes for a failure, filled in at ingest
+    #[serde(default)]
+    pub hints: Vec<hints::ErrorHint>,
+    // Id of the ingest connection that sent it (see `list_clients`), set by the observer
+    #[serde(default)]
+    pub client_id: Option<String>,
+    // Identical repeats collapsed into it, with `collapse_repeats` on
+    #[serde(default)]
+    pub occurrences: Option<occurrences::Occurrences>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
 pub struct NetworkResponse {
     pub status: u16,
     pub status_text: String,
-    pub headers: HashMap<String, String>,
-    pub body: Option<String>,
+    pub headers: Headers,
+    #[ts(type = "string | null")]
+    pub body: Option<bodies::Body>,
+    // Set when the body was too big to keep in memory; `body` is then a preview
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_file: Option<blobs::SpilledBody>,
+    #[serde(default)]
+    pub content_class: Option<classify::ContentClass>,
+    // Body bytes as they came over the wire, for clients that can tell
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub received_bytes: Option<u64>,
+    // Parsed from Server-Timing headers at ingest
+    #[serde(default)]
+    pub server_timing: Vec<servertiming::ServerTimingMetric>,
+    #[serde(skip)]
+    pub json_index: Option<Box<fieldindex::JsonIndex>>,
 }
 
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[serde(tag = "type")]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
 pub enum WebSocketMessage {
     #[serde(rename = "network-request")]
-    NetworkRequest(NetworkRequest),
+    NetworkRequest(Box<NetworkRequest>),
+    #[serde(rename = "offline-queue")]
+    OfflineQueue(OfflineQueue),
+    #[serde(rename = "marker")]
+    Marker(markers::Marker),
+    #[serde(rename = "hello")]
+    Hello(protocol::Hello),
+    // A request that is still in flight; completed later by `request-end`
+    #[serde(rename = "request-start")]
+    RequestStart(Box<NetworkRequest>),
+    #[serde(rename = "request-end")]
+    RequestEnd(protocol::RequestEnd),
+    // Progress of an in-flight request, e.g. `headers-received`
+    #[serde(rename = "request-state")]
+    RequestState(protocol::RequestStateUpdate),
+    #[serde(rename = "batch")]
+    Batch(protocol::Batch),
 }
 
-type RequestStore = Arc<Mutex<Vec<NetworkRequest>>>;
-type CommandSender = broadcast::Sender<String>;
-
-// Server-side deduplication to prevent infinite loops and duplicates
-type DeduplicationCache = Arc<Mutex<HashMap<String, u64>>>;
+// Requests a client SDK buffered while it had no connection to the observer,
+// flushed in one message once it reconnects
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct OfflineQueue {
+    pub requests: Vec<NetworkRequest>,
+}
 
-fn create_request_signature(request: &NetworkRequest) -> String {
-    let body_hash = request.body.as_ref()
-        .map(|body| {
-            if body.len() > 100 {
-                format!("{}...{}", &body[..50], &body[body.len()-50..])
-            } else {
-                body.clone()
-            }
-        })
-        .unwrap_or_default();
-    
-    let response_status = request.response.as_ref()
-        .map(|r| r.status.to_string())
-        .unwrap_or_else(|| "pending".to_string());
-    
-    format!("{}:{}:{}:{}", request.method, request.url, body_hash, response_status)
+// Directory for files the observer keeps on disk (attachments, exports, ...)
+pub(crate) fn data_dir(app_handle: &tauri::AppHandle) -> PathBuf {
+    app_handle
+        .path()
+        .app_data_dir()
+        .unwrap_or_else(|_| std::env::temp_dir().join("network-observer"))
 }
 
+pub(crate) type RequestStore = Arc<Mutex<Vec<NetworkRequest>>>;
+
 #[tauri::command]
 async fn get_requests(
     store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, limits::StoreLimitState>,
 ) -> Result<Vec<NetworkRequest>, String> {
-    let requests = store.lock().map_err(|e| e.to_string())?;
-    Ok(requests.clone())
+    // Requests spilled to disk by `overflow_to_disk` come first
+    limits::captured(&store_limits, &store)
 }
 
 #[tauri::command]
-async fn clear_requests(store: tauri::State<'_, RequestStore>) -> Result<(), String> {
-    let mut requests = store.lock().map_err(|e| e.to_string())?;
-    requests.clear();
-    Ok(())
-}
-
-
-async fn start_websocket_server(
-    store: RequestStore,
-    command_sender: CommandSender,
-    dedup_cache: DeduplicationCache,
-    app_handle: tauri::AppHandle,
-) {
-    let addr = "127.0.0.1:8085";
-    let listener = TcpListener::bind(addr)
-        .await
-        .expect("Failed to bind WebSocket server");
-    println!("WebSocket server listening on: {}", addr);
-
-    loop {
-        match listener.accept().await {
-            Ok((stream, addr)) => {
-                println!("New WebSocket connection from: {}", addr);
-
-                // Emit connection event to frontend
-                app_handle
-                    .emit("websocket-connected", addr.to_string())
-                    .ok();
-
-                let store = store.clone();
-                let dedup_cache = dedup_cache.clone();
-                let command_receiver = command_sender.subscribe();
-                let app_handle = app_handle.clone();
-                tauri::async_runtime::spawn(handle_connection(
-                    stream,
-                    store,
-                    dedup_cache,
-                    command_receiver,
-                    app_handle,
-                ));
-            }
-            Err(e) => {
-                eprintln!("Failed to accept WebSocket connection: {}", e);
-                // Continue listening despite errors
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-            }
-        }
-    }
+async fn clear_requests(
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, storage::StorageState>,
+    store_limits: tauri::State<'_, limits::StoreLimitState>,
+    tombstones: tauri::State<'_, tombstones::TombstoneState>,
+) -> Result<tombstones::ClearResult, String> {
+    // Pinned requests stay, and the rest can be brought back for a while
+    tombstones::clear(&tombstones, &store, &storage, &store_limits)
 }
 
-async fn handle_connection(
-    stream: TcpStream,
-    store: RequestStore,
-    dedup_cache: DeduplicationCache,
-    mut command_receiver: broadcast::Receiver<String>,
-    app_handle: tauri::AppHandle,
-) {
-    let ws_stream = match accept_async(stream).await {
-        Ok(ws) => ws,
-        Err(e) => {
-            eprintln!("WebSocket connection error: {}", e);
-            return;
-        }
-    };
-
-    let (ws_sender, mut ws_receiver) = ws_stream.split();
-    let ws_sender = Arc::new(tokio::sync::Mutex::new(ws_sender));
 
-    // Spawn a task to handle incoming commands and send them to the client
-    let sender_clone = ws_sender.clone();
-    let command_task = tokio::spawn(async move {
-        while let Ok(command) = command_receiver.recv().await {
-            let mut sender = sender_clone.lock().await;
-            if sender.send(Message::Text(command)).await.is_err() {
-                break;
-            }
-        }
-    });
-
-    // Handle incoming messages from the client
-    while let Some(msg) = ws_receiver.next().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                println!("Received WebSocket message: {}", text.len());
-                
-                // First try to parse as NetworkRequest (the original format)
-                if let Ok(mut request) = serde_json::from_str::<NetworkRequest>(&text) {
-                    if request.id.is_empty() {
-                        request.id = Uuid::new_v4().to_string();
-                    }
-
-                    // Server-side deduplication check
-                    let signature = create_request_signature(&request);
-                    let current_time = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs();
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    let state = server::IngestState::new();
+    let replay_store: ReplayStore = Arc::new(Mutex::new(Vec::new()));
+    let preflight_state: preflight::PreflightState = Default::default();
+    let asset_state: assets::AssetState = Default::default();
+    let triage_state: triage::TriageState = Default::default();
+    let persist_state: persist::PersistState = Default::default();
+    let export_jobs: export::stream::ExportJobs = Default::default();
+    let store_streams: export::chunks::StoreStreams = Default::default();
+    let retention_state: retention::RetentionState = Default::default();
+    let snapshot_store: snapshots::SnapshotStore = Default::default();
+    let json_tree_cache: json_tree::JsonTreeCache = Default::default();
+    let text_index: fulltext::TextIndexState = Default::default();
+    let tombstone_state: tombstones::TombstoneState = Default::default();
+    let archive_state = state.store_limits.lock().unwrap().archive.clone();
+    let sweeper_tombstones = tombstone_state.clone();
+    let exit_store = state.store.clone();
+    let exit_persist = persist_state.clone();
+    let exit_storage = state.storage.clone();
+    let exit_telemetry = state.telemetry.clone();
+    let exit_blobs = state.blob_store.clone();
+    let exit_archive = archive_state.clone();
+    let usage = state.telemetry.clone();
 
-                    let should_process = {
-                        let mut cache = dedup_cache.lock().unwrap();
-                        
-                        // Clean up old entries (older than 5 minutes)
-                        cache.retain(|_, &mut timestamp| current_time - timestamp < 300);
-                        
-                        // Check if we've seen this request recently (within 2 seconds)
-                        if let Some(&last_seen) = cache.get(&signature) {
-                            if current_time - last_seen < 2 {
-                                println!("Server: Duplicate request detected, skipping: {} {}", request.method, request.url);
-                                false
-                            } else {
-                                cache.insert(signature, current_time);
-                                true
-                            }
-                        } else {
-                            cache.insert(signature, current_time);
-                            true
-                        }
-                    };
+    let handler = tauri::generate_handler![
+        get_requests,
+        clear_requests,
+        agent::install_agent,
+        agent::uninstall_agent,
+        agent::get_agent_status,
+        alerts::set_alert_rules,
+        alerts::get_alert_rules,
+        alerts::get_alerts,
+        alerts::set_screenshot_config,
+        aliases::get_request,
+        analysis::auth::analyze_auth_failures,
+        analysis::cdn::get_cdn_report,
+        analysis::compression::analyze_compression,
+        analysis::headers::analyze_headers,
+        analysis::images::analyze_images,
+        analysis::lengths::analyze_content_lengths,
+        archive::search_archive,
+        archive::clear_archive,
+        assets::set_asset_collapse_config,
+        assets::get_asset_collapse_config,
+        assets::get_collapsed_summaries,
+        baselines::get_anomalies,
+        baselines::get_baselines,
+        baselines::set_anomaly_config,
+        baselines::reset_baselines,
+        baselines::save_baselines,
+        baselines::load_baselines,
+        blobs::get_request_body,
+        blobs::fetch_full_body,
+        blobs::set_body_limit,
+        blobs::get_body_limit,
+        blobs::set_body_compression,
+        blobs::get_body_compression,
+        blobs::get_body_storage_stats,
+        bodies::get_body_references,
+        bodies::get_body_dedup_stats,
+        bulk::tag_many,
+        bulk::delete_many,
+        bulk::delete_request,
+        bulk::delete_requests,
+        bulk::delete_matching,
+        bulk::export_many,
+        capture::pause_capture,
+        capture::resume_capture,
+        capture::get_capture_status,
+        capture::set_break_rules,
+        capture::get_break_rules,
+        capture::set_capture_schedule,
+        capture::get_capture_schedule,
+        classify::get_content_class_stats,
+        clients::list_clients,
+        clients::get_requests_for_client,
+        commands::send_command,
+        commands::set_command_channel_config,
+        commands::get_command_channel_config,
+        config::set_config,
+        config::get_config,
+        classify::get_requests_by_content_class,
+        context::get_requests_by_context,
+        context::get_context_stats,
+        derived::set_derived_fields,
+        derived::get_derived_fields,
+        derived::get_derived_field_stats,
+        experiments::set_experiment_rules,
+        experiments::get_experiment_rules,
+        experiments::get_experiment_stats,
+        export::compare::export_session_comparison,
+        export::har::export_har,
+        export::snippet::export_snippet,
+        export::stream::start_export,
+        export::stream::cancel_export,
+        export::chunks::start_store_stream,
+        export::chunks::ack_store_chunk,
+        export::chunks::cancel_store_stream,
+        export::window::export_window,
+        floodguard::set_connection_limits,
+        floodguard::get_connection_guard_status,
+        forms::get_form_fields,
+        import::import_capture,
+        index::find_requests,
+        query::query_requests,
+        fulltext::search_bodies,
+        initiator::search_initiators,
+        json_tree::get_json_node,
+        latest::set_latest_state_mode,
+        latest::get_endpoint_history,
+        launcher::launch_app,
+        limits::set_store_limits,
+        limits::get_store_limits,
+        limits::get_store_stats,
+        livestats::get_stats,
+        livestats::reset_stats,
+        perf::set_perf_mode,
+        perf::get_perf_breakdown,
+        markers::add_marker,
+        markers::get_markers,
+        markers::get_recording_windows,
+        notes::add_note,
+        notes::add_note_attachment,
+        notes::delete_note,
+        notes::set_note,
+        notes::export_bundle,
+        occurrences::get_request_occurrences,
+        pages::get_requests_page,
+        paginate::replay_paginated,
+        persist::list_past_sessions,
+        persist::load_past_session,
+        persist::delete_past_session,
+        pins::pin_request,
+        pins::unpin_request,
+        provenance::get_request_history,
+        preflight::set_collapse_preflights,
+        preflight::get_preflight_mode,
+        preflight::get_preflight_for,
+        quarantine::get_unparsed_messages,
+        quarantine::clear_unparsed_messages,
+        replay::replay_har,
+        replay::get_replay_reports,
+        replay::resend_request,
+        retention::set_retention_policy,
+        retention::get_retention_policy,
+        rulepacks::import_rule_pack,
+        rulepacks::update_rule_packs,
+        rulepacks::set_rule_pack_enabled,
+        rulepacks::remove_rule_pack,
+        rulepacks::list_rule_packs,
+        sampling::set_sampling_rules,
+        sampling::get_sampling_rules,
+        secrets::set_secret,
+        secrets::rotate_secret,
+        secrets::delete_secret,
+        secrets::list_secrets,
+        servertiming::get_server_timings,
+        session::save_session,
+        session::load_session,
+        sessions::create_session,
+        sessions::switch_session,
+        sessions::list_sessions,
+        snapshots::snapshot_store,
+        snapshots::diff_snapshots,
+        storage::get_recovery_status,
+        storage::discard_recovered_requests,
+        summaries::get_request_summaries,
+        summaries::get_total_count,
+        syntax::tokenize_body,
+        tags::tag_request,
+        tags::get_requests_by_tag,
+        telemetry::set_telemetry_enabled,
+        telemetry::get_telemetry_status,
+        telemetry::clear_telemetry,
+        tombstones::undo_clear,
+        triage::get_triage_queue,
+        triage::mark_reviewed,
+        triage::clear_reviewed,
+        upstream::set_upstream_proxy,
+        upstream::set_active_project,
+        upstream::get_upstream_settings,
+        watches::add_watch,
+        watches::remove_watch,
+        watches::list_watches,
+        watches::get_watch_hits
+    ];
 
-                    if should_process {
-                        println!("Parsed request: {} {}", request.method, request.url);
 
-                        {
-                            let mut requests = store.lock().unwrap();
-                            requests.push(request.clone());
-                        }
+    tauri::Builder::default()
+        .plugin(tauri_plugin_opener::init())
+        .manage(state.store.clone())
+        .manage(state.dedup_cache.clone())
+        .manage(state.command_bus.clone())
+        .manage(state.experiment_rules.clone())
+        .manage(state.derived_fields.clone())
+        .manage(state.marker_store.clone())
+        .manage(state.alert_store.clone())
+        .manage(state.watch_store.clone())
+        .manage(state.capture_state.clone())
+        .manage(state.latest_state.clone())
+        .manage(state.store_limits.clone())
+        .manage(state.blob_store.clone())
+        .manage(state.storage.clone())
+        .manage(state.sessions.clone())
+        .manage(state.sampling_state.clone())
+        .manage(state.rule_packs.clone())
+        .manage(state.unparsed_store.clone())
+        .manage(state.client_registry.clone())
+        .manage(state.baseline_state.clone())
+        .manage(state.live_stats.clone())
+        .manage(state.perf.clone())
+        .manage(state.readiness.clone())
+        .manage(state.telemetry.clone())
+        .manage(state.flood_guard.clone())
+        .manage(state.config.clone())
+        .manage(state.index.clone())
+        .manage(replay_store)
+        .manage(preflight_state)
+        .manage(asset_state)
+        .manage(triage_state)
+        .manage(persist_state.clone())
+        .manage(export_jobs)
+        .manage(store_streams)
+        .manage(retention_state.clone())
+        .manage(snapshot_store)
+        .manage(archive_state.clone())
+        .manage(json_tree_cache)
+        .manage(text_index)
+        .manage(tombstone_state)
+        .invoke_handler(move |invoke| {
+            // Which commands get used, when telemetry is on
+            telemetry::record_command(&usage, invoke.message.command());
+            handler(invoke)
+        })
+        .setup(move |app| {
+            let sink: events::Events = Arc::new(app.handle().clone());
+            telemetry::open(&state.telemetry, &data_dir(app.handle()));
+            rulepacks::load(&state.rule_packs, &data_dir(app.handle()));
+            archive::open(&archive_state, &data_dir(app.handle()));
+            config::load(
+                &state.config,
+                &state.store_limits,
+                &state.store,
+                &state.storage,
+                &*sink,
+                &data_dir(app.handle()),
+            );
 
-                        // Emit event to frontend
-                        if let Err(e) = app_handle.emit("new-request", &request) {
-                            println!("Failed to emit new-request event: {}", e);
-                        } else {
-                            println!("Successfully emitted new-request event");
-                        }
-                    }
-                } else {
-                    println!(
-                        "Failed to parse WebSocket message as NetworkRequest - Message length: {}",
-                        text.len()
-                    );
-                    println!(
-                        "First 200 chars: {}",
-                        &text[..std::cmp::min(200, text.len())]
-                    );
+            let database = data_dir(app.handle()).join(persist::DATABASE_FILE);
+            match persist::Persistence::open(&database) {
+                Ok(persistence) => {
+                    *persist_state.lock().unwrap() = Some(persistence);
+                    storage::track_changes(&state.storage);
+                    tauri::async_runtime::spawn(persist::write_behind(
+                        state.store.clone(),
+                        persist_state,
+                        state.storage.clone(),
+                    ));
                 }
+                Err(e) => println!("Session history disabled: {}", e),
             }
-            Ok(Message::Close(_)) => {
-                println!("WebSocket connection closed gracefully");
-                break;
-            }
-            Ok(Message::Ping(ping)) => {
-                // Respond to ping with pong for connection health
-                let mut sender = ws_sender.lock().await;
-                if let Err(e) = sender.send(Message::Pong(ping)).await {
-                    eprintln!("Failed to send pong response: {}", e);
-                    break;
+
+            // The crash journal, unless a different backend was asked for
+            let backend = match storage::configured() {
+                Some(spec) => tauri::async_runtime::block_on(storage::open(&spec)),
+                None => {
+                    let path = data_dir(app.handle()).join(journal::JOURNAL_FILE);
+                    journal::Journal::open(&path)
+                        .map(|journal| Box::new(journal) as Box<dyn storage::RequestStorage>)
                 }
+            };
+            match backend.and_then(|backend| state.attach_storage(backend)) {
+                Ok(0) => {}
+                Ok(recovered) => println!("Recovered {} requests from storage", recovered),
+                Err(e) => println!("Request storage disabled: {}", e),
             }
-            Ok(Message::Pong(_)) => {
-                // Pong received, connection is healthy
-                println!("WebSocket pong received");
-            }
-            Err(e) => {
-                eprintln!("WebSocket error: {}", e);
-                break;
-            }
-            _ => {
-                println!("Received unexpected WebSocket message type");
-            }
-        }
-    }
-    
-    // Clean up the command task when the connection ends
-    command_task.abort();
-}
+            // Recovered bodies are read back by now
+            state.blob_store.prune_stale();
+            tauri::async_runtime::spawn(storage::flusher(state.storage.clone()));
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    let request_store: RequestStore = Arc::new(Mutex::new(Vec::new()));
-    let dedup_cache: DeduplicationCache = Arc::new(Mutex::new(HashMap::new()));
-    let (command_sender, _) = broadcast::channel(100);
-
-    tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
-        .manage(request_store.clone())
-        .manage(command_sender.clone())
-        .invoke_handler(tauri::generate_handler![
-            get_requests,
-            clear_requests
-        ])
-        .setup(move |app| {
-            let app_handle = app.handle().clone();
-            let store = request_store.clone();
-            let dedup_cache = dedup_cache.clone();
-            let command_sender = command_sender.clone();
+            tauri::async_runtime::spawn(blobs::sweeper(
+                state.blob_store.clone(),
+                blobs::BodyHolders {
+                    store: state.store.clone(),
+                    sessions: state.sessions.clone(),
+                    latest: state.latest_state.clone(),
+                    tombstones: sweeper_tombstones,
+                    store_limits: state.store_limits.clone(),
+                },
+            ));
+            tauri::async_runtime::spawn(limits::spiller(state.store_limits.clone()));
+            tauri::async_runtime::spawn(config::watch(
+                state.config.clone(),
+                state.store_limits.clone(),
+                state.store.clone(),
+                state.storage.clone(),
+                sink.clone(),
+            ));
+            tauri::async_runtime::spawn(inbox::watch(state.clone(), sink.clone()));
+            tauri::async_runtime::spawn(capture::scheduler(
+                state.capture_state.clone(),
+                sink.clone(),
+            ));
+            tauri::async_runtime::spawn(retention::pruner(
+                retention_state,
+                state.store.clone(),
+                state.storage.clone(),
+                sink.clone(),
+            ));
 
             // Use tauri's async runtime instead of tokio::spawn
             tauri::async_runtime::spawn(async move {
-                start_websocket_server(store, command_sender, dedup_cache, app_handle).await;
+                server::start_websocket_server(state, sink).await;
             });
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |_app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Evicted requests still queued, while their bodies are on disk
+                archive::write_queued(&exit_archive);
+                // Once saved to session history the journal has nothing left to recover
+                match persist::flush(&exit_store, &exit_persist, &exit_storage) {
+                    Ok(_) => {
+                        storage::close(&exit_storage);
+                        exit_blobs.remove_files();
+                    }
+                    Err(e) => {
+                        println!("Failed to persist requests: {}", e);
+                        // Left for recovery, so it needs the latest writes and
+                        // the spilled bodies
+                        storage::flush(&exit_storage);
+                    }
+                }
+                telemetry::flush(&exit_telemetry);
+            }
+        });
 }
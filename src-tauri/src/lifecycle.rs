@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::markers::now_millis;
+use crate::NetworkRequest;
+
+// Where a request is in its life. Clients on protocol 0 never report it, so it
+// is inferred on ingest: pending without a response, complete with one.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash, TS)]
+#[serde(rename_all = "kebab-case")]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub enum RequestState {
+    #[default]
+    Pending,
+    HeadersReceived,
+    Complete,
+    Failed,
+    Aborted,
+    // Answered by a mock instead of the network
+    Mocked,
+}
+
+impl RequestState {
+    pub fn is_final(self) -> bool {
+        !matches!(self, RequestState::Pending | RequestState::HeadersReceived)
+    }
+
+    pub fn can_move_to(self, next: RequestState) -> bool {
+        match self {
+            RequestState::Pending => next != RequestState::Pending,
+            RequestState::HeadersReceived => next.is_final() && next != RequestState::Mocked,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct StateTransition {
+    pub state: RequestState,
+    #[ts(type = "number")]
+    pub at: u64,
+    // Why a request failed or was aborted, when the client says
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+// Payload of the `request-state-changed` event
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct StateChange {
+    pub id: String,
+    pub from: RequestState,
+    pub to: RequestState,
+    #[ts(type = "number")]
+    pub at: u64,
+    pub detail: Option<String>,
+}
+
+fn inferred_state(request: &NetworkRequest) -> RequestState {
+    if request.state == RequestState::Pending && request.response.is_some() {
+        RequestState::Complete
+    } else {
+        request.state
+    }
+}
+
+// Fills in the state and its history for a request as it arrives. Anything the
+// client already recorded is kept; otherwise the history is rebuilt from the
+// request's own timestamps.
+pub fn initialize(request: &mut NetworkRequest) {
+    request.state = inferred_state(request);
+    if !request.transitions.is_empty() {
+        return;
+    }
+
+    request.transitions.push(StateTransition {
+        state: RequestState::Pending,
+        at: request.timestamp,
+        detail: None,
+    });
+    if request.state != RequestState::Pending {
+        let at = request
+            .duration
+            .map(|duration| request.timestamp.saturating_add(duration))
+            .unwrap_or(request.timestamp);
+        request.transitions.push(StateTransition {
+            state: request.state,
+            at,
+            detail: None,
+        });
+    }
+}
+
+// Moves `request` to `next`, refusing transitions out of a final state or
+// backwards, so a late duplicate can't reopen a finished request
+pub fn transition(
+    request: &mut NetworkRequest,
+    next: RequestState,
+    at: Option<u64>,
+    detail: Option<String>,
+) -> Result<StateChange, String> {
+    let from = request.state;
+    if !from.can_move_to(next) {
+        return Err(format!(
+            "{}: cannot move from {:?} to {:?}",
+            request.id, from, next
+        ));
+    }
+
+    let at = at.unwrap_or_else(now_millis);
+    request.state = next;
+    request.transitions.push(StateTransition {
+        state: next,
+        at,
+        detail: detail.clone(),
+    });
+    Ok(StateChange {
+        id: request.id.clone(),
+        from,
+        to: next,
+        at,
+        detail,
+    })
+}
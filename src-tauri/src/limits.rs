@@ -0,0 +1,356 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use ts_rs::TS;
+
+use crate::archive::{self, ArchiveState};
+use crate::bodies;
+use crate::bulk::BulkResult;
+use crate::events::{self, AppEvent};
+use crate::filter::url_host;
+use crate::headers::Headers;
+use crate::overflow::{self, OverflowState};
+use crate::storage::{self, StorageState};
+use crate::{NetworkRequest, RequestStore};
+
+// `get_store_stats` flags the store this close to a limit
+const NEAR_LIMIT: f64 = 0.9;
+// How often requests queued for the overflow segment are written
+const SPILL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum EvictionPolicy {
+    // Oldest captured first
+    #[default]
+    Fifo,
+    // Least recently captured, updated or opened first
+    Lru,
+}
+
+// Caps on the live store; unset means unlimited
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct StoreLimits {
+    pub max_requests: Option<usize>,
+    // Request and response bodies together
+    #[ts(type = "number | null")]
+    pub max_body_bytes: Option<u64>,
+    #[serde(default)]
+    pub policy: EvictionPolicy,
+    // Move requests over the limits to a temporary file, where lookups still
+    // find them, rather than to the archive
+    #[serde(default)]
+    pub overflow_to_disk: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct StoreLimitMode {
+    pub limits: StoreLimits,
+    // Request id -> tick of its last use, for LRU
+    last_used: HashMap<String, u64>,
+    tick: u64,
+    // Where evicted requests go
+    pub(crate) archive: ArchiveState,
+    // Or, with `overflow_to_disk`, where they are spilled
+    pub(crate) overflow: OverflowState,
+}
+
+pub type StoreLimitState = Arc<Mutex<StoreLimitMode>>;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct StoreStats {
+    pub total: usize,
+    // Rough size of the store in memory; a shared body is counted once and a
+    // spilled one by its preview
+    #[ts(type = "number")]
+    pub estimated_bytes: u64,
+    // What `max_body_bytes` is checked against
+    #[ts(type = "number")]
+    pub body_bytes: u64,
+    pub per_host: BTreeMap<String, usize>,
+    // `2xx`, `4xx`, ..., `pending` or `failed` for requests without a response
+    pub per_status_class: BTreeMap<String, usize>,
+    #[ts(type = "number | null")]
+    pub oldest: Option<u64>,
+    #[ts(type = "number | null")]
+    pub newest: Option<u64>,
+    pub limits: StoreLimits,
+    // Spilled to disk by `overflow_to_disk`, on top of `total`
+    pub overflowed: usize,
+    // Share of the nearest limit in use, from 0 to 1; null without limits
+    pub capacity: Option<f64>,
+    // Eviction is close
+    pub near_limit: bool,
+}
+
+fn body_bytes(request: &NetworkRequest) -> u64 {
+    let request_body = request.body.as_ref().map_or(0, |body| body.len());
+    let response_body = request
+        .response
+        .as_ref()
+        .and_then(|response| response.body.as_ref())
+        .map_or(0, |body| body.len());
+    (request_body + response_body) as u64
+}
+
+// For a parked session that is taking traffic: the same request count and
+// body size limits, evicting into the same archive. LRU and spilling to disk
+// are left to the active session.
+pub(crate) fn parked(state: &StoreLimitState) -> StoreLimitState {
+    let mode = state.lock().unwrap();
+    Arc::new(Mutex::new(StoreLimitMode {
+        limits: StoreLimits {
+            max_requests: mode.limits.max_requests,
+            max_body_bytes: mode.limits.max_body_bytes,
+            ..StoreLimits::default()
+        },
+        archive: mode.archive.clone(),
+        ..StoreLimitMode::default()
+    }))
+}
+
+// Marks a request as just used
+pub fn touch(state: &StoreLimitState, id: &str) {
+    let mut mode = state.lock().unwrap();
+    mode.tick += 1;
+    let tick = mode.tick;
+    mode.last_used.insert(id.to_string(), tick);
+}
+
+// Evicts requests until the store is within its limits, queueing them for the
+// archive, and returns their ids. With `overflow_to_disk` they are queued for
+// the overflow segment instead and not returned, as lookups still find them.
+// In-flight requests are kept so their completion isn't lost, and so are
+// pinned ones.
+pub fn enforce(state: &StoreLimitState, requests: &mut Vec<NetworkRequest>) -> Vec<String> {
+    let mut mode = state.lock().unwrap();
+    let StoreLimits {
+        max_requests,
+        max_body_bytes,
+        policy,
+        overflow_to_disk,
+    } = mode.limits;
+
+    let mut count = requests.len();
+    let mut bytes = match max_body_bytes {
+        Some(_) => requests.iter().map(body_bytes).sum(),
+        None => 0,
+    };
+    let over = |count: usize, bytes: u64| {
+        max_requests.is_some_and(|max| count > max) || max_body_bytes.is_some_and(|max| bytes > max)
+    };
+    if !over(count, bytes) {
+        return Vec::new();
+    }
+
+    let mut order: Vec<usize> = (0..requests.len())
+        .filter(|&i| requests[i].state.is_final() && !requests[i].pinned)
+        .collect();
+    if policy == EvictionPolicy::Lru {
+        // Stable, so requests never used go in capture order
+        order.sort_by_key(|&i| mode.last_used.get(&requests[i].id).copied().unwrap_or(0));
+    }
+
+    let mut evicted = HashSet::new();
+    for i in order {
+        if !over(count, bytes) {
+            break;
+        }
+        evicted.insert(i);
+        count -= 1;
+        bytes = bytes.saturating_sub(body_bytes(&requests[i]));
+    }
+
+    let (kept, gone): (Vec<_>, Vec<_>) = std::mem::take(requests)
+        .into_iter()
+        .enumerate()
+        .partition(|(i, _)| !evicted.contains(i));
+    requests.extend(kept.into_iter().map(|(_, request)| request));
+    let mut gone: Vec<NetworkRequest> = gone.into_iter().map(|(_, request)| request).collect();
+    for request in &gone {
+        mode.last_used.remove(&request.id);
+    }
+    if overflow_to_disk {
+        // Written by `spill_queued`, once the store is unlocked
+        overflow::spill(&mode.overflow, std::mem::take(&mut gone));
+    }
+    let ids: Vec<String> = gone.iter().map(|r| r.id.clone()).collect();
+    // Written by `spill_queued` too
+    archive::queue(&mode.archive, gone);
+    // Forget requests deleted some other way
+    if mode.last_used.len() > requests.len() * 2 {
+        let live: HashSet<&str> = requests.iter().map(|r| r.id.as_str()).collect();
+        mode.last_used.retain(|id, _| live.contains(id.as_str()));
+    }
+    ids
+}
+
+// Applies straight away, evicting whatever is over the new limits
+#[tauri::command]
+pub async fn set_store_limits(
+    state: tauri::State<'_, StoreLimitState>,
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    app_handle: tauri::AppHandle,
+    limits: StoreLimits,
+) -> Result<BulkResult, String> {
+    if limits.max_requests == Some(0) {
+        return Err("max_requests must be at least 1".to_string());
+    }
+    state.lock().map_err(|e| e.to_string())?.limits = limits;
+
+    let ids = {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        enforce(&state, &mut requests)
+    };
+    storage::remove(&storage, &ids);
+    let result = BulkResult { ids };
+    if !result.ids.is_empty() {
+        events::emit(&app_handle, AppEvent::RequestsDeleted(&result))?;
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn get_store_limits(
+    state: tauri::State<'_, StoreLimitState>,
+) -> Result<StoreLimits, String> {
+    Ok(state.lock().map_err(|e| e.to_string())?.limits.clone())
+}
+
+fn status_class(request: &NetworkRequest) -> String {
+    match &request.response {
+        Some(response) => format!("{}xx", response.status / 100),
+        None if request.state.is_final() => "failed".to_string(),
+        None => "pending".to_string(),
+    }
+}
+
+fn text_bytes(request: &NetworkRequest) -> usize {
+    let headers = |headers: &Headers| {
+        headers
+            .iter()
+            .map(|(name, value)| name.len() + value.len())
+            .sum::<usize>()
+    };
+    request.url.len()
+        + request.method.len()
+        + headers(&request.headers)
+        + request.response.as_ref().map_or(0, |r| headers(&r.headers))
+}
+
+fn store_stats(requests: &[NetworkRequest], limits: &StoreLimits, overflowed: usize) -> StoreStats {
+    let mut per_host = BTreeMap::new();
+    let mut per_status_class = BTreeMap::new();
+    let mut shared_bodies: HashMap<*const u8, usize> = HashMap::new();
+    let mut fixed_bytes = 0;
+    let mut body_total = 0;
+    for request in requests {
+        *per_host
+            .entry(url_host(&request.url).to_string())
+            .or_insert(0) += 1;
+        *per_status_class.entry(status_class(request)).or_insert(0) += 1;
+        fixed_bytes += std::mem::size_of::<NetworkRequest>() + text_bytes(request);
+        body_total += body_bytes(request);
+        for body in bodies::bodies(request) {
+            shared_bodies.insert(body.as_ptr(), body.len());
+        }
+    }
+    let total = requests.len();
+    let shares = [
+        limits.max_requests.map(|max| total as f64 / max as f64),
+        limits
+            .max_body_bytes
+            .map(|max| body_total as f64 / max.max(1) as f64),
+    ];
+    let capacity = shares.into_iter().flatten().reduce(f64::max);
+    StoreStats {
+        total,
+        estimated_bytes: (fixed_bytes + shared_bodies.values().sum::<usize>()) as u64,
+        body_bytes: body_total,
+        per_host,
+        per_status_class,
+        oldest: requests.iter().map(|r| r.timestamp).min(),
+        newest: requests.iter().map(|r| r.timestamp).max(),
+        limits: limits.clone(),
+        overflowed,
+        capacity,
+        near_limit: capacity.is_some_and(|share| share >= NEAR_LIMIT),
+    }
+}
+
+#[tauri::command]
+pub async fn get_store_stats(
+    state: tauri::State<'_, StoreLimitState>,
+    store: tauri::State<'_, RequestStore>,
+) -> Result<StoreStats, String> {
+    let (limits, overflow) = {
+        let mode = state.lock().map_err(|e| e.to_string())?;
+        (mode.limits.clone(), mode.overflow.clone())
+    };
+    let overflowed = overflow::count(&overflow);
+    let requests = store.lock().map_err(|e| e.to_string())?;
+    Ok(store_stats(&requests, &limits, overflowed))
+}
+
+// Writes what eviction queued for the overflow segment and the archive;
+// whatever can't be spilled is archived after all
+pub(crate) fn spill_queued(state: &StoreLimitState) {
+    let (overflow, archive) = {
+        let mode = state.lock().unwrap();
+        (mode.overflow.clone(), mode.archive.clone())
+    };
+    archive::queue(&archive, overflow::write_queued(&overflow));
+    archive::write_queued(&archive);
+}
+
+// Runs for the life of the capture; spilling blocks on file and SQLite
+// writes, so it goes to the blocking pool
+pub(crate) async fn spiller(state: StoreLimitState) {
+    let mut interval = tokio::time::interval(SPILL_INTERVAL);
+    loop {
+        interval.tick().await;
+        let state = state.clone();
+        tokio::task::spawn_blocking(move || spill_queued(&state))
+            .await
+            .ok();
+    }
+}
+
+// Where evicted requests are queued, for the body sweeper
+pub(crate) fn archive(state: &StoreLimitState) -> ArchiveState {
+    state.lock().unwrap().archive.clone()
+}
+
+// The store's spill segment, for lookups that fall back to it
+pub(crate) fn overflow(state: &StoreLimitState) -> OverflowState {
+    state.lock().unwrap().overflow.clone()
+}
+
+// The whole capture, requests spilled to disk first, for commands that read
+// all of it; the store is copied and unlocked before the segment is read
+pub(crate) fn captured(
+    state: &StoreLimitState,
+    store: &RequestStore,
+) -> Result<Vec<NetworkRequest>, String> {
+    overflow::capture(&overflow(state), store)
+}
+
+// A request by its id or an alias, from the store or else the spill segment
+pub(crate) fn find_request(
+    state: &StoreLimitState,
+    store: &RequestStore,
+    id: &str,
+) -> Result<NetworkRequest, String> {
+    let found = {
+        let requests = store.lock().map_err(|e| e.to_string())?;
+        requests.iter().find(|r| r.has_id(id)).cloned()
+    };
+    found
+        .or_else(|| overflow::get(&overflow(state), id))
+        .ok_or_else(|| format!("Request not found: {}", id))
+}
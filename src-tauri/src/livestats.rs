@@ -0,0 +1,310 @@
+// Per-endpoint traffic stats kept up to date as requests are ingested, so
+// `get_stats` costs the same with a handful of requests or a million instead
+// of scanning the store. Counts, error counts and byte and duration sums are
+// exact; the p95 comes from a t-digest, exact for small endpoints and within a
+// fraction of a percent of rank for busy ones. They cover everything captured
+// since startup or `reset_stats`, offline queues included, and keep requests
+// evicted or deleted since; sessions loaded from files and requests recovered
+// from storage aren't counted.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::endpoint::endpoint_key;
+use crate::markers::now_millis;
+use crate::stats::GroupStats;
+use crate::NetworkRequest;
+
+// Bigger keeps more centroids, and so more precision, per digest
+const COMPRESSION: f64 = 100.0;
+// Values added before they are merged into the centroids
+const BUFFER_SIZE: usize = 500;
+// A request still in flight after this is taken to never finish, e.g. one
+// deleted or dropped by the client before its response; it stops counting as
+// pending, and counts again if it does finish after all
+const IN_FLIGHT_TIMEOUT_MS: u64 = 60 * 60 * 1000;
+// How often the in-flight requests are checked for that
+const SWEEP_EVERY_MS: u64 = 60 * 1000;
+
+// A merging t-digest (Dunning): sorted centroids that are small near the
+// tails and larger towards the median, so extreme percentiles stay sharp
+#[derive(Debug, Default)]
+pub(crate) struct TDigest {
+    // (mean, weight), by mean
+    centroids: Vec<(f64, f64)>,
+    weight: f64,
+    buffer: Vec<f64>,
+    min: f64,
+    max: f64,
+}
+
+impl TDigest {
+    pub(crate) fn add(&mut self, x: f64) {
+        if self.weight == 0.0 && self.buffer.is_empty() {
+            (self.min, self.max) = (x, x);
+        }
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+        self.buffer.push(x);
+        if self.buffer.len() >= BUFFER_SIZE {
+            self.compress();
+        }
+    }
+
+    fn compress(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let mut points = std::mem::take(&mut self.centroids);
+        points.extend(self.buffer.drain(..).map(|x| (x, 1.0)));
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let total: f64 = points.iter().map(|(_, weight)| weight).sum();
+
+        let mut merged: Vec<(f64, f64)> = Vec::with_capacity(points.len());
+        // Weight of the centroids before the last one
+        let mut before = 0.0;
+        for (mean, weight) in points {
+            if let Some(last) = merged.last_mut() {
+                let q = (before + (last.1 + weight) / 2.0) / total;
+                let limit = (4.0 * total * q * (1.0 - q) / COMPRESSION).max(1.0);
+                if last.1 + weight <= limit {
+                    last.1 += weight;
+                    last.0 += (mean - last.0) * weight / last.1;
+                    continue;
+                }
+                before += last.1;
+            }
+            merged.push((mean, weight));
+        }
+        self.centroids = merged;
+        self.weight = total;
+    }
+
+    // Same ranking as `stats::percentile`: `q` of the way from the first value
+    // to the last, interpolated between centroids by their middle value
+    pub(crate) fn quantile(&mut self, q: f64) -> Option<f64> {
+        self.compress();
+        if self.centroids.is_empty() {
+            return None;
+        }
+        let target = q.clamp(0.0, 1.0) * (self.weight - 1.0);
+        // Nothing merged yet, so the value at that rank is known
+        if self.centroids.len() as f64 == self.weight {
+            return Some(self.centroids[target.round() as usize].0);
+        }
+        let mut previous = (0.0, self.min);
+        let mut before = 0.0;
+        for &(mean, weight) in &self.centroids {
+            let middle = before + (weight - 1.0) / 2.0;
+            if target <= middle {
+                let (at, value) = previous;
+                if middle <= at {
+                    return Some(mean);
+                }
+                return Some(value + (mean - value) * (target - at) / (middle - at));
+            }
+            previous = (middle, mean);
+            before += weight;
+        }
+        let (at, value) = previous;
+        let end = self.weight - 1.0;
+        if end <= at {
+            return Some(value);
+        }
+        Some(value + (self.max - value) * (target - at) / (end - at))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Aggregate {
+    count: usize,
+    errors: usize,
+    pending: usize,
+    durations: TDigest,
+    duration_count: u64,
+    duration_sum: u64,
+    response_bytes: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct LiveStats {
+    endpoints: HashMap<String, Aggregate>,
+    // Counted while in flight, so completing them doesn't count them again:
+    // id -> endpoint and when it was first seen
+    in_flight: HashMap<String, (String, u64)>,
+    swept_at: u64,
+}
+
+impl LiveStats {
+    fn sweep(&mut self, now: u64) {
+        if now.saturating_sub(self.swept_at) < SWEEP_EVERY_MS {
+            return;
+        }
+        self.swept_at = now;
+        let endpoints = &mut self.endpoints;
+        self.in_flight.retain(|_, (endpoint, since)| {
+            let stuck = now.saturating_sub(*since) >= IN_FLIGHT_TIMEOUT_MS;
+            if stuck {
+                if let Some(aggregate) = endpoints.get_mut(endpoint) {
+                    aggregate.pending = aggregate.pending.saturating_sub(1);
+                }
+            }
+            !stuck
+        });
+    }
+}
+
+pub type LiveStatsState = Arc<Mutex<LiveStats>>;
+
+// Called when a request is stored and again when it finishes
+pub(crate) fn observe(state: &LiveStatsState, request: &NetworkRequest) {
+    let mut stats = state.lock().unwrap();
+    let stats = &mut *stats;
+    let now = now_millis();
+    stats.sweep(now);
+    let started = stats.in_flight.remove(&request.id);
+    let endpoint = endpoint_key(request);
+    let aggregate = stats.endpoints.entry(endpoint.clone()).or_default();
+    if !request.state.is_final() {
+        let since = match started {
+            Some((_, since)) => since,
+            None => {
+                aggregate.count += 1;
+                aggregate.pending += 1;
+                now
+            }
+        };
+        stats
+            .in_flight
+            .insert(request.id.clone(), (endpoint, since));
+        return;
+    }
+    let started = started.is_some();
+    if started {
+        aggregate.pending = aggregate.pending.saturating_sub(1);
+    } else {
+        aggregate.count += 1;
+    }
+    if let Some(response) = &request.response {
+        if response.status >= 400 {
+            aggregate.errors += 1;
+        }
+        aggregate.response_bytes += response.body.as_ref().map_or(0, |body| body.len());
+    }
+    if let Some(duration) = request.duration {
+        aggregate.durations.add(duration as f64);
+        aggregate.duration_count += 1;
+        aggregate.duration_sum += duration;
+    }
+}
+
+pub(crate) fn snapshot(state: &LiveStatsState) -> Vec<GroupStats> {
+    let mut stats = state.lock().unwrap();
+    let mut groups: Vec<GroupStats> = stats
+        .endpoints
+        .iter_mut()
+        .map(|(endpoint, aggregate)| GroupStats {
+            value: Some(endpoint.clone()),
+            count: aggregate.count,
+            errors: aggregate.errors,
+            pending: aggregate.pending,
+            error_rate: aggregate.errors as f64 / aggregate.count.max(1) as f64,
+            avg_duration_ms: (aggregate.duration_count > 0)
+                .then(|| aggregate.duration_sum as f64 / aggregate.duration_count as f64),
+            p95_duration_ms: aggregate
+                .durations
+                .quantile(0.95)
+                .map(|ms| ms.round() as u64),
+            total_response_bytes: aggregate.response_bytes,
+        })
+        .collect();
+    groups.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    groups
+}
+
+// Busiest endpoint first
+#[tauri::command]
+pub async fn get_stats(state: tauri::State<'_, LiveStatsState>) -> Result<Vec<GroupStats>, String> {
+    Ok(snapshot(&state))
+}
+
+#[tauri::command]
+pub async fn reset_stats(state: tauri::State<'_, LiveStatsState>) -> Result<(), String> {
+    *state.lock().map_err(|e| e.to_string())? = LiveStats::default();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::percentile;
+    use crate::testing::{pending, request};
+
+    // The same values in a scrambled order, so the digest doesn't see them sorted
+    fn scrambled(count: u64) -> impl Iterator<Item = u64> {
+        (0..count).map(move |i| i * 7_919 % count)
+    }
+
+    #[test]
+    fn has_no_percentiles_until_it_has_values() {
+        assert_eq!(TDigest::default().quantile(0.95), None);
+    }
+
+    #[test]
+    fn is_exact_for_a_few_values() {
+        let mut digest = TDigest::default();
+        let mut sorted: Vec<u64> = scrambled(40).collect();
+        for &value in &sorted {
+            digest.add(value as f64);
+        }
+        sorted.sort_unstable();
+        for q in [0.0, 0.5, 0.95, 1.0] {
+            let expected = percentile(&sorted, q * 100.0).unwrap() as f64;
+            assert_eq!(digest.quantile(q), Some(expected));
+        }
+    }
+
+    #[test]
+    fn stays_within_a_fraction_of_a_percent_for_many_values() {
+        let mut digest = TDigest::default();
+        for value in scrambled(100_000) {
+            digest.add(value as f64);
+        }
+        let p95 = digest.quantile(0.95).unwrap();
+        assert!((p95 - 95_000.0).abs() < 500.0, "p95 was {}", p95);
+        assert_eq!(digest.quantile(0.0), Some(0.0));
+        assert_eq!(digest.quantile(1.0), Some(99_999.0));
+    }
+
+    #[test]
+    fn counts_a_request_once_from_start_to_finish() {
+        let state = LiveStatsState::default();
+        let started = pending("a", "https://api.example.com/users");
+        observe(&state, &started);
+        observe(&state, &started);
+        assert_eq!(
+            (snapshot(&state)[0].count, snapshot(&state)[0].pending),
+            (1, 1)
+        );
+
+        observe(&state, &request("a", "https://api.example.com/users", 500));
+        let stats = &snapshot(&state)[0];
+        assert_eq!((stats.count, stats.pending, stats.errors), (1, 0, 1));
+    }
+
+    #[test]
+    fn stops_counting_requests_that_never_finish() {
+        let state = LiveStatsState::default();
+        observe(&state, &pending("a", "https://api.example.com/users"));
+        let mut stats = state.lock().unwrap();
+        let since = stats.in_flight["a"].1;
+
+        stats.sweep(since + IN_FLIGHT_TIMEOUT_MS - 1);
+        assert_eq!(stats.in_flight.len(), 1);
+        // Only checked once a minute
+        stats.sweep(since + IN_FLIGHT_TIMEOUT_MS);
+        assert_eq!(stats.in_flight.len(), 1);
+        stats.sweep(since + IN_FLIGHT_TIMEOUT_MS - 1 + SWEEP_EVERY_MS);
+        assert!(stats.in_flight.is_empty());
+        assert_eq!(stats.endpoints.values().next().unwrap().pending, 0);
+    }
+}
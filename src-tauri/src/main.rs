@@ -1,6 +1,15 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use network_observer_lib::{agent, launcher};
+
 fn main() {
-    network_observer_lib::run()
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(at) = args.iter().position(|arg| arg == launcher::LAUNCH_FLAG) {
+        std::process::exit(launcher::run(&args[at + 1..]))
+    } else if args.iter().any(|arg| arg == agent::AGENT_FLAG) {
+        agent::run()
+    } else {
+        network_observer_lib::run()
+    }
 }
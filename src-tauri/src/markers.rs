@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::events::{self, AppEvent};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "kebab-case")]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub enum MarkerKind {
+    RecordingStarted,
+    RecordingStopped,
+    Custom,
+}
+
+// A point on the capture timeline, sent by the client (`{"type": "marker", ...}`)
+// or added from the UI, e.g. when a screen recording of the bug starts and stops
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct Marker {
+    #[serde(default)]
+    pub id: String,
+    pub kind: MarkerKind,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[ts(type = "number")]
+    pub timestamp: u64,
+}
+
+pub type MarkerStore = Arc<Mutex<Vec<Marker>>>;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct RecordingWindow {
+    #[ts(type = "number")]
+    pub start_ts: u64,
+    // Missing while the recording is still running
+    #[ts(type = "number | null")]
+    pub end_ts: Option<u64>,
+    pub label: Option<String>,
+}
+
+pub fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+pub fn add_marker_to_store(markers: &MarkerStore, mut marker: Marker) -> Marker {
+    if marker.id.is_empty() {
+        marker.id = Uuid::new_v4().to_string();
+    }
+    let mut markers = markers.lock().unwrap();
+    let position = markers.partition_point(|m| m.timestamp <= marker.timestamp);
+    markers.insert(position, marker.clone());
+    marker
+}
+
+// Pairs each recording-started marker with the next recording-stopped marker
+pub fn recording_windows(markers: &[Marker]) -> Vec<RecordingWindow> {
+    let mut windows: Vec<RecordingWindow> = Vec::new();
+    for marker in markers {
+        match marker.kind {
+            MarkerKind::RecordingStarted => windows.push(RecordingWindow {
+                start_ts: marker.timestamp,
+                end_ts: None,
+                label: marker.label.clone(),
+            }),
+            MarkerKind::RecordingStopped => {
+                if let Some(window) = windows.iter_mut().rev().find(|w| w.end_ts.is_none()) {
+                    window.end_ts = Some(marker.timestamp);
+                }
+            }
+            MarkerKind::Custom => {}
+        }
+    }
+    windows
+}
+
+#[tauri::command]
+pub async fn add_marker(
+    markers: tauri::State<'_, MarkerStore>,
+    app_handle: tauri::AppHandle,
+    kind: MarkerKind,
+    label: Option<String>,
+    timestamp: Option<u64>,
+) -> Result<Marker, String> {
+    let marker = add_marker_to_store(
+        &markers,
+        Marker {
+            id: String::new(),
+            kind,
+            label,
+            timestamp: timestamp.unwrap_or_else(now_millis),
+        },
+    );
+    events::emit(&app_handle, AppEvent::MarkerAdded(&marker))?;
+    Ok(marker)
+}
+
+#[tauri::command]
+pub async fn get_markers(markers: tauri::State<'_, MarkerStore>) -> Result<Vec<Marker>, String> {
+    let markers = markers.lock().map_err(|e| e.to_string())?;
+    Ok(markers.clone())
+}
+
+#[tauri::command]
+pub async fn get_recording_windows(
+    markers: tauri::State<'_, MarkerStore>,
+) -> Result<Vec<RecordingWindow>, String> {
+    let markers = markers.lock().map_err(|e| e.to_string())?;
+    Ok(recording_windows(&markers))
+}
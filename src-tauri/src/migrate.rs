@@ -0,0 +1,138 @@
+// Versioning for the request JSON the observer keeps on disk: session history,
+// the crash journal and saved sessions. Whatever writes it records
+// REQUEST_VERSION next to it; reading older JSON runs it through the upgrades
+// first, so changing `NetworkRequest` doesn't orphan what users already saved.
+use serde_json::{json, Value};
+
+use crate::NetworkRequest;
+
+pub const REQUEST_VERSION: u32 = 1;
+
+// UPGRADES[n] rewrites version n + 1 JSON into version n + 2, in place. Add
+// one whenever a change to `NetworkRequest` can't be covered by
+// `#[serde(default)]` alone, e.g. a field renamed or changing shape. Bare
+// request lists, which `export_many` and share bundles wrote before they used
+// `envelope`, carry no version and are read as version 1, so an upgrade should
+// leave JSON already in its new shape alone.
+const UPGRADES: &[fn(&mut Value)] = &[];
+
+const _: () = assert!(UPGRADES.len() as u32 == REQUEST_VERSION - 1);
+
+fn check_version(version: u32) -> Result<(), String> {
+    if version > REQUEST_VERSION {
+        return Err(format!(
+            "Saved by a newer version of the app (request format {}, this one reads up to {})",
+            version, REQUEST_VERSION
+        ));
+    }
+    Ok(())
+}
+
+pub fn upgrade_request(value: &mut Value, version: u32) -> Result<(), String> {
+    check_version(version)?;
+    // Data from before versioning is version 1
+    let from = version.max(1) as usize - 1;
+    for upgrade in &UPGRADES[from..] {
+        upgrade(value);
+    }
+    Ok(())
+}
+
+pub fn decode_request(mut value: Value, version: u32) -> Result<NetworkRequest, String> {
+    upgrade_request(&mut value, version)?;
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+pub fn decode_requests(value: Value, version: u32) -> Result<Vec<NetworkRequest>, String> {
+    // Even with no requests to upgrade, a list from a newer app isn't ours to read
+    check_version(version)?;
+    match value {
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| decode_request(item, version))
+            .collect(),
+        _ => Err("expected a list of requests".to_string()),
+    }
+}
+
+// How request lists are written to files and services: the requests with the
+// version they are in
+pub fn envelope(requests: &[NetworkRequest]) -> Value {
+    json!({ "request_version": REQUEST_VERSION, "requests": requests })
+}
+
+// Reads what `envelope` writes, any object with `request_version` and
+// `requests` (saved sessions, window exports), or a bare list
+pub fn decode_envelope(value: Value) -> Result<Vec<NetworkRequest>, String> {
+    match value {
+        Value::Array(_) => decode_requests(value, 1),
+        Value::Object(mut object) => {
+            let version = object
+                .get("request_version")
+                .and_then(Value::as_u64)
+                .map_or(1, |version| u32::try_from(version).unwrap_or(u32::MAX));
+            let requests = object.remove("requests").ok_or("no requests found")?;
+            decode_requests(requests, version)
+        }
+        _ => Err("no requests found".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::request;
+
+    #[test]
+    fn round_trips_through_an_envelope() {
+        let requests = vec![request("a", "https://api.example.com/users", 200)];
+        let decoded = decode_envelope(envelope(&requests)).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].id, "a");
+        assert_eq!(decoded[0].response.as_ref().unwrap().status, 200);
+    }
+
+    #[test]
+    fn reads_bare_lists_and_unversioned_objects_as_version_one() {
+        let listed = json!([request("a", "https://api.example.com/", 200)]);
+        assert_eq!(decode_envelope(listed.clone()).unwrap().len(), 1);
+        assert_eq!(
+            decode_envelope(json!({ "requests": listed }))
+                .unwrap()
+                .len(),
+            1
+        );
+        // Version 0 is what came before versioning
+        assert!(upgrade_request(&mut json!({}), 0).is_ok());
+    }
+
+    #[test]
+    fn refuses_data_from_a_newer_app() {
+        let newer = REQUEST_VERSION + 1;
+        assert!(
+            decode_envelope(json!({ "request_version": newer, "requests": [] }))
+                .unwrap_err()
+                .starts_with("Saved by a newer version")
+        );
+        // A version too big for u32 is newer too, not version 1
+        let huge = u64::from(u32::MAX) + 1;
+        assert!(decode_envelope(json!({ "request_version": huge, "requests": [] })).is_err());
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_request_list() {
+        assert_eq!(
+            decode_envelope(json!("requests")).unwrap_err(),
+            "no requests found"
+        );
+        assert_eq!(
+            decode_envelope(json!({ "request_version": 1 })).unwrap_err(),
+            "no requests found"
+        );
+        assert_eq!(
+            decode_requests(json!({ "id": "a" }), 1).unwrap_err(),
+            "expected a list of requests"
+        );
+        assert!(decode_requests(json!([{ "id": "a" }]), 1).is_err());
+    }
+}
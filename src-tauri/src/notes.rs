@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::blobs;
+use crate::export::anonymize::{anonymize, AnonymizeProfile};
+use crate::limits::{self, StoreLimitState};
+use crate::markers::now_millis;
+use crate::migrate::envelope;
+use crate::storage::{self, StorageState};
+use crate::{data_dir, NetworkRequest, RequestStore};
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct Attachment {
+    pub id: String,
+    pub file_name: String,
+    // Copy kept under the app data directory, so the original can be moved or deleted
+    pub path: String,
+    #[ts(type = "number")]
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct RequestNote {
+    pub id: String,
+    pub text: String,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[ts(type = "number")]
+    pub created_at: u64,
+}
+
+fn attachments_dir(app_handle: &tauri::AppHandle, note_id: &str) -> PathBuf {
+    data_dir(app_handle).join("attachments").join(note_id)
+}
+
+fn store_attachment(dir: &Path, source: &str) -> Result<Attachment, String> {
+    let source = Path::new(source);
+    let file_name = source
+        .file_name()
+        .ok_or_else(|| format!("Not a file: {}", source.display()))?
+        .to_string_lossy()
+        .to_string();
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let id = Uuid::new_v4().to_string();
+    let target = dir.join(format!("{}-{}", &id[..8], file_name));
+    let size = std::fs::copy(source, &target)
+        .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+
+    Ok(Attachment {
+        id,
+        file_name,
+        path: target.to_string_lossy().to_string(),
+        size,
+    })
+}
+
+fn find_request<'a>(
+    requests: &'a mut [NetworkRequest],
+    id: &str,
+) -> Result<&'a mut NetworkRequest, String> {
+    requests
+        .iter_mut()
+        .find(|r| r.has_id(id))
+        .ok_or_else(|| format!("Request not found: {}", id))
+}
+
+#[tauri::command]
+pub async fn add_note(
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    app_handle: tauri::AppHandle,
+    request_id: String,
+    text: String,
+    attachments: Option<Vec<String>>,
+) -> Result<RequestNote, String> {
+    let note_id = Uuid::new_v4().to_string();
+    let dir = attachments_dir(&app_handle, &note_id);
+    // Copy files before taking the lock; attachments can be large
+    let attachments = attachments
+        .unwrap_or_default()
+        .iter()
+        .map(|path| store_attachment(&dir, path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let note = RequestNote {
+        id: note_id,
+        text,
+        attachments,
+        created_at: now_millis(),
+    };
+    let updated = {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        let request = find_request(&mut requests, &request_id)?;
+        request.notes.push(note.clone());
+        request.clone()
+    };
+    storage::put(&storage, &updated);
+    Ok(note)
+}
+
+#[tauri::command]
+pub async fn add_note_attachment(
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    app_handle: tauri::AppHandle,
+    request_id: String,
+    note_id: String,
+    path: String,
+) -> Result<Attachment, String> {
+    let attachment = store_attachment(&attachments_dir(&app_handle, &note_id), &path)?;
+
+    let updated = {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        let request = find_request(&mut requests, &request_id)?;
+        let note = request
+            .notes
+            .iter_mut()
+            .find(|n| n.id == note_id)
+            .ok_or_else(|| format!("Note not found: {}", note_id))?;
+        note.attachments.push(attachment.clone());
+        request.clone()
+    };
+    storage::put(&storage, &updated);
+    Ok(attachment)
+}
+
+#[tauri::command]
+pub async fn delete_note(
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    app_handle: tauri::AppHandle,
+    request_id: String,
+    note_id: String,
+) -> Result<(), String> {
+    let updated = {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        let request = find_request(&mut requests, &request_id)?;
+        request.notes.retain(|n| n.id != note_id);
+        request.clone()
+    };
+    storage::put(&storage, &updated);
+    let dir = attachments_dir(&app_handle, &note_id);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// The request's triage note, i.e. its first note, set to `text`: created if
+// missing and removed, with any attachments, when `text` is empty
+#[tauri::command]
+pub async fn set_note(
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    app_handle: tauri::AppHandle,
+    id: String,
+    text: String,
+) -> Result<Option<RequestNote>, String> {
+    let (note, removed, updated) = {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        let request = find_request(&mut requests, &id)?;
+        let (note, removed) = if text.trim().is_empty() {
+            let removed = (!request.notes.is_empty()).then(|| request.notes.remove(0));
+            (None, removed)
+        } else if let Some(note) = request.notes.first_mut() {
+            note.text = text;
+            (Some(note.clone()), None)
+        } else {
+            let note = RequestNote {
+                id: Uuid::new_v4().to_string(),
+                text,
+                attachments: Vec::new(),
+                created_at: now_millis(),
+            };
+            request.notes.push(note.clone());
+            (Some(note), None)
+        };
+        (note, removed, request.clone())
+    };
+    storage::put(&storage, &updated);
+
+    if let Some(removed) = removed {
+        let dir = attachments_dir(&app_handle, &removed.id);
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(note)
+}
+
+// Writes a self-contained directory with the captured requests (notes included) and
+// copies of every attachment, rewriting attachment paths to be relative to the bundle
+#[tauri::command]
+pub async fn export_bundle(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    path: String,
+    anonymize_with: Option<AnonymizeProfile>,
+) -> Result<String, String> {
+    let mut requests = limits::captured(&store_limits, &store)?;
+    blobs::restore(&mut requests);
+    anonymize(&mut requests, anonymize_with);
+
+    let bundle = PathBuf::from(&path);
+    for note in requests.iter_mut().flat_map(|r| r.notes.iter_mut()) {
+        let dir = bundle.join("attachments").join(&note.id);
+        for attachment in &mut note.attachments {
+            std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            // Stored copies carry an id prefix, so names stay unique within a note
+            let stored_name = Path::new(&attachment.path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| attachment.file_name.clone());
+            std::fs::copy(&attachment.path, dir.join(&stored_name))
+                .map_err(|e| format!("Failed to copy attachment {}: {}", attachment.path, e))?;
+            attachment.path = format!("attachments/{}/{}", note.id, stored_name);
+        }
+    }
+
+    std::fs::create_dir_all(&bundle).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&envelope(&requests)).map_err(|e| e.to_string())?;
+    std::fs::write(bundle.join("requests.json"), json).map_err(|e| e.to_string())?;
+    Ok(path)
+}
@@ -0,0 +1,63 @@
+// Repeats of a stored request collapsed into it, when `collapse_repeats` is on
+// in the config. A completed request with the same client, method, URL, body
+// and status as one stored earlier (see `create_request_signature`), arriving
+// after the dedup window, is usually a genuine repeat such as a polling call:
+// instead of a new entry it bumps the count on the first one and adds its
+// timestamp. Repeats within the dedup window are still dropped as duplicates,
+// and one seen more than five minutes after the last starts a new entry.
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::limits::{self, StoreLimitState};
+use crate::{NetworkRequest, RequestStore};
+
+// The latest ones are kept past this; the count stays exact
+const MAX_TIMESTAMPS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct Occurrences {
+    // The stored request included
+    #[ts(type = "number")]
+    pub count: u64,
+    // Oldest first
+    #[ts(type = "number[]")]
+    pub timestamps: Vec<u64>,
+}
+
+fn of(request: &NetworkRequest) -> Occurrences {
+    request.occurrences.clone().unwrap_or(Occurrences {
+        count: 1,
+        timestamps: vec![request.timestamp],
+    })
+}
+
+// Adds `repeat` to the stored request `id` and returns it updated, or None
+// once that request is gone
+pub(crate) fn record(
+    store: &RequestStore,
+    id: &str,
+    repeat: &NetworkRequest,
+) -> Option<NetworkRequest> {
+    let mut requests = store.lock().unwrap();
+    let request = requests.iter_mut().find(|r| r.has_id(id))?;
+    let mut occurrences = of(request);
+    occurrences.count += 1;
+    occurrences.timestamps.push(repeat.timestamp);
+    if occurrences.timestamps.len() > MAX_TIMESTAMPS {
+        let excess = occurrences.timestamps.len() - MAX_TIMESTAMPS;
+        occurrences.timestamps.drain(..excess);
+    }
+    request.occurrences = Some(occurrences);
+    Some(request.clone())
+}
+
+#[tauri::command]
+pub async fn get_request_occurrences(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    id: String,
+) -> Result<Occurrences, String> {
+    let request = limits::find_request(&store_limits, &store, &id)?;
+    Ok(of(&request))
+}
@@ -0,0 +1,295 @@
+// With `overflow_to_disk` in the store limits, requests over the limits are
+// moved to a temporary SQLite segment instead of being evicted to the archive.
+// They drop out of memory but not out of the capture: commands that read it
+// go through `limits::captured` or `limits::find_request`, which read them
+// back from the segment, just more slowly, and no `requests-deleted` event is
+// sent for them. The segment is a file in the temp directory, created on the
+// first spill, emptied by `clear_requests` and deleted when dropped; it is
+// never loaded at startup.
+// Eviction only queues requests for it, so the store isn't locked while they
+// are written; until then, lookups find them in the queue.
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::blobs;
+use crate::migrate::{decode_request, REQUEST_VERSION};
+use crate::{NetworkRequest, RequestStore};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS overflow_requests (
+        position INTEGER PRIMARY KEY AUTOINCREMENT,
+        id TEXT NOT NULL UNIQUE,
+        format_version INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    -- Ids absorbed through dedup, so lookups by them don't scan the table
+    CREATE TABLE IF NOT EXISTS overflow_aliases (
+        alias TEXT PRIMARY KEY,
+        id TEXT NOT NULL
+    );
+";
+
+#[derive(Debug)]
+pub(crate) struct Segment {
+    db: Connection,
+    path: PathBuf,
+}
+
+impl Drop for Segment {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.path).ok();
+        for suffix in ["-wal", "-shm"] {
+            let mut side = self.path.clone().into_os_string();
+            side.push(suffix);
+            std::fs::remove_file(side).ok();
+        }
+    }
+}
+
+impl Segment {
+    fn open() -> Result<Self, String> {
+        let dir = std::env::temp_dir().join("network-observer-overflow");
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let path = dir.join(format!("{}-{}.sqlite3", std::process::id(), Uuid::new_v4()));
+        let db = Connection::open(&path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        db.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+        Ok(Segment { db, path })
+    }
+
+    fn insert(&mut self, requests: &[NetworkRequest]) -> Result<(), String> {
+        let tx = self.db.transaction().map_err(|e| e.to_string())?;
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT OR REPLACE INTO overflow_requests (id, format_version, data) \
+                     VALUES (?1, ?2, ?3)",
+                )
+                .map_err(|e| e.to_string())?;
+            let mut alias = tx
+                .prepare("INSERT OR REPLACE INTO overflow_aliases (alias, id) VALUES (?1, ?2)")
+                .map_err(|e| e.to_string())?;
+            for request in requests {
+                let data = serde_json::to_string(request).map_err(|e| e.to_string())?;
+                insert
+                    .execute(params![request.id, REQUEST_VERSION, data])
+                    .map_err(|e| e.to_string())?;
+                for id in &request.aliases {
+                    alias
+                        .execute(params![id, request.id])
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    // In the order they were spilled. Rows that no longer decode are skipped.
+    fn all(&self) -> Result<Vec<NetworkRequest>, String> {
+        let mut select = self
+            .db
+            .prepare("SELECT data, format_version FROM overflow_requests ORDER BY position")
+            .map_err(|e| e.to_string())?;
+        let rows = select
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, u32>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        let mut requests = Vec::new();
+        for row in rows {
+            let (data, version) = row.map_err(|e| e.to_string())?;
+            if let Ok(request) = decode(&data, version) {
+                requests.push(request);
+            }
+        }
+        Ok(requests)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<NetworkRequest>, String> {
+        let row = self
+            .db
+            .query_row(
+                "SELECT data, format_version FROM overflow_requests WHERE id = ?1
+                 OR id = (SELECT id FROM overflow_aliases WHERE alias = ?1)",
+                params![id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, u32>(1)?)),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        row.map(|(data, version)| decode(&data, version))
+            .transpose()
+    }
+
+    fn count(&self) -> Result<usize, String> {
+        self.db
+            .query_row("SELECT COUNT(*) FROM overflow_requests", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map(|count| count as usize)
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn decode(data: &str, version: u32) -> Result<NetworkRequest, String> {
+    serde_json::from_str::<Value>(data)
+        .map_err(|e| e.to_string())
+        .and_then(|value| decode_request(value, version))
+}
+
+// The segment, and what is queued for it. Lock `segment` first when taking
+// both.
+#[derive(Debug, Default)]
+pub(crate) struct Overflow {
+    segment: Mutex<Option<Segment>>,
+    queued: Mutex<Vec<NetworkRequest>>,
+}
+
+pub(crate) type OverflowState = Arc<Overflow>;
+
+impl Overflow {
+    // Exchanges everything with `other`, for switching sessions
+    pub(crate) fn swap(&self, other: &Overflow) {
+        std::mem::swap(
+            &mut *self.segment.lock().unwrap(),
+            &mut *other.segment.lock().unwrap(),
+        );
+        std::mem::swap(
+            &mut *self.queued.lock().unwrap(),
+            &mut *other.queued.lock().unwrap(),
+        );
+    }
+
+    // Spill files the queued requests still need, for the body sweeper
+    pub(crate) fn queued_bodies(&self) -> Vec<String> {
+        let queued = self.queued.lock().unwrap();
+        queued
+            .iter()
+            .flat_map(blobs::spilled_bodies)
+            .map(|spilled| spilled.path.clone())
+            .collect()
+    }
+}
+
+// Queues requests over the limits for the segment; cheap enough to call with
+// the store locked
+pub(crate) fn spill(state: &OverflowState, requests: Vec<NetworkRequest>) {
+    if !requests.is_empty() {
+        state.queued.lock().unwrap().extend(requests);
+    }
+}
+
+// Writes the queue to the segment, bodies read back from their spill files
+// first since those are deleted once nothing points at them. Returns the
+// requests that couldn't be written, to be evicted instead.
+pub(crate) fn write_queued(state: &OverflowState) -> Vec<NetworkRequest> {
+    let mut segment = state.segment.lock().unwrap();
+    let mut requests = state.queued.lock().unwrap().clone();
+    if requests.is_empty() {
+        return requests;
+    }
+    let written = requests.len();
+    if segment.is_none() {
+        match Segment::open() {
+            Ok(opened) => *segment = Some(opened),
+            Err(e) => println!("Store overflow disabled: {}", e),
+        }
+    }
+    blobs::restore(&mut requests);
+    let failed = match segment.as_mut().map(|segment| segment.insert(&requests)) {
+        Some(Ok(())) => Vec::new(),
+        Some(Err(e)) => {
+            println!("Failed to spill {} requests: {}", requests.len(), e);
+            requests
+        }
+        None => requests,
+    };
+    // Still holding the segment, so lookups never see a request in both
+    state.queued.lock().unwrap().drain(..written);
+    failed
+}
+
+pub(crate) fn get(state: &OverflowState, id: &str) -> Option<NetworkRequest> {
+    let segment = state.segment.lock().unwrap();
+    let queued = state.queued.lock().unwrap();
+    if let Some(request) = queued.iter().find(|r| r.has_id(id)) {
+        return Some(request.clone());
+    }
+    drop(queued);
+    let found = segment.as_ref()?.get(id);
+    found.unwrap_or_else(|e| {
+        println!("Failed to read store overflow: {}", e);
+        None
+    })
+}
+
+// Spilled requests followed by `live`, so the oldest come first as in the store
+pub(crate) fn with_live(state: &OverflowState, live: &[NetworkRequest]) -> Vec<NetworkRequest> {
+    let segment = state.segment.lock().unwrap();
+    let mut requests = match segment.as_ref().map(Segment::all) {
+        Some(Ok(requests)) => requests,
+        Some(Err(e)) => {
+            println!("Failed to read store overflow: {}", e);
+            Vec::new()
+        }
+        None => Vec::new(),
+    };
+    requests.extend_from_slice(&state.queued.lock().unwrap());
+    requests.extend_from_slice(live);
+    requests
+}
+
+// The whole capture, spilled requests first, for commands that look at all of
+// it. The store is copied and unlocked before the segment is read, and a
+// request evicted in between is only counted once.
+pub(crate) fn capture(
+    state: &OverflowState,
+    store: &RequestStore,
+) -> Result<Vec<NetworkRequest>, String> {
+    let live = store.lock().map_err(|e| e.to_string())?.clone();
+    let ids: HashSet<&str> = live.iter().map(|r| r.id.as_str()).collect();
+    let mut requests = with_live(state, &[]);
+    requests.retain(|r| !ids.contains(r.id.as_str()));
+    requests.extend_from_slice(&live);
+    Ok(requests)
+}
+
+// The spilled requests `keep` accepts, followed by `live`, the store's own
+// matches. Spilled requests aren't indexed, so they are scanned; one evicted
+// since the store was read is already in `live` if it matches.
+pub(crate) fn with_matching(
+    state: &OverflowState,
+    live: Vec<NetworkRequest>,
+    keep: impl Fn(&NetworkRequest) -> bool,
+) -> Vec<NetworkRequest> {
+    let ids: HashSet<&str> = live.iter().map(|r| r.id.as_str()).collect();
+    let mut found = with_live(state, &[]);
+    found.retain(|r| !ids.contains(r.id.as_str()) && keep(r));
+    found.extend(live);
+    found
+}
+
+pub(crate) fn count(state: &OverflowState) -> usize {
+    let segment = state.segment.lock().unwrap();
+    let written = segment
+        .as_ref()
+        .and_then(|segment| segment.count().ok())
+        .unwrap_or(0);
+    written + state.queued.lock().unwrap().len()
+}
+
+// Empties the segment and the queue and returns what they held
+pub(crate) fn take(state: &OverflowState) -> Vec<NetworkRequest> {
+    let mut segment = state.segment.lock().unwrap();
+    let mut requests = match segment.as_ref().map(Segment::all) {
+        Some(Ok(requests)) => requests,
+        _ => Vec::new(),
+    };
+    *segment = None;
+    requests.append(&mut state.queued.lock().unwrap());
+    requests
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::limits::{self, StoreLimitState};
+use crate::{NetworkRequest, RequestStore};
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum RequestSort {
+    #[default]
+    Oldest,
+    Newest,
+    // Longest first; requests without a duration go last
+    Slowest,
+}
+
+impl RequestSort {
+    fn name(self) -> &'static str {
+        match self {
+            RequestSort::Oldest => "oldest",
+            RequestSort::Newest => "newest",
+            RequestSort::Slowest => "slowest",
+        }
+    }
+
+    // Position in the page order, lowest first; ties go by id
+    fn key(self, request: &NetworkRequest) -> u64 {
+        match self {
+            RequestSort::Oldest => request.timestamp,
+            RequestSort::Newest => u64::MAX - request.timestamp,
+            RequestSort::Slowest => request.duration.map_or(u64::MAX, |duration| {
+                u64::MAX - 1 - duration.min(u64::MAX - 1)
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct RequestPage {
+    pub requests: Vec<NetworkRequest>,
+    // Pass back to get the next page; None on the last one
+    pub next_cursor: Option<String>,
+    // Requests in the store, across all pages
+    pub total: usize,
+}
+
+// The cursor names the last request of a page by its sort key, so pages stay
+// consistent while requests are added or evicted in between
+fn encode_cursor(sort: RequestSort, key: u64, id: &str) -> String {
+    format!("{}:{}:{}", sort.name(), key, id)
+}
+
+fn decode_cursor(sort: RequestSort, cursor: &str) -> Result<(u64, String), String> {
+    let invalid = || format!("Invalid cursor: {}", cursor);
+    let mut parts = cursor.splitn(3, ':');
+    let (Some(name), Some(key), Some(id)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    if name != sort.name() {
+        return Err(format!(
+            "Cursor was made for the {} order, not {}",
+            name,
+            sort.name()
+        ));
+    }
+    let key = key.parse().map_err(|_| invalid())?;
+    Ok((key, id.to_string()))
+}
+
+pub fn build_page(
+    requests: &[NetworkRequest],
+    cursor: Option<&str>,
+    limit: Option<usize>,
+    sort: RequestSort,
+) -> Result<RequestPage, String> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let after = cursor
+        .map(|cursor| decode_cursor(sort, cursor))
+        .transpose()?;
+
+    let mut keyed: Vec<(u64, &NetworkRequest)> = requests
+        .iter()
+        .map(|request| (sort.key(request), request))
+        .filter(|(key, request)| match &after {
+            Some((after_key, after_id)) => {
+                (*key, request.id.as_str()) > (*after_key, after_id.as_str())
+            }
+            None => true,
+        })
+        .collect();
+    keyed.sort_by(|(a_key, a), (b_key, b)| a_key.cmp(b_key).then_with(|| a.id.cmp(&b.id)));
+
+    let next_cursor = (keyed.len() > limit).then(|| {
+        let (key, last) = keyed[limit - 1];
+        encode_cursor(sort, key, &last.id)
+    });
+    keyed.truncate(limit);
+    Ok(RequestPage {
+        requests: keyed
+            .into_iter()
+            .map(|(_, request)| request.clone())
+            .collect(),
+        next_cursor,
+        total: requests.len(),
+    })
+}
+
+// Like `get_requests`, but a page at a time so large stores stay responsive
+#[tauri::command]
+pub async fn get_requests_page(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+    sort: Option<RequestSort>,
+) -> Result<RequestPage, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    build_page(
+        &requests,
+        cursor.as_deref(),
+        limit,
+        sort.unwrap_or_default(),
+    )
+}
@@ -0,0 +1,300 @@
+// Replays a stored list request and keeps following its pagination, for
+// checking a list endpoint end to end rather than just its first page. The
+// next page comes from a `Link: <...>; rel="next"` header, a next-page URL in
+// the body (`next`, `links.next`, ...) or a cursor field (`next_cursor`,
+// `nextPageToken`, ...), which is sent back in the query parameter the request
+// already uses for it, or the one the field is named after. Items are taken
+// from the body's list (the body itself, or `data`, `items`, `results`, ...)
+// and collected across pages.
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::time::Instant;
+use ts_rs::TS;
+
+use crate::forms;
+use crate::headers::Headers;
+use crate::limits::StoreLimitState;
+use crate::replay::{self, Resent};
+use crate::RequestStore;
+
+// Where a next-page URL sits in a JSON body
+const NEXT_URL_POINTERS: &[&str] = &[
+    "/next",
+    "/links/next",
+    "/links/next/href",
+    "/_links/next/href",
+    "/paging/next",
+    "/pagination/next",
+    "/meta/next",
+];
+
+// Cursor fields and the query parameter each one is usually sent back in
+const CURSOR_FIELDS: &[(&str, &str)] = &[
+    ("/next_cursor", "cursor"),
+    ("/nextCursor", "cursor"),
+    ("/meta/next_cursor", "cursor"),
+    ("/pagination/next_cursor", "cursor"),
+    ("/response_metadata/next_cursor", "cursor"),
+    ("/next_page_token", "page_token"),
+    ("/nextPageToken", "pageToken"),
+];
+
+// Cursor parameters spelled differently by the API take precedence
+const CURSOR_PARAMS: &[&str] = &["cursor", "page_token", "pageToken", "after", "next"];
+
+// Members that usually hold the page's items
+const ITEM_FIELDS: &[&str] = &["data", "items", "results", "records", "entries", "values"];
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct PageOutcome {
+    pub url: String,
+    pub status: Option<u16>,
+    #[ts(type = "number")]
+    pub duration_ms: u64,
+    // Found on this page
+    pub items: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum PaginationEnd {
+    // The last page had no next one
+    LastPage,
+    MaxPages,
+    // The next page pointed back at one already fetched
+    Repeated,
+    // A page failed to send or came back without a 2xx status
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct PaginatedReplay {
+    pub id: String,
+    pub pages: Vec<PageOutcome>,
+    // Every page's items, in page order
+    #[ts(type = "Array<unknown>")]
+    pub items: Vec<Value>,
+    pub end: PaginationEnd,
+}
+
+// The `rel="next"` target of a Link header
+fn link_next(value: &str) -> Option<String> {
+    let mut rest = value;
+    while let Some(start) = rest.find('<') {
+        let end = start + rest[start..].find('>')?;
+        let target = &rest[start + 1..end];
+        let params_end = rest[end..].find('<').map_or(rest.len(), |i| end + i);
+        let is_next = rest[end + 1..params_end].split(';').any(|param| {
+            param.split_once('=').is_some_and(|(name, value)| {
+                name.trim().eq_ignore_ascii_case("rel")
+                    && value
+                        .trim()
+                        .trim_matches(['"', ','])
+                        .split_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("next"))
+            })
+        });
+        if is_next {
+            return Some(target.to_string());
+        }
+        rest = &rest[params_end..];
+    }
+    None
+}
+
+fn origin(url: &str) -> &str {
+    let authority_start = url.find("://").map_or(0, |i| i + 3);
+    let path_start = url[authority_start..]
+        .find(['/', '?', '#'])
+        .map_or(url.len(), |i| authority_start + i);
+    &url[..path_start]
+}
+
+// A next-page reference against the page it came from
+fn resolve(base: &str, next: &str) -> String {
+    let base = base.split('#').next().unwrap_or(base);
+    if next.contains("://") {
+        next.to_string()
+    } else if let Some(rest) = next.strip_prefix("//") {
+        let scheme = base.split_once("://").map_or("https", |(scheme, _)| scheme);
+        format!("{}://{}", scheme, rest)
+    } else if next.starts_with('/') {
+        format!("{}{}", origin(base), next)
+    } else if next.starts_with('?') {
+        format!("{}{}", base.split('?').next().unwrap_or(base), next)
+    } else {
+        let path = base.split('?').next().unwrap_or(base);
+        // Relative to the last path segment's directory
+        let dir_end = path.rfind('/').filter(|&i| i >= origin(base).len());
+        format!("{}/{}", &path[..dir_end.unwrap_or(path.len())], next)
+    }
+}
+
+// Sets `name` in the query, replacing the value already there
+fn with_query_param(url: &str, name: &str, value: &str) -> String {
+    let (url, fragment) = match url.split_once('#') {
+        Some((url, fragment)) => (url, Some(fragment)),
+        None => (url, None),
+    };
+    let (base, query) = url.split_once('?').unwrap_or((url, ""));
+    let pair = format!("{}={}", name, forms::encode_component(value));
+    let mut replaced = false;
+    let mut pairs: Vec<String> = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            if p.split('=').next() == Some(name) {
+                replaced = true;
+                pair.clone()
+            } else {
+                p.to_string()
+            }
+        })
+        .collect();
+    if !replaced {
+        pairs.push(pair);
+    }
+    let mut out = format!("{}?{}", base, pairs.join("&"));
+    if let Some(fragment) = fragment {
+        out.push('#');
+        out.push_str(fragment);
+    }
+    out
+}
+
+fn query_params(url: &str) -> impl Iterator<Item = &str> {
+    let query = url.split('#').next().unwrap_or(url).split_once('?');
+    query
+        .map(|(_, query)| query)
+        .unwrap_or("")
+        .split('&')
+        .filter_map(|pair| pair.split('=').next())
+        .filter(|name| !name.is_empty())
+}
+
+fn next_page(url: &str, headers: &Headers, body: Option<&Value>) -> Option<String> {
+    if let Some(next) = headers.get_all("link").find_map(link_next) {
+        return Some(resolve(url, &next));
+    }
+    let body = body?;
+    let next_url = NEXT_URL_POINTERS
+        .iter()
+        .filter_map(|pointer| body.pointer(pointer)?.as_str())
+        .find(|next| next.contains("://") || next.starts_with(['/', '?']));
+    if let Some(next) = next_url {
+        return Some(resolve(url, next));
+    }
+    let (cursor, param) = CURSOR_FIELDS.iter().find_map(|(pointer, param)| {
+        let cursor = match body.pointer(pointer)? {
+            Value::String(cursor) if !cursor.is_empty() => cursor.clone(),
+            Value::Number(cursor) => cursor.to_string(),
+            _ => return None,
+        };
+        Some((cursor, *param))
+    })?;
+    let param = query_params(url)
+        .find(|name| CURSOR_PARAMS.contains(name))
+        .unwrap_or(param);
+    Some(with_query_param(url, param, &cursor))
+}
+
+fn items(body: &Value) -> Vec<Value> {
+    if let Value::Array(items) = body {
+        return items.clone();
+    }
+    let Value::Object(object) = body else {
+        return Vec::new();
+    };
+    let named = ITEM_FIELDS
+        .iter()
+        .find_map(|field| object.get(*field)?.as_array());
+    // Otherwise the only list in the body
+    let mut lists = object.values().filter_map(Value::as_array);
+    let only = match (lists.next(), lists.next()) {
+        (Some(list), None) => Some(list),
+        _ => None,
+    };
+    named.or(only).cloned().unwrap_or_default()
+}
+
+// Replays request `id` and follows its next pages, `max_pages` at most with
+// the first one included. Pages go through the upstream proxy of `project`,
+// or of the active project.
+#[tauri::command]
+pub async fn replay_paginated(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    app_handle: tauri::AppHandle,
+    id: String,
+    max_pages: usize,
+    project: Option<String>,
+) -> Result<PaginatedReplay, String> {
+    if max_pages == 0 {
+        return Err("max_pages must be at least 1".to_string());
+    }
+    let outgoing = replay::outgoing(&store, &store_limits, &id)?;
+    let (client, _relay) = replay::client(&app_handle, project.as_deref()).await?;
+
+    let mut pages = Vec::new();
+    let mut all_items = Vec::new();
+    let mut seen = HashSet::new();
+    let mut url = outgoing.url.clone();
+    let end = loop {
+        seen.insert(url.clone());
+        let sent = Instant::now();
+        let result = replay::send_outgoing(&client, &outgoing, &url).await;
+        let duration_ms = sent.elapsed().as_millis() as u64;
+        let Resent {
+            status,
+            headers,
+            body,
+        } = match result {
+            Ok(resent) => resent,
+            Err(e) => {
+                pages.push(PageOutcome {
+                    url,
+                    status: None,
+                    duration_ms,
+                    items: 0,
+                    error: Some(e),
+                });
+                break PaginationEnd::Failed;
+            }
+        };
+        let body: Option<Value> = serde_json::from_str(&body).ok();
+        let page_items = body.as_ref().map(items).unwrap_or_default();
+        pages.push(PageOutcome {
+            url: url.clone(),
+            status: Some(status),
+            duration_ms,
+            items: page_items.len(),
+            error: None,
+        });
+        all_items.extend(page_items);
+
+        if !(200..300).contains(&status) {
+            break PaginationEnd::Failed;
+        }
+        let Some(next) = next_page(&url, &headers, body.as_ref()) else {
+            break PaginationEnd::LastPage;
+        };
+        if seen.contains(&next) {
+            break PaginationEnd::Repeated;
+        }
+        if pages.len() >= max_pages {
+            break PaginationEnd::MaxPages;
+        }
+        url = next;
+    };
+    Ok(PaginatedReplay {
+        id,
+        pages,
+        items: all_items,
+        end,
+    })
+}
@@ -0,0 +1,228 @@
+// Timings of the observer's own ingest pipeline, for users who hit its
+// throughput limits to attach to an issue. Off by default; `set_perf_mode`
+// switches it on, optionally timing only one frame in `sample_every` to keep
+// the overhead down under heavy traffic, and starts a new breakdown.
+// `get_perf_breakdown` reports, per stage, how often it was timed and how long
+// it took, in pipeline order:
+//
+// - `parse`: decoding the frame's JSON and each message in it
+// - `dedup`: the duplicate check, aliases included
+// - `enrich`: redaction, tagging, derived fields and spilling large bodies
+// - `store`: adding to the store, the limits and the storage backend,
+//   `index` not included
+// - `index`: updating the request index
+// - `emit`: events to the frontend
+// - `rules`: alerts, watches, break rules and baselines
+//
+// Only requests that make it into the store are timed past `dedup`; dropped
+// ones stop there. `request-end` is timed from `enrich` on. Nothing about the
+// traffic itself is kept.
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+use crate::livestats::TDigest;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum PerfStage {
+    Parse,
+    Dedup,
+    Enrich,
+    Store,
+    Index,
+    Emit,
+    Rules,
+}
+
+const STAGES: [PerfStage; 7] = [
+    PerfStage::Parse,
+    PerfStage::Dedup,
+    PerfStage::Enrich,
+    PerfStage::Store,
+    PerfStage::Index,
+    PerfStage::Emit,
+    PerfStage::Rules,
+];
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct StageTiming {
+    pub stage: PerfStage,
+    // Times it was timed; `parse` counts frames and messages separately
+    #[ts(type = "number")]
+    pub samples: u64,
+    pub total_ms: f64,
+    pub mean_us: f64,
+    pub p95_us: Option<f64>,
+    pub max_us: f64,
+    // Of the time spent in all stages
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct PerfBreakdown {
+    pub enabled: bool,
+    pub sample_every: u32,
+    // When the breakdown started, in ms since the epoch
+    #[ts(type = "number | null")]
+    pub since: Option<u64>,
+    #[ts(type = "number")]
+    pub sampled_frames: u64,
+    pub stages: Vec<StageTiming>,
+}
+
+#[derive(Debug, Default)]
+struct StageProfile {
+    samples: u64,
+    total: Duration,
+    max: Duration,
+    // In microseconds
+    durations: TDigest,
+}
+
+#[derive(Debug, Default)]
+struct Profile {
+    since: Option<u64>,
+    stages: [StageProfile; STAGES.len()],
+}
+
+// Atomics for what every frame checks, so it costs next to nothing while off
+#[derive(Debug, Default)]
+pub struct Perf {
+    enabled: AtomicBool,
+    sample_every: AtomicU32,
+    frames: AtomicU64,
+    sampled: AtomicU64,
+    profile: Mutex<Profile>,
+}
+
+pub type PerfState = Arc<Perf>;
+
+// Whether one frame is timed, decided when it arrives so all its stages are
+pub(crate) struct Probe<'a> {
+    perf: &'a Perf,
+    sampled: bool,
+}
+
+pub(crate) fn probe(state: &PerfState) -> Probe<'_> {
+    let sampled = state.enabled.load(Ordering::Relaxed) && {
+        let every = state.sample_every.load(Ordering::Relaxed).max(1) as u64;
+        state
+            .frames
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(every)
+    };
+    if sampled {
+        state.sampled.fetch_add(1, Ordering::Relaxed);
+    }
+    Probe {
+        perf: state,
+        sampled,
+    }
+}
+
+impl Probe<'_> {
+    pub(crate) fn start(&self) -> Option<Instant> {
+        self.sampled.then(Instant::now)
+    }
+
+    // Records the time since `started`, and returns it
+    pub(crate) fn stop(&self, stage: PerfStage, started: Option<Instant>) -> Duration {
+        self.stop_excluding(stage, started, Duration::ZERO)
+    }
+
+    // Same, less time already recorded for a stage nested inside this one
+    pub(crate) fn stop_excluding(
+        &self,
+        stage: PerfStage,
+        started: Option<Instant>,
+        nested: Duration,
+    ) -> Duration {
+        let Some(started) = started else {
+            return Duration::ZERO;
+        };
+        let elapsed = started.elapsed().saturating_sub(nested);
+        let mut profile = self.perf.profile.lock().unwrap();
+        let timed = &mut profile.stages[stage as usize];
+        timed.samples += 1;
+        timed.total += elapsed;
+        timed.max = timed.max.max(elapsed);
+        timed.durations.add(elapsed.as_secs_f64() * 1e6);
+        elapsed
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+// Switching it on starts a new breakdown; switching it off keeps the last one
+pub(crate) fn set_mode(state: &PerfState, enabled: bool, sample_every: Option<u32>) {
+    let mut profile = state.profile.lock().unwrap();
+    if enabled {
+        *profile = Profile {
+            since: Some(now_ms()),
+            ..Default::default()
+        };
+        state.frames.store(0, Ordering::Relaxed);
+        state.sampled.store(0, Ordering::Relaxed);
+    }
+    state
+        .sample_every
+        .store(sample_every.unwrap_or(1).max(1), Ordering::Relaxed);
+    state.enabled.store(enabled, Ordering::Relaxed);
+}
+
+pub(crate) fn breakdown(state: &PerfState) -> PerfBreakdown {
+    let mut profile = state.profile.lock().unwrap();
+    let spent: Duration = profile.stages.iter().map(|stage| stage.total).sum();
+    let stages = STAGES
+        .iter()
+        .zip(profile.stages.iter_mut())
+        .map(|(&stage, timed)| StageTiming {
+            stage,
+            samples: timed.samples,
+            total_ms: timed.total.as_secs_f64() * 1e3,
+            mean_us: timed.total.as_secs_f64() * 1e6 / timed.samples.max(1) as f64,
+            p95_us: timed.durations.quantile(0.95),
+            max_us: timed.max.as_secs_f64() * 1e6,
+            share: if spent.is_zero() {
+                0.0
+            } else {
+                timed.total.as_secs_f64() / spent.as_secs_f64()
+            },
+        })
+        .collect();
+    PerfBreakdown {
+        enabled: state.enabled.load(Ordering::Relaxed),
+        sample_every: state.sample_every.load(Ordering::Relaxed).max(1),
+        since: profile.since,
+        sampled_frames: state.sampled.load(Ordering::Relaxed),
+        stages,
+    }
+}
+
+#[tauri::command]
+pub async fn set_perf_mode(
+    state: tauri::State<'_, PerfState>,
+    enabled: bool,
+    sample_every: Option<u32>,
+) -> Result<(), String> {
+    set_mode(&state, enabled, sample_every);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_perf_breakdown(
+    state: tauri::State<'_, PerfState>,
+) -> Result<PerfBreakdown, String> {
+    Ok(breakdown(&state))
+}
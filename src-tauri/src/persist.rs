@@ -0,0 +1,433 @@
+// Keeps captured requests in a SQLite database so sessions survive restarts.
+// The in-memory store stays the source of truth; a background task writes
+// whatever changed in it to the session being recorded every few seconds,
+// spilled bodies included, as the changes queued for `storage` tell it.
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::blobs::{self, BlobStore};
+use crate::derived::DerivedFields;
+use crate::events::{self, AppEvent};
+use crate::experiments::ExperimentRules;
+use crate::index::{self, IndexState};
+use crate::limits::{self, StoreLimitState};
+use crate::markers::now_millis;
+use crate::migrate::{decode_request, REQUEST_VERSION};
+use crate::provenance::{self, ProvenanceKind};
+use crate::server::enrich_request;
+use crate::session::{LoadMode, LoadedSession};
+use crate::storage::{self, Changes, StorageState};
+use crate::{NetworkRequest, RequestStore};
+
+pub(crate) const DATABASE_FILE: &str = "sessions.sqlite3";
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+// Schema changes in order; MIGRATIONS[n] takes the database from version n
+// to n + 1, tracked in `PRAGMA user_version`. Never edit one that shipped.
+const MIGRATIONS: &[&str] = &[
+    // 1: sessions and their requests. IF NOT EXISTS because databases from
+    // before migrations were tracked already have these tables at version 0.
+    "
+    CREATE TABLE IF NOT EXISTS sessions (
+        id TEXT PRIMARY KEY,
+        started_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS requests (
+        session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        id TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (session_id, id)
+    );
+    CREATE INDEX IF NOT EXISTS requests_by_time ON requests (session_id, timestamp);
+    ",
+    // 2: which `migrate::REQUEST_VERSION` each row's JSON was written in
+    "ALTER TABLE requests ADD COLUMN format_version INTEGER NOT NULL DEFAULT 1;",
+];
+
+fn migrate(db: &mut Connection) -> Result<(), String> {
+    let version: usize = db
+        .query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+        .map_err(|e| e.to_string())? as usize;
+    if version > MIGRATIONS.len() {
+        return Err(format!(
+            "Session history was written by a newer version of the app (schema {})",
+            version
+        ));
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = db.transaction().map_err(|e| e.to_string())?;
+        tx.execute_batch(migration)
+            .map_err(|e| format!("Migration {} failed: {}", index + 1, e))?;
+        tx.pragma_update(None, "user_version", (index + 1) as i64)
+            .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub struct Persistence {
+    db: Connection,
+    // The session the store is written to; only created once there's something in it
+    session_id: String,
+    session_started: bool,
+    // Ids of the requests written to the session
+    written: HashSet<String>,
+}
+
+// None until the database is open, and in the headless agent, which hands
+// its capture over instead
+pub type PersistState = Arc<Mutex<Option<Persistence>>>;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct PastSession {
+    pub id: String,
+    #[ts(type = "number")]
+    pub started_at: u64,
+    // Last time anything in it was written
+    #[ts(type = "number")]
+    pub updated_at: u64,
+    pub request_count: usize,
+    // The session the store is being recorded into
+    pub current: bool,
+}
+
+impl Persistence {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let mut db = Connection::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        db.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")
+            .map_err(|e| e.to_string())?;
+        migrate(&mut db)?;
+        Ok(Persistence {
+            db,
+            session_id: Uuid::new_v4().to_string(),
+            session_started: false,
+            written: HashSet::new(),
+        })
+    }
+
+    // Copies of the requests to write and the ids of the rows to delete
+    fn pending(
+        &self,
+        requests: &[NetworkRequest],
+        changes: &Changes,
+    ) -> (Vec<NetworkRequest>, Vec<String>) {
+        if changes.all {
+            let live: HashSet<&str> = requests.iter().map(|r| r.id.as_str()).collect();
+            let removed = self
+                .written
+                .iter()
+                .filter(|id| !live.contains(id.as_str()))
+                .cloned()
+                .collect();
+            return (requests.to_vec(), removed);
+        }
+        let changed = requests
+            .iter()
+            .filter(|request| changes.ids.contains(&request.id))
+            .cloned()
+            .collect();
+        let removed = changes
+            .removed
+            .iter()
+            .filter(|id| self.written.contains(*id))
+            .cloned()
+            .collect();
+        (changed, removed)
+    }
+
+    // Writes `changed`, with their bodies already read back, and deletes
+    // `removed`. Returns the number of rows touched.
+    fn write(&mut self, changed: &[NetworkRequest], removed: &[String]) -> Result<usize, String> {
+        if changed.is_empty() && removed.is_empty() {
+            return Ok(0);
+        }
+        let changed = changed
+            .iter()
+            .map(|request| {
+                let data = serde_json::to_string(request).map_err(|e| e.to_string())?;
+                Ok((request.id.clone(), request.timestamp, data))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let now = now_millis() as i64;
+        let tx = self.db.transaction().map_err(|e| e.to_string())?;
+        if !self.session_started {
+            tx.execute(
+                "INSERT OR IGNORE INTO sessions (id, started_at, updated_at) VALUES (?1, ?2, ?2)",
+                params![self.session_id, now],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        {
+            let mut upsert = tx
+                .prepare(
+                    "INSERT INTO requests (session_id, id, timestamp, data, format_version)
+                     VALUES (?1, ?2, ?3, ?4, ?5)
+                     ON CONFLICT (session_id, id)
+                     DO UPDATE SET timestamp = ?3, data = ?4, format_version = ?5",
+                )
+                .map_err(|e| e.to_string())?;
+            for (id, timestamp, data) in &changed {
+                upsert
+                    .execute(params![
+                        self.session_id,
+                        id,
+                        *timestamp as i64,
+                        data,
+                        REQUEST_VERSION
+                    ])
+                    .map_err(|e| e.to_string())?;
+            }
+            let mut delete = tx
+                .prepare("DELETE FROM requests WHERE session_id = ?1 AND id = ?2")
+                .map_err(|e| e.to_string())?;
+            for id in removed {
+                delete
+                    .execute(params![self.session_id, id])
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        tx.execute(
+            "UPDATE sessions SET updated_at = ?2 WHERE id = ?1",
+            params![self.session_id, now],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+
+        self.session_started = true;
+        for id in removed {
+            self.written.remove(id);
+        }
+        let touched = changed.len() + removed.len();
+        self.written
+            .extend(changed.into_iter().map(|(id, _, _)| id));
+        Ok(touched)
+    }
+
+    fn sessions(&self) -> Result<Vec<PastSession>, String> {
+        let mut query = self
+            .db
+            .prepare(
+                "SELECT s.id, s.started_at, s.updated_at, COUNT(r.id)
+                 FROM sessions s LEFT JOIN requests r ON r.session_id = s.id
+                 GROUP BY s.id ORDER BY s.started_at DESC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = query
+            .query_map([], |row| {
+                let id: String = row.get(0)?;
+                Ok(PastSession {
+                    current: id == self.session_id,
+                    id,
+                    started_at: row.get::<_, i64>(1)? as u64,
+                    updated_at: row.get::<_, i64>(2)? as u64,
+                    request_count: row.get::<_, i64>(3)? as usize,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    fn session_exists(&self, id: &str) -> Result<bool, String> {
+        self.db
+            .query_row("SELECT 1 FROM sessions WHERE id = ?1", params![id], |_| {
+                Ok(())
+            })
+            .optional()
+            .map(|found| found.is_some())
+            .map_err(|e| e.to_string())
+    }
+
+    fn read_session(&self, id: &str) -> Result<Vec<NetworkRequest>, String> {
+        let mut query = self
+            .db
+            .prepare(
+                "SELECT data, format_version FROM requests WHERE session_id = ?1
+                 ORDER BY timestamp, rowid",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = query
+            .query_map(params![id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, u32>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        let mut requests = Vec::new();
+        for row in rows {
+            let (data, version) = row.map_err(|e| e.to_string())?;
+            let decoded = serde_json::from_str(&data)
+                .map_err(|e| e.to_string())
+                .and_then(|value| decode_request(value, version));
+            match decoded {
+                Ok(request) => requests.push(request),
+                Err(e) => println!("Skipping unreadable stored request: {}", e),
+            }
+        }
+        Ok(requests)
+    }
+
+    // Makes `id` the session being recorded, as if it had never been closed
+    fn resume(&mut self, id: &str, requests: &[NetworkRequest]) {
+        self.session_id = id.to_string();
+        self.session_started = true;
+        self.written = requests.iter().map(|request| request.id.clone()).collect();
+    }
+
+    fn delete_session(&mut self, id: &str) -> Result<bool, String> {
+        let deleted = self
+            .db
+            .execute("DELETE FROM sessions WHERE id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+        Ok(deleted > 0)
+    }
+}
+
+// Reads the bodies back and writes them; a failed write leaves the whole store
+// to write next time
+fn write_pending(
+    persistence: &mut Persistence,
+    (mut changed, removed): (Vec<NetworkRequest>, Vec<String>),
+    storage: &StorageState,
+) -> Result<usize, String> {
+    blobs::restore(&mut changed);
+    persistence
+        .write(&changed, &removed)
+        .inspect_err(|_| storage::track_changes(storage))
+}
+
+pub(crate) fn flush(
+    store: &RequestStore,
+    persist: &PersistState,
+    storage: &StorageState,
+) -> Result<usize, String> {
+    let mut persistence = persist.lock().map_err(|e| e.to_string())?;
+    let Some(persistence) = persistence.as_mut() else {
+        return Ok(0);
+    };
+    let Some(changes) = storage::take_changes(storage) else {
+        return Ok(0);
+    };
+    let pending = {
+        let requests = store.lock().map_err(|e| e.to_string())?;
+        persistence.pending(&requests, &changes)
+    };
+    // After the store is unlocked, so ingest doesn't wait on the disk
+    write_pending(persistence, pending, storage)
+}
+
+// Runs for the life of the app
+pub(crate) async fn write_behind(
+    store: RequestStore,
+    persist: PersistState,
+    storage: StorageState,
+) {
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+    loop {
+        interval.tick().await;
+        let (store, persist, storage) = (store.clone(), persist.clone(), storage.clone());
+        let flushed =
+            tauri::async_runtime::spawn_blocking(move || flush(&store, &persist, &storage)).await;
+        if let Ok(Err(e)) = flushed {
+            println!("Failed to persist requests: {}", e);
+        }
+    }
+}
+
+fn with_persistence<T>(
+    persist: &PersistState,
+    f: impl FnOnce(&mut Persistence) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut persistence = persist.lock().map_err(|e| e.to_string())?;
+    match persistence.as_mut() {
+        Some(persistence) => f(persistence),
+        None => Err("Session history is not available".to_string()),
+    }
+}
+
+// Newest first, including the one being recorded once it has requests
+#[tauri::command]
+pub async fn list_past_sessions(
+    persist: tauri::State<'_, PersistState>,
+) -> Result<Vec<PastSession>, String> {
+    with_persistence(&persist, |persistence| persistence.sessions())
+}
+
+// Replaces the store with a past session's requests and carries on recording
+// into that session. Whatever the current session holds is saved first. Like
+// `load_session`, it keeps to the store limits and emits store-replaced.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn load_past_session(
+    store: tauri::State<'_, RequestStore>,
+    persist: tauri::State<'_, PersistState>,
+    experiment_rules: tauri::State<'_, ExperimentRules>,
+    derived_fields: tauri::State<'_, DerivedFields>,
+    blob_store: tauri::State<'_, BlobStore>,
+    storage: tauri::State<'_, StorageState>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    index: tauri::State<'_, IndexState>,
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<NetworkRequest>, String> {
+    let (current, evicted) = with_persistence(&persist, |persistence| {
+        if !persistence.session_exists(&id)? {
+            return Err(format!("Session not found: {}", id));
+        }
+        // Holding the store throughout so nothing arrives between saving and replacing it
+        let mut current = store.lock().map_err(|e| e.to_string())?;
+        let changes = storage::take_changes(&storage).unwrap_or_default();
+        let pending = persistence.pending(&current, &changes);
+        write_pending(persistence, pending, &storage)?;
+        let mut requests = persistence.read_session(&id)?;
+        persistence.resume(&id, &requests);
+        // Rebuilds what isn't stored, like the JSON field index
+        for request in requests.iter_mut() {
+            enrich_request(request, &experiment_rules, &derived_fields);
+            provenance::record(request, ProvenanceKind::Restored, Some(id.clone()));
+            blob_store.store(request);
+        }
+        *current = requests;
+        let evicted = limits::enforce(&store_limits, &mut current);
+        Ok((current.clone(), evicted))
+    })?;
+    storage::replace_all(&storage, &current);
+    index::invalidate(&index);
+
+    let loaded = LoadedSession {
+        path: id,
+        mode: LoadMode::Replace,
+        request_count: current.len(),
+        evicted: evicted.len(),
+    };
+    events::emit(&app_handle, AppEvent::StoreReplaced(&loaded))?;
+    Ok(current)
+}
+
+#[tauri::command]
+pub async fn delete_past_session(
+    persist: tauri::State<'_, PersistState>,
+    id: String,
+) -> Result<(), String> {
+    with_persistence(&persist, |persistence| {
+        if id == persistence.session_id {
+            return Err("Can't delete the session being recorded; clear it instead".to_string());
+        }
+        match persistence.delete_session(&id)? {
+            true => Ok(()),
+            false => Err(format!("Session not found: {}", id)),
+        }
+    })
+}
@@ -0,0 +1,52 @@
+// Pinned requests are kept by `clear_requests`, the retention policy and the
+// store limits, so a reproduction survives cleaning up around it. Only deleting
+// it explicitly removes it.
+use crate::bulk::BulkResult;
+use crate::events::{self, AppEvent};
+use crate::storage::{self, StorageState};
+use crate::RequestStore;
+
+fn set_pinned(
+    store: &RequestStore,
+    storage: &StorageState,
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    pinned: bool,
+) -> Result<BulkResult, String> {
+    let updated = {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        let request = requests
+            .iter_mut()
+            .find(|r| r.has_id(id))
+            .ok_or_else(|| format!("Request not found: {}", id))?;
+        request.pinned = pinned;
+        request.clone()
+    };
+    storage::put(storage, &updated);
+
+    let result = BulkResult {
+        ids: vec![updated.id.clone()],
+    };
+    events::emit(app_handle, AppEvent::RequestsUpdated(&result))?;
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn pin_request(
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<BulkResult, String> {
+    set_pinned(&store, &storage, &app_handle, &id, true)
+}
+
+#[tauri::command]
+pub async fn unpin_request(
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<BulkResult, String> {
+    set_pinned(&store, &storage, &app_handle, &id, false)
+}
@@ -0,0 +1,127 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+use crate::limits::{self, StoreLimitState};
+use crate::{NetworkRequest, RequestStore};
+
+// Browsers send the real request right after the preflight succeeds; anything
+// later than this is more likely the next call hitting a cached preflight
+const PAIRING_WINDOW_MS: u64 = 10_000;
+
+// Whether listings hide preflights that were paired with their request. Only
+// the listings change; the preflights stay in the store for CORS analysis.
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct PreflightMode {
+    pub collapse: bool,
+}
+
+pub type PreflightState = Arc<Mutex<PreflightMode>>;
+
+pub fn is_preflight(request: &NetworkRequest) -> bool {
+    request.method.eq_ignore_ascii_case("OPTIONS")
+        && request
+            .headers
+            .get("access-control-request-method")
+            .is_some()
+}
+
+// Preflight id -> id of the request it cleared, and the reverse
+#[derive(Debug, Default)]
+pub struct PreflightPairs {
+    by_preflight: HashMap<String, String>,
+    by_request: HashMap<String, String>,
+}
+
+impl PreflightPairs {
+    // Pairs each request with the oldest unclaimed preflight for the same URL
+    // and method that came before it within the window
+    pub fn build(requests: &[NetworkRequest]) -> Self {
+        let mut order: Vec<&NetworkRequest> = requests.iter().collect();
+        order.sort_by_key(|r| r.timestamp);
+
+        let mut waiting: HashMap<(&str, String), VecDeque<&NetworkRequest>> = HashMap::new();
+        let mut pairs = PreflightPairs::default();
+        for request in order {
+            if is_preflight(request) {
+                let method = request
+                    .headers
+                    .get("access-control-request-method")
+                    .unwrap_or_default()
+                    .trim()
+                    .to_uppercase();
+                waiting
+                    .entry((request.url.as_str(), method))
+                    .or_default()
+                    .push_back(request);
+                continue;
+            }
+
+            let key = (request.url.as_str(), request.method.to_uppercase());
+            let Some(queue) = waiting.get_mut(&key) else {
+                continue;
+            };
+            while queue
+                .front()
+                .is_some_and(|p| request.timestamp.saturating_sub(p.timestamp) > PAIRING_WINDOW_MS)
+            {
+                queue.pop_front();
+            }
+            if let Some(preflight) = queue.pop_front() {
+                pairs
+                    .by_preflight
+                    .insert(preflight.id.clone(), request.id.clone());
+                pairs
+                    .by_request
+                    .insert(request.id.clone(), preflight.id.clone());
+            }
+        }
+        pairs
+    }
+
+    pub fn preflight_of(&self, request_id: &str) -> Option<&str> {
+        self.by_request.get(request_id).map(String::as_str)
+    }
+
+    // Paired preflights are hidden when collapsing; unpaired ones stay visible
+    // since a preflight with no follow-up is usually a CORS failure
+    pub fn is_paired_preflight(&self, id: &str) -> bool {
+        self.by_preflight.contains_key(id)
+    }
+}
+
+#[tauri::command]
+pub async fn set_collapse_preflights(
+    preflight: tauri::State<'_, PreflightState>,
+    enabled: bool,
+) -> Result<PreflightMode, String> {
+    let mut mode = preflight.lock().map_err(|e| e.to_string())?;
+    mode.collapse = enabled;
+    Ok(mode.clone())
+}
+
+#[tauri::command]
+pub async fn get_preflight_mode(
+    preflight: tauri::State<'_, PreflightState>,
+) -> Result<PreflightMode, String> {
+    Ok(preflight.lock().map_err(|e| e.to_string())?.clone())
+}
+
+// The preflight that cleared request `id`, if one was captured
+#[tauri::command]
+pub async fn get_preflight_for(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    id: String,
+) -> Result<Option<NetworkRequest>, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    let pairs = PreflightPairs::build(&requests);
+    Ok(requests
+        .iter()
+        .find(|r| r.has_id(&id))
+        .and_then(|r| pairs.preflight_of(&r.id))
+        .and_then(|preflight| requests.iter().find(|r| r.id == preflight))
+        .cloned())
+}
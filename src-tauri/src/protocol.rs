@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ts_rs::TS;
+
+use crate::lifecycle::RequestState;
+use crate::markers::Marker;
+use crate::{NetworkRequest, NetworkResponse, OfflineQueue};
+
+// Bumped whenever a message is added or changes shape. Clients announce the
+// version they speak in `hello`; messages without a type are protocol 0.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct Hello {
+    pub protocol: u32,
+    #[serde(default)]
+    pub client: Option<String>,
+    #[serde(default)]
+    pub client_version: Option<String>,
+}
+
+// Completes a request announced earlier with `request-start`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct RequestEnd {
+    pub id: String,
+    pub response: NetworkResponse,
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub duration: Option<u64>,
+    // How the request ended; `complete` unless the client says otherwise
+    #[serde(default)]
+    pub state: Option<RequestState>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+// Moves a request announced with `request-start` to a new state (protocol 2)
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct RequestStateUpdate {
+    pub id: String,
+    pub state: RequestState,
+    // When the client saw the change; defaults to when it arrives
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub timestamp: Option<u64>,
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+// Several messages in one frame; each is processed as if sent on its own
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct Batch {
+    #[ts(as = "Vec<crate::WebSocketMessage>")]
+    pub messages: Vec<Value>,
+}
+
+// Messages the observer sends back to a client
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub enum ServerMessage {
+    Welcome {
+        protocol: u32,
+        server_version: String,
+    },
+    // Sent in place of silence when a message can't be ingested
+    Error(ParseError),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub enum ParseErrorCode {
+    InvalidJson,
+    UnknownType,
+    InvalidMessage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct ParseError {
+    pub code: ParseErrorCode,
+    pub message: String,
+    // The message's `type` and `id`, when present, so the client can tell which
+    // of its messages was rejected
+    pub message_type: Option<String>,
+    pub id: Option<String>,
+    // Field that failed, e.g. `response.status` or `headers[2]`
+    pub path: Option<String>,
+    // What the field should have been, e.g. `u16` or `a sequence`
+    pub expected: Option<String>,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}: {}", path, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+const MESSAGE_TYPES: &[&str] = &[
+    "network-request",
+    "offline-queue",
+    "marker",
+    "hello",
+    "request-start",
+    "request-end",
+    "request-state",
+    "batch",
+];
+
+impl ParseError {
+    pub fn invalid_json(error: &serde_json::Error) -> Self {
+        ParseError {
+            code: ParseErrorCode::InvalidJson,
+            message: error.to_string(),
+            message_type: None,
+            id: None,
+            path: None,
+            expected: None,
+        }
+    }
+
+    // Works out why `value` isn't a valid message by deserializing it again as
+    // the payload its `type` asks for, tracking the path to the failing field
+    pub fn diagnose(value: &Value) -> Self {
+        let message_type = value
+            .get("type")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let id = value.get("id").and_then(Value::as_str).map(str::to_string);
+
+        let result = match message_type.as_deref() {
+            // Untyped messages are protocol 0 network requests
+            None | Some("network-request") | Some("request-start") => {
+                check::<NetworkRequest>(value)
+            }
+            Some("offline-queue") => check::<OfflineQueue>(value),
+            Some("marker") => check::<Marker>(value),
+            Some("hello") => check::<Hello>(value),
+            Some("request-end") => check::<RequestEnd>(value),
+            Some("request-state") => check::<RequestStateUpdate>(value),
+            Some("batch") => check::<Batch>(value),
+            Some(other) => {
+                return ParseError {
+                    code: ParseErrorCode::UnknownType,
+                    message: format!("unknown message type `{}`", other),
+                    message_type,
+                    id,
+                    path: Some("type".to_string()),
+                    expected: Some(MESSAGE_TYPES.join(", ")),
+                };
+            }
+        };
+
+        let (message, path, expected) = match result {
+            Err(error) => describe(error),
+            // The payload is fine on its own, so the envelope is at fault
+            // (e.g. `type` isn't a string or the value isn't an object)
+            Ok(()) => (
+                "expected an object with a string `type`".to_string(),
+                Some("type".to_string()).filter(|_| value.is_object()),
+                Some("object".to_string()),
+            ),
+        };
+
+        ParseError {
+            code: ParseErrorCode::InvalidMessage,
+            message,
+            message_type,
+            id,
+            path,
+            expected,
+        }
+    }
+}
+
+fn check<'de, T: Deserialize<'de>>(
+    value: &'de Value,
+) -> Result<(), serde_path_to_error::Error<serde_json::Error>> {
+    serde_path_to_error::deserialize::<_, T>(value).map(|_| ())
+}
+
+fn describe(
+    error: serde_path_to_error::Error<serde_json::Error>,
+) -> (String, Option<String>, Option<String>) {
+    let mut path = Some(error.path().to_string()).filter(|p| p != ".");
+    let message = error.into_inner().to_string();
+
+    let expected = if let Some(rest) = message.strip_prefix("missing field `") {
+        // Reported against the parent, so extend the path with the field itself
+        let field = rest.split('`').next().unwrap_or_default();
+        path = Some(match path {
+            Some(parent) => format!("{}.{}", parent, field),
+            None => field.to_string(),
+        });
+        None
+    } else {
+        message
+            .split_once(", expected ")
+            .map(|(_, expected)| expected.to_string())
+    };
+
+    (message, path, expected)
+}
+
+impl ServerMessage {
+    pub fn welcome() -> Self {
+        ServerMessage::Welcome {
+            protocol: PROTOCOL_VERSION,
+            server_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
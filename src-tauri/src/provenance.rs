@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::lifecycle::StateTransition;
+use crate::limits::{self, StoreLimitState};
+use crate::markers::now_millis;
+use crate::{NetworkRequest, RequestStore};
+
+// What the observer did to an entry after the client reported it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "kebab-case")]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub enum ProvenanceKind {
+    // Stored as it arrived over the live connection
+    Captured,
+    // Stored from an offline queue, the background agent's capture or a file
+    // from another tool; detail is the file's path
+    Imported,
+    // A duplicate was dropped and its id kept as an alias; detail is that id
+    DuplicateMerged,
+    // Earlier entries for the same endpoint were dropped in latest-only mode
+    Superseded,
+    // The response arrived with `request-end`
+    ResponseAttached,
+    // A body was moved to disk, leaving a preview
+    BodySpilled,
+    // Tags were changed from the app
+    Retagged,
+    // Reloaded from session history or a saved session file; detail is the
+    // session id or the file's path
+    Restored,
+    // Loaded from request storage at startup, usually the crash journal
+    Recovered,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct ProvenanceEntry {
+    pub kind: ProvenanceKind,
+    #[ts(type = "number")]
+    pub at: u64,
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+pub fn record(request: &mut NetworkRequest, kind: ProvenanceKind, detail: Option<String>) {
+    request.provenance.push(ProvenanceEntry {
+        kind,
+        at: now_millis(),
+        detail,
+    });
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct RequestHistory {
+    pub request_id: String,
+    pub aliases: Vec<String>,
+    pub provenance: Vec<ProvenanceEntry>,
+    pub transitions: Vec<StateTransition>,
+}
+
+// Everything that happened to an entry, oldest first, so what it shows can be
+// traced back to what the client sent
+#[tauri::command]
+pub async fn get_request_history(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    id: String,
+) -> Result<RequestHistory, String> {
+    let request = limits::find_request(&store_limits, &store, &id)?;
+    Ok(RequestHistory {
+        request_id: request.id,
+        aliases: request.aliases,
+        provenance: request.provenance,
+        transitions: request.transitions,
+    })
+}
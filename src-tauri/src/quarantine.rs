@@ -0,0 +1,67 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::events::{self, AppEvent, EventSink};
+use crate::markers::now_millis;
+use crate::text;
+
+// Oldest messages are dropped past this, and each one keeps only a prefix of
+// its text so a misbehaving client can't grow the buffer without bound
+const MAX_UNPARSED_MESSAGES: usize = 200;
+const MAX_UNPARSED_TEXT_LEN: usize = 16 * 1024;
+
+// A message the ingest server couldn't make sense of, kept so the sender's
+// instrumentation can be fixed instead of the traffic silently disappearing
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct UnparsedMessage {
+    pub id: String,
+    #[ts(type = "number")]
+    pub received_at: u64,
+    pub peer: String,
+    pub error: String,
+    // Length of the full message; `text` may be truncated
+    pub length: usize,
+    pub text: String,
+}
+
+pub type UnparsedStore = Arc<Mutex<VecDeque<UnparsedMessage>>>;
+
+pub fn record(store: &UnparsedStore, peer: &str, text: &str, error: String, sink: &dyn EventSink) {
+    let message = UnparsedMessage {
+        id: Uuid::new_v4().to_string(),
+        received_at: now_millis(),
+        peer: peer.to_string(),
+        error,
+        length: text.len(),
+        text: text::prefix(text, MAX_UNPARSED_TEXT_LEN).to_string(),
+    };
+    {
+        let mut messages = store.lock().unwrap();
+        if messages.len() >= MAX_UNPARSED_MESSAGES {
+            messages.pop_front();
+        }
+        messages.push_back(message.clone());
+    }
+
+    if let Err(e) = events::emit(sink, AppEvent::UnparsedMessage(&message)) {
+        println!("Failed to emit unparsed-message event: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn get_unparsed_messages(
+    store: tauri::State<'_, UnparsedStore>,
+) -> Result<Vec<UnparsedMessage>, String> {
+    let messages = store.lock().map_err(|e| e.to_string())?;
+    Ok(messages.iter().cloned().collect())
+}
+
+#[tauri::command]
+pub async fn clear_unparsed_messages(store: tauri::State<'_, UnparsedStore>) -> Result<(), String> {
+    store.lock().map_err(|e| e.to_string())?.clear();
+    Ok(())
+}
@@ -0,0 +1,462 @@
+// A small query language for filtering the store from a search box, e.g.
+// `method=POST status>=500 host~api.example.com body:"user_id"`. Terms are
+// separated by spaces and must all match; a value with spaces goes in double
+// quotes. Each term is `field op value`:
+//
+// - `method`, `host`, `url`, `path`, `tag`, `state`: `=` and `!=` compare the
+//   whole value, `~` (or `:`) and `!~` look for it inside; case doesn't matter
+// - `status`, `duration` (ms), `size` (response body bytes): `=`, `!=`, `>`,
+//   `>=`, `<`, `<=`; `status=5xx` matches a whole class
+// - `body`: `~` (or `:`) and `!~` on the request or response body as kept in
+//   memory, case-sensitive
+// - `header.<name>`: the value of a request or response header, like `host`;
+//   `header:<name>` only requires the header to be there
+//
+// A word without an operator, or with a `:` after something other than a
+// field name (`localhost:8080`), matches anywhere in the URL.
+use serde::Serialize;
+use std::collections::HashSet;
+use ts_rs::TS;
+
+use crate::filter::{url_host, RequestFilter};
+use crate::index::{self, IndexState};
+use crate::limits::{self, StoreLimitState};
+use crate::overflow;
+use crate::summaries::RequestSummary;
+use crate::{NetworkRequest, RequestStore};
+
+const DEFAULT_LIMIT: usize = 200;
+const MAX_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Contains,
+    NotContains,
+}
+
+// Longest first, so `>=` isn't read as `>`
+const OPS: &[(&str, Op)] = &[
+    (">=", Op::Ge),
+    ("<=", Op::Le),
+    ("!=", Op::Ne),
+    ("!~", Op::NotContains),
+    ("=", Op::Eq),
+    (">", Op::Gt),
+    ("<", Op::Lt),
+    ("~", Op::Contains),
+    (":", Op::Contains),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Field {
+    Method,
+    Host,
+    Url,
+    Path,
+    Tag,
+    State,
+    Status,
+    Duration,
+    Size,
+    Body,
+    Header(String),
+    HasHeader,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Number(u64),
+    // `5xx`
+    StatusClass(u16),
+    // Lowercased, except for bodies
+    Text(String),
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    field: Field,
+    op: Op,
+    value: Value,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct QueryResult {
+    // Matches in the whole store, of which the first `limit` are listed
+    pub total: usize,
+    pub requests: Vec<RequestSummary>,
+}
+
+// Splits on spaces outside double quotes, dropping the quotes
+fn tokens(query: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut started = false;
+    for c in query.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                started = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if started {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                started = false;
+            }
+            c => {
+                current.push(c);
+                started = true;
+            }
+        }
+    }
+    if quoted {
+        return Err("Unclosed quote in query".to_string());
+    }
+    if started {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+fn field(name: &str) -> Option<Field> {
+    let name = name.to_ascii_lowercase();
+    if let Some(header) = name.strip_prefix("header.") {
+        return (!header.is_empty()).then(|| Field::Header(header.to_string()));
+    }
+    Some(match name.as_str() {
+        "method" => Field::Method,
+        "host" => Field::Host,
+        "url" => Field::Url,
+        "path" => Field::Path,
+        "tag" => Field::Tag,
+        "state" => Field::State,
+        "status" => Field::Status,
+        "duration" => Field::Duration,
+        "size" => Field::Size,
+        "body" => Field::Body,
+        "header" => Field::HasHeader,
+        _ => return None,
+    })
+}
+
+fn term(token: &str) -> Result<Term, String> {
+    let invalid = |reason: &str| format!("Invalid query term `{}`: {}", token, reason);
+    let name_end = token
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')))
+        .unwrap_or(token.len());
+    let (name, rest) = token.split_at(name_end);
+    let bare = || Term {
+        field: Field::Url,
+        op: Op::Contains,
+        value: Value::Text(token.to_lowercase()),
+    };
+    let Some(&(symbol, op)) = OPS.iter().find(|(symbol, _)| rest.starts_with(symbol)) else {
+        return Ok(bare());
+    };
+    let value = &rest[symbol.len()..];
+    let field = match field(name) {
+        Some(field) => field,
+        // `https://...` or `localhost:8080`
+        None if symbol == ":" => return Ok(bare()),
+        None => return Err(invalid("unknown field")),
+    };
+    if value.is_empty() {
+        return Err(invalid("missing value"));
+    }
+
+    let value = match field {
+        Field::Status | Field::Duration | Field::Size => {
+            let class = value
+                .strip_suffix("xx")
+                .filter(|_| field == Field::Status && matches!(op, Op::Eq | Op::Ne))
+                .and_then(|digit| digit.parse::<u16>().ok())
+                .filter(|digit| (1..=9).contains(digit));
+            match class {
+                Some(class) => Value::StatusClass(class),
+                None if matches!(op, Op::Contains | Op::NotContains) => {
+                    return Err(invalid("compare numbers with =, !=, >, >=, < or <="));
+                }
+                None => Value::Number(value.parse().map_err(|_| invalid("expected a number"))?),
+            }
+        }
+        _ if matches!(op, Op::Gt | Op::Ge | Op::Lt | Op::Le) => {
+            return Err(invalid("only numbers can be ordered"));
+        }
+        Field::Body if !matches!(op, Op::Contains | Op::NotContains) => {
+            return Err(invalid("search bodies with ~, : or !~"));
+        }
+        Field::Body => Value::Text(value.to_string()),
+        _ => Value::Text(value.to_lowercase()),
+    };
+    Ok(Term { field, op, value })
+}
+
+fn parse(query: &str) -> Result<Vec<Term>, String> {
+    tokens(query)?.iter().map(|token| term(token)).collect()
+}
+
+// What the index can narrow down by; every term is still checked afterwards
+fn index_filter(terms: &[Term]) -> RequestFilter {
+    let mut filter = RequestFilter::default();
+    for term in terms {
+        match (&term.field, term.op, &term.value) {
+            (Field::Method, Op::Eq, Value::Text(method)) if filter.method.is_none() => {
+                filter.method = Some(method.clone());
+            }
+            (Field::Host, Op::Eq, Value::Text(host)) if filter.host.is_none() => {
+                filter.host = Some(host.clone());
+            }
+            (Field::Status, op, value) => {
+                let (min, max) = match (op, value) {
+                    (Op::Eq, Value::Number(status)) => (*status, *status),
+                    (Op::Eq, Value::StatusClass(class)) => {
+                        (*class as u64 * 100, *class as u64 * 100 + 99)
+                    }
+                    (Op::Gt, Value::Number(status)) => (status.saturating_add(1), u64::MAX),
+                    (Op::Ge, Value::Number(status)) => (*status, u64::MAX),
+                    (Op::Lt, Value::Number(status)) => (0, status.saturating_sub(1)),
+                    (Op::Le, Value::Number(status)) => (0, *status),
+                    _ => continue,
+                };
+                let clamp = |status: u64| status.min(u16::MAX as u64) as u16;
+                let min = filter.status_min.unwrap_or(0).max(clamp(min));
+                let max = filter.status_max.unwrap_or(u16::MAX).min(clamp(max));
+                filter.status_min = Some(min);
+                filter.status_max = Some(max);
+            }
+            _ => {}
+        }
+    }
+    filter
+}
+
+fn url_path(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = rest.find('/').map_or("/", |start| &rest[start..]);
+    path.split(['?', '#']).next().unwrap_or("/")
+}
+
+fn compare_text(op: Op, actual: &str, expected: &str) -> bool {
+    let actual = actual.to_lowercase();
+    match op {
+        Op::Eq => actual == expected,
+        Op::Ne => actual != expected,
+        Op::Contains => actual.contains(expected),
+        Op::NotContains => !actual.contains(expected),
+        _ => false,
+    }
+}
+
+// For fields with several values (tags, headers), whether any one matches;
+// negated operators need all of them not to
+fn compare_any<'a>(op: Op, mut actual: impl Iterator<Item = &'a str>, expected: &str) -> bool {
+    match op {
+        Op::Ne | Op::NotContains => actual.all(|value| compare_text(op, value, expected)),
+        _ => actual.any(|value| compare_text(op, value, expected)),
+    }
+}
+
+fn compare_number(op: Op, actual: Option<u64>, expected: &Value) -> bool {
+    let Some(actual) = actual else {
+        return op == Op::Ne;
+    };
+    match (op, expected) {
+        (Op::Eq, Value::StatusClass(class)) => actual / 100 == *class as u64,
+        (Op::Ne, Value::StatusClass(class)) => actual / 100 != *class as u64,
+        (Op::Eq, Value::Number(n)) => actual == *n,
+        (Op::Ne, Value::Number(n)) => actual != *n,
+        (Op::Gt, Value::Number(n)) => actual > *n,
+        (Op::Ge, Value::Number(n)) => actual >= *n,
+        (Op::Lt, Value::Number(n)) => actual < *n,
+        (Op::Le, Value::Number(n)) => actual <= *n,
+        _ => false,
+    }
+}
+
+impl Term {
+    fn matches(&self, request: &NetworkRequest) -> bool {
+        let response = request.response.as_ref();
+        let text = match &self.value {
+            Value::Text(text) => text.as_str(),
+            _ => "",
+        };
+        match &self.field {
+            Field::Method => compare_text(self.op, &request.method, text),
+            Field::Host => compare_text(self.op, url_host(&request.url), text),
+            Field::Url => compare_text(self.op, &request.url, text),
+            Field::Path => compare_text(self.op, url_path(&request.url), text),
+            Field::Tag => compare_any(self.op, request.tags.iter().map(String::as_str), text),
+            Field::State => {
+                let state = serde_json::to_value(request.state).unwrap_or_default();
+                compare_text(self.op, state.as_str().unwrap_or_default(), text)
+            }
+            Field::Status => {
+                compare_number(self.op, response.map(|r| r.status as u64), &self.value)
+            }
+            Field::Duration => compare_number(self.op, request.duration, &self.value),
+            Field::Size => compare_number(
+                self.op,
+                Some(
+                    response
+                        .and_then(|r| r.body.as_ref())
+                        .map_or(0, |b| b.len()) as u64,
+                ),
+                &self.value,
+            ),
+            Field::Body => {
+                let mut bodies = request
+                    .body
+                    .iter()
+                    .chain(response.and_then(|r| r.body.as_ref()));
+                let found = bodies.any(|body| body.contains(text));
+                found == (self.op == Op::Contains)
+            }
+            Field::Header(name) => {
+                let values = request
+                    .headers
+                    .get_all(name)
+                    .chain(response.into_iter().flat_map(|r| r.headers.get_all(name)));
+                compare_any(self.op, values, text)
+            }
+            Field::HasHeader => {
+                let present = request.headers.contains(text)
+                    || response.is_some_and(|r| r.headers.contains(text));
+                present == matches!(self.op, Op::Eq | Op::Contains)
+            }
+        }
+    }
+}
+
+// Requests matching `query`, in store order
+#[tauri::command]
+pub async fn query_requests(
+    index: tauri::State<'_, IndexState>,
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<QueryResult, String> {
+    let terms = parse(&query)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let live: Vec<RequestSummary> = {
+        let requests = store.lock().map_err(|e| e.to_string())?;
+        let mut positions = index::matching(&index, &requests, &index_filter(&terms));
+        positions.retain(|&i| terms.iter().all(|term| term.matches(&requests[i])));
+        positions
+            .into_iter()
+            .map(|i| RequestSummary::from_request(&requests[i]))
+            .collect()
+    };
+    // Spilled requests come first and are scanned, as they aren't indexed
+    let ids: HashSet<&str> = live.iter().map(|r| r.id.as_str()).collect();
+    let spilled = overflow::with_live(&limits::overflow(&store_limits), &[]);
+    let mut found: Vec<RequestSummary> = spilled
+        .iter()
+        .filter(|r| !ids.contains(r.id.as_str()))
+        .filter(|r| terms.iter().all(|term| term.matches(r)))
+        .map(RequestSummary::from_request)
+        .collect();
+    found.extend(live);
+    Ok(QueryResult {
+        total: found.len(),
+        requests: found.into_iter().take(limit).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::request;
+
+    fn error(query: &str) -> String {
+        parse(query).unwrap_err()
+    }
+
+    fn matches(query: &str, request: &NetworkRequest) -> bool {
+        parse(query)
+            .unwrap()
+            .iter()
+            .all(|term| term.matches(request))
+    }
+
+    #[test]
+    fn splits_on_spaces_outside_quotes() {
+        assert_eq!(
+            tokens(r#"method=POST  body:"user id" "#).unwrap(),
+            ["method=POST", "body:user id"]
+        );
+        assert_eq!(tokens(r#"tag="""#).unwrap(), ["tag="]);
+        assert_eq!(tokens("   ").unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn rejects_malformed_terms() {
+        assert_eq!(error(r#"body:"user"#), "Unclosed quote in query");
+        assert!(error("colour=red").ends_with("unknown field"));
+        assert!(error("status=").ends_with("missing value"));
+        assert!(error("status>abc").ends_with("expected a number"));
+        assert!(error("status~5").contains("compare numbers"));
+        assert!(error("method>GET").ends_with("only numbers can be ordered"));
+        assert!(error("body=token").contains("search bodies"));
+        assert!(error("header.=x").ends_with("unknown field"));
+        // Only whole classes from 1xx to 9xx, and only compared for equality
+        assert!(error("status=0xx").ends_with("expected a number"));
+        assert!(error("status>=5xx").ends_with("expected a number"));
+    }
+
+    #[test]
+    fn reads_unknown_words_as_url_searches() {
+        let local = request("a", "http://localhost:8080/health", 200);
+        let remote = request("b", "https://api.example.com/health", 200);
+        assert!(matches("localhost:8080", &local));
+        assert!(!matches("localhost:8080", &remote));
+        assert!(matches("HEALTH", &remote));
+    }
+
+    #[test]
+    fn compares_numbers_and_status_classes() {
+        let failed = request("a", "https://api.example.com/users", 503);
+        assert!(matches("status=5xx", &failed));
+        assert!(!matches("status!=5xx", &failed));
+        assert!(matches("status>=500 status<600", &failed));
+        assert!(!matches("status>503", &failed));
+        assert!(matches("duration<=10 size=0", &failed));
+    }
+
+    #[test]
+    fn negated_terms_need_every_value_to_differ() {
+        let mut tagged = request("a", "https://api.example.com/users", 200);
+        tagged.tags = vec!["slow".to_string(), "auth".to_string()];
+        assert!(matches("tag=auth", &tagged));
+        assert!(!matches("tag!=auth", &tagged));
+        assert!(matches("tag!=billing", &tagged));
+    }
+
+    #[test]
+    fn narrows_the_index_by_method_host_and_status_range() {
+        let filter = index_filter(
+            &parse("method=post host=api.example.com status>=500 status<503").unwrap(),
+        );
+        assert_eq!(filter.method.as_deref(), Some("post"));
+        assert_eq!(filter.host.as_deref(), Some("api.example.com"));
+        assert_eq!(
+            (filter.status_min, filter.status_max),
+            (Some(500), Some(502))
+        );
+
+        let filter = index_filter(&parse("status=4xx method!=GET").unwrap());
+        assert_eq!(
+            (filter.status_min, filter.status_max),
+            (Some(400), Some(499))
+        );
+        assert_eq!(filter.method, None);
+    }
+}
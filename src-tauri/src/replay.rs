@@ -0,0 +1,362 @@
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::blobs::{self, BodyPart};
+use crate::events::{self, AppEvent};
+use crate::forms::{self, FormField};
+use crate::har::{self, HarEntry};
+use crate::headers::Headers;
+use crate::limits::{self, StoreLimitState};
+use crate::markers::now_millis;
+use crate::secrets;
+use crate::upstream;
+use crate::RequestStore;
+
+// Longest gap honoured when replaying with the recorded timing
+const MAX_RECORDED_GAP_MS: u64 = 10_000;
+const REQUEST_TIMEOUT_SECS: u64 = 30;
+// Completed replay reports kept for `get_replay_reports`
+const MAX_REPORTS: usize = 20;
+
+// Set by the client per connection; forwarding them would break the request
+const SKIPPED_HEADERS: &[&str] = &[
+    "host",
+    "content-length",
+    "connection",
+    "keep-alive",
+    "transfer-encoding",
+    "upgrade",
+    "accept-encoding",
+];
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ReplayOutcome {
+    pub index: usize,
+    pub method: String,
+    pub original_url: String,
+    pub url: String,
+    pub original_status: u16,
+    pub status: Option<u16>,
+    #[ts(type = "number")]
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+impl ReplayOutcome {
+    fn status_changed(&self) -> bool {
+        self.status
+            .is_some_and(|status| status != self.original_status)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ReplayReport {
+    pub id: String,
+    pub har_path: String,
+    pub base_url: String,
+    #[ts(type = "number")]
+    pub started_at: u64,
+    #[ts(type = "number")]
+    pub finished_at: u64,
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+    // Completed with a different status than the recording
+    pub status_changed: usize,
+    pub outcomes: Vec<ReplayOutcome>,
+}
+
+pub type ReplayStore = Arc<Mutex<Vec<ReplayReport>>>;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ResendOutcome {
+    // Of the stored request that was sent again
+    pub id: String,
+    pub method: String,
+    pub url: String,
+    pub original_status: Option<u16>,
+    pub status: Option<u16>,
+    #[ts(type = "number")]
+    pub duration_ms: u64,
+    pub body: Option<String>,
+    pub error: Option<String>,
+}
+
+// Keeps path and query from the recording and swaps in the target's scheme and
+// host; a path on `base_url` is prepended (`https://staging/api` + `/users`)
+pub fn rebase_url(url: &str, base_url: &str) -> String {
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let path = rest.find('/').map(|start| &rest[start..]).unwrap_or("/");
+    format!("{}{}", base_url.trim_end_matches('/'), path)
+}
+
+// Delay before each entry: fixed spacing for a rate, otherwise the recorded gaps
+fn schedule(entries: &[HarEntry], rate: Option<f64>) -> Vec<u64> {
+    if let Some(rate) = rate.filter(|rate| *rate > 0.0) {
+        let spacing = (1_000.0 / rate).round() as u64;
+        return (0..entries.len())
+            .map(|i| if i == 0 { 0 } else { spacing })
+            .collect();
+    }
+
+    let starts: Vec<Option<u64>> = entries
+        .iter()
+        .map(|entry| har::parse_timestamp(&entry.started_date_time))
+        .collect();
+    starts
+        .iter()
+        .enumerate()
+        .map(
+            |(i, start)| match (i.checked_sub(1).and_then(|p| starts[p]), start) {
+                (Some(previous), Some(start)) => {
+                    start.saturating_sub(previous).min(MAX_RECORDED_GAP_MS)
+                }
+                _ => 0,
+            },
+        )
+        .collect()
+}
+
+fn forwarded(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    // HTTP/2 pseudo headers (`:authority`, ...) show up in browser HARs
+    !name.starts_with(':') && !SKIPPED_HEADERS.contains(&name.as_str())
+}
+
+async fn send(
+    client: &reqwest::Client,
+    entry: &HarEntry,
+    url: &str,
+    authorization: Option<&str>,
+) -> Result<u16, String> {
+    let method =
+        reqwest::Method::from_bytes(entry.request.method.as_bytes()).map_err(|e| e.to_string())?;
+    let mut builder = client.request(method, url);
+    for header in &entry.request.headers {
+        if !forwarded(&header.name) {
+            continue;
+        }
+        if header.name.eq_ignore_ascii_case("authorization") && authorization.is_some() {
+            continue;
+        }
+        builder = builder.header(&header.name, &header.value);
+    }
+    if let Some(authorization) = authorization {
+        builder = builder.header("authorization", authorization);
+    }
+    if let Some(text) = entry
+        .request
+        .post_data
+        .as_ref()
+        .and_then(|p| p.text.clone())
+    {
+        builder = builder.body(text);
+    }
+
+    let response = builder.send().await.map_err(|e| e.to_string())?;
+    let status = response.status().as_u16();
+    // Drain the body so timing covers the full response
+    response.bytes().await.map_err(|e| e.to_string())?;
+    Ok(status)
+}
+
+// A client going through the upstream proxy of `project`, or of the active
+// project, and the relay it needs kept alive
+pub(crate) async fn client(
+    app_handle: &tauri::AppHandle,
+    project: Option<&str>,
+) -> Result<(reqwest::Client, Option<upstream::Relay>), String> {
+    let proxy = upstream::proxy_for(app_handle, project)?;
+    let builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .redirect(reqwest::redirect::Policy::none());
+    let (builder, relay) = upstream::apply(builder, proxy.as_ref()).await?;
+    Ok((builder.build().map_err(|e| e.to_string())?, relay))
+}
+
+// Replays every entry of a HAR file against `base_url`, emitting `replay-progress`
+// per request. `rate` is requests per second; without it the recorded timing is
+// reproduced, with long idle gaps capped. `auth_secret` names a keychain secret
+// that replaces the recorded Authorization header, since recorded tokens have
+// usually expired. Requests go through the upstream proxy of `project`, or of
+// the active project.
+#[tauri::command]
+pub async fn replay_har(
+    replays: tauri::State<'_, ReplayStore>,
+    app_handle: tauri::AppHandle,
+    path: String,
+    base_url: String,
+    rate: Option<f64>,
+    auth_secret: Option<String>,
+    project: Option<String>,
+) -> Result<ReplayReport, String> {
+    let har = har::read_har(&path)?;
+    let authorization = auth_secret
+        .as_deref()
+        .map(secrets::authorization)
+        .transpose()?;
+    let entries = har.log.entries;
+    // Held until the replay is done; NTLM/Negotiate traffic runs through it
+    let (client, _relay) = client(&app_handle, project.as_deref()).await?;
+
+    let started_at = now_millis();
+    let delays = schedule(&entries, rate);
+    let mut outcomes = Vec::with_capacity(entries.len());
+
+    for (index, (entry, delay)) in entries.iter().zip(delays).enumerate() {
+        if delay > 0 {
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+        }
+
+        let url = rebase_url(&entry.request.url, &base_url);
+        let sent = Instant::now();
+        let result = send(&client, entry, &url, authorization.as_deref()).await;
+        let outcome = ReplayOutcome {
+            index,
+            method: entry.request.method.clone(),
+            original_url: entry.request.url.clone(),
+            url,
+            original_status: entry.response.status,
+            duration_ms: sent.elapsed().as_millis() as u64,
+            status: result.as_ref().ok().copied(),
+            error: result.err(),
+        };
+
+        if let Err(e) = events::emit(&app_handle, AppEvent::ReplayProgress(&outcome)) {
+            println!("Failed to emit replay-progress event: {}", e);
+        }
+        outcomes.push(outcome);
+    }
+
+    let report = ReplayReport {
+        id: Uuid::new_v4().to_string(),
+        har_path: path,
+        base_url,
+        started_at,
+        finished_at: now_millis(),
+        total: outcomes.len(),
+        completed: outcomes.iter().filter(|o| o.status.is_some()).count(),
+        failed: outcomes.iter().filter(|o| o.error.is_some()).count(),
+        status_changed: outcomes.iter().filter(|o| o.status_changed()).count(),
+        outcomes,
+    };
+
+    let mut reports = replays.lock().map_err(|e| e.to_string())?;
+    reports.push(report.clone());
+    let overflow = reports.len().saturating_sub(MAX_REPORTS);
+    reports.drain(..overflow);
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn get_replay_reports(
+    replays: tauri::State<'_, ReplayStore>,
+) -> Result<Vec<ReplayReport>, String> {
+    let reports = replays.lock().map_err(|e| e.to_string())?;
+    Ok(reports.clone())
+}
+
+// A stored request as it goes out again
+pub(crate) struct Outgoing {
+    pub method: String,
+    pub url: String,
+    pub headers: Headers,
+    pub body: Option<String>,
+    pub original_status: Option<u16>,
+}
+
+pub(crate) struct Resent {
+    pub status: u16,
+    pub headers: Headers,
+    pub body: String,
+}
+
+// The request `id` with its full body, spilled or not
+pub(crate) fn outgoing(
+    store: &RequestStore,
+    store_limits: &StoreLimitState,
+    id: &str,
+) -> Result<Outgoing, String> {
+    let request = limits::find_request(store_limits, store, id)?;
+    Ok(Outgoing {
+        body: blobs::body_of(&request, Some(BodyPart::Request))?,
+        method: request.method,
+        url: request.url,
+        headers: request.headers,
+        original_status: request.response.map(|r| r.status),
+    })
+}
+
+pub(crate) async fn send_outgoing(
+    client: &reqwest::Client,
+    outgoing: &Outgoing,
+    url: &str,
+) -> Result<Resent, String> {
+    let method =
+        reqwest::Method::from_bytes(outgoing.method.as_bytes()).map_err(|e| e.to_string())?;
+    let mut builder = client.request(method, url);
+    for (name, value) in outgoing.headers.iter().filter(|(name, _)| forwarded(name)) {
+        builder = builder.header(name, value);
+    }
+    if let Some(body) = &outgoing.body {
+        builder = builder.body(body.clone());
+    }
+    let response = builder.send().await.map_err(|e| e.to_string())?;
+    let status = response.status().as_u16();
+    let mut headers = Headers::new();
+    for (name, value) in response.headers() {
+        headers.push(name.as_str(), String::from_utf8_lossy(value.as_bytes()));
+    }
+    let body = response.text().await.map_err(|e| e.to_string())?;
+    Ok(Resent {
+        status,
+        headers,
+        body,
+    })
+}
+
+// Sends a stored request again as it was captured. `form` replaces the fields
+// of a form-encoded body (see `get_form_fields`), so one value can be changed
+// without hand-encoding the rest. The request goes through the upstream proxy
+// of `project`, or of the active project.
+#[tauri::command]
+pub async fn resend_request(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    app_handle: tauri::AppHandle,
+    id: String,
+    form: Option<Vec<FormField>>,
+    project: Option<String>,
+) -> Result<ResendOutcome, String> {
+    let mut outgoing = outgoing(&store, &store_limits, &id)?;
+    if let Some(fields) = form {
+        if !forms::is_form(&outgoing.headers) {
+            return Err(format!("Request body is not form-encoded: {}", id));
+        }
+        outgoing.body = Some(forms::encode(&fields));
+    }
+
+    let (client, _relay) = client(&app_handle, project.as_deref()).await?;
+    let sent = Instant::now();
+    let result = send_outgoing(&client, &outgoing, &outgoing.url).await;
+    let (status, body, error) = match result {
+        Ok(resent) => (Some(resent.status), Some(resent.body), None),
+        Err(e) => (None, None, Some(e)),
+    };
+    Ok(ResendOutcome {
+        id,
+        method: outgoing.method,
+        url: outgoing.url,
+        original_status: outgoing.original_status,
+        status,
+        duration_ms: sent.elapsed().as_millis() as u64,
+        body,
+        error,
+    })
+}
@@ -0,0 +1,168 @@
+// Prunes the store in the background by age and by total storage size, so a
+// capture left running doesn't grow without bound. Unlike the store limits,
+// which apply as requests arrive, this runs on a timer.
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use ts_rs::TS;
+
+use crate::bulk::BulkResult;
+use crate::events::{self, AppEvent, Events};
+use crate::markers::now_millis;
+use crate::storage::{self, StorageState};
+use crate::{NetworkRequest, RequestStore};
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(30);
+
+// Unset means keep forever
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct RetentionPolicy {
+    // Requests captured longer ago than this are pruned
+    #[ts(type = "number | null")]
+    pub max_age_minutes: Option<u64>,
+    // Bodies held in memory plus what spilled ones take on disk, for the
+    // whole store; the oldest requests go first
+    #[ts(type = "number | null")]
+    pub max_storage_bytes: Option<u64>,
+}
+
+pub type RetentionState = Arc<Mutex<RetentionPolicy>>;
+
+fn storage_bytes(request: &NetworkRequest) -> u64 {
+    let mut parts = vec![(&request.body, &request.body_file)];
+    if let Some(response) = &request.response {
+        parts.push((&response.body, &response.body_file));
+    }
+    parts
+        .into_iter()
+        .map(|(body, spilled)| {
+            let inline = body.as_ref().map_or(0, |body| body.len() as u64);
+            let on_disk = spilled
+                .as_ref()
+                .map_or(0, |s| s.compressed_size.unwrap_or(s.size));
+            inline + on_disk
+        })
+        .sum()
+}
+
+// Removes what the policy no longer allows and returns their ids. In-flight
+// requests are kept so their completion isn't lost, and pinned ones are kept.
+fn prune(policy: &RetentionPolicy, requests: &mut Vec<NetworkRequest>, now: u64) -> Vec<String> {
+    let cutoff = policy
+        .max_age_minutes
+        .map(|minutes| now.saturating_sub(minutes.saturating_mul(60_000)));
+    let mut pruned = HashSet::new();
+    if let Some(cutoff) = cutoff {
+        for (i, request) in requests.iter().enumerate() {
+            if request.timestamp < cutoff && request.state.is_final() && !request.pinned {
+                pruned.insert(i);
+            }
+        }
+    }
+    if let Some(budget) = policy.max_storage_bytes {
+        let mut total: u64 = requests
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !pruned.contains(i))
+            .map(|(_, r)| storage_bytes(r))
+            .sum();
+        let mut oldest: Vec<usize> = (0..requests.len())
+            .filter(|i| {
+                !pruned.contains(i) && requests[*i].state.is_final() && !requests[*i].pinned
+            })
+            .collect();
+        oldest.sort_by_key(|&i| requests[i].timestamp);
+        for i in oldest {
+            if total <= budget {
+                break;
+            }
+            total = total.saturating_sub(storage_bytes(&requests[i]));
+            pruned.insert(i);
+        }
+    }
+    if pruned.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ids = Vec::with_capacity(pruned.len());
+    let mut index = 0;
+    requests.retain(|request| {
+        let keep = !pruned.contains(&index);
+        index += 1;
+        if !keep {
+            ids.push(request.id.clone());
+        }
+        keep
+    });
+    ids
+}
+
+fn apply(
+    retention: &RetentionState,
+    store: &RequestStore,
+    storage: &StorageState,
+) -> Result<BulkResult, String> {
+    let policy = *retention.lock().map_err(|e| e.to_string())?;
+    let (ids, remaining) = {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        let ids = prune(&policy, &mut requests, now_millis());
+        let remaining = (!ids.is_empty()).then(|| requests.clone());
+        (ids, remaining)
+    };
+    if let Some(remaining) = remaining {
+        storage::replace_all(storage, &remaining);
+    }
+    Ok(BulkResult { ids })
+}
+
+// Runs for the life of the app
+pub(crate) async fn pruner(
+    retention: RetentionState,
+    store: RequestStore,
+    storage: StorageState,
+    sink: Events,
+) {
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+    loop {
+        interval.tick().await;
+        match apply(&retention, &store, &storage) {
+            Ok(result) if !result.ids.is_empty() => {
+                if let Err(e) = events::emit(&*sink, AppEvent::RequestsPruned(&result)) {
+                    println!("Failed to emit requests-pruned event: {}", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => println!("Failed to apply retention policy: {}", e),
+        }
+    }
+}
+
+// Applies straight away, pruning whatever the new policy doesn't allow
+#[tauri::command]
+pub async fn set_retention_policy(
+    retention: tauri::State<'_, RetentionState>,
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    app_handle: tauri::AppHandle,
+    policy: RetentionPolicy,
+) -> Result<BulkResult, String> {
+    if policy.max_age_minutes == Some(0) {
+        return Err("max_age_minutes must be at least 1".to_string());
+    }
+    *retention.lock().map_err(|e| e.to_string())? = policy;
+
+    let result = apply(&retention, &store, &storage)?;
+    if !result.ids.is_empty() {
+        events::emit(&app_handle, AppEvent::RequestsPruned(&result))?;
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn get_retention_policy(
+    retention: tauri::State<'_, RetentionState>,
+) -> Result<RetentionPolicy, String> {
+    Ok(*retention.lock().map_err(|e| e.to_string())?)
+}
@@ -0,0 +1,427 @@
+// Shareable bundles of capture rules, e.g. an ignore list for a common
+// analytics SDK or a team's redaction rules, imported from a file or URL:
+//
+//   {"name": "analytics", "version": "1.2.0",
+//    "ignore": [{"url_contains": "google-analytics.com"}],
+//    "redact": ["x-device-id", "email"]}
+//
+// Packs apply to requests as they are captured, in order, while enabled.
+// Installed packs are kept in `rule-packs.json` in the data directory, and
+// `update_rule_packs` fetches each one's source again for a newer version.
+// Built-in packs ship with the app (`rulepacks/` next to `src/`), are installed
+// on first launch and updated with the app; they can be disabled, not removed.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+use crate::bodies::Body;
+use crate::data_dir;
+use crate::filter::RequestFilter;
+use crate::markers::now_millis;
+use crate::{Headers, NetworkRequest};
+
+const PACKS_FILE: &str = "rule-packs.json";
+const REDACTED: &str = "REDACTED";
+const BUILTIN_PREFIX: &str = "builtin:";
+
+// Name and contents of each built-in pack
+const BUILTIN_PACKS: &[(&str, &str)] =
+    &[("sdk-noise", include_str!("../rulepacks/sdk-noise.json"))];
+
+// Adds `tags` to matching requests, e.g. to mark analytics beacons as noise
+// without dropping them
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct TagRule {
+    pub filter: RequestFilter,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct RulePack {
+    pub name: String,
+    // Dotted numbers, compared part by part: `1.10` is newer than `1.9`
+    pub version: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    // Matching requests aren't stored at all
+    #[serde(default)]
+    pub ignore: Vec<RequestFilter>,
+    // Header, query parameter and JSON body field names whose values are
+    // replaced with `REDACTED`, matched case-insensitively
+    #[serde(default)]
+    pub redact: Vec<String>,
+    #[serde(default)]
+    pub tag: Vec<TagRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct InstalledPack {
+    pub pack: RulePack,
+    pub enabled: bool,
+    // The file path or URL it was imported from, and is updated from
+    pub source: String,
+    #[ts(type = "number")]
+    pub installed_at: u64,
+}
+
+pub type RulePackState = Arc<Mutex<Vec<InstalledPack>>>;
+
+fn packs_path(dir: &Path) -> PathBuf {
+    dir.join(PACKS_FILE)
+}
+
+// Installed packs from earlier runs
+pub(crate) fn load(state: &RulePackState, dir: &Path) {
+    let path = packs_path(dir);
+    let packs: Result<Vec<InstalledPack>, String> = match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid rule packs {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let mut packs = match packs {
+        Ok(packs) => packs,
+        Err(e) => {
+            println!("Rule packs disabled: {}", e);
+            return;
+        }
+    };
+
+    for (name, json) in BUILTIN_PACKS {
+        let pack: RulePack = match serde_json::from_str(json) {
+            Ok(pack) => pack,
+            Err(e) => {
+                println!("Invalid built-in rule pack {}: {}", name, e);
+                continue;
+            }
+        };
+        match packs.iter_mut().find(|p| p.pack.name == pack.name) {
+            Some(installed) if is_newer(&pack.version, &installed.pack.version) => {
+                installed.pack = pack;
+                installed.installed_at = now_millis();
+            }
+            Some(_) => {}
+            None => packs.push(InstalledPack {
+                pack,
+                enabled: true,
+                source: format!("{}{}", BUILTIN_PREFIX, name),
+                installed_at: now_millis(),
+            }),
+        }
+    }
+    *state.lock().unwrap() = packs;
+}
+
+fn save(app_handle: &tauri::AppHandle, packs: &[InstalledPack]) -> Result<(), String> {
+    let dir = data_dir(app_handle);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = packs_path(&dir);
+    let json = serde_json::to_string_pretty(packs).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+fn is_newer(version: &str, than: &str) -> bool {
+    let (mut a, mut b) = (version_parts(version), version_parts(than));
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    a > b
+}
+
+async fn fetch(source: &str) -> Result<RulePack, String> {
+    let json = if let Some(name) = source.strip_prefix(BUILTIN_PREFIX) {
+        BUILTIN_PACKS
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+            .map(|(_, json)| json.to_string())
+            .ok_or_else(|| format!("Rule pack not found: {}", source))?
+    } else if source.starts_with("http://") || source.starts_with("https://") {
+        let response = reqwest::get(source)
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Failed to fetch {}: {}", source, e))?;
+        let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+        String::from_utf8_lossy(&bytes).into_owned()
+    } else {
+        std::fs::read_to_string(source).map_err(|e| format!("Failed to read {}: {}", source, e))?
+    };
+    let pack: RulePack =
+        serde_json::from_str(&json).map_err(|e| format!("Invalid rule pack {}: {}", source, e))?;
+    if pack.name.trim().is_empty() {
+        return Err(format!("Rule pack from {} has no name", source));
+    }
+    if pack.version.trim().is_empty() {
+        return Err(format!("Rule pack {} has no version", pack.name));
+    }
+    Ok(pack)
+}
+
+// Whether an enabled pack says not to store this request
+pub(crate) fn ignored(state: &RulePackState, request: &NetworkRequest) -> bool {
+    let packs = state.lock().unwrap();
+    packs
+        .iter()
+        .filter(|installed| installed.enabled)
+        .flat_map(|installed| &installed.pack.ignore)
+        .any(|filter| filter.matches(request))
+}
+
+fn redact_headers(headers: &mut Headers, names: &[String]) {
+    for (name, value) in headers.iter_mut() {
+        if names.iter().any(|n| n.eq_ignore_ascii_case(name.trim())) {
+            *value = REDACTED.to_string();
+        }
+    }
+}
+
+fn redact_query(query: &str, names: &[String]) -> String {
+    query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if names.iter().any(|n| n.eq_ignore_ascii_case(name)) => {
+                format!("{}={}", name, REDACTED)
+            }
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn redact_url(url: &mut String, names: &[String]) {
+    let (before_fragment, fragment) = match url.split_once('#') {
+        Some((before, fragment)) => (before, Some(fragment)),
+        None => (url.as_str(), None),
+    };
+    let Some((base, query)) = before_fragment.split_once('?') else {
+        return;
+    };
+    let mut redacted = format!("{}?{}", base, redact_query(query, names));
+    if let Some(fragment) = fragment {
+        redacted.push('#');
+        redacted.push_str(fragment);
+    }
+    *url = redacted;
+}
+
+fn redact_json(value: &mut Value, names: &[String]) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                if names.iter().any(|n| n.eq_ignore_ascii_case(key)) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value, names);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| redact_json(item, names)),
+        _ => {}
+    }
+}
+
+fn redact_body(body: &mut Option<Body>, names: &[String]) {
+    let Some(text) = body.as_deref() else {
+        return;
+    };
+    if let Ok(mut value) = serde_json::from_str::<Value>(text) {
+        let before = value.clone();
+        redact_json(&mut value, names);
+        // Untouched bodies keep their original formatting
+        if value != before {
+            *body = Some(value.to_string().into());
+        }
+    } else if text.contains('=') && !text.contains(['\n', '<', '{']) {
+        let redacted = redact_query(text, names);
+        if redacted != text {
+            *body = Some(redacted.into());
+        }
+    }
+}
+
+// Adds the tags of every matching tag rule in an enabled pack
+pub(crate) fn tag(state: &RulePackState, request: &mut NetworkRequest) {
+    let packs = state.lock().unwrap();
+    let rules = packs
+        .iter()
+        .filter(|installed| installed.enabled)
+        .flat_map(|installed| &installed.pack.tag);
+    for rule in rules {
+        if !rule.filter.matches(request) {
+            continue;
+        }
+        for tag in &rule.tags {
+            if !request.tags.contains(tag) {
+                request.tags.push(tag.clone());
+            }
+        }
+    }
+}
+
+// Applies the redaction rules of every enabled pack
+pub(crate) fn redact(state: &RulePackState, request: &mut NetworkRequest) {
+    let names: Vec<String> = {
+        let packs = state.lock().unwrap();
+        packs
+            .iter()
+            .filter(|installed| installed.enabled)
+            .flat_map(|installed| installed.pack.redact.iter().cloned())
+            .collect()
+    };
+    redact_names(request, &names);
+}
+
+// Masks the values of these header, query parameter and JSON body keys
+pub(crate) fn redact_names(request: &mut NetworkRequest, names: &[String]) {
+    if names.is_empty() {
+        return;
+    }
+    redact_url(&mut request.url, names);
+    redact_headers(&mut request.headers, names);
+    redact_body(&mut request.body, names);
+    if let Some(response) = request.response.as_mut() {
+        redact_headers(&mut response.headers, names);
+        redact_body(&mut response.body, names);
+    }
+}
+
+// Installs a pack from a file path or URL, enabled. A pack of the same name is
+// replaced by a newer version only.
+#[tauri::command]
+pub async fn import_rule_pack(
+    rule_packs: tauri::State<'_, RulePackState>,
+    app_handle: tauri::AppHandle,
+    source: String,
+) -> Result<InstalledPack, String> {
+    let pack = fetch(&source).await?;
+    let mut packs = rule_packs.lock().map_err(|e| e.to_string())?;
+    let existing = packs.iter().position(|p| p.pack.name == pack.name);
+    if let Some(existing) = existing {
+        let installed = &packs[existing].pack.version;
+        if !is_newer(&pack.version, installed) {
+            return Err(format!(
+                "Rule pack {} {} is already installed",
+                pack.name, installed
+            ));
+        }
+    }
+    let installed = InstalledPack {
+        pack,
+        enabled: existing.is_none_or(|i| packs[i].enabled),
+        source,
+        installed_at: now_millis(),
+    };
+    match existing {
+        Some(i) => packs[i] = installed.clone(),
+        None => packs.push(installed.clone()),
+    }
+    save(&app_handle, &packs)?;
+    Ok(installed)
+}
+
+// Fetches every pack's source again and installs the newer versions, keeping
+// whether each is enabled. Returns the packs that were updated; sources that
+// can't be reached are skipped.
+#[tauri::command]
+pub async fn update_rule_packs(
+    rule_packs: tauri::State<'_, RulePackState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<InstalledPack>, String> {
+    let sources: Vec<(String, String)> = {
+        let packs = rule_packs.lock().map_err(|e| e.to_string())?;
+        packs
+            .iter()
+            .map(|p| (p.pack.name.clone(), p.source.clone()))
+            .collect()
+    };
+    let mut fetched = Vec::new();
+    for (name, source) in sources {
+        match fetch(&source).await {
+            Ok(pack) if pack.name == name => fetched.push(pack),
+            Ok(pack) => println!(
+                "{} now serves rule pack {}, not {}",
+                source, pack.name, name
+            ),
+            Err(e) => println!("Failed to update rule pack {}: {}", name, e),
+        }
+    }
+
+    let mut packs = rule_packs.lock().map_err(|e| e.to_string())?;
+    let mut updated = Vec::new();
+    for pack in fetched {
+        let Some(installed) = packs.iter_mut().find(|p| p.pack.name == pack.name) else {
+            continue;
+        };
+        if is_newer(&pack.version, &installed.pack.version) {
+            installed.pack = pack;
+            installed.installed_at = now_millis();
+            updated.push(installed.clone());
+        }
+    }
+    if !updated.is_empty() {
+        save(&app_handle, &packs)?;
+    }
+    Ok(updated)
+}
+
+#[tauri::command]
+pub async fn set_rule_pack_enabled(
+    rule_packs: tauri::State<'_, RulePackState>,
+    app_handle: tauri::AppHandle,
+    name: String,
+    enabled: bool,
+) -> Result<InstalledPack, String> {
+    let mut packs = rule_packs.lock().map_err(|e| e.to_string())?;
+    let installed = packs
+        .iter_mut()
+        .find(|p| p.pack.name == name)
+        .ok_or_else(|| format!("Rule pack not found: {}", name))?;
+    installed.enabled = enabled;
+    let installed = installed.clone();
+    save(&app_handle, &packs)?;
+    Ok(installed)
+}
+
+#[tauri::command]
+pub async fn remove_rule_pack(
+    rule_packs: tauri::State<'_, RulePackState>,
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> Result<(), String> {
+    let mut packs = rule_packs.lock().map_err(|e| e.to_string())?;
+    let builtin = packs
+        .iter()
+        .any(|p| p.pack.name == name && p.source.starts_with(BUILTIN_PREFIX));
+    if builtin {
+        return Err(format!(
+            "Rule pack {} is built in; disable it instead",
+            name
+        ));
+    }
+    let before = packs.len();
+    packs.retain(|p| p.pack.name != name);
+    if packs.len() == before {
+        return Err(format!("Rule pack not found: {}", name));
+    }
+    save(&app_handle, &packs)
+}
+
+#[tauri::command]
+pub async fn list_rule_packs(
+    rule_packs: tauri::State<'_, RulePackState>,
+) -> Result<Vec<InstalledPack>, String> {
+    Ok(rule_packs.lock().map_err(|e| e.to_string())?.clone())
+}
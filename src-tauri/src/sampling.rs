@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::filter::RequestFilter;
+use crate::NetworkRequest;
+
+// Keeps 1 in `keep_one_in` requests matching `filter`, e.g. a chatty telemetry
+// beacon: `{"filter": {"url_contains": "/collect"}, "keep_one_in": 20}`.
+// Counters are kept for everything seen so the endpoint stays measurable.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct SamplingRule {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub filter: RequestFilter,
+    pub keep_one_in: u32,
+    // Errors (4xx/5xx) bypass sampling so failures are never sampled away
+    #[serde(default = "default_keep_errors")]
+    pub keep_errors: bool,
+    #[serde(default)]
+    #[ts(type = "number")]
+    pub seen: u64,
+    #[serde(default)]
+    #[ts(type = "number")]
+    pub sampled_out: u64,
+    // Summed durations of sampled-out requests, for averaging what wasn't stored
+    #[serde(default)]
+    #[ts(type = "number")]
+    pub sampled_out_duration_ms: u64,
+}
+
+fn default_keep_errors() -> bool {
+    true
+}
+
+pub type SamplingState = Arc<Mutex<Vec<SamplingRule>>>;
+
+fn is_error(request: &NetworkRequest) -> bool {
+    request
+        .response
+        .as_ref()
+        .is_some_and(|response| response.status >= 400)
+}
+
+// Whether an incoming request should be stored. The first matching rule decides;
+// requests no rule matches are always kept.
+pub fn keep(sampling: &SamplingState, request: &NetworkRequest) -> bool {
+    let mut rules = sampling.lock().unwrap();
+    let Some(rule) = rules.iter_mut().find(|rule| rule.filter.matches(request)) else {
+        return true;
+    };
+
+    rule.seen += 1;
+    if rule.keep_errors && is_error(request) {
+        return true;
+    }
+    // seen is 1-based, so the first matching request is always kept
+    let keep = rule.keep_one_in <= 1 || (rule.seen - 1) % rule.keep_one_in as u64 == 0;
+    if !keep {
+        rule.sampled_out += 1;
+        rule.sampled_out_duration_ms += request.duration.unwrap_or(0);
+    }
+    keep
+}
+
+// Replaces the rules and resets their counters
+#[tauri::command]
+pub async fn set_sampling_rules(
+    sampling: tauri::State<'_, SamplingState>,
+    rules: Vec<SamplingRule>,
+) -> Result<Vec<SamplingRule>, String> {
+    let rules: Vec<SamplingRule> = rules
+        .into_iter()
+        .map(|mut rule| {
+            if rule.id.is_empty() {
+                rule.id = Uuid::new_v4().to_string();
+            }
+            rule.keep_one_in = rule.keep_one_in.max(1);
+            rule.seen = 0;
+            rule.sampled_out = 0;
+            rule.sampled_out_duration_ms = 0;
+            rule
+        })
+        .collect();
+    let mut current = sampling.lock().map_err(|e| e.to_string())?;
+    *current = rules.clone();
+    Ok(rules)
+}
+
+// Rules with their current counters
+#[tauri::command]
+pub async fn get_sampling_rules(
+    sampling: tauri::State<'_, SamplingState>,
+) -> Result<Vec<SamplingRule>, String> {
+    let rules = sampling.lock().map_err(|e| e.to_string())?;
+    Ok(rules.clone())
+}
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::process::Command;
+use ts_rs::TS;
+
+// Screenshots are taken with the platform's own capture tool so no native
+// capture library has to be linked into the app. `command` overrides the
+// built-in tool; `{path}` in it is replaced with the output file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ScreenshotConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    // 0-based display to capture when no custom command is given
+    #[serde(default)]
+    pub screen: Option<u32>,
+    // e.g. `import -window "Simulator" {path}` to capture a single window
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub directory: Option<String>,
+}
+
+fn output_dir(config: &ScreenshotConfig) -> PathBuf {
+    config
+        .directory
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            std::env::temp_dir()
+                .join("network-observer")
+                .join("screenshots")
+        })
+}
+
+#[cfg(target_os = "macos")]
+fn default_command(screen: Option<u32>, path: &str) -> Command {
+    let mut command = Command::new("screencapture");
+    command.arg("-x");
+    if let Some(screen) = screen {
+        command.arg("-D").arg((screen + 1).to_string());
+    }
+    command.arg(path);
+    command
+}
+
+#[cfg(target_os = "windows")]
+fn default_command(screen: Option<u32>, path: &str) -> Command {
+    let script = format!(
+        "Add-Type -AssemblyName System.Windows.Forms,System.Drawing; \
+         $s = [System.Windows.Forms.Screen]::AllScreens[{}].Bounds; \
+         $b = New-Object System.Drawing.Bitmap $s.Width, $s.Height; \
+         $g = [System.Drawing.Graphics]::FromImage($b); \
+         $g.CopyFromScreen($s.Location, [System.Drawing.Point]::Empty, $s.Size); \
+         $b.Save('{}')",
+        screen.unwrap_or(0),
+        path.replace('\'', "''")
+    );
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-Command", &script]);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn default_command(_screen: Option<u32>, path: &str) -> Command {
+    // ImageMagick's `import` works on any X11 session; Wayland users can set `command` to grim
+    let mut command = Command::new("import");
+    command.args(["-window", "root", path]);
+    command
+}
+
+fn custom_command(template: &str, path: &str) -> Command {
+    let line = template.replace("{path}", path);
+    #[cfg(target_os = "windows")]
+    {
+        let mut command = Command::new("cmd");
+        command.args(["/C", &line]);
+        command
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let mut command = Command::new("sh");
+        command.args(["-c", &line]);
+        command
+    }
+}
+
+// Captures a screenshot and returns the path of the written image
+pub async fn capture(config: &ScreenshotConfig, name: &str) -> Result<String, String> {
+    let dir = output_dir(config);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir
+        .join(format!("{}.png", name))
+        .to_string_lossy()
+        .to_string();
+
+    let mut command = match &config.command {
+        Some(template) => custom_command(template, &path),
+        None => default_command(config.screen, &path),
+    };
+    let output = command
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run screenshot tool: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Screenshot tool exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(path)
+}
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use ts_rs::TS;
+
+use crate::data_dir;
+use crate::markers::now_millis;
+
+// Keychain service every secret is stored under; the secret's name is the account
+const KEYCHAIN_SERVICE: &str = "network-observer";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum SecretKind {
+    // Sent as `Authorization: Bearer <token>`
+    Token,
+    ClientSecret,
+    // `user:password` for an upstream proxy
+    ProxyCredentials,
+    Other,
+}
+
+// What the observer knows about a secret without opening the keychain. Only
+// this goes to disk (`secrets.json`); values never leave the keychain.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct SecretInfo {
+    pub name: String,
+    pub kind: SecretKind,
+    #[ts(type = "number")]
+    pub created_at: u64,
+    #[ts(type = "number | null")]
+    pub rotated_at: Option<u64>,
+}
+
+fn index_path(app_handle: &tauri::AppHandle) -> PathBuf {
+    data_dir(app_handle).join("secrets.json")
+}
+
+fn read_index(app_handle: &tauri::AppHandle) -> Result<Vec<SecretInfo>, String> {
+    let path = index_path(app_handle);
+    match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid secrets index {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+fn write_index(app_handle: &tauri::AppHandle, index: &[SecretInfo]) -> Result<(), String> {
+    let path = index_path(app_handle);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(index).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn entry(name: &str) -> Result<keyring::Entry, String> {
+    if name.trim().is_empty() {
+        return Err("Secret name must not be empty".to_string());
+    }
+    keyring::Entry::new(KEYCHAIN_SERVICE, name).map_err(|e| format!("Keychain: {}", e))
+}
+
+// The stored value of secret `name`, for replay and proxy auth
+pub fn read_secret(name: &str) -> Result<String, String> {
+    entry(name)?.get_password().map_err(|e| match e {
+        keyring::Error::NoEntry => format!("No secret named {}", name),
+        e => format!("Keychain: {}", e),
+    })
+}
+
+// Authorization header for secret `name`: used as is when it already names a
+// scheme (`Basic dXNlcjpwYXNz`), otherwise sent as a bearer token
+pub fn authorization(name: &str) -> Result<String, String> {
+    let value = read_secret(name)?;
+    if value.trim().contains(' ') {
+        Ok(value)
+    } else {
+        Ok(format!("Bearer {}", value.trim()))
+    }
+}
+
+// Creates secret `name`, or replaces it along with its kind
+#[tauri::command]
+pub async fn set_secret(
+    app_handle: tauri::AppHandle,
+    name: String,
+    kind: SecretKind,
+    value: String,
+) -> Result<SecretInfo, String> {
+    entry(&name)?
+        .set_password(&value)
+        .map_err(|e| format!("Keychain: {}", e))?;
+
+    let mut index = read_index(&app_handle)?;
+    index.retain(|info| info.name != name);
+    let info = SecretInfo {
+        name,
+        kind,
+        created_at: now_millis(),
+        rotated_at: None,
+    };
+    index.push(info.clone());
+    write_index(&app_handle, &index)?;
+    Ok(info)
+}
+
+// Swaps the value of an existing secret, keeping its kind
+#[tauri::command]
+pub async fn rotate_secret(
+    app_handle: tauri::AppHandle,
+    name: String,
+    value: String,
+) -> Result<SecretInfo, String> {
+    let mut index = read_index(&app_handle)?;
+    let info = index
+        .iter_mut()
+        .find(|info| info.name == name)
+        .ok_or_else(|| format!("No secret named {}", name))?;
+    entry(&name)?
+        .set_password(&value)
+        .map_err(|e| format!("Keychain: {}", e))?;
+
+    info.rotated_at = Some(now_millis());
+    let info = info.clone();
+    write_index(&app_handle, &index)?;
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn delete_secret(app_handle: tauri::AppHandle, name: String) -> Result<(), String> {
+    match entry(&name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(format!("Keychain: {}", e)),
+    }
+    let mut index = read_index(&app_handle)?;
+    index.retain(|info| info.name != name);
+    write_index(&app_handle, &index)
+}
+
+#[tauri::command]
+pub async fn list_secrets(app_handle: tauri::AppHandle) -> Result<Vec<SecretInfo>, String> {
+    read_index(&app_handle)
+}
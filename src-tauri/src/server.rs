@@ -0,0 +1,916 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::{accept_hdr_async, tungstenite::Message};
+use uuid::Uuid;
+
+use crate::alerts::{self, AlertStore};
+use crate::baselines::{self, BaselineState};
+use crate::blobs::BlobStore;
+use crate::capture::CaptureState;
+use crate::clients::{self, Client, ClientRegistry, ClientStats};
+use crate::commands::{CommandBus, Received};
+use crate::config::{self, ConfigState};
+use crate::derived::{self, DerivedFields};
+use crate::events::{self, AppEvent, Events};
+use crate::experiments::{self, ExperimentRules};
+use crate::floodguard::{self, FloodGuardState};
+use crate::index::{self, IndexState};
+use crate::latest::{self, LatestState};
+use crate::launcher::Readiness;
+use crate::lifecycle::{self, RequestState};
+use crate::limits::{self, StoreLimitState};
+use crate::livestats::{self, LiveStatsState};
+use crate::markers::{self, Marker, MarkerStore};
+use crate::occurrences;
+use crate::perf::{self, PerfBreakdown, PerfStage, PerfState, Probe};
+use crate::provenance::ProvenanceKind;
+use crate::quarantine::{self, UnparsedStore};
+use crate::rulepacks::{self, RulePackState};
+use crate::sampling::{self, SamplingState};
+use crate::sessions::SessionState;
+use crate::stats::GroupStats;
+use crate::storage::{self, RequestStorage, StorageState};
+use crate::telemetry::{self, TelemetryState};
+use crate::watches::{self, WatchStore};
+use crate::{
+    agent, aliases, bulk, capture, classify, fieldindex, hints, protocol, provenance, servertiming,
+    text, NetworkRequest, OfflineQueue, RequestStore, WebSocketMessage,
+};
+
+// Derived fields computed by the observer rather than reported by the client
+pub(crate) fn enrich_request(
+    request: &mut NetworkRequest,
+    experiment_rules: &ExperimentRules,
+    derived_fields: &DerivedFields,
+) {
+    classify::classify_request(request);
+    fieldindex::index_request(request);
+    servertiming::annotate_request(request);
+    hints::annotate_request(request);
+    if let Ok(rules) = experiment_rules.lock() {
+        experiments::label_request(request, &rules);
+    }
+    if let Ok(fields) = derived_fields.lock() {
+        derived::derive_fields(request, &fields);
+    }
+}
+
+// Server-side deduplication to prevent infinite loops and duplicates.
+// Signature -> (last seen, id of the entry duplicates are merged into)
+pub(crate) type DeduplicationCache = Arc<Mutex<HashMap<String, (u64, String)>>>;
+
+pub(crate) fn create_request_signature(request: &NetworkRequest) -> String {
+    let body_hash = request
+        .body
+        .as_ref()
+        .map(|body| {
+            if body.len() > 100 {
+                format!("{}...{}", text::prefix(body, 50), text::suffix(body, 50))
+            } else {
+                body.to_string()
+            }
+        })
+        .unwrap_or_default();
+
+    let response_status = request
+        .response
+        .as_ref()
+        .map(|r| r.status.to_string())
+        .unwrap_or_else(|| "pending".to_string());
+
+    // Two apps making the same call aren't duplicates of each other
+    let client = request.client_id.as_deref().unwrap_or_default();
+    format!(
+        "{}:{}:{}:{}:{}",
+        client, request.method, request.url, body_hash, response_status
+    )
+}
+
+// Queued requests skip the time-window dedup: they were all sent long before
+// they arrive, and identical queued calls are usually genuine repeats. They are
+// only dropped if an entry with the same id has already been stored. Imported
+// capture files go the same way, with their path as `source`.
+pub(crate) fn import_requests(
+    store: &RequestStore,
+    experiment_rules: &ExperimentRules,
+    derived_fields: &DerivedFields,
+    blob_store: &BlobStore,
+    queued: Vec<NetworkRequest>,
+    source: Option<String>,
+) -> Vec<NetworkRequest> {
+    let mut imported = Vec::new();
+    let mut requests = store.lock().unwrap();
+
+    for mut request in queued {
+        if request.id.is_empty() {
+            request.id = Uuid::new_v4().to_string();
+        } else if requests.iter().any(|r| r.has_id(&request.id)) {
+            continue;
+        }
+        lifecycle::initialize(&mut request);
+        provenance::record(&mut request, ProvenanceKind::Imported, source.clone());
+        enrich_request(&mut request, experiment_rules, derived_fields);
+        blob_store.store(&mut request);
+
+        // Keep the timeline ordered by the original timestamps
+        let position = requests.partition_point(|r| r.timestamp <= request.timestamp);
+        requests.insert(position, request.clone());
+        imported.push(request);
+    }
+
+    imported
+}
+
+// Holds the store to its limits after an import and writes the imported
+// requests through; returns the ids evicted
+pub(crate) fn store_imported(
+    store: &RequestStore,
+    store_limits: &StoreLimitState,
+    storage: &StorageState,
+    index: &IndexState,
+    imported: &[NetworkRequest],
+) -> Vec<String> {
+    let evicted = {
+        let mut requests = store.lock().unwrap();
+        for request in imported {
+            limits::touch(store_limits, &request.id);
+        }
+        limits::enforce(store_limits, &mut requests)
+    };
+    // Inserted in timestamp order rather than pushed
+    index::invalidate(index);
+    for request in imported {
+        storage::put(storage, request);
+    }
+    storage::remove(storage, &evicted);
+    evicted
+}
+
+// Stores a queue of requests sent earlier, from a client's offline buffer or
+// from the background agent
+fn import_queue(state: &IngestState, queue: OfflineQueue, sink: &Events) {
+    let received = queue.requests.len();
+    let imported = import_requests(
+        &state.store,
+        &state.experiment_rules,
+        &state.derived_fields,
+        &state.blob_store,
+        queue.requests,
+        None,
+    );
+    println!(
+        "Imported {} of {} queued offline requests",
+        imported.len(),
+        received
+    );
+    if imported.is_empty() {
+        return;
+    }
+    telemetry::record_ingest(&state.telemetry, imported.len());
+    for request in &imported {
+        livestats::observe(&state.live_stats, request);
+    }
+
+    let evicted = store_imported(
+        &state.store,
+        &state.store_limits,
+        &state.storage,
+        &state.index,
+        &imported,
+    );
+    if let Err(e) = events::emit(&**sink, AppEvent::RequestsImported(&imported)) {
+        println!("Failed to emit requests-imported event: {}", e);
+    }
+    emit_removed(evicted, sink);
+}
+
+// Tells the frontend about requests dropped to make room
+fn emit_removed(ids: Vec<String>, sink: &Events) {
+    if !ids.is_empty() {
+        events::emit(
+            &**sink,
+            AppEvent::RequestsDeleted(&bulk::BulkResult { ids }),
+        )
+        .ok();
+    }
+}
+
+// Everything a connection needs to process incoming traffic. The app manages
+// each store so its commands see the same data the server writes.
+#[derive(Clone, Default)]
+pub struct IngestState {
+    pub(crate) store: RequestStore,
+    pub(crate) dedup_cache: DeduplicationCache,
+    pub(crate) experiment_rules: ExperimentRules,
+    pub(crate) derived_fields: DerivedFields,
+    pub(crate) marker_store: MarkerStore,
+    pub(crate) alert_store: AlertStore,
+    pub(crate) watch_store: WatchStore,
+    pub(crate) capture_state: CaptureState,
+    pub(crate) latest_state: LatestState,
+    pub(crate) store_limits: StoreLimitState,
+    pub(crate) blob_store: BlobStore,
+    pub(crate) storage: StorageState,
+    pub(crate) sessions: SessionState,
+    pub(crate) sampling_state: SamplingState,
+    pub(crate) rule_packs: RulePackState,
+    pub(crate) unparsed_store: UnparsedStore,
+    pub(crate) client_registry: ClientRegistry,
+    pub(crate) command_bus: CommandBus,
+    pub(crate) baseline_state: BaselineState,
+    pub(crate) readiness: Readiness,
+    pub(crate) telemetry: TelemetryState,
+    pub(crate) flood_guard: FloodGuardState,
+    pub(crate) config: ConfigState,
+    pub(crate) index: IndexState,
+    pub(crate) live_stats: LiveStatsState,
+    pub(crate) perf: PerfState,
+}
+
+impl IngestState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn requests(&self) -> Vec<NetworkRequest> {
+        self.store.lock().unwrap().clone()
+    }
+
+    // Same as the `get_requests` command: the store with its overflow on disk
+    pub fn all_requests(&self) -> Vec<NetworkRequest> {
+        limits::captured(&self.store_limits, &self.store).unwrap()
+    }
+
+    // Same as the `get_stats` command
+    pub fn stats(&self) -> Vec<GroupStats> {
+        livestats::snapshot(&self.live_stats)
+    }
+
+    // Same as the `set_perf_mode` command
+    pub fn set_perf_mode(&self, enabled: bool, sample_every: Option<u32>) {
+        perf::set_mode(&self.perf, enabled, sample_every)
+    }
+
+    // Same as the `get_perf_breakdown` command
+    pub fn perf_breakdown(&self) -> PerfBreakdown {
+        perf::breakdown(&self.perf)
+    }
+
+    pub fn markers(&self) -> Vec<Marker> {
+        self.marker_store.lock().unwrap().clone()
+    }
+
+    pub fn clients(&self) -> Vec<ClientStats> {
+        self.client_registry
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    // Same as the `send_command` command
+    pub async fn send_command(&self, command: &serde_json::Value) -> usize {
+        self.command_bus.send(command.to_string()).await
+    }
+
+    // Same as the `set_store_limits` command, without the event
+    pub fn set_store_limits(&self, store_limits: limits::StoreLimits) -> Vec<String> {
+        self.store_limits.lock().unwrap().limits = store_limits;
+        let evicted = limits::enforce(&self.store_limits, &mut self.store.lock().unwrap());
+        storage::remove(&self.storage, &evicted);
+        evicted
+    }
+
+    // Same as the `set_config` command, without writing the config file
+    pub fn apply_config(
+        &self,
+        config: config::ObserverConfig,
+        sink: &Events,
+    ) -> Result<(), String> {
+        config::apply(
+            &self.config,
+            &self.store_limits,
+            &self.store,
+            &self.storage,
+            &**sink,
+            config,
+        )
+    }
+
+    // Same as the `set_connection_limits` command
+    pub fn set_connection_limits(
+        &self,
+        limits: floodguard::ConnectionLimits,
+    ) -> Result<(), String> {
+        floodguard::set_limits(&self.flood_guard, limits)
+    }
+
+    // Loads what the backend holds and writes every change through to it;
+    // returns how many requests were loaded
+    pub fn attach_storage(&self, backend: Box<dyn RequestStorage>) -> Result<usize, String> {
+        storage::attach(self, backend)
+    }
+
+    // Writes queued changes to the storage backend without waiting for the
+    // next batch
+    pub fn flush_storage(&self) {
+        storage::flush(&self.storage)
+    }
+
+    // Writes requests queued for the overflow segment without waiting for the
+    // next pass
+    pub fn spill_overflow(&self) {
+        limits::spill_queued(&self.store_limits)
+    }
+
+    // Where `serve` listens, once it does
+    pub async fn ready(&self, timeout: std::time::Duration) -> Result<SocketAddr, String> {
+        self.readiness.wait(timeout).await
+    }
+}
+
+pub const PORT_VAR: &str = "NETWORK_OBSERVER_PORT";
+const DEFAULT_PORT: u16 = 8085;
+
+// Where ingest listens: loopback, on the port NETWORK_OBSERVER_PORT names or
+// 8085. The UI, the agent and the launcher all read it, and the agent the
+// launcher starts inherits it.
+pub(crate) fn ingest_addr() -> SocketAddr {
+    let port = match std::env::var(PORT_VAR) {
+        Ok(value) => match value.trim().parse::<u16>() {
+            Ok(port) if port > 0 => port,
+            _ => {
+                eprintln!(
+                    "Ignoring {}={}, not a port; using {}",
+                    PORT_VAR, value, DEFAULT_PORT
+                );
+                DEFAULT_PORT
+            }
+        },
+        Err(_) => DEFAULT_PORT,
+    };
+    SocketAddr::from(([127, 0, 0, 1], port))
+}
+
+pub(crate) async fn start_websocket_server(state: IngestState, sink: Events) {
+    // A background agent holds the port until it hands over what it captured
+    if let Some(queue) = agent::take_over().await {
+        println!("Took over {} requests from the agent", queue.requests.len());
+        import_queue(&state, queue, &sink);
+    }
+    let addr = ingest_addr();
+    let listener = TcpListener::bind(addr)
+        .await
+        .expect("Failed to bind WebSocket server");
+    println!("WebSocket server listening on: {}", addr);
+    serve(listener, state, sink).await;
+}
+
+// Accepts ingest connections on `listener` until the task is dropped. Doesn't
+// need the app, so tests can run it against a port of their own.
+pub async fn serve(listener: TcpListener, state: IngestState, sink: Events) {
+    if let Ok(addr) = listener.local_addr() {
+        state.readiness.mark_bound(addr);
+    }
+    loop {
+        match listener.accept().await {
+            Ok((stream, addr)) => {
+                println!("New WebSocket connection from: {}", addr);
+
+                let state = state.clone();
+                let sink = sink.clone();
+                tokio::spawn(handle_connection(stream, addr, state, sink));
+            }
+            Err(e) => {
+                eprintln!("Failed to accept WebSocket connection: {}", e);
+                // Continue listening despite errors
+                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            }
+        }
+    }
+}
+
+fn ingest_request(state: &IngestState, mut request: NetworkRequest, sink: &Events, probe: &Probe) {
+    if request.id.is_empty() {
+        request.id = Uuid::new_v4().to_string();
+    }
+
+    // Server-side deduplication check
+    let started = probe.start();
+    let signature = create_request_signature(&request);
+    let current_time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let dedup_window = config::dedup_window(&state.config);
+    // The stored request this one repeats, when repeats are collapsed
+    let mut repeat_of = None;
+    let should_process = {
+        let mut cache = state.dedup_cache.lock().unwrap();
+
+        // Clean up old entries (older than 5 minutes)
+        cache.retain(|_, (timestamp, _)| current_time - *timestamp < 300);
+
+        // Check if we've seen this request recently (within the dedup window)
+        if let Some((last_seen, merged_into)) = cache.get(&signature) {
+            if current_time - last_seen < dedup_window {
+                println!(
+                    "Server: Duplicate request detected, skipping: {} {}",
+                    request.method, request.url
+                );
+                if let Some(updated) = aliases::record_alias(&state.store, merged_into, &request.id)
+                {
+                    storage::put(&state.storage, &updated);
+                }
+                false
+            } else if config::collapse_repeats(&state.config) && request.response.is_some() {
+                let merged_into = merged_into.clone();
+                cache.insert(signature.clone(), (current_time, merged_into.clone()));
+                repeat_of = Some(merged_into);
+                true
+            } else {
+                cache.insert(signature.clone(), (current_time, request.id.clone()));
+                true
+            }
+        } else {
+            cache.insert(signature.clone(), (current_time, request.id.clone()));
+            true
+        }
+    };
+    probe.stop(PerfStage::Dedup, started);
+
+    if should_process && !capture::accept(&state.capture_state, Some(&request), &**sink) {
+        println!(
+            "Capture paused, dropping: {} {}",
+            request.method, request.url
+        );
+    } else if should_process && !sampling::keep(&state.sampling_state, &request) {
+        println!("Sampled out: {} {}", request.method, request.url);
+    } else if should_process && rulepacks::ignored(&state.rule_packs, &request) {
+        println!("Ignored by rule pack: {} {}", request.method, request.url);
+    } else if should_process && config::ignored(&state.config, &request) {
+        println!("Ignored by config: {} {}", request.method, request.url);
+    } else if should_process {
+        if let Some(id) = repeat_of {
+            let started = probe.start();
+            if let Some(updated) = occurrences::record(&state.store, &id, &request) {
+                println!("Repeat of {}: {} {}", id, request.method, request.url);
+                // Stats count the repeat itself, with its own duration
+                lifecycle::initialize(&mut request);
+                livestats::observe(&state.live_stats, &request);
+                limits::touch(&state.store_limits, &updated.id);
+                storage::put(&state.storage, &updated);
+                probe.stop(PerfStage::Store, started);
+                let started = probe.start();
+                if let Err(e) = events::emit(&**sink, AppEvent::RequestUpdated(&updated)) {
+                    println!("Failed to emit request-updated event: {}", e);
+                }
+                probe.stop(PerfStage::Emit, started);
+                evaluate_rules(state, &updated, sink, probe);
+                return;
+            }
+            // What it repeats has been deleted; later repeats count on this one
+            state
+                .dedup_cache
+                .lock()
+                .unwrap()
+                .insert(signature, (current_time, request.id.clone()));
+        }
+        println!("Parsed request: {} {}", request.method, request.url);
+        let started = probe.start();
+        config::redact(&state.config, &mut request);
+        rulepacks::redact(&state.rule_packs, &mut request);
+        rulepacks::tag(&state.rule_packs, &mut request);
+        lifecycle::initialize(&mut request);
+        provenance::record(&mut request, ProvenanceKind::Captured, None);
+        enrich_request(&mut request, &state.experiment_rules, &state.derived_fields);
+        state.blob_store.store(&mut request);
+        probe.stop(PerfStage::Enrich, started);
+
+        let started = probe.start();
+        let (replaced, evicted, indexed) = {
+            let mut requests = state.store.lock().unwrap();
+            let replaced = latest::supersede(&state.latest_state, &mut requests, &request);
+            if !replaced.is_empty() {
+                let detail = replaced.join(", ");
+                provenance::record(&mut request, ProvenanceKind::Superseded, Some(detail));
+            }
+            requests.push(request.clone());
+            let index_started = probe.start();
+            index::insert(&state.index, &requests);
+            let indexed = probe.stop(PerfStage::Index, index_started);
+            limits::touch(&state.store_limits, &request.id);
+            let evicted = limits::enforce(&state.store_limits, &mut requests);
+            index::evict(&state.index, &requests, &evicted);
+            (replaced, evicted, indexed)
+        };
+        storage::put(&state.storage, &request);
+        storage::remove(&state.storage, &replaced);
+        storage::remove(&state.storage, &evicted);
+        telemetry::record_ingest(&state.telemetry, 1);
+        livestats::observe(&state.live_stats, &request);
+        probe.stop_excluding(PerfStage::Store, started, indexed);
+
+        let started = probe.start();
+        emit_removed(replaced, sink);
+        emit_removed(evicted, sink);
+
+        // Emit event to frontend
+        if let Err(e) = events::emit(&**sink, AppEvent::NewRequest(&request)) {
+            println!("Failed to emit new-request event: {}", e);
+        } else {
+            println!("Successfully emitted new-request event");
+        }
+        probe.stop(PerfStage::Emit, started);
+
+        evaluate_rules(state, &request, sink, probe);
+    }
+}
+
+fn evaluate_rules(state: &IngestState, request: &NetworkRequest, sink: &Events, probe: &Probe) {
+    let started = probe.start();
+    alerts::evaluate(&state.alert_store, request, sink);
+    watches::evaluate(&state.watch_store, request, &**sink);
+    capture::check_break_rules(&state.capture_state, request, &**sink);
+    baselines::observe(&state.baseline_state, request, &**sink);
+    probe.stop(PerfStage::Rules, started);
+}
+
+// Fills in the response of a pending request sent earlier with `request-start`
+fn complete_request(state: &IngestState, end: protocol::RequestEnd, sink: &Events, probe: &Probe) {
+    let next = end.state.unwrap_or(RequestState::Complete);
+    if !next.is_final() {
+        println!("request-end with non-final state {:?}: {}", next, end.id);
+        return;
+    }
+
+    let started = probe.start();
+    let (completed, change, evicted, nested) = {
+        let mut requests = state.store.lock().unwrap();
+        let Some(request) = requests
+            .iter_mut()
+            .find(|r| r.has_id(&end.id) && !r.state.is_final())
+        else {
+            // Started while paused or sampled out, or already completed
+            println!("No pending request for request-end: {}", end.id);
+            return;
+        };
+        let duration = end
+            .duration
+            .unwrap_or_else(|| markers::now_millis().saturating_sub(request.timestamp));
+        let at = request.timestamp.saturating_add(duration);
+        let change = match lifecycle::transition(request, next, Some(at), end.error) {
+            Ok(change) => change,
+            Err(e) => {
+                println!("Ignoring request-end: {}", e);
+                return;
+            }
+        };
+        request.response = Some(end.response);
+        request.duration = Some(duration);
+        provenance::record(request, ProvenanceKind::ResponseAttached, None);
+        let enrich_started = probe.start();
+        enrich_request(request, &state.experiment_rules, &state.derived_fields);
+        state.blob_store.store(request);
+        let enriched = probe.stop(PerfStage::Enrich, enrich_started);
+        let completed = request.clone();
+        let index_started = probe.start();
+        index::record_status(&state.index, &completed);
+        let indexed = probe.stop(PerfStage::Index, index_started);
+        limits::touch(&state.store_limits, &completed.id);
+        // The response body may have taken the store over its byte limit
+        let evicted = limits::enforce(&state.store_limits, &mut requests);
+        index::evict(&state.index, &requests, &evicted);
+        (completed, change, evicted, enriched + indexed)
+    };
+    storage::put(&state.storage, &completed);
+    storage::remove(&state.storage, &evicted);
+    livestats::observe(&state.live_stats, &completed);
+    probe.stop_excluding(PerfStage::Store, started, nested);
+
+    let started = probe.start();
+    if let Err(e) = events::emit(&**sink, AppEvent::RequestUpdated(&completed)) {
+        println!("Failed to emit request-updated event: {}", e);
+    }
+    events::emit(&**sink, AppEvent::RequestStateChanged(&change)).ok();
+    emit_removed(evicted, sink);
+    probe.stop(PerfStage::Emit, started);
+    evaluate_rules(state, &completed, sink, probe);
+}
+
+// Intermediate progress, or a request that ended without a response
+fn update_request_state(state: &IngestState, update: protocol::RequestStateUpdate, sink: &Events) {
+    let (updated, change) = {
+        let mut requests = state.store.lock().unwrap();
+        let Some(request) = requests.iter_mut().find(|r| r.has_id(&update.id)) else {
+            println!("No request for request-state: {}", update.id);
+            return;
+        };
+        match lifecycle::transition(request, update.state, update.timestamp, update.detail) {
+            Ok(change) => {
+                // A failure's detail may explain it
+                hints::annotate_request(request);
+                limits::touch(&state.store_limits, &request.id);
+                (request.clone(), change)
+            }
+            Err(e) => {
+                println!("Ignoring request-state: {}", e);
+                return;
+            }
+        }
+    };
+    storage::put(&state.storage, &updated);
+    if updated.state.is_final() {
+        livestats::observe(&state.live_stats, &updated);
+    }
+
+    if let Err(e) = events::emit(&**sink, AppEvent::RequestUpdated(&updated)) {
+        println!("Failed to emit request-updated event: {}", e);
+    }
+    events::emit(&**sink, AppEvent::RequestStateChanged(&change)).ok();
+}
+
+// Keeps the message in the quarantine buffer as well as logging it
+fn log_unparsed(state: &IngestState, client: &Client, text: &str, error: String, sink: &Events) {
+    println!(
+        "Failed to parse WebSocket message as NetworkRequest - Message length: {}",
+        text.len()
+    );
+    println!("First 200 bytes: {}", text::prefix(text, 200));
+    clients::record_parse_failure(&state.client_registry, client);
+    quarantine::record(&state.unparsed_store, &client.peer, text, error, &**sink);
+}
+
+// Handles one decoded message, collecting anything to send back to the client
+fn handle_message(
+    state: &IngestState,
+    client: &Client,
+    value: serde_json::Value,
+    sink: &Events,
+    replies: &mut Vec<protocol::ServerMessage>,
+    probe: &Probe,
+) {
+    // First try to parse as NetworkRequest (the original format)
+    let started = probe.start();
+    if let Ok(mut request) = NetworkRequest::deserialize(&value) {
+        probe.stop(PerfStage::Parse, started);
+        request.client_id = Some(client.id.clone());
+        ingest_request(state, request, sink, probe);
+        return;
+    }
+
+    let message = WebSocketMessage::deserialize(&value);
+    probe.stop(PerfStage::Parse, started);
+    let message = match message {
+        Ok(message) => message,
+        Err(_) => {
+            let error = protocol::ParseError::diagnose(&value);
+            log_unparsed(state, client, &value.to_string(), error.to_string(), sink);
+            replies.push(protocol::ServerMessage::Error(error));
+            return;
+        }
+    };
+
+    match message {
+        WebSocketMessage::NetworkRequest(mut request)
+        | WebSocketMessage::RequestStart(mut request) => {
+            request.client_id = Some(client.id.clone());
+            ingest_request(state, *request, sink, probe);
+        }
+        WebSocketMessage::RequestEnd(end) => complete_request(state, end, sink, probe),
+        WebSocketMessage::RequestState(update) => update_request_state(state, update, sink),
+        WebSocketMessage::Marker(marker) => {
+            let marker = markers::add_marker_to_store(&state.marker_store, marker);
+            println!("Marker received: {:?}", marker.kind);
+            if let Err(e) = events::emit(&**sink, AppEvent::MarkerAdded(&marker)) {
+                println!("Failed to emit marker-added event: {}", e);
+            }
+        }
+        WebSocketMessage::OfflineQueue(mut queue) => {
+            if !capture::accept(&state.capture_state, None, &**sink) {
+                println!("Capture paused, dropping offline queue");
+                return;
+            }
+            for request in &mut queue.requests {
+                request.client_id = Some(client.id.clone());
+            }
+            import_queue(state, queue, sink);
+        }
+        WebSocketMessage::Hello(hello) => {
+            println!(
+                "Client hello: {} {} (protocol {})",
+                hello.client.as_deref().unwrap_or("unknown"),
+                hello.client_version.as_deref().unwrap_or(""),
+                hello.protocol
+            );
+            clients::record_hello(&state.client_registry, client, &hello);
+            replies.push(protocol::ServerMessage::welcome());
+        }
+        WebSocketMessage::Batch(batch) => {
+            for message in batch.messages {
+                handle_message(state, client, message, sink, replies, probe);
+            }
+        }
+    }
+}
+
+// Traffic for a session in the background isn't shown until it is switched to
+struct Background;
+
+impl events::EventSink for Background {
+    fn emit_event(&self, _name: &str, _payload: serde_json::Value) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+// Everything done with one text frame, returning the replies for the client
+pub(crate) fn handle_text(
+    state: &IngestState,
+    client: &Client,
+    text: &str,
+    sink: &Events,
+) -> Vec<protocol::ServerMessage> {
+    // Not held while ingesting, so connections don't wait on each other
+    let parked = state
+        .sessions
+        .lock()
+        .unwrap()
+        .route(&state.client_registry, client);
+    if let Some(parked) = parked {
+        {
+            let active = state.latest_state.lock().unwrap();
+            let mut latest = parked.latest.lock().unwrap();
+            latest.enabled = active.enabled;
+            latest.keep_history = active.keep_history;
+        }
+        // Only the active session is journaled and indexed, and only held
+        // in part to the store limits
+        let background = IngestState {
+            store: parked.store,
+            latest_state: parked.latest,
+            live_stats: parked.live_stats,
+            dedup_cache: parked.dedup_cache,
+            store_limits: limits::parked(&state.store_limits),
+            storage: Default::default(),
+            index: Default::default(),
+            ..state.clone()
+        };
+        let sink: Events = Arc::new(Background);
+        return handle_frame(&background, client, text, &sink);
+    }
+    handle_frame(state, client, text, sink)
+}
+
+fn handle_frame(
+    state: &IngestState,
+    client: &Client,
+    text: &str,
+    sink: &Events,
+) -> Vec<protocol::ServerMessage> {
+    let mut replies = Vec::new();
+    let probe = perf::probe(&state.perf);
+    let started = probe.start();
+    let value = serde_json::from_str::<serde_json::Value>(text);
+    probe.stop(PerfStage::Parse, started);
+    match value {
+        Ok(value) => handle_message(state, client, value, sink, &mut replies, &probe),
+        Err(e) => {
+            let error = protocol::ParseError::invalid_json(&e);
+            log_unparsed(state, client, text, error.to_string(), sink);
+            replies.push(protocol::ServerMessage::Error(error));
+        }
+    }
+    replies
+}
+
+async fn handle_connection(stream: TcpStream, addr: SocketAddr, state: IngestState, sink: Events) {
+    let mut origin = None;
+    // The error type is tungstenite's, not ours
+    #[allow(clippy::result_large_err)]
+    let handshake = accept_hdr_async(stream, |request: &Request, response: Response| {
+        origin = request
+            .headers()
+            .get("origin")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        Ok(response)
+    });
+    let mut ws_stream = match handshake.await {
+        Ok(ws) => ws,
+        Err(e) => {
+            eprintln!("WebSocket connection error: {}", e);
+            return;
+        }
+    };
+
+    let source = floodguard::source(origin.as_deref(), &addr.ip().to_string());
+    let _slot = match floodguard::admit(&state.flood_guard, &source) {
+        Ok(slot) => slot,
+        Err(rejection) => {
+            println!("Rejected WebSocket connection: {}", rejection.reason);
+            let frame = CloseFrame {
+                code: rejection.code,
+                reason: rejection.reason.into(),
+            };
+            ws_stream.close(Some(frame)).await.ok();
+            return;
+        }
+    };
+
+    // Emit connection event to frontend
+    let peer = addr.to_string();
+    events::emit(&*sink, AppEvent::WebsocketConnected(&peer)).ok();
+
+    let client = clients::connect(&state.client_registry, &peer);
+    let command_queue = state.command_bus.subscribe(&client);
+    let (ws_sender, mut ws_receiver) = ws_stream.split();
+    let ws_sender = Arc::new(tokio::sync::Mutex::new(ws_sender));
+
+    // Spawn a task to handle incoming commands and send them to the client
+    let sender_clone = ws_sender.clone();
+    let registry = state.client_registry.clone();
+    let command_client = client.clone();
+    let command_sink = sink.clone();
+    let command_task = tokio::spawn(async move {
+        loop {
+            let command = match command_queue.recv().await {
+                Received::Command(command) => command,
+                // This client is slow to drain its queue; keep forwarding
+                // what is still buffered
+                Received::Lagged { dropped, queued } => {
+                    clients::record_lag(
+                        &registry,
+                        &command_client,
+                        dropped,
+                        queued,
+                        &*command_sink,
+                    );
+                    continue;
+                }
+            };
+            let mut sender = sender_clone.lock().await;
+            if sender.send(Message::Text(command)).await.is_err() {
+                break;
+            }
+            clients::record_command_sent(&registry, &command_client);
+        }
+    });
+
+    // Handle incoming messages from the client
+    while let Some(msg) = ws_receiver.next().await {
+        match msg {
+            Ok(Message::Text(text)) => {
+                println!("Received WebSocket message: {}", text.len());
+                clients::record_message(&state.client_registry, &client, text.len());
+
+                let replies = handle_text(&state, &client, &text, &sink);
+                let mut sender = ws_sender.lock().await;
+                for reply in replies {
+                    let Ok(reply) = serde_json::to_string(&reply) else {
+                        continue;
+                    };
+                    if let Err(e) = sender.send(Message::Text(reply)).await {
+                        eprintln!("Failed to send reply: {}", e);
+                        break;
+                    }
+                }
+            }
+            Ok(Message::Close(_)) => {
+                println!("WebSocket connection closed gracefully");
+                break;
+            }
+            Ok(Message::Ping(ping)) => {
+                // Respond to ping with pong for connection health
+                let mut sender = ws_sender.lock().await;
+                if let Err(e) = sender.send(Message::Pong(ping)).await {
+                    eprintln!("Failed to send pong response: {}", e);
+                    break;
+                }
+            }
+            Ok(Message::Pong(_)) => {
+                // Pong received, connection is healthy
+                println!("WebSocket pong received");
+            }
+            Err(e) => {
+                eprintln!("WebSocket error: {}", e);
+                break;
+            }
+            _ => {
+                println!("Received unexpected WebSocket message type");
+            }
+        }
+    }
+
+    // Clean up the command task when the connection ends
+    command_task.abort();
+    state.command_bus.unsubscribe(&client);
+    clients::disconnect(&state.client_registry, &client);
+}
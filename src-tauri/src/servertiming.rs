@@ -0,0 +1,163 @@
+// `Server-Timing` response headers (https://www.w3.org/TR/server-timing/):
+// the backend's own breakdown of where a request's time went, e.g.
+// `db;dur=53.2;desc="Primary DB", cache;desc=hit, app;dur=47`
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::limits::{self, StoreLimitState};
+use crate::{NetworkRequest, RequestStore};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS)]
+#[ts(export, export_to = "packages/client-js/src/generated/")]
+pub struct ServerTimingMetric {
+    pub name: String,
+    pub duration_ms: Option<f64>,
+    pub description: Option<String>,
+}
+
+// Splits on `separator` where it isn't inside a quoted string
+fn split_unquoted(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    match value
+        .strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+    {
+        Some(inner) => {
+            let mut unescaped = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => unescaped.extend(chars.next()),
+                    c => unescaped.push(c),
+                }
+            }
+            unescaped
+        }
+        None => value.to_string(),
+    }
+}
+
+fn parse_metric(text: &str) -> Option<ServerTimingMetric> {
+    let mut parts = split_unquoted(text, ';').into_iter();
+    let name = parts.next()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let mut metric = ServerTimingMetric {
+        name: name.to_string(),
+        duration_ms: None,
+        description: None,
+    };
+    // Only the first occurrence of each parameter counts; unknown ones are ignored
+    for param in parts {
+        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+        match key.trim().to_ascii_lowercase().as_str() {
+            "dur" if metric.duration_ms.is_none() => {
+                metric.duration_ms = unquote(value).parse().ok().filter(|d: &f64| d.is_finite());
+            }
+            "desc" if metric.description.is_none() => {
+                metric.description = Some(unquote(value));
+            }
+            _ => {}
+        }
+    }
+    Some(metric)
+}
+
+// Every metric across all Server-Timing headers, in order
+pub fn parse(values: impl Iterator<Item = impl AsRef<str>>) -> Vec<ServerTimingMetric> {
+    values
+        .flat_map(|value| {
+            split_unquoted(value.as_ref(), ',')
+                .into_iter()
+                .filter_map(parse_metric)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+pub fn annotate_request(request: &mut NetworkRequest) {
+    if let Some(response) = request.response.as_mut() {
+        response.server_timing = parse(response.headers.get_all("server-timing"));
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ServerTimings {
+    pub request_id: String,
+    // As measured by the client
+    #[ts(type = "number | null")]
+    pub client_duration_ms: Option<u64>,
+    pub metrics: Vec<ServerTimingMetric>,
+    // The `total` metric when the server sends one, otherwise the sum of the
+    // others (which may overlap, so treat it as an upper bound)
+    pub server_total_ms: Option<f64>,
+    // Client time not covered by the server: network, queueing, TLS, ...
+    pub unaccounted_ms: Option<f64>,
+}
+
+fn timings(request: &NetworkRequest) -> ServerTimings {
+    let metrics = request
+        .response
+        .as_ref()
+        .map(|response| response.server_timing.clone())
+        .unwrap_or_default();
+    let reported = metrics
+        .iter()
+        .find(|m| m.name.eq_ignore_ascii_case("total"));
+    let server_total_ms = match reported.and_then(|m| m.duration_ms) {
+        Some(total) => Some(total),
+        None => {
+            let durations: Vec<f64> = metrics.iter().filter_map(|m| m.duration_ms).collect();
+            (!durations.is_empty()).then(|| durations.iter().sum())
+        }
+    };
+    let unaccounted_ms = request
+        .duration
+        .zip(server_total_ms)
+        .map(|(client, server)| (client as f64 - server).max(0.0));
+
+    ServerTimings {
+        request_id: request.id.clone(),
+        client_duration_ms: request.duration,
+        metrics,
+        server_total_ms,
+        unaccounted_ms,
+    }
+}
+
+#[tauri::command]
+pub async fn get_server_timings(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    id: String,
+) -> Result<ServerTimings, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    requests
+        .iter()
+        .find(|r| r.has_id(&id))
+        .map(timings)
+        .ok_or_else(|| format!("Request not found: {}", id))
+}
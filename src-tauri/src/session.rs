@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::Path;
+use ts_rs::TS;
+
+use crate::blobs::{self, BlobStore};
+use crate::derived::DerivedFields;
+use crate::events::{self, AppEvent};
+use crate::experiments::ExperimentRules;
+use crate::har::{self, Har};
+use crate::import;
+use crate::lifecycle;
+use crate::limits::{self, StoreLimitState};
+use crate::markers::{now_millis, Marker, MarkerStore};
+use crate::migrate::{decode_envelope, decode_requests, REQUEST_VERSION};
+use crate::provenance::{self, ProvenanceKind};
+use crate::server::enrich_request;
+use crate::storage::{self, StorageState};
+use crate::{NetworkRequest, RequestStore};
+
+const SESSION_FORMAT: &str = "network-observer-session";
+// Bump when the layout changes, and teach `upgrade` to read the old one
+const SESSION_VERSION: u32 = 1;
+
+// A whole debugging session as `save_session` writes it: the requests with
+// their notes and tags, and the timeline markers. Requests are read as JSON
+// first so `migrate` can upgrade them.
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionFile<R> {
+    format: String,
+    version: u32,
+    saved_at: u64,
+    #[serde(default = "first_request_version")]
+    request_version: u32,
+    requests: R,
+    #[serde(default)]
+    markers: Vec<Marker>,
+}
+
+fn first_request_version() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum LoadMode {
+    // The file's contents take the place of the current store
+    #[default]
+    Replace,
+    // Added to the current store, skipping requests it already has
+    Merge,
+}
+
+// What `load_session` did to the store
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct LoadedSession {
+    // The file loaded, or the id of the past session reopened
+    pub path: String,
+    pub mode: LoadMode,
+    pub request_count: usize,
+    // Dropped to stay within the store limits
+    pub evicted: usize,
+}
+
+// Reads captured traffic from any file the observer writes or commonly imports:
+// a plain request list (`export_many`), a window export, a saved session, a
+// share bundle directory (its `requests.json`), a HAR archive or a capture
+// from another tool (see `import`)
+pub fn load_requests(path: &str) -> Result<Vec<NetworkRequest>, String> {
+    if let Some(imported) = import::read(Path::new(path)) {
+        return imported;
+    }
+    let file = if Path::new(path).is_dir() {
+        Path::new(path).join("requests.json")
+    } else {
+        Path::new(path).to_path_buf()
+    };
+    let text = std::fs::read_to_string(&file)
+        .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+    let value: Value = serde_json::from_str(&text)
+        .map_err(|e| format!("{}: invalid JSON: {}", file.display(), e))?;
+
+    let parsed = match value {
+        Value::Object(ref object) if object.contains_key("log") => {
+            serde_json::from_value::<Har>(value)
+                .map(|har| har.log.entries.iter().map(har::entry_to_request).collect())
+                .map_err(|e| e.to_string())
+        }
+        _ => decode_envelope(value),
+    };
+    let mut requests = parsed.map_err(|e| format!("{}: {}", file.display(), e))?;
+    // Exports from before request states were tracked
+    requests.iter_mut().for_each(lifecycle::initialize);
+    Ok(requests)
+}
+
+// The requests and markers of a file written by `save_session`
+fn read_session_file(path: &str) -> Result<(Vec<NetworkRequest>, Vec<Marker>), String> {
+    let text =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let value: Value =
+        serde_json::from_str(&text).map_err(|e| format!("{}: invalid JSON: {}", path, e))?;
+    if value.get("format").and_then(Value::as_str) != Some(SESSION_FORMAT) {
+        return Err(format!("{}: not a saved session", path));
+    }
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > SESSION_VERSION as u64 {
+        return Err(format!(
+            "{} was saved by a newer version of the app (format version {})",
+            path, version
+        ));
+    }
+    let session: SessionFile<Value> =
+        serde_json::from_value(upgrade(value)).map_err(|e| format!("{}: {}", path, e))?;
+    let requests = decode_requests(session.requests, session.request_version)
+        .map_err(|e| format!("{}: {}", path, e))?;
+    Ok((requests, session.markers))
+}
+
+// Rewrites an older session file into the current layout. There is only one
+// version so far.
+fn upgrade(value: Value) -> Value {
+    value
+}
+
+// Adds `loaded` to the store in timestamp order, skipping requests it already has
+pub(crate) fn merge(requests: &mut Vec<NetworkRequest>, loaded: Vec<NetworkRequest>) {
+    for request in loaded {
+        if requests.iter().any(|r| r.has_id(&request.id)) {
+            continue;
+        }
+        let position = requests.partition_point(|r| r.timestamp <= request.timestamp);
+        requests.insert(position, request);
+    }
+}
+
+// Writes the whole store, with full bodies, and returns how many requests were saved
+#[tauri::command]
+pub async fn save_session(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    markers: tauri::State<'_, MarkerStore>,
+    path: String,
+) -> Result<usize, String> {
+    let mut requests = limits::captured(&store_limits, &store)?;
+    let markers = markers.lock().map_err(|e| e.to_string())?.clone();
+    blobs::restore(&mut requests);
+
+    let count = requests.len();
+    let session = SessionFile {
+        format: SESSION_FORMAT.to_string(),
+        version: SESSION_VERSION,
+        saved_at: now_millis(),
+        request_version: REQUEST_VERSION,
+        requests,
+        markers,
+    };
+    let json = serde_json::to_string(&session).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    Ok(count)
+}
+
+// Opens a file written by `save_session` and returns the store as it is
+// afterwards, held to the store limits
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn load_session(
+    store: tauri::State<'_, RequestStore>,
+    markers: tauri::State<'_, MarkerStore>,
+    experiment_rules: tauri::State<'_, ExperimentRules>,
+    derived_fields: tauri::State<'_, DerivedFields>,
+    blob_store: tauri::State<'_, BlobStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    storage: tauri::State<'_, StorageState>,
+    app_handle: tauri::AppHandle,
+    path: String,
+    mode: Option<LoadMode>,
+) -> Result<Vec<NetworkRequest>, String> {
+    let (mut loaded, loaded_markers) = read_session_file(&path)?;
+    for request in loaded.iter_mut() {
+        lifecycle::initialize(request);
+        // Rebuilds what isn't saved, like the JSON field index
+        enrich_request(request, &experiment_rules, &derived_fields);
+        provenance::record(request, ProvenanceKind::Restored, Some(path.clone()));
+        blob_store.store(request);
+    }
+
+    let mode = mode.unwrap_or_default();
+    let mut requests = store.lock().map_err(|e| e.to_string())?;
+    let mut timeline = markers.lock().map_err(|e| e.to_string())?;
+    match mode {
+        LoadMode::Replace => {
+            *requests = loaded;
+            *timeline = loaded_markers;
+        }
+        LoadMode::Merge => {
+            merge(&mut requests, loaded);
+            let known: HashSet<String> = timeline.iter().map(|m| m.id.clone()).collect();
+            for marker in loaded_markers {
+                if known.contains(&marker.id) {
+                    continue;
+                }
+                let position = timeline.partition_point(|m| m.timestamp <= marker.timestamp);
+                timeline.insert(position, marker);
+            }
+        }
+    }
+    drop(timeline);
+    let evicted = limits::enforce(&store_limits, &mut requests);
+    let current = requests.clone();
+    drop(requests);
+    storage::replace_all(&storage, &current);
+
+    let loaded = LoadedSession {
+        path,
+        mode,
+        request_count: current.len(),
+        evicted: evicted.len(),
+    };
+    events::emit(&app_handle, AppEvent::StoreReplaced(&loaded))?;
+    Ok(current)
+}
@@ -0,0 +1,291 @@
+// Named capture sessions, so traffic from apps debugged side by side doesn't
+// interleave (unlike past sessions in `persist`, which are earlier launches).
+// Commands always see the active session through the managed store; the others
+// keep their requests in their own store until switched to. A connection goes
+// to the first session listing its `hello` client name or connection id, and
+// to the active session otherwise. Each session also keeps its own overflow
+// segment, endpoint history, live stats and duplicate cache. Store limits only
+// apply to the active session; a session's requests are held to them when it
+// is switched to.
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+use crate::blobs;
+use crate::clients::{Client, ClientRegistry};
+use crate::events::{self, AppEvent};
+use crate::latest::LatestState;
+use crate::limits::{self, StoreLimitState};
+use crate::livestats::LiveStatsState;
+use crate::markers::now_millis;
+use crate::overflow::OverflowState;
+use crate::server::DeduplicationCache;
+use crate::storage::{self, StorageState};
+use crate::{NetworkRequest, RequestStore};
+
+const DEFAULT_SESSION: &str = "default";
+
+// What ingest keeps for a session. Empty while the session is active, when it
+// is all in the managed state; only the history of the latest state is kept
+// here, its settings are shared.
+#[derive(Clone, Default)]
+pub(crate) struct SessionStores {
+    pub(crate) store: RequestStore,
+    pub(crate) overflow: OverflowState,
+    pub(crate) latest: LatestState,
+    pub(crate) live_stats: LiveStatsState,
+    pub(crate) dedup_cache: DeduplicationCache,
+}
+
+// The managed state of the active session, to swap with a parked one
+pub(crate) struct ActiveStores<'a> {
+    pub(crate) store: &'a RequestStore,
+    pub(crate) store_limits: &'a StoreLimitState,
+    pub(crate) latest: &'a LatestState,
+    pub(crate) live_stats: &'a LiveStatsState,
+    pub(crate) dedup_cache: &'a DeduplicationCache,
+}
+
+impl SessionStores {
+    // Exchanges everything but the requests, which the caller has locked
+    fn swap_with(&self, active: &ActiveStores) -> Result<(), String> {
+        limits::overflow(active.store_limits).swap(&self.overflow);
+        std::mem::swap(
+            &mut active.latest.lock().map_err(|e| e.to_string())?.history,
+            &mut self.latest.lock().map_err(|e| e.to_string())?.history,
+        );
+        std::mem::swap(
+            &mut *active.live_stats.lock().map_err(|e| e.to_string())?,
+            &mut *self.live_stats.lock().map_err(|e| e.to_string())?,
+        );
+        std::mem::swap(
+            &mut *active.dedup_cache.lock().map_err(|e| e.to_string())?,
+            &mut *self.dedup_cache.lock().map_err(|e| e.to_string())?,
+        );
+        Ok(())
+    }
+}
+
+pub struct CaptureSession {
+    name: String,
+    clients: Vec<String>,
+    created_at: u64,
+    parked: SessionStores,
+}
+
+impl CaptureSession {
+    fn new(name: String, clients: Vec<String>) -> Self {
+        CaptureSession {
+            name,
+            clients,
+            created_at: now_millis(),
+            parked: Default::default(),
+        }
+    }
+}
+
+pub struct Sessions {
+    active: String,
+    sessions: Vec<CaptureSession>,
+}
+
+impl Default for Sessions {
+    fn default() -> Self {
+        Sessions {
+            active: DEFAULT_SESSION.to_string(),
+            sessions: vec![CaptureSession::new(DEFAULT_SESSION.to_string(), Vec::new())],
+        }
+    }
+}
+
+pub type SessionState = Arc<Mutex<Sessions>>;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct SessionInfo {
+    pub name: String,
+    // `hello` client names and connection ids routed to this session
+    pub clients: Vec<String>,
+    #[ts(type = "number")]
+    pub created_at: u64,
+    pub request_count: usize,
+    pub active: bool,
+}
+
+impl Sessions {
+    // The stores of an inactive session this client's traffic belongs to;
+    // None means the active session. Callers let go of the lock before
+    // ingesting; a switch swaps what the stores hold under their own locks.
+    pub(crate) fn route(
+        &self,
+        registry: &ClientRegistry,
+        client: &Client,
+    ) -> Option<SessionStores> {
+        let name = registry
+            .lock()
+            .unwrap()
+            .get(&client.id)
+            .and_then(|stats| stats.client.clone());
+        let session = self.sessions.iter().find(|session| {
+            session
+                .clients
+                .iter()
+                .any(|c| *c == client.id || Some(c) == name.as_ref())
+        })?;
+        (session.name != self.active).then(|| session.parked.clone())
+    }
+
+    // Spill files of the sessions not active, for the body sweeper
+    pub(crate) fn parked_bodies(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for session in self.sessions.iter().filter(|s| s.name != self.active) {
+            let stores = &session.parked;
+            if let (Ok(requests), Ok(latest)) = (stores.store.lock(), stores.latest.lock()) {
+                let held = requests.iter().chain(latest.history.values().flatten());
+                let spilled = held.flat_map(blobs::spilled_bodies);
+                paths.extend(spilled.map(|spilled| spilled.path.clone()));
+            }
+            paths.extend(stores.overflow.queued_bodies());
+        }
+        paths
+    }
+
+    fn info(&self, session: &CaptureSession, active_store: &RequestStore) -> SessionInfo {
+        let active = session.name == self.active;
+        let store = if active {
+            active_store
+        } else {
+            &session.parked.store
+        };
+        SessionInfo {
+            name: session.name.clone(),
+            clients: session.clients.clone(),
+            created_at: session.created_at,
+            request_count: store.lock().map_or(0, |requests| requests.len()),
+            active,
+        }
+    }
+}
+
+// Adds a session holding `requests`, named `name`, or `name (2)` and so on
+// when that is taken
+pub(crate) fn add_imported(
+    state: &SessionState,
+    active_store: &RequestStore,
+    name: &str,
+    requests: Vec<NetworkRequest>,
+) -> Result<SessionInfo, String> {
+    let mut sessions = state.lock().map_err(|e| e.to_string())?;
+    let taken = |name: &str| sessions.sessions.iter().any(|s| s.name == name);
+    let mut unique = name.to_string();
+    let mut n = 1;
+    while taken(&unique) {
+        n += 1;
+        unique = format!("{} ({})", name, n);
+    }
+    let session = CaptureSession::new(unique, Vec::new());
+    *session.parked.store.lock().map_err(|e| e.to_string())? = requests;
+    let info = sessions.info(&session, active_store);
+    sessions.sessions.push(session);
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn create_session(
+    sessions: tauri::State<'_, SessionState>,
+    store: tauri::State<'_, RequestStore>,
+    name: String,
+    clients: Option<Vec<String>>,
+) -> Result<SessionInfo, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Session name is empty".to_string());
+    }
+    let mut sessions = sessions.lock().map_err(|e| e.to_string())?;
+    if sessions.sessions.iter().any(|s| s.name == name) {
+        return Err(format!("Session already exists: {}", name));
+    }
+    let session = CaptureSession::new(name, clients.unwrap_or_default());
+    let info = sessions.info(&session, &store);
+    sessions.sessions.push(session);
+    Ok(info)
+}
+
+// Makes another session the one commands and the frontend see, evicting what
+// is over the store limits. The crash journal and session history follow the
+// active session.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn switch_session(
+    sessions: tauri::State<'_, SessionState>,
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    latest: tauri::State<'_, LatestState>,
+    live_stats: tauri::State<'_, LiveStatsState>,
+    dedup_cache: tauri::State<'_, DeduplicationCache>,
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> Result<SessionInfo, String> {
+    let active = ActiveStores {
+        store: &store,
+        store_limits: &store_limits,
+        latest: &latest,
+        live_stats: &live_stats,
+        dedup_cache: &dedup_cache,
+    };
+    let (info, requests) = switch(&sessions, &active, &name)?;
+    storage::replace_all(&storage, &requests);
+
+    events::emit(&app_handle, AppEvent::SessionSwitched(&info))?;
+    Ok(info)
+}
+
+// Returns the session switched to and its requests
+fn switch(
+    state: &SessionState,
+    active: &ActiveStores,
+    name: &str,
+) -> Result<(SessionInfo, Vec<NetworkRequest>), String> {
+    let mut sessions = state.lock().map_err(|e| e.to_string())?;
+    let target = sessions
+        .sessions
+        .iter()
+        .position(|s| s.name == name)
+        .ok_or_else(|| format!("Session not found: {}", name))?;
+    if sessions.sessions[target].name != sessions.active {
+        let current = sessions
+            .sessions
+            .iter()
+            .position(|s| s.name == sessions.active)
+            .ok_or_else(|| format!("Session not found: {}", sessions.active))?;
+        let mut requests = active.store.lock().map_err(|e| e.to_string())?;
+        // Park the active session, then bring the target's in
+        for session in [current, target] {
+            let parked = &sessions.sessions[session].parked;
+            std::mem::swap(
+                &mut *requests,
+                &mut *parked.store.lock().map_err(|e| e.to_string())?,
+            );
+            parked.swap_with(active)?;
+        }
+        limits::enforce(active.store_limits, &mut requests);
+        sessions.active = name.to_string();
+    }
+    let info = sessions.info(&sessions.sessions[target], active.store);
+    let requests = active.store.lock().map_err(|e| e.to_string())?.clone();
+    Ok((info, requests))
+}
+
+#[tauri::command]
+pub async fn list_sessions(
+    sessions: tauri::State<'_, SessionState>,
+    store: tauri::State<'_, RequestStore>,
+) -> Result<Vec<SessionInfo>, String> {
+    let sessions = sessions.lock().map_err(|e| e.to_string())?;
+    Ok(sessions
+        .sessions
+        .iter()
+        .map(|session| sessions.info(session, &store))
+        .collect())
+}
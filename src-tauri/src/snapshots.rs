@@ -0,0 +1,155 @@
+// Named points in time of the store, for isolating the calls one user action
+// triggers: snapshot before, snapshot after, diff. A snapshot only keeps each
+// request's id and a fingerprint of its content, so taking one is cheap; the
+// diff returns the requests as they are in the store now.
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+use crate::limits::{self, StoreLimitState};
+use crate::markers::now_millis;
+use crate::{NetworkRequest, RequestStore};
+
+pub struct Snapshot {
+    name: String,
+    taken_at: u64,
+    // Request id -> fingerprint, in store order
+    entries: Vec<(String, u64)>,
+}
+
+pub type SnapshotStore = Arc<Mutex<Vec<Snapshot>>>;
+
+// Hashes a request's JSON without building the string
+struct HashWriter(DefaultHasher);
+
+impl std::io::Write for HashWriter {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.write(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn fingerprint(request: &NetworkRequest) -> u64 {
+    let mut writer = HashWriter(DefaultHasher::new());
+    // Serializing a request can't fail; an empty hash just shows it as changed
+    serde_json::to_writer(&mut writer, request).ok();
+    writer.0.finish()
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct SnapshotInfo {
+    pub name: String,
+    #[ts(type = "number")]
+    pub taken_at: u64,
+    pub request_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct SnapshotDiff {
+    // In the later snapshot only: what happened in between
+    pub added: Vec<NetworkRequest>,
+    // In both, but different in the later one, e.g. completed or retagged
+    pub changed: Vec<NetworkRequest>,
+    // Ids in the earlier snapshot only
+    pub removed: Vec<String>,
+}
+
+fn find<'a>(snapshots: &'a [Snapshot], name: &str) -> Result<&'a Snapshot, String> {
+    snapshots
+        .iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| format!("Snapshot not found: {}", name))
+}
+
+// Taking a snapshot under an existing name replaces it
+#[tauri::command]
+pub async fn snapshot_store(
+    snapshots: tauri::State<'_, SnapshotStore>,
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    name: String,
+) -> Result<SnapshotInfo, String> {
+    let entries: Vec<(String, u64)> = {
+        let requests = limits::captured(&store_limits, &store)?;
+        requests
+            .iter()
+            .map(|r| (r.id.clone(), fingerprint(r)))
+            .collect()
+    };
+    let snapshot = Snapshot {
+        name,
+        taken_at: now_millis(),
+        entries,
+    };
+    let info = SnapshotInfo {
+        name: snapshot.name.clone(),
+        taken_at: snapshot.taken_at,
+        request_count: snapshot.entries.len(),
+    };
+
+    let mut snapshots = snapshots.lock().map_err(|e| e.to_string())?;
+    snapshots.retain(|s| s.name != snapshot.name);
+    snapshots.push(snapshot);
+    Ok(info)
+}
+
+// What changed from snapshot `a` to snapshot `b`. Requests deleted from the
+// store since `b` was taken are left out of `added` and `changed`.
+#[tauri::command]
+pub async fn diff_snapshots(
+    snapshots: tauri::State<'_, SnapshotStore>,
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    a: String,
+    b: String,
+) -> Result<SnapshotDiff, String> {
+    let (added, changed, removed) = {
+        let snapshots = snapshots.lock().map_err(|e| e.to_string())?;
+        let (from, to) = (find(&snapshots, &a)?, find(&snapshots, &b)?);
+        let before: HashMap<&str, u64> = from
+            .entries
+            .iter()
+            .map(|(id, print)| (id.as_str(), *print))
+            .collect();
+        let after: HashSet<&str> = to.entries.iter().map(|(id, _)| id.as_str()).collect();
+
+        let mut added = HashSet::new();
+        let mut changed = HashSet::new();
+        for (id, print) in &to.entries {
+            match before.get(id.as_str()) {
+                None => added.insert(id.clone()),
+                Some(earlier) if earlier != print => changed.insert(id.clone()),
+                Some(_) => false,
+            };
+        }
+        let removed: Vec<String> = from
+            .entries
+            .iter()
+            .filter(|(id, _)| !after.contains(id.as_str()))
+            .map(|(id, _)| id.clone())
+            .collect();
+        (added, changed, removed)
+    };
+
+    let requests = limits::captured(&store_limits, &store)?;
+    let pick = |ids: &HashSet<String>| -> Vec<NetworkRequest> {
+        requests
+            .iter()
+            .filter(|r| ids.contains(&r.id))
+            .cloned()
+            .collect()
+    };
+    Ok(SnapshotDiff {
+        added: pick(&added),
+        changed: pick(&changed),
+        removed,
+    })
+}
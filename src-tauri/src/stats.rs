@@ -0,0 +1,70 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use ts_rs::TS;
+
+use crate::NetworkRequest;
+
+// Traffic summary for one group of requests (a context value, an experiment variant, ...)
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct GroupStats {
+    pub value: Option<String>,
+    pub count: usize,
+    pub errors: usize,
+    pub pending: usize,
+    pub error_rate: f64,
+    pub avg_duration_ms: Option<f64>,
+    #[ts(type = "number | null")]
+    pub p95_duration_ms: Option<u64>,
+    pub total_response_bytes: usize,
+}
+
+pub fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p / 100.0) * (sorted.len() - 1) as f64).round() as usize;
+    sorted.get(rank.min(sorted.len() - 1)).copied()
+}
+
+pub fn group_stats<F>(requests: &[NetworkRequest], key: F) -> Vec<GroupStats>
+where
+    F: Fn(&NetworkRequest) -> Option<String>,
+{
+    let mut groups: HashMap<Option<String>, Vec<&NetworkRequest>> = HashMap::new();
+    for request in requests {
+        groups.entry(key(request)).or_default().push(request);
+    }
+
+    let mut stats: Vec<GroupStats> = groups
+        .into_iter()
+        .map(|(value, members)| {
+            let mut durations: Vec<u64> = members.iter().filter_map(|r| r.duration).collect();
+            durations.sort_unstable();
+            let errors = members
+                .iter()
+                .filter(|r| r.response.as_ref().is_some_and(|res| res.status >= 400))
+                .count();
+            let pending = members.iter().filter(|r| !r.state.is_final()).count();
+            let total_response_bytes = members
+                .iter()
+                .filter_map(|r| r.response.as_ref())
+                .map(|res| res.body.as_ref().map(|b| b.len()).unwrap_or(0))
+                .sum();
+
+            GroupStats {
+                value,
+                count: members.len(),
+                errors,
+                pending,
+                error_rate: errors as f64 / members.len() as f64,
+                avg_duration_ms: (!durations.is_empty())
+                    .then(|| durations.iter().sum::<u64>() as f64 / durations.len() as f64),
+                p95_duration_ms: percentile(&durations, 95.0),
+                total_response_bytes,
+            }
+        })
+        .collect();
+    stats.sort_by_key(|s| std::cmp::Reverse(s.count));
+    stats
+}
@@ -0,0 +1,566 @@
+// Where captured requests are kept besides the in-memory store, which stays
+// what every command reads. Each change to the store is written through to a
+// `RequestStorage`, and whatever it holds is loaded back at startup. The UI
+// uses the crash journal; headless runs can pick a durable backend with
+// NETWORK_OBSERVER_STORAGE: `memory`, `sqlite:<path>` or an http(s) URL.
+// Writes for stored, changed or removed requests are buffered and flushed in
+// batches, every `FLUSH_INTERVAL` or once `FLUSH_BATCH` are waiting, so a busy
+// capture doesn't wait on the disk for each request. The same changes tell
+// session history which requests it has to write.
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use ts_rs::TS;
+
+use crate::blobs;
+use crate::bulk::BulkResult;
+use crate::events::{self, AppEvent};
+use crate::limits;
+use crate::markers::now_millis;
+use crate::migrate::{decode_envelope, decode_request, envelope, REQUEST_VERSION};
+use crate::provenance::{self, ProvenanceKind};
+use crate::server::{self, IngestState};
+use crate::{NetworkRequest, RequestStore};
+
+pub const STORAGE_VAR: &str = "NETWORK_OBSERVER_STORAGE";
+const FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+const FLUSH_BATCH: usize = 500;
+
+pub trait RequestStorage: Send {
+    // What a previous run left, oldest first
+    fn load(&mut self) -> Result<Vec<NetworkRequest>, String>;
+    // After a request was stored or changed
+    fn put(&mut self, request: &NetworkRequest) -> Result<(), String>;
+    // Several at once, in order
+    fn put_all(&mut self, requests: &[NetworkRequest]) -> Result<(), String> {
+        requests.iter().try_for_each(|request| self.put(request))
+    }
+    // After requests were evicted or superseded
+    fn remove(&mut self, ids: &[String]) -> Result<(), String>;
+    // After requests were removed: `requests` is everything still in the store
+    fn replace_all(&mut self, requests: &[NetworkRequest]) -> Result<(), String>;
+    // On a clean exit, once the capture is safe in session history
+    fn close(&mut self) {}
+}
+
+// Keeps nothing beyond the store itself
+pub struct Memory;
+
+impl RequestStorage for Memory {
+    fn load(&mut self) -> Result<Vec<NetworkRequest>, String> {
+        Ok(Vec::new())
+    }
+
+    fn put(&mut self, _request: &NetworkRequest) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn remove(&mut self, _ids: &[String]) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn replace_all(&mut self, _requests: &[NetworkRequest]) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+// One row per request, kept across clean exits too
+pub struct Sqlite {
+    db: Connection,
+}
+
+impl Sqlite {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let db = Connection::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        db.execute_batch(
+            "
+            PRAGMA journal_mode = WAL;
+            CREATE TABLE IF NOT EXISTS stored_requests (
+                position INTEGER PRIMARY KEY AUTOINCREMENT,
+                id TEXT NOT NULL UNIQUE,
+                format_version INTEGER NOT NULL,
+                data TEXT NOT NULL
+            );
+            ",
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(Sqlite { db })
+    }
+}
+
+fn upsert(db: &Connection, request: &NetworkRequest) -> Result<(), String> {
+    let data = serde_json::to_string(request).map_err(|e| e.to_string())?;
+    db.execute(
+        "INSERT INTO stored_requests (id, format_version, data) VALUES (?1, ?2, ?3)
+         ON CONFLICT (id) DO UPDATE SET format_version = ?2, data = ?3",
+        params![request.id, REQUEST_VERSION, data],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+impl RequestStorage for Sqlite {
+    fn load(&mut self) -> Result<Vec<NetworkRequest>, String> {
+        let mut statement = self
+            .db
+            .prepare("SELECT format_version, data FROM stored_requests ORDER BY position")
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        let mut requests = Vec::new();
+        for row in rows {
+            let (version, data) = row.map_err(|e| e.to_string())?;
+            let decoded = serde_json::from_str(&data)
+                .map_err(|e| e.to_string())
+                .and_then(|value| decode_request(value, version));
+            match decoded {
+                Ok(request) => requests.push(request),
+                Err(e) => println!("Skipping unreadable stored request: {}", e),
+            }
+        }
+        Ok(requests)
+    }
+
+    fn put(&mut self, request: &NetworkRequest) -> Result<(), String> {
+        upsert(&self.db, request)
+    }
+
+    fn put_all(&mut self, requests: &[NetworkRequest]) -> Result<(), String> {
+        let tx = self.db.transaction().map_err(|e| e.to_string())?;
+        for request in requests {
+            upsert(&tx, request)?;
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    fn remove(&mut self, ids: &[String]) -> Result<(), String> {
+        let tx = self.db.transaction().map_err(|e| e.to_string())?;
+        {
+            let mut delete = tx
+                .prepare("DELETE FROM stored_requests WHERE id = ?1")
+                .map_err(|e| e.to_string())?;
+            for id in ids {
+                delete.execute(params![id]).map_err(|e| e.to_string())?;
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    fn replace_all(&mut self, requests: &[NetworkRequest]) -> Result<(), String> {
+        let tx = self.db.transaction().map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM stored_requests", [])
+            .map_err(|e| e.to_string())?;
+        for request in requests {
+            upsert(&tx, request)?;
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+}
+
+// A service that keeps the capture elsewhere, e.g. a CI collector. Loaded
+// from `GET <url>/requests` at startup; changes are sent in the background,
+// in order, as `POST <url>/requests` (stored or changed) and
+// `PUT <url>/requests` (everything still stored), both carrying
+// `{ request_version, requests }`, and `DELETE <url>/requests` with `{ ids }`
+// (evicted or superseded).
+pub struct Remote {
+    loaded: Vec<NetworkRequest>,
+    writes: mpsc::UnboundedSender<(reqwest::Method, Value)>,
+}
+
+impl Remote {
+    pub async fn connect(url: &str) -> Result<Self, String> {
+        let client = reqwest::Client::new();
+        let endpoint = format!("{}/requests", url.trim_end_matches('/'));
+        let response = client
+            .get(&endpoint)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach {}: {}", endpoint, e))?;
+        let loaded = if response.status() == reqwest::StatusCode::NOT_FOUND {
+            Vec::new()
+        } else {
+            let response = response.error_for_status().map_err(|e| e.to_string())?;
+            let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+            let body: Value = serde_json::from_slice(&bytes)
+                .map_err(|e| format!("Invalid response from {}: {}", endpoint, e))?;
+            decode_envelope(body)?
+        };
+
+        let (writes, mut pending) = mpsc::unbounded_channel::<(reqwest::Method, Value)>();
+        tokio::spawn(async move {
+            while let Some((method, body)) = pending.recv().await {
+                let sent = client
+                    .request(method, &endpoint)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(body.to_string())
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+                if let Err(e) = sent {
+                    println!("Failed to write to request storage: {}", e);
+                }
+            }
+        });
+        Ok(Remote { loaded, writes })
+    }
+
+    fn send(&self, method: reqwest::Method, body: Value) -> Result<(), String> {
+        self.writes
+            .send((method, body))
+            .map_err(|_| "Request storage connection closed".to_string())
+    }
+}
+
+impl RequestStorage for Remote {
+    fn load(&mut self) -> Result<Vec<NetworkRequest>, String> {
+        Ok(std::mem::take(&mut self.loaded))
+    }
+
+    fn put(&mut self, request: &NetworkRequest) -> Result<(), String> {
+        self.put_all(std::slice::from_ref(request))
+    }
+
+    fn put_all(&mut self, requests: &[NetworkRequest]) -> Result<(), String> {
+        self.send(reqwest::Method::POST, envelope(requests))
+    }
+
+    fn remove(&mut self, ids: &[String]) -> Result<(), String> {
+        self.send(reqwest::Method::DELETE, serde_json::json!({ "ids": ids }))
+    }
+
+    fn replace_all(&mut self, requests: &[NetworkRequest]) -> Result<(), String> {
+        self.send(reqwest::Method::PUT, envelope(requests))
+    }
+}
+
+// The backend NETWORK_OBSERVER_STORAGE asks for, if it is set
+pub fn configured() -> Option<String> {
+    std::env::var(STORAGE_VAR)
+        .ok()
+        .filter(|spec| !spec.trim().is_empty())
+}
+
+pub async fn open(spec: &str) -> Result<Box<dyn RequestStorage>, String> {
+    let spec = spec.trim();
+    if spec == "memory" {
+        Ok(Box::new(Memory))
+    } else if let Some(path) = spec.strip_prefix("sqlite:") {
+        Ok(Box::new(Sqlite::open(Path::new(path))?))
+    } else if spec.starts_with("http://") || spec.starts_with("https://") {
+        Ok(Box::new(Remote::connect(spec).await?))
+    } else {
+        Err(format!(
+            "Unknown request storage {:?}; expected memory, sqlite:<path> or an http(s) URL",
+            spec
+        ))
+    }
+}
+
+pub struct Storage {
+    backend: Box<dyn RequestStorage>,
+    // Requests loaded from the backend at startup, until discarded
+    recovered: Vec<String>,
+    recovered_at: Option<u64>,
+    // Written on the next flush; a request changed again meanwhile is
+    // written once, in its latest state
+    pending: Vec<NetworkRequest>,
+    pending_index: HashMap<String, usize>,
+    // Removed on the next flush, after the writes
+    removed: HashSet<String>,
+    // Since session history last wrote the store, once it is recording
+    changes: Option<Changes>,
+}
+
+// Requests stored, changed or removed; `all` when the whole store may have
+// changed
+#[derive(Debug, Default)]
+pub(crate) struct Changes {
+    pub(crate) ids: HashSet<String>,
+    pub(crate) removed: HashSet<String>,
+    pub(crate) all: bool,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Storage {
+            backend: Box::new(Memory),
+            recovered: Vec::new(),
+            recovered_at: None,
+            pending: Vec::new(),
+            pending_index: HashMap::new(),
+            removed: HashSet::new(),
+            changes: None,
+        }
+    }
+}
+
+impl Storage {
+    fn flush(&mut self) {
+        self.pending_index.clear();
+        let pending = std::mem::take(&mut self.pending);
+        if !pending.is_empty() {
+            if let Err(e) = self.backend.put_all(&pending) {
+                println!("Failed to store {} requests: {}", pending.len(), e);
+            }
+        }
+        let removed: Vec<String> = self.removed.drain().collect();
+        if !removed.is_empty() {
+            if let Err(e) = self.backend.remove(&removed) {
+                println!("Failed to remove {} stored requests: {}", removed.len(), e);
+            }
+        }
+    }
+
+    fn queued(&self) -> usize {
+        self.pending.len() + self.removed.len()
+    }
+}
+
+pub type StorageState = Arc<Mutex<Storage>>;
+
+// Loads what the backend holds into the store and writes through to it from
+// now on. Returns how many requests were loaded.
+pub(crate) fn attach(
+    state: &IngestState,
+    mut backend: Box<dyn RequestStorage>,
+) -> Result<usize, String> {
+    let mut recovered = backend.load()?;
+    // Spilled bodies are read back and spilled again to this run's directory,
+    // since the last run's is pruned
+    blobs::restore(&mut recovered);
+    for request in recovered.iter_mut() {
+        server::enrich_request(request, &state.experiment_rules, &state.derived_fields);
+        provenance::record(request, ProvenanceKind::Recovered, None);
+        state.blob_store.store(request);
+    }
+    let moved: Vec<NetworkRequest> = recovered
+        .iter()
+        .filter(|request| blobs::spilled_bodies(request).next().is_some())
+        .cloned()
+        .collect();
+    let ids: Vec<String> = recovered.iter().map(|r| r.id.clone()).collect();
+    let count = ids.len();
+    // The backend may hold more than the limits now allow
+    let evicted = {
+        let mut requests = state.store.lock().unwrap();
+        requests.extend(recovered);
+        limits::enforce(&state.store_limits, &mut requests)
+    };
+    let mut storage = state.storage.lock().unwrap();
+    let mut changes = storage.changes.take();
+    if let Some(changes) = changes.as_mut() {
+        changes.all = true;
+    }
+    *storage = Storage {
+        backend,
+        recovered_at: (count > 0).then(now_millis),
+        recovered: ids,
+        changes,
+        ..Storage::default()
+    };
+    drop(storage);
+    for request in &moved {
+        put(&state.storage, request);
+    }
+    remove(&state.storage, &evicted);
+    Ok(count)
+}
+
+// Queues a request to be written after it was stored or changed
+pub fn put(state: &StorageState, request: &NetworkRequest) {
+    let Ok(mut storage) = state.lock() else {
+        return;
+    };
+    storage.removed.remove(&request.id);
+    if let Some(changes) = storage.changes.as_mut() {
+        changes.removed.remove(&request.id);
+        changes.ids.insert(request.id.clone());
+    }
+    match storage.pending_index.get(&request.id) {
+        Some(&i) => storage.pending[i] = request.clone(),
+        None => {
+            let i = storage.pending.len();
+            storage.pending_index.insert(request.id.clone(), i);
+            storage.pending.push(request.clone());
+        }
+    }
+    if storage.queued() >= FLUSH_BATCH {
+        storage.flush();
+    }
+}
+
+// Queues requests to be removed after they were evicted or superseded, so
+// they don't come back on the next start
+pub fn remove(state: &StorageState, ids: &[String]) {
+    if ids.is_empty() {
+        return;
+    }
+    let Ok(mut storage) = state.lock() else {
+        return;
+    };
+    let storage = &mut *storage;
+    if ids.iter().any(|id| storage.pending_index.contains_key(id)) {
+        let gone: HashSet<&String> = ids.iter().collect();
+        storage
+            .pending
+            .retain(|request| !gone.contains(&request.id));
+        storage.pending_index = storage
+            .pending
+            .iter()
+            .enumerate()
+            .map(|(i, request)| (request.id.clone(), i))
+            .collect();
+    }
+    storage.removed.extend(ids.iter().cloned());
+    if let Some(changes) = storage.changes.as_mut() {
+        for id in ids {
+            changes.ids.remove(id);
+        }
+        changes.removed.extend(ids.iter().cloned());
+    }
+    if storage.queued() >= FLUSH_BATCH {
+        storage.flush();
+    }
+}
+
+// Brings the backend in line with the store after requests were removed,
+// so they don't come back on the next start. `requests` already holds the
+// latest state of anything queued.
+pub fn replace_all(state: &StorageState, requests: &[NetworkRequest]) {
+    let Ok(mut storage) = state.lock() else {
+        return;
+    };
+    storage.pending.clear();
+    storage.pending_index.clear();
+    storage.removed.clear();
+    if let Some(changes) = storage.changes.as_mut() {
+        *changes = Changes {
+            all: true,
+            ..Changes::default()
+        };
+    }
+    if let Err(e) = storage.backend.replace_all(requests) {
+        println!("Failed to rewrite request storage: {}", e);
+    }
+}
+
+// Starts keeping changes for session history, or starts over after it failed
+// to write them, from the whole store
+pub(crate) fn track_changes(state: &StorageState) {
+    if let Ok(mut storage) = state.lock() {
+        storage.changes = Some(Changes {
+            all: true,
+            ..Changes::default()
+        });
+    }
+}
+
+// What changed since the last call; None unless changes are kept
+pub(crate) fn take_changes(state: &StorageState) -> Option<Changes> {
+    let mut storage = state.lock().ok()?;
+    storage.changes.as_mut().map(std::mem::take)
+}
+
+// Writes whatever is queued now
+pub fn flush(state: &StorageState) {
+    if let Ok(mut storage) = state.lock() {
+        storage.flush();
+    }
+}
+
+// Runs for the life of the capture, on whichever runtime spawned it; the
+// writes themselves block, so they go to its blocking pool
+pub(crate) async fn flusher(state: StorageState) {
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+    loop {
+        interval.tick().await;
+        let state = state.clone();
+        tokio::task::spawn_blocking(move || flush(&state))
+            .await
+            .ok();
+    }
+}
+
+pub(crate) fn close(state: &StorageState) {
+    if let Ok(mut storage) = state.lock() {
+        storage.flush();
+        std::mem::replace(&mut storage.backend, Box::new(Memory)).close();
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct RecoveryStatus {
+    // Ids of requests recovered from the previous run that are still in the store
+    pub recovered: Vec<String>,
+    #[ts(type = "number | null")]
+    pub recovered_at: Option<u64>,
+}
+
+#[tauri::command]
+pub async fn get_recovery_status(
+    storage: tauri::State<'_, StorageState>,
+    store: tauri::State<'_, RequestStore>,
+) -> Result<RecoveryStatus, String> {
+    let storage = storage.lock().map_err(|e| e.to_string())?;
+    let requests = store.lock().map_err(|e| e.to_string())?;
+    Ok(RecoveryStatus {
+        recovered: storage
+            .recovered
+            .iter()
+            .filter(|id| requests.iter().any(|r| r.id == **id))
+            .cloned()
+            .collect(),
+        recovered_at: storage.recovered_at,
+    })
+}
+
+// Drops what was recovered from the previous run, keeping this run's capture
+#[tauri::command]
+pub async fn discard_recovered_requests(
+    storage: tauri::State<'_, StorageState>,
+    store: tauri::State<'_, RequestStore>,
+    app_handle: tauri::AppHandle,
+) -> Result<BulkResult, String> {
+    let recovered: HashSet<String> = {
+        let mut storage = storage.lock().map_err(|e| e.to_string())?;
+        storage.recovered_at = None;
+        std::mem::take(&mut storage.recovered).into_iter().collect()
+    };
+    if recovered.is_empty() {
+        return Ok(BulkResult { ids: Vec::new() });
+    }
+    let (ids, remaining) = {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        let mut ids = Vec::new();
+        requests.retain(|request| {
+            let discard = recovered.contains(&request.id);
+            if discard {
+                ids.push(request.id.clone());
+            }
+            !discard
+        });
+        (ids, requests.clone())
+    };
+    replace_all(&storage, &remaining);
+
+    let result = BulkResult { ids };
+    if !result.ids.is_empty() {
+        events::emit(&app_handle, AppEvent::RequestsDeleted(&result))?;
+    }
+    Ok(result)
+}
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::classify::ContentClass;
+use crate::derived::DerivedValues;
+use crate::filter::{url_host, RequestFilter};
+use crate::limits::{self, StoreLimitState};
+use crate::preflight::{PreflightPairs, PreflightState};
+use crate::{text, NetworkRequest, RequestStore};
+
+// Rows carry a capped URL so every row costs roughly the same to ship and render
+const MAX_SUMMARY_URL_LEN: usize = 512;
+const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct RequestSummary {
+    pub id: String,
+    pub method: String,
+    pub url: String,
+    pub host: String,
+    pub status: Option<u16>,
+    #[ts(type = "number")]
+    pub timestamp: u64,
+    #[ts(type = "number | null")]
+    pub duration: Option<u64>,
+    pub response_size: usize,
+    pub content_class: Option<ContentClass>,
+    pub tags: Vec<String>,
+    pub derived: DerivedValues,
+    // The OPTIONS preflight that cleared this request, when one was captured
+    pub preflight_id: Option<String>,
+}
+
+impl RequestSummary {
+    pub fn from_request(request: &NetworkRequest) -> Self {
+        let url = if request.url.len() > MAX_SUMMARY_URL_LEN {
+            format!("{}…", text::prefix(&request.url, MAX_SUMMARY_URL_LEN))
+        } else {
+            request.url.clone()
+        };
+
+        RequestSummary {
+            id: request.id.clone(),
+            method: request.method.clone(),
+            host: url_host(&request.url).to_string(),
+            url,
+            status: request.response.as_ref().map(|r| r.status),
+            timestamp: request.timestamp,
+            duration: request.duration,
+            response_size: response_size(request),
+            content_class: request.response.as_ref().and_then(|r| r.content_class),
+            tags: request.tags.clone(),
+            derived: request.derived.clone(),
+            preflight_id: None,
+        }
+    }
+}
+
+// Summary row with the request's preflight filled in
+pub(crate) fn summarize(request: &NetworkRequest, pairs: &PreflightPairs) -> RequestSummary {
+    RequestSummary {
+        preflight_id: pairs.preflight_of(&request.id).map(str::to_string),
+        ..RequestSummary::from_request(request)
+    }
+}
+
+fn response_size(request: &NetworkRequest) -> usize {
+    request
+        .response
+        .as_ref()
+        .and_then(|r| r.body.as_ref())
+        .map(|b| b.len())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum SortField {
+    #[default]
+    Timestamp,
+    Duration,
+    Status,
+    Size,
+    Method,
+    Url,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct Sort {
+    #[serde(default)]
+    pub field: SortField,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct Range {
+    pub offset: usize,
+    pub limit: usize,
+}
+
+pub fn sort_requests(requests: &mut [&NetworkRequest], sort: Sort) {
+    match sort.field {
+        SortField::Timestamp => requests.sort_by_key(|r| r.timestamp),
+        SortField::Duration => requests.sort_by_key(|r| r.duration),
+        SortField::Status => requests.sort_by_key(|r| r.response.as_ref().map(|res| res.status)),
+        SortField::Size => requests.sort_by_key(|r| response_size(r)),
+        SortField::Method => requests.sort_by(|a, b| a.method.cmp(&b.method)),
+        SortField::Url => requests.sort_by(|a, b| a.url.cmp(&b.url)),
+    }
+    if sort.descending {
+        requests.reverse();
+    }
+}
+
+pub fn select<'a>(
+    requests: &'a [NetworkRequest],
+    filter: Option<&RequestFilter>,
+) -> Vec<&'a NetworkRequest> {
+    requests
+        .iter()
+        .filter(|r| filter.is_none_or(|f| f.matches(r)))
+        .collect()
+}
+
+// `select`, minus the preflights folded into their request when collapsing
+pub(crate) fn listed<'a>(
+    requests: &'a [NetworkRequest],
+    filter: Option<&RequestFilter>,
+    pairs: &PreflightPairs,
+    collapse_preflights: bool,
+) -> Vec<&'a NetworkRequest> {
+    let mut selected = select(requests, filter);
+    if collapse_preflights {
+        selected.retain(|r| !pairs.is_paired_preflight(&r.id));
+    }
+    selected
+}
+
+#[tauri::command]
+pub async fn get_request_summaries(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    preflight: tauri::State<'_, PreflightState>,
+    range: Range,
+    sort: Option<Sort>,
+    filter: Option<RequestFilter>,
+) -> Result<Vec<RequestSummary>, String> {
+    filter.as_ref().map_or(Ok(()), RequestFilter::validate)?;
+    let collapse = preflight.lock().map_err(|e| e.to_string())?.collapse;
+    let requests = limits::captured(&store_limits, &store)?;
+    let pairs = PreflightPairs::build(&requests);
+    let mut selected = listed(&requests, filter.as_ref(), &pairs, collapse);
+    sort_requests(&mut selected, sort.unwrap_or_default());
+
+    Ok(selected
+        .into_iter()
+        .skip(range.offset)
+        .take(range.limit.min(MAX_PAGE_SIZE))
+        .map(|request| summarize(request, &pairs))
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_total_count(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    preflight: tauri::State<'_, PreflightState>,
+    filter: Option<RequestFilter>,
+) -> Result<usize, String> {
+    filter.as_ref().map_or(Ok(()), RequestFilter::validate)?;
+    let collapse = preflight.lock().map_err(|e| e.to_string())?.collapse;
+    let requests = limits::captured(&store_limits, &store)?;
+    if !collapse {
+        return Ok(select(&requests, filter.as_ref()).len());
+    }
+    let pairs = PreflightPairs::build(&requests);
+    Ok(listed(&requests, filter.as_ref(), &pairs, true).len())
+}
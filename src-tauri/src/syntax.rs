@@ -0,0 +1,481 @@
+// Syntax tokens for a body, lexed here so the frontend can highlight bodies of
+// several megabytes without tokenizing them on the webview's main thread. The
+// lexers are forgiving: malformed input still gets tokens for the parts that
+// make sense, and text between tokens is shown plain.
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::blobs::{self, BodyPart};
+use crate::classify::{self, ContentClass};
+use crate::limits::{self, StoreLimitState};
+use crate::RequestStore;
+
+// Larger bodies are tokenized up to here; the rest is shown plain
+const MAX_TOKENS: usize = 2_000_000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum SyntaxLanguage {
+    Json,
+    Xml,
+    Html,
+    #[serde(rename = "javascript")]
+    JavaScript,
+    // Nothing to highlight
+    Plain,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum TokenKind {
+    // JSON object keys
+    Key,
+    String,
+    Number,
+    // true, false, null, and undefined and the like in JavaScript
+    Literal,
+    Keyword,
+    Regex,
+    Comment,
+    Punctuation,
+    // Markup tag names, and attribute names
+    Tag,
+    Attribute,
+    // Doctypes, processing instructions and CDATA markers
+    Meta,
+}
+
+// `[kind, start, length]`, in UTF-16 code units so it slices a JS string
+// directly; a tuple to keep the payload small for big bodies
+#[derive(Debug, Clone, Copy, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct Token(pub TokenKind, pub u32, pub u32);
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct BodyTokens {
+    pub language: SyntaxLanguage,
+    pub tokens: Vec<Token>,
+    // Stopped at the token limit; the rest of the body has no tokens
+    pub truncated: bool,
+}
+
+fn language_for(class: ContentClass, body: &str) -> SyntaxLanguage {
+    match class {
+        ContentClass::Json => SyntaxLanguage::Json,
+        ContentClass::Html => SyntaxLanguage::Html,
+        ContentClass::Xml => SyntaxLanguage::Xml,
+        ContentClass::JavaScript => SyntaxLanguage::JavaScript,
+        // SVG
+        ContentClass::Image if body.trim_start().starts_with('<') => SyntaxLanguage::Xml,
+        _ => SyntaxLanguage::Plain,
+    }
+}
+
+// Byte ranges while lexing; converted to UTF-16 once at the end
+struct Lexer<'a> {
+    text: &'a [u8],
+    tokens: Vec<(TokenKind, usize, usize)>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(text: &'a str) -> Self {
+        Lexer {
+            text: text.as_bytes(),
+            tokens: Vec::new(),
+        }
+    }
+
+    fn full(&self) -> bool {
+        self.tokens.len() >= MAX_TOKENS
+    }
+
+    fn push(&mut self, kind: TokenKind, start: usize, end: usize) {
+        if end > start {
+            self.tokens.push((kind, start, end));
+        }
+    }
+
+    fn at(&self, i: usize) -> u8 {
+        self.text.get(i).copied().unwrap_or(0)
+    }
+
+    fn starts_with(&self, i: usize, prefix: &[u8]) -> bool {
+        self.text[i.min(self.text.len())..].starts_with(prefix)
+    }
+
+    // Index of the first `needle` at or after `from`, or the end of the text
+    fn find(&self, from: usize, needle: &[u8]) -> usize {
+        (from..self.text.len())
+            .find(|&i| self.starts_with(i, needle))
+            .unwrap_or(self.text.len())
+    }
+
+    // End of a string opened by the quote at `start`, past the closing quote
+    fn string_end(&self, start: usize) -> usize {
+        let quote = self.text[start];
+        let mut i = start + 1;
+        while i < self.text.len() {
+            match self.text[i] {
+                b'\\' => i += 2,
+                c if c == quote => return i + 1,
+                // JSON and JS strings, other than templates, end with the line
+                b'\n' if quote != b'`' => return i,
+                _ => i += 1,
+            }
+        }
+        self.text.len()
+    }
+
+    fn number_end(&self, mut i: usize) -> usize {
+        while i < self.text.len() {
+            let c = self.text[i];
+            let exponent_sign = matches!(c, b'+' | b'-') && matches!(self.at(i - 1), b'e' | b'E');
+            if c.is_ascii_alphanumeric() || c == b'.' || c == b'_' || exponent_sign {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        i
+    }
+
+    fn word_end(&self, mut i: usize) -> usize {
+        while i < self.text.len() {
+            let c = self.text[i];
+            if c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || c >= 0x80 {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        i
+    }
+
+    fn json(&mut self) {
+        let mut i = 0;
+        while i < self.text.len() && !self.full() {
+            let c = self.text[i];
+            match c {
+                b'"' => {
+                    let end = self.string_end(i);
+                    let mut next = end;
+                    while self.at(next).is_ascii_whitespace() {
+                        next += 1;
+                    }
+                    let kind = if self.at(next) == b':' {
+                        TokenKind::Key
+                    } else {
+                        TokenKind::String
+                    };
+                    self.push(kind, i, end);
+                    i = end;
+                }
+                b'{' | b'}' | b'[' | b']' | b':' | b',' => {
+                    self.push(TokenKind::Punctuation, i, i + 1);
+                    i += 1;
+                }
+                b'-' | b'0'..=b'9' => {
+                    let end = self.number_end(i + 1);
+                    self.push(TokenKind::Number, i, end);
+                    i = end;
+                }
+                c if c.is_ascii_alphabetic() => {
+                    let end = self.word_end(i);
+                    self.push(TokenKind::Literal, i, end);
+                    i = end;
+                }
+                _ => i += 1,
+            }
+        }
+    }
+
+    fn javascript(&mut self, start: usize, end: usize) {
+        const KEYWORDS: &str =
+            "async await break case catch class const continue debugger default delete do \
+             else export extends finally for from function if import in instanceof let new \
+             of return static super switch this throw try typeof var void while with yield";
+        const LITERALS: &[&str] = &["true", "false", "null", "undefined", "NaN", "Infinity"];
+
+        // Whether a `/` here starts a regex rather than dividing
+        let mut regex_allowed = true;
+        let mut i = start;
+        while i < end && !self.full() {
+            let c = self.text[i];
+            match c {
+                _ if c.is_ascii_whitespace() => {
+                    i += 1;
+                    continue;
+                }
+                b'/' if self.at(i + 1) == b'/' => {
+                    let line_end = self.find(i, b"\n").min(end);
+                    self.push(TokenKind::Comment, i, line_end);
+                    i = line_end;
+                    continue;
+                }
+                b'/' if self.at(i + 1) == b'*' => {
+                    let close = (self.find(i + 2, b"*/") + 2).min(end);
+                    self.push(TokenKind::Comment, i, close);
+                    i = close;
+                    continue;
+                }
+                b'/' if regex_allowed => {
+                    let mut j = i + 1;
+                    let mut class = false;
+                    while j < end && self.text[j] != b'\n' {
+                        match self.text[j] {
+                            b'\\' => j += 1,
+                            b'[' => class = true,
+                            b']' => class = false,
+                            b'/' if !class => break,
+                            _ => {}
+                        }
+                        j += 1;
+                    }
+                    let close = self.word_end((j + 1).min(end)).min(end);
+                    self.push(TokenKind::Regex, i, close);
+                    i = close;
+                    regex_allowed = false;
+                }
+                b'"' | b'\'' | b'`' => {
+                    let close = self.string_end(i).min(end);
+                    self.push(TokenKind::String, i, close);
+                    i = close;
+                    regex_allowed = false;
+                }
+                b'0'..=b'9' => {
+                    let close = self.number_end(i).min(end);
+                    self.push(TokenKind::Number, i, close);
+                    i = close;
+                    regex_allowed = false;
+                }
+                b'.' if self.at(i + 1).is_ascii_digit() => {
+                    let close = self.number_end(i + 1).min(end);
+                    self.push(TokenKind::Number, i, close);
+                    i = close;
+                    regex_allowed = false;
+                }
+                c if c.is_ascii_alphabetic() || c == b'_' || c == b'$' || c >= 0x80 => {
+                    let close = self.word_end(i).min(end);
+                    let word = std::str::from_utf8(&self.text[i..close]).unwrap_or_default();
+                    if KEYWORDS
+                        .split_ascii_whitespace()
+                        .any(|keyword| keyword == word)
+                    {
+                        self.push(TokenKind::Keyword, i, close);
+                        regex_allowed = !matches!(word, "this" | "super");
+                    } else {
+                        if LITERALS.contains(&word) {
+                            self.push(TokenKind::Literal, i, close);
+                        }
+                        regex_allowed = false;
+                    }
+                    i = close;
+                }
+                _ => {
+                    self.push(TokenKind::Punctuation, i, i + 1);
+                    regex_allowed = !matches!(c, b')' | b']' | b'}');
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    fn markup(&mut self, html: bool) {
+        let mut i = 0;
+        while i < self.text.len() && !self.full() {
+            if self.text[i] != b'<' {
+                i = self.find(i, b"<");
+                continue;
+            }
+            if self.starts_with(i, b"<!--") {
+                let close = (self.find(i + 4, b"-->") + 3).min(self.text.len());
+                self.push(TokenKind::Comment, i, close);
+                i = close;
+            } else if self.starts_with(i, b"<![CDATA[") {
+                let close = self.find(i + 9, b"]]>");
+                self.push(TokenKind::Meta, i, i + 9);
+                self.push(TokenKind::String, i + 9, close);
+                let end = (close + 3).min(self.text.len());
+                self.push(TokenKind::Meta, close, end);
+                i = end;
+            } else if self.starts_with(i, b"<!") || self.starts_with(i, b"<?") {
+                let close = (self.find(i, b">") + 1).min(self.text.len());
+                self.push(TokenKind::Meta, i, close);
+                i = close;
+            } else {
+                i = self.tag(i, html);
+            }
+        }
+    }
+
+    // Lexes the tag opening at `start` and returns where lexing carries on
+    fn tag(&mut self, start: usize, html: bool) -> usize {
+        let closing = self.at(start + 1) == b'/';
+        let name_start = start + if closing { 2 } else { 1 };
+        self.push(TokenKind::Punctuation, start, name_start);
+        let name_end = self.name_end(name_start);
+        if name_end == name_start {
+            // A stray `<` in text
+            return name_start;
+        }
+        self.push(TokenKind::Tag, name_start, name_end);
+        let name = String::from_utf8_lossy(&self.text[name_start..name_end]).to_lowercase();
+
+        let mut i = name_end;
+        // After `=`, an unquoted word is the attribute's value
+        let mut value = false;
+        while i < self.text.len() {
+            let c = self.text[i];
+            match c {
+                b'>' => {
+                    self.push(TokenKind::Punctuation, i, i + 1);
+                    i += 1;
+                    break;
+                }
+                b'/' if self.at(i + 1) == b'>' => {
+                    self.push(TokenKind::Punctuation, i, i + 2);
+                    return i + 2;
+                }
+                b'"' | b'\'' => {
+                    let quote = c;
+                    let close = (i + 1..self.text.len())
+                        .find(|&j| self.text[j] == quote)
+                        .map_or(self.text.len(), |j| j + 1);
+                    self.push(TokenKind::String, i, close);
+                    i = close;
+                }
+                b'=' => {
+                    self.push(TokenKind::Punctuation, i, i + 1);
+                    i += 1;
+                    value = true;
+                    continue;
+                }
+                _ if c.is_ascii_whitespace() => {
+                    i += 1;
+                    continue;
+                }
+                _ => {
+                    let end = self.name_end(i).max(i + 1);
+                    let kind = if value {
+                        TokenKind::String
+                    } else {
+                        TokenKind::Attribute
+                    };
+                    self.push(kind, i, end);
+                    i = end;
+                }
+            }
+            value = false;
+        }
+
+        // Script contents are JavaScript until their closing tag; styles stay plain
+        if html && !closing && (name == "script" || name == "style") {
+            let close = self.closing_tag(i, &name);
+            if name == "script" {
+                self.javascript(i, close);
+            }
+            return close;
+        }
+        i
+    }
+
+    // Where `</name` next starts, matching case-insensitively
+    fn closing_tag(&self, from: usize, name: &str) -> usize {
+        let mut i = from;
+        loop {
+            i = self.find(i, b"</");
+            let after = i + 2;
+            let matched = self
+                .text
+                .get(after..after + name.len())
+                .is_some_and(|t| t.eq_ignore_ascii_case(name.as_bytes()));
+            if matched || i >= self.text.len() {
+                return i;
+            }
+            i = after;
+        }
+    }
+
+    fn name_end(&self, mut i: usize) -> usize {
+        while i < self.text.len() {
+            let c = self.text[i];
+            if c.is_ascii_whitespace() || matches!(c, b'>' | b'/' | b'=' | b'"' | b'\'' | b'<') {
+                break;
+            }
+            i += 1;
+        }
+        i
+    }
+
+    // Byte ranges to UTF-16 offsets in one pass; tokens are in text order
+    fn finish(self, text: &str) -> Vec<Token> {
+        let mut tokens = Vec::with_capacity(self.tokens.len());
+        let mut chars = text.char_indices().peekable();
+        let mut units = 0u32;
+        let mut advance = |to: usize| {
+            while let Some(&(i, c)) = chars.peek() {
+                if i >= to {
+                    break;
+                }
+                units += c.len_utf16() as u32;
+                chars.next();
+            }
+            units
+        };
+        for (kind, start, end) in self.tokens {
+            let start = advance(start);
+            let end = advance(end);
+            tokens.push(Token(kind, start, end - start));
+        }
+        tokens
+    }
+}
+
+pub fn tokenize(text: &str, language: SyntaxLanguage) -> BodyTokens {
+    let mut lexer = Lexer::new(text);
+    match language {
+        SyntaxLanguage::Json => lexer.json(),
+        SyntaxLanguage::Xml => lexer.markup(false),
+        SyntaxLanguage::Html => lexer.markup(true),
+        SyntaxLanguage::JavaScript => lexer.javascript(0, text.len()),
+        SyntaxLanguage::Plain => {}
+    }
+    let truncated = lexer.full();
+    BodyTokens {
+        language,
+        tokens: lexer.finish(text),
+        truncated,
+    }
+}
+
+// Tokens for the full body. The language follows the body's content class
+// unless `language` picks one.
+#[tauri::command]
+pub async fn tokenize_body(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    id: String,
+    part: Option<BodyPart>,
+    language: Option<SyntaxLanguage>,
+) -> Result<BodyTokens, String> {
+    let request = limits::find_request(&store_limits, &store, &id)?;
+    let body = blobs::body_of(&request, part)?.unwrap_or_default();
+    let language = match language {
+        Some(language) => language,
+        None => {
+            let class = match part.unwrap_or_default() {
+                BodyPart::Request => classify::classify_body(&request.headers, Some(&body)),
+                BodyPart::Response => request
+                    .response
+                    .as_ref()
+                    .and_then(|response| response.content_class)
+                    .unwrap_or(ContentClass::Empty),
+            };
+            language_for(class, &body)
+        }
+    };
+    Ok(tokenize(&body, language))
+}
@@ -0,0 +1,59 @@
+// Tags on a single request, from triage. They travel with the request into
+// saved sessions and exports; `bulk::tag_many` edits many requests at once.
+use crate::bulk::BulkResult;
+use crate::events::{self, AppEvent};
+use crate::limits::{self, StoreLimitState};
+use crate::provenance::{self, ProvenanceKind};
+use crate::storage::{self, StorageState};
+use crate::{NetworkRequest, RequestStore};
+
+// Replaces the request's tags; duplicates and blank tags are dropped
+#[tauri::command]
+pub async fn tag_request(
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    app_handle: tauri::AppHandle,
+    id: String,
+    tags: Vec<String>,
+) -> Result<NetworkRequest, String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() && !cleaned.contains(&tag) {
+            cleaned.push(tag);
+        }
+    }
+
+    let tagged = {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        let request = requests
+            .iter_mut()
+            .find(|r| r.has_id(&id))
+            .ok_or_else(|| format!("Request not found: {}", id))?;
+        let detail = cleaned.join(" ");
+        request.tags = cleaned;
+        provenance::record(request, ProvenanceKind::Retagged, Some(detail));
+        request.clone()
+    };
+    storage::put(&storage, &tagged);
+
+    let result = BulkResult {
+        ids: vec![tagged.id.clone()],
+    };
+    events::emit(&app_handle, AppEvent::RequestsUpdated(&result))?;
+    Ok(tagged)
+}
+
+#[tauri::command]
+pub async fn get_requests_by_tag(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    tag: String,
+) -> Result<Vec<NetworkRequest>, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    Ok(requests
+        .iter()
+        .filter(|r| r.tags.contains(&tag))
+        .cloned()
+        .collect())
+}
@@ -0,0 +1,244 @@
+// Opt-in counters about the observer itself, to guide its development: which
+// commands are used, roughly how much traffic a run ingests, and what panicked.
+// Never anything about the captured traffic. Nothing is sent anywhere; the
+// report and crash file stay in the data directory for the user to read and
+// attach to an issue if they choose. NETWORK_OBSERVER_NO_TELEMETRY switches it
+// off for good, whatever the setting says.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+pub const OFF_SWITCH_VAR: &str = "NETWORK_OBSERVER_NO_TELEMETRY";
+const REPORT_FILE: &str = "telemetry.json";
+const CRASH_FILE: &str = "crash-report.txt";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct TelemetryReport {
+    pub app_version: String,
+    pub os: String,
+    // Command name -> times invoked, across runs
+    #[ts(type = "Record<string, number>")]
+    pub feature_usage: BTreeMap<String, u64>,
+    // Requests ingested per run, bucketed (e.g. `100-999`) -> number of runs
+    #[ts(type = "Record<string, number>")]
+    pub ingest_volume: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct TelemetryStatus {
+    pub enabled: bool,
+    // Set by NETWORK_OBSERVER_NO_TELEMETRY; telemetry can't be enabled
+    pub switched_off: bool,
+    pub report_path: Option<String>,
+    // Only there once something panicked with telemetry on
+    pub crash_report_path: Option<String>,
+    pub report: Option<TelemetryReport>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReportFile {
+    enabled: bool,
+    report: TelemetryReport,
+}
+
+#[derive(Default)]
+pub struct Telemetry {
+    enabled: bool,
+    switched_off: bool,
+    dir: Option<PathBuf>,
+    // Earlier runs, plus this run's feature usage
+    report: TelemetryReport,
+    ingested: u64,
+}
+
+pub type TelemetryState = Arc<Mutex<Telemetry>>;
+
+fn volume_bucket(count: u64) -> &'static str {
+    match count {
+        0 => "0",
+        1..=99 => "1-99",
+        100..=999 => "100-999",
+        1_000..=9_999 => "1k-10k",
+        10_000..=99_999 => "10k-100k",
+        _ => "100k+",
+    }
+}
+
+impl Telemetry {
+    fn recording(&self) -> bool {
+        self.enabled && !self.switched_off
+    }
+
+    // What the report file holds: this run counts once, however often it is written
+    fn snapshot(&self) -> TelemetryReport {
+        let mut report = self.report.clone();
+        report.app_version = env!("CARGO_PKG_VERSION").to_string();
+        report.os = std::env::consts::OS.to_string();
+        *report
+            .ingest_volume
+            .entry(volume_bucket(self.ingested).to_string())
+            .or_default() += 1;
+        report
+    }
+
+    fn path(&self, file: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(file))
+    }
+
+    fn write(&self) -> Result<(), String> {
+        let Some(path) = self.path(REPORT_FILE) else {
+            return Ok(());
+        };
+        let file = ReportFile {
+            enabled: self.enabled,
+            report: self.snapshot(),
+        };
+        let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    // Forgets everything collected, on disk too
+    fn clear(&mut self) {
+        self.report = TelemetryReport::default();
+        self.ingested = 0;
+        for file in [REPORT_FILE, CRASH_FILE] {
+            if let Some(path) = self.path(file) {
+                std::fs::remove_file(path).ok();
+            }
+        }
+    }
+
+    fn status(&self) -> TelemetryStatus {
+        let existing = |file| {
+            self.path(file)
+                .filter(|path| path.exists())
+                .map(|path| path.display().to_string())
+        };
+        TelemetryStatus {
+            enabled: self.recording(),
+            switched_off: self.switched_off,
+            report_path: existing(REPORT_FILE),
+            crash_report_path: existing(CRASH_FILE),
+            report: self.recording().then(|| self.snapshot()),
+        }
+    }
+}
+
+// Picks up the setting and counts from earlier runs, and starts writing a
+// crash report on panics while telemetry is on
+pub(crate) fn open(state: &TelemetryState, dir: &Path) {
+    let mut telemetry = state.lock().unwrap();
+    telemetry.dir = Some(dir.to_path_buf());
+    telemetry.switched_off = std::env::var_os(OFF_SWITCH_VAR).is_some();
+    if telemetry.switched_off {
+        // Whatever an earlier run collected goes too
+        telemetry.clear();
+        return;
+    }
+    let saved = telemetry
+        .path(REPORT_FILE)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str::<ReportFile>(&json).ok());
+    if let Some(saved) = saved {
+        telemetry.enabled = saved.enabled;
+        telemetry.report = saved.report;
+    }
+
+    let state = state.clone();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // Never block a panicking thread on the lock
+        if let Ok(telemetry) = state.try_lock() {
+            if let (true, Some(path)) = (telemetry.recording(), telemetry.path(CRASH_FILE)) {
+                let report = format!(
+                    "Network Observer {} on {}\n{}\n",
+                    env!("CARGO_PKG_VERSION"),
+                    std::env::consts::OS,
+                    info
+                );
+                std::fs::write(path, report).ok();
+                telemetry.write().ok();
+            }
+        }
+        previous(info);
+    }));
+}
+
+pub(crate) fn record_command(state: &TelemetryState, command: &str) {
+    if let Ok(mut telemetry) = state.lock() {
+        if telemetry.recording() {
+            *telemetry
+                .report
+                .feature_usage
+                .entry(command.to_string())
+                .or_default() += 1;
+        }
+    }
+}
+
+pub(crate) fn record_ingest(state: &TelemetryState, count: usize) {
+    if let Ok(mut telemetry) = state.lock() {
+        if telemetry.recording() {
+            telemetry.ingested += count as u64;
+        }
+    }
+}
+
+// On exit, so the report has this run in it
+pub(crate) fn flush(state: &TelemetryState) {
+    if let Ok(telemetry) = state.lock() {
+        if telemetry.recording() {
+            if let Err(e) = telemetry.write() {
+                println!("Failed to write telemetry report: {}", e);
+            }
+        }
+    }
+}
+
+// Off by default. Turning it off deletes what was collected.
+#[tauri::command]
+pub async fn set_telemetry_enabled(
+    telemetry: tauri::State<'_, TelemetryState>,
+    enabled: bool,
+) -> Result<TelemetryStatus, String> {
+    let mut telemetry = telemetry.lock().map_err(|e| e.to_string())?;
+    if enabled && telemetry.switched_off {
+        return Err(format!("Telemetry is switched off by {}", OFF_SWITCH_VAR));
+    }
+    telemetry.enabled = enabled;
+    if enabled {
+        telemetry.write()?;
+    } else {
+        telemetry.clear();
+    }
+    Ok(telemetry.status())
+}
+
+// Everything collected so far, exactly as it would be shared
+#[tauri::command]
+pub async fn get_telemetry_status(
+    telemetry: tauri::State<'_, TelemetryState>,
+) -> Result<TelemetryStatus, String> {
+    let telemetry = telemetry.lock().map_err(|e| e.to_string())?;
+    if telemetry.recording() {
+        telemetry.write()?;
+    }
+    Ok(telemetry.status())
+}
+
+#[tauri::command]
+pub async fn clear_telemetry(
+    telemetry: tauri::State<'_, TelemetryState>,
+) -> Result<TelemetryStatus, String> {
+    let mut telemetry = telemetry.lock().map_err(|e| e.to_string())?;
+    telemetry.clear();
+    if telemetry.recording() {
+        telemetry.write()?;
+    }
+    Ok(telemetry.status())
+}
@@ -0,0 +1,40 @@
+// Requests for unit tests to start from
+use serde_json::json;
+
+use crate::NetworkRequest;
+
+// A completed GET answered with `status` and no bodies
+pub(crate) fn request(id: &str, url: &str, status: u16) -> NetworkRequest {
+    serde_json::from_value(json!({
+        "id": id,
+        "url": url,
+        "method": "GET",
+        "headers": [],
+        "body": null,
+        "timestamp": 1_000,
+        "duration": 10,
+        "state": "complete",
+        "response": {
+            "status": status,
+            "status_text": "",
+            "headers": [],
+            "body": null
+        }
+    }))
+    .unwrap()
+}
+
+// Still waiting for its response
+pub(crate) fn pending(id: &str, url: &str) -> NetworkRequest {
+    serde_json::from_value(json!({
+        "id": id,
+        "url": url,
+        "method": "GET",
+        "headers": [],
+        "body": null,
+        "timestamp": 1_000,
+        "duration": null,
+        "response": null
+    }))
+    .unwrap()
+}
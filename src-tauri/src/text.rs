@@ -0,0 +1,26 @@
+// Byte-capped slices of user-supplied text. Plain `&s[..n]` panics when `n`
+// lands inside a multi-byte character, which any non-ASCII body can trigger.
+
+// Longest prefix of at most `max_bytes` bytes
+pub fn prefix(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+// Longest suffix of at most `max_bytes` bytes
+pub fn suffix(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut start = text.len() - max_bytes;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    &text[start..]
+}
@@ -0,0 +1,252 @@
+// What `clear_requests` removed, kept for `UNDO_WINDOW` so a clear hit by
+// mistake in the middle of a repro can be taken back with `undo_clear`. A
+// second clear within the window adds to the buffer and restarts it, so undo
+// brings back everything cleared since. Spilled bodies of buried requests are
+// left on disk until they expire.
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+use crate::blobs::{self, SpilledBody};
+use crate::bulk::BulkResult;
+use crate::events::{self, AppEvent};
+use crate::limits::{self, StoreLimitState};
+use crate::overflow;
+use crate::session;
+use crate::storage::{self, StorageState};
+use crate::{NetworkRequest, RequestStore};
+
+pub const UNDO_WINDOW: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Default)]
+pub struct Tombstones {
+    requests: Vec<NetworkRequest>,
+    expires: Option<Instant>,
+}
+
+pub type TombstoneState = Arc<Mutex<Tombstones>>;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct ClearResult {
+    pub cleared: usize,
+    // How long `undo_clear` can bring them back
+    #[ts(type = "number")]
+    pub undo_secs: u64,
+}
+
+impl Tombstones {
+    // Spill files the buried requests still need
+    pub(crate) fn spilled_bodies(&self) -> impl Iterator<Item = &SpilledBody> {
+        self.requests.iter().flat_map(blobs::spilled_bodies)
+    }
+}
+
+// Drops the buffer once its window has passed
+fn expire(state: &TombstoneState) {
+    let mut tombstones = state.lock().unwrap();
+    if tombstones.expires.is_some_and(|at| at <= Instant::now()) {
+        *tombstones = Tombstones::default();
+    }
+}
+
+fn bury(state: &TombstoneState, cleared: Vec<NetworkRequest>) {
+    let mut tombstones = state.lock().unwrap();
+    if tombstones.expires.is_some_and(|at| at <= Instant::now()) {
+        tombstones.requests.clear();
+    }
+    tombstones.requests.extend(cleared);
+    tombstones.expires = Some(Instant::now() + UNDO_WINDOW);
+}
+
+// Removes every request that isn't pinned, overflow on disk included, keeping
+// them for `undo_clear`
+pub fn clear(
+    state: &TombstoneState,
+    store: &RequestStore,
+    storage: &StorageState,
+    store_limits: &StoreLimitState,
+) -> Result<ClearResult, String> {
+    let overflow = limits::overflow(store_limits);
+    let (count, remaining) = {
+        let mut requests = store.lock().map_err(|e| e.to_string())?;
+        let (kept, mut cleared): (Vec<_>, Vec<_>) = std::mem::take(&mut *requests)
+            .into_iter()
+            .partition(|r| r.pinned);
+        *requests = kept;
+        cleared.splice(0..0, overflow::take(&overflow));
+        let count = cleared.len();
+        // Before the store is unlocked, so the body sweeper always sees them
+        bury(state, cleared);
+        (count, requests.clone())
+    };
+    storage::replace_all(storage, &remaining);
+
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(UNDO_WINDOW).await;
+        expire(&state);
+    });
+    Ok(ClearResult {
+        cleared: count,
+        undo_secs: UNDO_WINDOW.as_secs(),
+    })
+}
+
+// Puts the buried requests back in timestamp order, alongside anything
+// captured since the clear, and returns their ids
+pub fn undo(
+    state: &TombstoneState,
+    store: &RequestStore,
+    storage: &StorageState,
+    store_limits: &StoreLimitState,
+) -> Result<(BulkResult, Vec<String>), String> {
+    expire(state);
+    // The store stays locked from taking them out to putting them back, so
+    // the body sweeper always sees them
+    let mut requests = store.lock().map_err(|e| e.to_string())?;
+    let buried = std::mem::take(&mut state.lock().map_err(|e| e.to_string())?.requests);
+    if buried.is_empty() {
+        return Err(format!(
+            "Nothing to undo; cleared requests are kept for {}s",
+            UNDO_WINDOW.as_secs()
+        ));
+    }
+    let ids: Vec<String> = buried.iter().map(|r| r.id.clone()).collect();
+    session::merge(&mut requests, buried);
+    let evicted = limits::enforce(store_limits, &mut requests);
+    let current = requests.clone();
+    drop(requests);
+    storage::replace_all(storage, &current);
+    let gone: HashSet<&String> = evicted.iter().collect();
+    let restored = ids.into_iter().filter(|id| !gone.contains(id)).collect();
+    Ok((BulkResult { ids: restored }, evicted))
+}
+
+#[tauri::command]
+pub async fn undo_clear(
+    state: tauri::State<'_, TombstoneState>,
+    store: tauri::State<'_, RequestStore>,
+    storage: tauri::State<'_, StorageState>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    app_handle: tauri::AppHandle,
+) -> Result<BulkResult, String> {
+    let (restored, evicted) = undo(&state, &store, &storage, &store_limits)?;
+    if !evicted.is_empty() {
+        events::emit(
+            &app_handle,
+            AppEvent::RequestsDeleted(&BulkResult { ids: evicted }),
+        )?;
+    }
+    events::emit(&app_handle, AppEvent::RequestsRestored(&restored))?;
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::request;
+
+    struct Capture {
+        tombstones: TombstoneState,
+        store: RequestStore,
+        storage: StorageState,
+        store_limits: StoreLimitState,
+    }
+
+    impl Capture {
+        fn new() -> Self {
+            Capture {
+                tombstones: TombstoneState::default(),
+                store: RequestStore::default(),
+                storage: StorageState::default(),
+                store_limits: StoreLimitState::default(),
+            }
+        }
+
+        fn capture(&self, id: &str, timestamp: u64) {
+            let mut captured = request(id, "https://api.example.com/", 200);
+            captured.timestamp = timestamp;
+            self.store.lock().unwrap().push(captured);
+        }
+
+        // On a runtime, which `clear` starts its expiry timer on
+        fn clear(&self) -> usize {
+            clear(
+                &self.tombstones,
+                &self.store,
+                &self.storage,
+                &self.store_limits,
+            )
+            .unwrap()
+            .cleared
+        }
+
+        fn undo(&self) -> Result<Vec<String>, String> {
+            undo(
+                &self.tombstones,
+                &self.store,
+                &self.storage,
+                &self.store_limits,
+            )
+            .map(|(restored, _)| restored.ids)
+        }
+
+        fn ids(&self) -> Vec<String> {
+            self.store
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|r| r.id.clone())
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn undoes_every_clear_within_the_window() {
+        let capture = Capture::new();
+        capture.capture("a", 1);
+        capture.capture("b", 3);
+        assert_eq!(capture.clear(), 2);
+        capture.capture("c", 5);
+        assert_eq!(capture.clear(), 1);
+        // Captured after both clears
+        capture.capture("d", 2);
+
+        let mut restored = capture.undo().unwrap();
+        restored.sort();
+        assert_eq!(restored, ["a", "b", "c"]);
+        assert_eq!(capture.ids(), ["a", "d", "b", "c"]);
+        assert!(capture.undo().unwrap_err().starts_with("Nothing to undo"));
+    }
+
+    #[tokio::test]
+    async fn keeps_pinned_requests_through_a_clear() {
+        let capture = Capture::new();
+        capture.capture("a", 1);
+        capture.capture("b", 2);
+        capture.store.lock().unwrap()[1].pinned = true;
+        assert_eq!(capture.clear(), 1);
+        assert_eq!(capture.ids(), ["b"]);
+    }
+
+    #[tokio::test]
+    async fn forgets_clears_once_the_window_has_passed() {
+        let capture = Capture::new();
+        capture.capture("a", 1);
+        capture.clear();
+        capture.tombstones.lock().unwrap().expires = Some(Instant::now());
+        capture.capture("b", 2);
+        capture.clear();
+        // The first clear's requests went when the second one started a new window
+        assert_eq!(capture.undo().unwrap(), ["b"]);
+
+        capture.capture("c", 3);
+        capture.clear();
+        capture.tombstones.lock().unwrap().expires = Some(Instant::now());
+        assert!(capture.undo().is_err());
+        assert!(capture.ids().is_empty());
+    }
+}
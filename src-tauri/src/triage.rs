@@ -0,0 +1,311 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock, Mutex};
+use ts_rs::TS;
+
+use crate::endpoint::endpoint_key;
+use crate::lifecycle::RequestState;
+use crate::limits::{self, StoreLimitState};
+use crate::preflight::PreflightPairs;
+use crate::stats::percentile;
+use crate::summaries::{self, RequestSummary};
+use crate::{NetworkRequest, RequestStore};
+
+const MAX_SCORE: u32 = 100;
+const DEFAULT_QUEUE_SIZE: usize = 50;
+// An endpoint needs this many timed calls before "slower than usual" means anything
+const MIN_BASELINE_SAMPLES: usize = 5;
+// Without a baseline only genuinely slow calls count
+const SLOW_WITHOUT_BASELINE_MS: u64 = 5_000;
+// Failed calls to the same endpoint this soon before a request make it a retry
+const RETRY_WINDOW_MS: u64 = 10_000;
+const LARGE_RESPONSE_BYTES: usize = 1024 * 1024;
+
+// Credentials that should never travel in a URL or body where they get logged
+static SECRET_PATTERNS: LazyLock<Vec<(&'static str, Regex)>> = LazyLock::new(|| {
+    [
+        ("AWS access key", r"\bAKIA[0-9A-Z]{16}\b"),
+        ("private key", r"-----BEGIN [A-Z ]*PRIVATE KEY-----"),
+        ("GitHub token", r"\bgh[pousr]_[A-Za-z0-9]{36,}\b"),
+        ("Stripe secret key", r"\bsk_live_[A-Za-z0-9]{16,}\b"),
+        ("Slack token", r"\bxox[abprs]-[A-Za-z0-9-]{10,}\b"),
+        (
+            "credential in query string",
+            r"[?&](password|passwd|secret|api_key|apikey|access_token)=[^&#]+",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, pattern)| (name, Regex::new(pattern).unwrap()))
+    .collect()
+});
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum SeverityFactor {
+    Status,
+    Duration,
+    Size,
+    Retries,
+    Secrets,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct SeverityReason {
+    pub factor: SeverityFactor,
+    pub points: u32,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct TriageItem {
+    pub request: RequestSummary,
+    // 0-100, the capped sum of `reasons`
+    pub score: u32,
+    pub reasons: Vec<SeverityReason>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct TriageQueue {
+    pub items: Vec<TriageItem>,
+    // Unreviewed requests scoring above zero, including those past `limit`
+    pub remaining: usize,
+    pub reviewed: usize,
+}
+
+// Ids of requests the user has already looked at
+pub type TriageState = Arc<Mutex<HashSet<String>>>;
+
+// What scoring one request needs to know about the rest of the capture
+struct Context {
+    // Endpoint -> median duration, for endpoints with enough samples
+    baselines: HashMap<String, u64>,
+    // Endpoint -> timestamps of failed calls
+    failures: HashMap<String, Vec<u64>>,
+}
+
+fn is_failure(request: &NetworkRequest) -> bool {
+    matches!(request.state, RequestState::Failed | RequestState::Aborted)
+        || request
+            .response
+            .as_ref()
+            .is_some_and(|r| r.status == 0 || r.status == 429 || r.status >= 500)
+}
+
+impl Context {
+    fn build(requests: &[NetworkRequest]) -> Self {
+        let mut durations: HashMap<String, Vec<u64>> = HashMap::new();
+        let mut failures: HashMap<String, Vec<u64>> = HashMap::new();
+        for request in requests {
+            let endpoint = endpoint_key(request);
+            if is_failure(request) {
+                failures
+                    .entry(endpoint.clone())
+                    .or_default()
+                    .push(request.timestamp);
+            }
+            if let Some(duration) = request.duration {
+                durations.entry(endpoint).or_default().push(duration);
+            }
+        }
+
+        let baselines = durations
+            .into_iter()
+            .filter(|(_, samples)| samples.len() >= MIN_BASELINE_SAMPLES)
+            .filter_map(|(endpoint, mut samples)| {
+                samples.sort_unstable();
+                Some((endpoint, percentile(&samples, 50.0)?))
+            })
+            .collect();
+        Context {
+            baselines,
+            failures,
+        }
+    }
+}
+
+fn status_reason(request: &NetworkRequest) -> Option<SeverityReason> {
+    let (points, detail) = match (request.state, request.response.as_ref().map(|r| r.status)) {
+        (RequestState::Aborted, _) => (15, "aborted".to_string()),
+        (RequestState::Failed, _) | (_, Some(0)) => (35, "failed without a response".to_string()),
+        (_, Some(status)) if status >= 500 => (40, format!("server error {}", status)),
+        (_, Some(429)) => (30, "rate limited (429)".to_string()),
+        (_, Some(status @ (401 | 403))) => (25, format!("rejected ({})", status)),
+        (_, Some(status)) if status >= 400 => (20, format!("client error {}", status)),
+        _ => return None,
+    };
+    Some(SeverityReason {
+        factor: SeverityFactor::Status,
+        points,
+        detail,
+    })
+}
+
+fn duration_reason(request: &NetworkRequest, context: &Context) -> Option<SeverityReason> {
+    let duration = request.duration?;
+    let (points, detail) = match context.baselines.get(&endpoint_key(request)) {
+        Some(&baseline) => {
+            let ratio = duration as f64 / baseline.max(1) as f64;
+            if ratio < 3.0 || duration < 500 {
+                return None;
+            }
+            let points = if ratio >= 10.0 { 25 } else { 15 };
+            (
+                points,
+                format!("{}ms, {:.1}x the {}ms median", duration, ratio, baseline),
+            )
+        }
+        None if duration >= SLOW_WITHOUT_BASELINE_MS => (15, format!("{}ms", duration)),
+        None => return None,
+    };
+    Some(SeverityReason {
+        factor: SeverityFactor::Duration,
+        points,
+        detail,
+    })
+}
+
+fn size_reason(request: &NetworkRequest) -> Option<SeverityReason> {
+    let size = request.response.as_ref()?.body.as_ref()?.len();
+    if size < LARGE_RESPONSE_BYTES {
+        return None;
+    }
+    let points = if size >= 5 * LARGE_RESPONSE_BYTES {
+        20
+    } else {
+        10
+    };
+    Some(SeverityReason {
+        factor: SeverityFactor::Size,
+        points,
+        detail: format!(
+            "{:.1} MB response",
+            size as f64 / LARGE_RESPONSE_BYTES as f64
+        ),
+    })
+}
+
+fn retry_reason(request: &NetworkRequest, context: &Context) -> Option<SeverityReason> {
+    let failures = context.failures.get(&endpoint_key(request))?;
+    let window = request.timestamp.saturating_sub(RETRY_WINDOW_MS)..request.timestamp;
+    let retries = failures.iter().filter(|t| window.contains(t)).count();
+    if retries == 0 {
+        return None;
+    }
+    Some(SeverityReason {
+        factor: SeverityFactor::Retries,
+        points: (retries as u32 * 10).min(20),
+        detail: format!("{} failed attempts just before", retries),
+    })
+}
+
+fn secret_reason(request: &NetworkRequest) -> Option<SeverityReason> {
+    let haystacks = [
+        Some(request.url.as_str()),
+        request.body.as_deref(),
+        request.response.as_ref().and_then(|r| r.body.as_deref()),
+    ];
+    let found: Vec<&str> = SECRET_PATTERNS
+        .iter()
+        .filter(|(_, regex)| haystacks.iter().flatten().any(|text| regex.is_match(text)))
+        .map(|(name, _)| *name)
+        .collect();
+    if found.is_empty() {
+        return None;
+    }
+    Some(SeverityReason {
+        factor: SeverityFactor::Secrets,
+        points: 30,
+        detail: found.join(", "),
+    })
+}
+
+fn score(request: &NetworkRequest, context: &Context) -> (u32, Vec<SeverityReason>) {
+    let reasons: Vec<SeverityReason> = [
+        status_reason(request),
+        duration_reason(request, context),
+        size_reason(request),
+        retry_reason(request, context),
+        secret_reason(request),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let score = reasons.iter().map(|r| r.points).sum::<u32>().min(MAX_SCORE);
+    (score, reasons)
+}
+
+// Highest-scoring unreviewed requests first; ties go to the most recent
+#[tauri::command]
+pub async fn get_triage_queue(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    triage: tauri::State<'_, TriageState>,
+    limit: Option<usize>,
+    min_score: Option<u32>,
+) -> Result<TriageQueue, String> {
+    let reviewed = triage.lock().map_err(|e| e.to_string())?.clone();
+    let requests = limits::captured(&store_limits, &store)?;
+    let context = Context::build(&requests);
+    let min_score = min_score.unwrap_or(1).max(1);
+
+    let mut scored: Vec<(u32, Vec<SeverityReason>, &NetworkRequest)> = requests
+        .iter()
+        .filter(|r| !reviewed.contains(&r.id))
+        .map(|r| {
+            let (score, reasons) = score(r, &context);
+            (score, reasons, r)
+        })
+        .filter(|(score, _, _)| *score >= min_score)
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(b.2.timestamp.cmp(&a.2.timestamp)));
+
+    let remaining = scored.len();
+    let pairs = PreflightPairs::build(&requests);
+    let items = scored
+        .into_iter()
+        .take(limit.unwrap_or(DEFAULT_QUEUE_SIZE))
+        .map(|(score, reasons, request)| TriageItem {
+            request: summaries::summarize(request, &pairs),
+            score,
+            reasons,
+        })
+        .collect();
+    Ok(TriageQueue {
+        items,
+        remaining,
+        reviewed: reviewed.len(),
+    })
+}
+
+// Takes requests out of the queue; ids may be aliases. Returns how many were
+// newly marked.
+#[tauri::command]
+pub async fn mark_reviewed(
+    store: tauri::State<'_, RequestStore>,
+    store_limits: tauri::State<'_, StoreLimitState>,
+    triage: tauri::State<'_, TriageState>,
+    ids: Vec<String>,
+) -> Result<usize, String> {
+    let requests = limits::captured(&store_limits, &store)?;
+    let mut reviewed = triage.lock().map_err(|e| e.to_string())?;
+    let mut marked = 0;
+    for id in &ids {
+        if let Some(request) = requests.iter().find(|r| r.has_id(id)) {
+            if reviewed.insert(request.id.clone()) {
+                marked += 1;
+            }
+        }
+    }
+    Ok(marked)
+}
+
+#[tauri::command]
+pub async fn clear_reviewed(triage: tauri::State<'_, TriageState>) -> Result<(), String> {
+    triage.lock().map_err(|e| e.to_string())?.clear();
+    Ok(())
+}
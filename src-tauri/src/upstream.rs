@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use ts_rs::TS;
+
+use crate::data_dir;
+use crate::secrets;
+
+mod ntlm;
+mod relay;
+#[cfg(windows)]
+mod sspi;
+
+pub use relay::Relay;
+
+// How the observer proves itself to an upstream proxy. No proxy mode exists
+// yet, so this covers replay traffic only.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum ProxyAuth {
+    // `credentials_secret` sent with every request
+    #[default]
+    Basic,
+    // NTLMv2: as the logged-in user through SSPI on Windows, or anywhere with
+    // `credentials_secret`
+    Ntlm,
+    // Kerberos through SSPI on Windows; with `credentials_secret` the proxy gets
+    // NTLM tokens under the Negotiate scheme, which it accepts as a fallback
+    Negotiate,
+}
+
+impl ProxyAuth {
+    fn scheme(self) -> &'static str {
+        match self {
+            ProxyAuth::Basic => "Basic",
+            ProxyAuth::Ntlm => "NTLM",
+            ProxyAuth::Negotiate => "Negotiate",
+        }
+    }
+}
+
+// One side of a connection-based auth exchange: the token to send next, given
+// the proxy's last challenge (None to start)
+trait Handshake: Send {
+    fn step(&mut self, challenge: Option<&[u8]>) -> Result<Vec<u8>, String>;
+}
+
+// A corporate proxy the observer's own outgoing requests (replay) go through,
+// e.g. `{ "url": "http://proxy.corp:3128", "credentials_secret": "corp-proxy" }`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct UpstreamProxy {
+    pub url: String,
+    #[serde(default)]
+    pub auth: ProxyAuth,
+    // Keychain secret holding `user:password`; for NTLM the user may carry a
+    // domain, `CORP\jdoe:password` or `jdoe@corp.example:password`
+    #[serde(default)]
+    pub credentials_secret: Option<String>,
+    // Hosts reached directly, in NO_PROXY syntax (`localhost`, `.corp.internal`, `10.0.0.0/8`)
+    #[serde(default)]
+    pub bypass: Vec<String>,
+}
+
+// Proxies per project, persisted in `upstream-proxies.json`. Without a proxy for
+// the project in use, the system proxy environment (HTTPS_PROXY, ...) applies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct UpstreamSettings {
+    #[serde(default)]
+    pub active_project: Option<String>,
+    #[serde(default)]
+    pub projects: BTreeMap<String, UpstreamProxy>,
+}
+
+fn settings_path(app_handle: &tauri::AppHandle) -> PathBuf {
+    data_dir(app_handle).join("upstream-proxies.json")
+}
+
+fn read_settings(app_handle: &tauri::AppHandle) -> Result<UpstreamSettings, String> {
+    let path = settings_path(app_handle);
+    match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid proxy settings {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(UpstreamSettings::default()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+fn write_settings(
+    app_handle: &tauri::AppHandle,
+    settings: &UpstreamSettings,
+) -> Result<(), String> {
+    let path = settings_path(app_handle);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn credentials(name: &str) -> Result<(String, String), String> {
+    let credentials = secrets::read_secret(name)?;
+    let (user, password) = credentials
+        .split_once(':')
+        .ok_or_else(|| format!("Secret {} is not in user:password form", name))?;
+    Ok((user.to_string(), password.to_string()))
+}
+
+impl UpstreamProxy {
+    fn validate(&self) -> Result<reqwest::Url, String> {
+        let url = reqwest::Url::parse(&self.url)
+            .map_err(|e| format!("Invalid proxy URL {}: {}", self.url, e))?;
+        reqwest::Proxy::all(url.as_str())
+            .map_err(|e| format!("Invalid proxy URL {}: {}", self.url, e))?;
+        // The relay speaks plain HTTP to the proxy
+        if self.auth != ProxyAuth::Basic && url.scheme() != "http" {
+            return Err(format!(
+                "{} proxies must be http:// URLs",
+                self.auth.scheme()
+            ));
+        }
+        Ok(url)
+    }
+
+    fn with_bypass(&self, proxy: reqwest::Proxy) -> reqwest::Proxy {
+        if self.bypass.is_empty() {
+            return proxy;
+        }
+        proxy.no_proxy(reqwest::NoProxy::from_string(&self.bypass.join(",")))
+    }
+
+    fn to_reqwest(&self) -> Result<reqwest::Proxy, String> {
+        let mut proxy = reqwest::Proxy::all(&self.url)
+            .map_err(|e| format!("Invalid proxy URL {}: {}", self.url, e))?;
+        if let Some(name) = &self.credentials_secret {
+            let (user, password) = credentials(name)?;
+            proxy = proxy.basic_auth(&user, &password);
+        }
+        Ok(self.with_bypass(proxy))
+    }
+
+    // Starts a handshake per upstream connection: with the stored account when
+    // there is one, otherwise as the logged-in user
+    fn handshakes(&self, host: &str) -> Result<relay::NewHandshake, String> {
+        if let Some(name) = &self.credentials_secret {
+            let (account, password) = credentials(name)?;
+            return Ok(Arc::new(move || {
+                Ok(Box::new(ntlm::NtlmHandshake::new(&account, &password)) as Box<dyn Handshake>)
+            }));
+        }
+        self.system_handshakes(host)
+    }
+
+    #[cfg(windows)]
+    fn system_handshakes(&self, host: &str) -> Result<relay::NewHandshake, String> {
+        let package = self.auth.scheme();
+        let target = format!("HTTP/{}", host);
+        Ok(Arc::new(move || {
+            Ok(Box::new(sspi::SspiHandshake::new(package, &target)?) as Box<dyn Handshake>)
+        }))
+    }
+
+    #[cfg(not(windows))]
+    fn system_handshakes(&self, _host: &str) -> Result<relay::NewHandshake, String> {
+        Err(format!(
+            "{} as the logged-in user needs Windows; set credentials_secret to authenticate with NTLM",
+            self.auth.scheme()
+        ))
+    }
+}
+
+// The upstream proxy for `project`, or for the active project when None
+pub fn proxy_for(
+    app_handle: &tauri::AppHandle,
+    project: Option<&str>,
+) -> Result<Option<UpstreamProxy>, String> {
+    let settings = read_settings(app_handle)?;
+    let Some(project) = project.or(settings.active_project.as_deref()) else {
+        return Ok(None);
+    };
+    Ok(settings.projects.get(project).cloned())
+}
+
+// Routes `builder` through `proxy`. NTLM and Negotiate proxies are reached
+// through a local relay, which must outlive the client built from `builder`.
+pub async fn apply(
+    builder: reqwest::ClientBuilder,
+    proxy: Option<&UpstreamProxy>,
+) -> Result<(reqwest::ClientBuilder, Option<Relay>), String> {
+    let Some(proxy) = proxy else {
+        return Ok((builder, None));
+    };
+    if proxy.auth == ProxyAuth::Basic {
+        return Ok((builder.proxy(proxy.to_reqwest()?), None));
+    }
+
+    let url = proxy.validate()?;
+    let host = url.host_str().unwrap_or_default().to_string();
+    let address = format!("{}:{}", host, url.port_or_known_default().unwrap_or(80));
+    let relay = relay::start(address, proxy.auth.scheme(), proxy.handshakes(&host)?).await?;
+    let relayed = reqwest::Proxy::all(&relay.url).map_err(|e| e.to_string())?;
+    Ok((builder.proxy(proxy.with_bypass(relayed)), Some(relay)))
+}
+
+// Sets or, with `proxy: null`, removes the proxy for `project`
+#[tauri::command]
+pub async fn set_upstream_proxy(
+    app_handle: tauri::AppHandle,
+    project: String,
+    proxy: Option<UpstreamProxy>,
+) -> Result<UpstreamSettings, String> {
+    let mut settings = read_settings(&app_handle)?;
+    match proxy {
+        Some(proxy) => {
+            proxy.validate()?;
+            settings.projects.insert(project, proxy);
+        }
+        None => {
+            settings.projects.remove(&project);
+        }
+    }
+    write_settings(&app_handle, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn set_active_project(
+    app_handle: tauri::AppHandle,
+    project: Option<String>,
+) -> Result<UpstreamSettings, String> {
+    let mut settings = read_settings(&app_handle)?;
+    settings.active_project = project;
+    write_settings(&app_handle, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_upstream_settings(
+    app_handle: tauri::AppHandle,
+) -> Result<UpstreamSettings, String> {
+    read_settings(&app_handle)
+}
@@ -0,0 +1,403 @@
+// NTLMv2 client (MS-NLMP) with explicit credentials, for platforms without
+// SSPI or when the proxy account differs from the logged-in user. Messages
+// carry no MIC or version block, which proxies don't require.
+use hmac::{Hmac, Mac};
+use md4::{Digest, Md4};
+use md5::Md5;
+use uuid::Uuid;
+
+use super::Handshake;
+
+const SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+
+const NEGOTIATE_UNICODE: u32 = 0x0000_0001;
+const REQUEST_TARGET: u32 = 0x0000_0004;
+const NEGOTIATE_NTLM: u32 = 0x0000_0200;
+const NEGOTIATE_ALWAYS_SIGN: u32 = 0x0000_8000;
+const NEGOTIATE_EXTENDED_SESSIONSECURITY: u32 = 0x0008_0000;
+const NEGOTIATE_128: u32 = 0x2000_0000;
+const NEGOTIATE_56: u32 = 0x8000_0000;
+
+const NEGOTIATE_FLAGS: u32 = NEGOTIATE_UNICODE
+    | REQUEST_TARGET
+    | NEGOTIATE_NTLM
+    | NEGOTIATE_ALWAYS_SIGN
+    | NEGOTIATE_EXTENDED_SESSIONSECURITY
+    | NEGOTIATE_128
+    | NEGOTIATE_56;
+
+// AV pair ids in the challenge's target info
+const AV_EOL: u16 = 0;
+const AV_TIMESTAMP: u16 = 7;
+
+// Seconds between 1601-01-01 (FILETIME epoch) and 1970-01-01
+const FILETIME_UNIX_OFFSET_SECS: u64 = 11_644_473_600;
+
+fn utf16le(text: &str) -> Vec<u8> {
+    text.encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect()
+}
+
+fn hmac_md5(key: &[u8], parts: &[&[u8]]) -> [u8; 16] {
+    let mut mac = Hmac::<Md5>::new_from_slice(key).expect("HMAC takes any key length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+// NTOWFv2: HMAC-MD5 keyed by the NT hash over UPPER(user) + domain
+pub fn ntowf_v2(user: &str, domain: &str, password: &str) -> [u8; 16] {
+    let nt_hash = Md4::digest(utf16le(password));
+    let identity = format!("{}{}", user.to_uppercase(), domain);
+    hmac_md5(&nt_hash, &[&utf16le(&identity)])
+}
+
+// Splits `DOMAIN\user`, `user@domain` or plain `user`
+pub fn split_user(account: &str) -> (String, String) {
+    if let Some((domain, user)) = account.split_once('\\') {
+        return (user.to_string(), domain.to_string());
+    }
+    if let Some((user, domain)) = account.split_once('@') {
+        return (user.to_string(), domain.to_string());
+    }
+    (account.to_string(), String::new())
+}
+
+struct Challenge {
+    flags: u32,
+    server_challenge: [u8; 8],
+    target_info: Vec<u8>,
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+// Length/offset pair pointing into the message payload
+fn read_field(bytes: &[u8], at: usize) -> Option<&[u8]> {
+    let len = read_u16(bytes, at)? as usize;
+    let offset = read_u32(bytes, at + 4)? as usize;
+    bytes.get(offset..offset.checked_add(len)?)
+}
+
+fn parse_challenge(bytes: &[u8]) -> Result<Challenge, String> {
+    let invalid = || "Malformed NTLM challenge from proxy".to_string();
+    if bytes.get(..8) != Some(SIGNATURE) || read_u32(bytes, 8) != Some(2) {
+        return Err(invalid());
+    }
+    let flags = read_u32(bytes, 20).ok_or_else(invalid)?;
+    let server_challenge = bytes
+        .get(24..32)
+        .and_then(|c| c.try_into().ok())
+        .ok_or_else(invalid)?;
+    // Older servers end the message before the target info
+    let target_info = read_field(bytes, 40).unwrap_or_default().to_vec();
+    Ok(Challenge {
+        flags,
+        server_challenge,
+        target_info,
+    })
+}
+
+fn av_timestamp(target_info: &[u8]) -> Option<[u8; 8]> {
+    let mut at = 0;
+    while let (Some(id), Some(len)) = (read_u16(target_info, at), read_u16(target_info, at + 2)) {
+        let value = target_info.get(at + 4..at + 4 + len as usize)?;
+        match id {
+            AV_EOL => return None,
+            AV_TIMESTAMP => return value.try_into().ok(),
+            _ => at += 4 + len as usize,
+        }
+    }
+    None
+}
+
+fn filetime_now() -> [u8; 8] {
+    let since_unix = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let ticks = (since_unix.as_secs() + FILETIME_UNIX_OFFSET_SECS) * 10_000_000
+        + since_unix.subsec_nanos() as u64 / 100;
+    ticks.to_le_bytes()
+}
+
+// NTv2 and LMv2 responses for a challenge
+pub fn responses(
+    ntowf: &[u8; 16],
+    server_challenge: &[u8; 8],
+    client_challenge: &[u8; 8],
+    timestamp: &[u8; 8],
+    target_info: &[u8],
+    lm_zeroed: bool,
+) -> (Vec<u8>, Vec<u8>) {
+    let mut blob = vec![0x01, 0x01, 0, 0, 0, 0, 0, 0];
+    blob.extend_from_slice(timestamp);
+    blob.extend_from_slice(client_challenge);
+    blob.extend_from_slice(&[0; 4]);
+    blob.extend_from_slice(target_info);
+    blob.extend_from_slice(&[0; 4]);
+
+    let proof = hmac_md5(ntowf, &[server_challenge, &blob]);
+    let mut nt = proof.to_vec();
+    nt.extend_from_slice(&blob);
+
+    // With a server timestamp the LM response must be zeros (MS-NLMP 3.1.5.1.2)
+    let lm = if lm_zeroed {
+        vec![0; 24]
+    } else {
+        let mut lm = hmac_md5(ntowf, &[server_challenge, client_challenge]).to_vec();
+        lm.extend_from_slice(client_challenge);
+        lm
+    };
+    (nt, lm)
+}
+
+fn negotiate_message() -> Vec<u8> {
+    let mut message = SIGNATURE.to_vec();
+    message.extend_from_slice(&1u32.to_le_bytes());
+    message.extend_from_slice(&NEGOTIATE_FLAGS.to_le_bytes());
+    // Empty domain and workstation
+    message.extend_from_slice(&[0; 16]);
+    message
+}
+
+fn authenticate_message(
+    flags: u32,
+    lm: &[u8],
+    nt: &[u8],
+    domain: &[u8],
+    user: &[u8],
+    workstation: &[u8],
+) -> Vec<u8> {
+    const HEADER_LEN: usize = 64;
+    let mut header = SIGNATURE.to_vec();
+    header.extend_from_slice(&3u32.to_le_bytes());
+    let mut payload = Vec::new();
+    let mut field = |header: &mut Vec<u8>, value: &[u8]| {
+        let offset = (HEADER_LEN + payload.len()) as u32;
+        header.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        header.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        header.extend_from_slice(&offset.to_le_bytes());
+        payload.extend_from_slice(value);
+    };
+    field(&mut header, lm);
+    field(&mut header, nt);
+    field(&mut header, domain);
+    field(&mut header, user);
+    field(&mut header, workstation);
+    // No session key: the proxy only needs the connection authenticated
+    field(&mut header, &[]);
+    header.extend_from_slice(&flags.to_le_bytes());
+    header.extend_from_slice(&payload);
+    header
+}
+
+pub struct NtlmHandshake {
+    user: String,
+    domain: String,
+    password: String,
+}
+
+impl NtlmHandshake {
+    // `account` as in `split_user`
+    pub fn new(account: &str, password: &str) -> Self {
+        let (user, domain) = split_user(account);
+        NtlmHandshake {
+            user,
+            domain,
+            password: password.to_string(),
+        }
+    }
+}
+
+impl Handshake for NtlmHandshake {
+    fn step(&mut self, challenge: Option<&[u8]>) -> Result<Vec<u8>, String> {
+        let Some(challenge) = challenge else {
+            return Ok(negotiate_message());
+        };
+
+        let challenge = parse_challenge(challenge)?;
+        let ntowf = ntowf_v2(&self.user, &self.domain, &self.password);
+        let client_challenge: [u8; 8] = Uuid::new_v4().as_bytes()[..8]
+            .try_into()
+            .expect("uuid is 16 bytes");
+        let server_timestamp = av_timestamp(&challenge.target_info);
+        let timestamp = server_timestamp.unwrap_or_else(filetime_now);
+        let (nt, lm) = responses(
+            &ntowf,
+            &challenge.server_challenge,
+            &client_challenge,
+            &timestamp,
+            &challenge.target_info,
+            server_timestamp.is_some(),
+        );
+
+        let flags = challenge.flags & NEGOTIATE_FLAGS;
+        Ok(authenticate_message(
+            flags,
+            &lm,
+            &nt,
+            &utf16le(&self.domain),
+            &utf16le(&self.user),
+            &[],
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The NTLMv2 example in MS-NLMP 4.2.4
+    const SERVER_CHALLENGE: [u8; 8] = [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef];
+    const CLIENT_CHALLENGE: [u8; 8] = [0xaa; 8];
+
+    fn av_pair(id: u16, value: &[u8]) -> Vec<u8> {
+        let mut pair = id.to_le_bytes().to_vec();
+        pair.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        pair.extend_from_slice(value);
+        pair
+    }
+
+    fn target_info() -> Vec<u8> {
+        let mut info = av_pair(2, &utf16le("Domain"));
+        info.extend(av_pair(1, &utf16le("Server")));
+        info.extend(av_pair(AV_EOL, &[]));
+        info
+    }
+
+    fn challenge_message(target_info: &[u8]) -> Vec<u8> {
+        let mut message = SIGNATURE.to_vec();
+        message.extend_from_slice(&2u32.to_le_bytes());
+        // Empty target name
+        message.extend_from_slice(&[0; 8]);
+        message.extend_from_slice(&NEGOTIATE_FLAGS.to_le_bytes());
+        message.extend_from_slice(&SERVER_CHALLENGE);
+        message.extend_from_slice(&[0; 8]);
+        message.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        message.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        message.extend_from_slice(&48u32.to_le_bytes());
+        message.extend_from_slice(target_info);
+        message
+    }
+
+    #[test]
+    fn matches_the_specification_example() {
+        let ntowf = ntowf_v2("User", "Domain", "Password");
+        assert_eq!(
+            ntowf,
+            [
+                0x0c, 0x86, 0x8a, 0x40, 0x3b, 0xfd, 0x7a, 0x93, 0xa3, 0x00, 0x1e, 0xf2, 0x2e, 0xf0,
+                0x2e, 0x3f
+            ]
+        );
+        let (nt, lm) = responses(
+            &ntowf,
+            &SERVER_CHALLENGE,
+            &CLIENT_CHALLENGE,
+            &[0; 8],
+            &target_info(),
+            false,
+        );
+        assert_eq!(
+            nt[..16],
+            [
+                0x68, 0xcd, 0x0a, 0xb8, 0x51, 0xe5, 0x1c, 0x96, 0xaa, 0xbc, 0x92, 0x7b, 0xeb, 0xef,
+                0x6a, 0x1c
+            ]
+        );
+        assert_eq!(
+            lm[..16],
+            [
+                0x86, 0xc3, 0x50, 0x97, 0xac, 0x9c, 0xec, 0x10, 0x25, 0x54, 0x76, 0x4a, 0x57, 0xcc,
+                0xcc, 0x19
+            ]
+        );
+        assert_eq!(lm[16..], CLIENT_CHALLENGE);
+    }
+
+    #[test]
+    fn zeroes_the_lm_response_when_the_server_sends_a_timestamp() {
+        let ntowf = ntowf_v2("User", "Domain", "Password");
+        let (_, lm) = responses(
+            &ntowf,
+            &SERVER_CHALLENGE,
+            &CLIENT_CHALLENGE,
+            &[0; 8],
+            &[],
+            true,
+        );
+        assert_eq!(lm, [0; 24]);
+    }
+
+    #[test]
+    fn splits_accounts_into_user_and_domain() {
+        assert_eq!(
+            split_user("CORP\\alice"),
+            ("alice".to_string(), "CORP".to_string())
+        );
+        assert_eq!(
+            split_user("alice@corp.example"),
+            ("alice".to_string(), "corp.example".to_string())
+        );
+        assert_eq!(split_user("alice"), ("alice".to_string(), String::new()));
+    }
+
+    #[test]
+    fn reads_the_challenge_and_its_timestamp() {
+        let mut info = av_pair(AV_TIMESTAMP, &[7; 8]);
+        info.extend(target_info());
+        let challenge = parse_challenge(&challenge_message(&info)).unwrap();
+        assert_eq!(challenge.server_challenge, SERVER_CHALLENGE);
+        assert_eq!(challenge.flags, NEGOTIATE_FLAGS);
+        assert_eq!(av_timestamp(&challenge.target_info), Some([7; 8]));
+        // Nothing after the end of the list counts
+        let mut ended = target_info();
+        ended.extend(av_pair(AV_TIMESTAMP, &[7; 8]));
+        assert_eq!(av_timestamp(&ended), None);
+        // Nor a pair running past the end
+        assert_eq!(av_timestamp(&av_pair(AV_TIMESTAMP, &[7; 8])[..10]), None);
+    }
+
+    #[test]
+    fn rejects_malformed_challenges() {
+        let message = challenge_message(&target_info());
+        let mut negotiate = message.clone();
+        negotiate[8] = 1;
+        for bytes in [&message[..30], &negotiate[..], b"NTLMSSP"] {
+            assert_eq!(
+                parse_challenge(bytes).err().unwrap(),
+                "Malformed NTLM challenge from proxy"
+            );
+        }
+        // Old servers stop before the target info
+        let challenge = parse_challenge(&message[..32]).unwrap();
+        assert!(challenge.target_info.is_empty());
+    }
+
+    #[test]
+    fn answers_a_challenge_after_negotiating() {
+        let mut handshake = NtlmHandshake::new("CORP\\alice", "secret");
+        let negotiate = handshake.step(None).unwrap();
+        assert_eq!(&negotiate[..8], SIGNATURE);
+        assert_eq!(read_u32(&negotiate, 8), Some(1));
+
+        let authenticate = handshake
+            .step(Some(&challenge_message(&target_info())))
+            .unwrap();
+        assert_eq!(read_u32(&authenticate, 8), Some(3));
+        assert_eq!(read_field(&authenticate, 28), Some(&utf16le("CORP")[..]));
+        assert_eq!(read_field(&authenticate, 36), Some(&utf16le("alice")[..]));
+        // NTv2 proof plus the blob around the target info
+        assert_eq!(
+            read_field(&authenticate, 20).unwrap().len(),
+            16 + 28 + target_info().len() + 4
+        );
+    }
+}
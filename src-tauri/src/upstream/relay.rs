@@ -0,0 +1,381 @@
+// Local HTTP proxy that replay's client talks to in place of an upstream proxy
+// wanting NTLM or Negotiate. reqwest only speaks Basic to proxies, and these
+// schemes authenticate a connection rather than a request, so the relay runs
+// the handshake on each upstream connection and then hands it over as is.
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::Handshake;
+
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+// NTLM takes two legs and Kerberos one; more means the proxy keeps refusing
+const MAX_LEGS: usize = 4;
+
+pub type NewHandshake = Arc<dyn Fn() -> Result<Box<dyn Handshake>, String> + Send + Sync>;
+
+struct Upstream {
+    // `host:port` of the real proxy
+    address: String,
+    // `NTLM` or `Negotiate`
+    scheme: &'static str,
+    new_handshake: NewHandshake,
+}
+
+// Stops accepting when dropped; open tunnels end with their client
+pub struct Relay {
+    pub url: String,
+    accept: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for Relay {
+    fn drop(&mut self) {
+        self.accept.abort();
+    }
+}
+
+pub async fn start(
+    address: String,
+    scheme: &'static str,
+    new_handshake: NewHandshake,
+) -> Result<Relay, String> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| format!("Failed to start proxy relay: {}", e))?;
+    let local = listener.local_addr().map_err(|e| e.to_string())?;
+    let upstream = Arc::new(Upstream {
+        address,
+        scheme,
+        new_handshake,
+    });
+
+    let accept = tokio::spawn(async move {
+        while let Ok((client, _)) = listener.accept().await {
+            tokio::spawn(serve(client, upstream.clone()));
+        }
+    });
+    Ok(Relay {
+        url: format!("http://{}", local),
+        accept,
+    })
+}
+
+struct Head {
+    first_line: String,
+    headers: Vec<(String, String)>,
+    raw: Vec<u8>,
+}
+
+impl Head {
+    fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.headers
+            .iter()
+            .filter(move |(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn content_length(&self) -> usize {
+        self.values("content-length")
+            .next()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0)
+    }
+
+    fn is_chunked(&self) -> bool {
+        self.values("transfer-encoding")
+            .any(|v| v.to_ascii_lowercase().contains("chunked"))
+    }
+
+    fn status(&self) -> Option<u16> {
+        self.first_line.split(' ').nth(1)?.parse().ok()
+    }
+
+    // The token in `Proxy-Authenticate: <scheme> <base64>`
+    fn challenge(&self, scheme: &str) -> Option<Vec<u8>> {
+        self.values("proxy-authenticate").find_map(|value| {
+            let (name, token) = value.trim().split_once(' ')?;
+            if !name.eq_ignore_ascii_case(scheme) {
+                return None;
+            }
+            STANDARD.decode(token.trim()).ok()
+        })
+    }
+}
+
+// None when the peer closed before sending anything
+async fn read_head<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Head>, String> {
+    let mut raw = Vec::new();
+    let mut lines = Vec::new();
+    loop {
+        let start = raw.len();
+        let read = reader
+            .read_until(b'\n', &mut raw)
+            .await
+            .map_err(|e| e.to_string())?;
+        if read == 0 {
+            return match raw.is_empty() {
+                true => Ok(None),
+                false => Err("Connection closed mid-header".to_string()),
+            };
+        }
+        if raw.len() > MAX_HEAD_BYTES {
+            return Err("Header block too large".to_string());
+        }
+        let line = String::from_utf8_lossy(&raw[start..])
+            .trim_end()
+            .to_string();
+        if line.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+
+    let mut lines = lines.into_iter();
+    let first_line = lines.next().unwrap_or_default();
+    let headers = lines
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect();
+    Ok(Some(Head {
+        first_line,
+        headers,
+        raw,
+    }))
+}
+
+// Skips the body of a 407 so the next leg can go out on the same connection
+async fn discard_body<R: AsyncBufRead + Unpin>(reader: &mut R, head: &Head) -> Result<(), String> {
+    let io = |e: std::io::Error| e.to_string();
+    if !head.is_chunked() {
+        let mut body = vec![0; head.content_length()];
+        return reader.read_exact(&mut body).await.map(|_| ()).map_err(io);
+    }
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.map_err(io)?;
+        let size = line.trim().split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size, 16).map_err(|_| "Malformed chunk".to_string())?;
+        if size == 0 {
+            // Trailers, up to the blank line
+            while read_head_line(reader).await? {}
+            return Ok(());
+        }
+        let mut chunk = vec![0; size + 2];
+        reader.read_exact(&mut chunk).await.map_err(io)?;
+    }
+}
+
+// False at the blank line ending a block
+async fn read_head_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<bool, String> {
+    let mut line = String::new();
+    let read = reader
+        .read_line(&mut line)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(read > 0 && !line.trim().is_empty())
+}
+
+fn with_authorization(request: &Head, scheme: &str, token: &[u8]) -> Vec<u8> {
+    let mut message = format!("{}\r\n", request.first_line);
+    for (name, value) in &request.headers {
+        if !name.eq_ignore_ascii_case("proxy-authorization") {
+            message.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    message.push_str(&format!(
+        "Proxy-Authorization: {} {}\r\n\r\n",
+        scheme,
+        STANDARD.encode(token)
+    ));
+    message.into_bytes()
+}
+
+async fn serve(client: TcpStream, upstream: Arc<Upstream>) {
+    let mut client = BufReader::new(client);
+    if let Err(e) = relay(&mut client, &upstream).await {
+        println!("Proxy relay: {}", e);
+        let response = format!(
+            "HTTP/1.1 502 Bad Gateway\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            e.len(),
+            e
+        );
+        let _ = client.get_mut().write_all(response.as_bytes()).await;
+    }
+}
+
+// Errors only before anything was sent back, so the caller can still answer 502
+async fn relay(client: &mut BufReader<TcpStream>, upstream: &Upstream) -> Result<(), String> {
+    let Some(request) = read_head(client).await? else {
+        return Ok(());
+    };
+    // Each leg resends the request, so its body has to be at hand
+    if request.is_chunked() {
+        return Err("Chunked request bodies can't be relayed".to_string());
+    }
+    let mut body = vec![0; request.content_length()];
+    client
+        .read_exact(&mut body)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let connection = TcpStream::connect(&upstream.address)
+        .await
+        .map_err(|e| format!("Failed to reach proxy {}: {}", upstream.address, e))?;
+    let mut proxy = BufReader::new(connection);
+    let mut handshake = (upstream.new_handshake)()?;
+    let mut challenge: Option<Vec<u8>> = None;
+
+    for _ in 0..MAX_LEGS {
+        let token = handshake.step(challenge.as_deref())?;
+        let mut message = with_authorization(&request, upstream.scheme, &token);
+        message.extend_from_slice(&body);
+        proxy
+            .get_mut()
+            .write_all(&message)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let response = read_head(&mut proxy)
+            .await?
+            .ok_or_else(|| "Proxy closed the connection during authentication".to_string())?;
+        if response.status() != Some(407) {
+            hand_over(client, proxy, &response).await;
+            return Ok(());
+        }
+        discard_body(&mut proxy, &response).await?;
+        challenge = Some(
+            response
+                .challenge(upstream.scheme)
+                .ok_or_else(|| format!("Proxy refused {} authentication", upstream.scheme))?,
+        );
+    }
+    Err(format!(
+        "Proxy still wants {} authentication after {} attempts",
+        upstream.scheme, MAX_LEGS
+    ))
+}
+
+// Passes the authenticated connection's answer on and pipes both ways until
+// either side closes, including whatever either reader had buffered
+async fn hand_over(client: &mut BufReader<TcpStream>, proxy: BufReader<TcpStream>, head: &Head) {
+    let mut pending = head.raw.clone();
+    pending.extend_from_slice(proxy.buffer());
+    let mut proxy_stream = proxy.into_inner();
+    let early = client.buffer().to_vec();
+    client.consume(early.len());
+
+    let client_stream = client.get_mut();
+    if client_stream.write_all(&pending).await.is_err()
+        || proxy_stream.write_all(&early).await.is_err()
+    {
+        return;
+    }
+    let _ = tokio::io::copy_bidirectional(client_stream, &mut proxy_stream).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn head(bytes: &[u8]) -> Result<Option<Head>, String> {
+        read_head(&mut &bytes[..]).await
+    }
+
+    #[tokio::test]
+    async fn parses_a_challenge_response_head() {
+        let token = STANDARD.encode(b"challenge");
+        let raw = format!(
+            "HTTP/1.1 407 Proxy Authentication Required\r\n\
+             Proxy-Authenticate: Basic realm=\"proxy\"\r\n\
+             proxy-authenticate:  NTLM {}\r\n\
+             Content-Length: 5\r\n\r\nbody!",
+            token
+        );
+        let head = head(raw.as_bytes()).await.unwrap().unwrap();
+        assert_eq!(head.status(), Some(407));
+        assert_eq!(head.content_length(), 5);
+        assert!(!head.is_chunked());
+        assert_eq!(head.challenge("ntlm"), Some(b"challenge".to_vec()));
+        assert_eq!(head.challenge("Negotiate"), None);
+        // The body is left for whoever reads next
+        assert!(raw.as_bytes().starts_with(&head.raw));
+        assert_eq!(&raw.as_bytes()[head.raw.len()..], b"body!");
+    }
+
+    #[tokio::test]
+    async fn tells_a_closed_connection_from_a_cut_off_head() {
+        assert!(head(b"").await.unwrap().is_none());
+        assert_eq!(
+            head(b"HTTP/1.1 200 OK\r\nContent-Len").await.err().unwrap(),
+            "Connection closed mid-header"
+        );
+        let huge = format!(
+            "HTTP/1.1 200 OK\r\nX-Big: {}\r\n\r\n",
+            "a".repeat(MAX_HEAD_BYTES)
+        );
+        assert_eq!(
+            head(huge.as_bytes()).await.err().unwrap(),
+            "Header block too large"
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_a_chunked_407_body_and_its_trailers() {
+        let raw = b"HTTP/1.1 407 Proxy Authentication Required\r\n\
+            Transfer-Encoding: gzip, Chunked\r\n\r\n\
+            4;ext=1\r\nDeny\r\nA\r\n0123456789\r\n0\r\nX-Trailer: yes\r\n\r\n\
+            HTTP/1.1 200 OK\r\n\r\n";
+        let mut reader = &raw[..];
+        let denied = read_head(&mut reader).await.unwrap().unwrap();
+        assert!(denied.is_chunked());
+        discard_body(&mut reader, &denied).await.unwrap();
+        let next = read_head(&mut reader).await.unwrap().unwrap();
+        assert_eq!(next.status(), Some(200));
+        assert!(reader.is_empty());
+    }
+
+    #[tokio::test]
+    async fn skips_a_sized_407_body() {
+        let raw = b"HTTP/1.1 407 Nope\r\nContent-Length: 4\r\n\r\nDenyHTTP/1.1 200 OK\r\n\r\n";
+        let mut reader = &raw[..];
+        let denied = read_head(&mut reader).await.unwrap().unwrap();
+        discard_body(&mut reader, &denied).await.unwrap();
+        let next = read_head(&mut reader).await.unwrap().unwrap();
+        assert_eq!(next.status(), Some(200));
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_chunks() {
+        let raw = b"HTTP/1.1 407 Nope\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\nDeny\r\n";
+        let mut reader = &raw[..];
+        let denied = read_head(&mut reader).await.unwrap().unwrap();
+        assert_eq!(
+            discard_body(&mut reader, &denied).await.err().unwrap(),
+            "Malformed chunk"
+        );
+        // Cut off inside a chunk
+        let raw = b"HTTP/1.1 407 Nope\r\nTransfer-Encoding: chunked\r\n\r\n10\r\nDeny";
+        let mut reader = &raw[..];
+        let denied = read_head(&mut reader).await.unwrap().unwrap();
+        assert!(discard_body(&mut reader, &denied).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn replaces_the_client_authorization() {
+        let raw = b"GET http://example.com/ HTTP/1.1\r\n\
+            Host: example.com\r\nProxy-Authorization: Basic abc\r\n\r\n";
+        let request = head(raw).await.unwrap().unwrap();
+        let message = with_authorization(&request, "NTLM", b"token");
+        assert_eq!(
+            String::from_utf8(message).unwrap(),
+            format!(
+                "GET http://example.com/ HTTP/1.1\r\nHost: example.com\r\n\
+                 Proxy-Authorization: NTLM {}\r\n\r\n",
+                STANDARD.encode(b"token")
+            )
+        );
+    }
+}
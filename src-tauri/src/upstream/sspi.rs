@@ -0,0 +1,151 @@
+// Negotiate (Kerberos, falling back to NTLM) or plain NTLM through SSPI, as
+// the logged-in Windows user, so no proxy password has to be stored
+use std::ptr;
+use windows_sys::Win32::Foundation::{SEC_E_OK, SEC_I_CONTINUE_NEEDED};
+use windows_sys::Win32::Security::Authentication::Identity::{
+    AcquireCredentialsHandleW, DeleteSecurityContext, FreeContextBuffer, FreeCredentialsHandle,
+    InitializeSecurityContextW, SecBuffer, SecBufferDesc, ISC_REQ_ALLOCATE_MEMORY, SECBUFFER_TOKEN,
+    SECBUFFER_VERSION, SECPKG_CRED_OUTBOUND, SECURITY_NATIVE_DREP,
+};
+use windows_sys::Win32::Security::Credentials::SecHandle;
+
+use super::Handshake;
+
+fn wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+pub struct SspiHandshake {
+    credentials: SecHandle,
+    context: Option<SecHandle>,
+    // SPN of the proxy, `HTTP/proxy.corp`
+    target: Vec<u16>,
+}
+
+impl SspiHandshake {
+    // `package` is `Negotiate` or `NTLM`
+    pub fn new(package: &str, target: &str) -> Result<Self, String> {
+        let package = wide(package);
+        let mut credentials = SecHandle {
+            dwLower: 0,
+            dwUpper: 0,
+        };
+        let mut expiry = 0;
+        // SAFETY: every pointer is to a live local or null where SSPI allows it
+        let status = unsafe {
+            AcquireCredentialsHandleW(
+                ptr::null(),
+                package.as_ptr(),
+                SECPKG_CRED_OUTBOUND,
+                ptr::null(),
+                ptr::null(),
+                None,
+                ptr::null(),
+                &mut credentials,
+                &mut expiry,
+            )
+        };
+        if status != SEC_E_OK {
+            return Err(format!(
+                "SSPI: no credentials for the logged-in user ({:#010x})",
+                status
+            ));
+        }
+        Ok(SspiHandshake {
+            credentials,
+            context: None,
+            target: wide(target),
+        })
+    }
+}
+
+impl Handshake for SspiHandshake {
+    fn step(&mut self, challenge: Option<&[u8]>) -> Result<Vec<u8>, String> {
+        let challenge = challenge.unwrap_or_default();
+        let mut input_buffer = SecBuffer {
+            cbBuffer: challenge.len() as u32,
+            BufferType: SECBUFFER_TOKEN,
+            pvBuffer: challenge.as_ptr() as *mut _,
+        };
+        let input = SecBufferDesc {
+            ulVersion: SECBUFFER_VERSION,
+            cBuffers: 1,
+            pBuffers: &mut input_buffer,
+        };
+        let mut output_buffer = SecBuffer {
+            cbBuffer: 0,
+            BufferType: SECBUFFER_TOKEN,
+            pvBuffer: ptr::null_mut(),
+        };
+        let mut output = SecBufferDesc {
+            ulVersion: SECBUFFER_VERSION,
+            cBuffers: 1,
+            pBuffers: &mut output_buffer,
+        };
+        let mut context = self.context.unwrap_or(SecHandle {
+            dwLower: 0,
+            dwUpper: 0,
+        });
+        let mut attributes = 0;
+        let mut expiry = 0;
+
+        // SAFETY: the buffers outlive the call; SSPI allocates the output token,
+        // which is copied and freed below
+        let status = unsafe {
+            InitializeSecurityContextW(
+                &self.credentials,
+                self.context
+                    .as_ref()
+                    .map_or(ptr::null(), |context| context as *const SecHandle),
+                self.target.as_ptr(),
+                ISC_REQ_ALLOCATE_MEMORY,
+                0,
+                SECURITY_NATIVE_DREP,
+                if self.context.is_some() {
+                    &input
+                } else {
+                    ptr::null()
+                },
+                0,
+                &mut context,
+                &mut output,
+                &mut attributes,
+                &mut expiry,
+            )
+        };
+        if status != SEC_E_OK && status != SEC_I_CONTINUE_NEEDED {
+            return Err(format!(
+                "SSPI: InitializeSecurityContext failed ({:#010x})",
+                status
+            ));
+        }
+        self.context = Some(context);
+
+        if output_buffer.pvBuffer.is_null() {
+            return Ok(Vec::new());
+        }
+        // SAFETY: SSPI filled `cbBuffer` bytes at `pvBuffer`
+        let token = unsafe {
+            let token = std::slice::from_raw_parts(
+                output_buffer.pvBuffer as *const u8,
+                output_buffer.cbBuffer as usize,
+            )
+            .to_vec();
+            FreeContextBuffer(output_buffer.pvBuffer);
+            token
+        };
+        Ok(token)
+    }
+}
+
+impl Drop for SspiHandshake {
+    fn drop(&mut self) {
+        // SAFETY: both handles came from SSPI and are released once
+        unsafe {
+            if let Some(context) = &self.context {
+                DeleteSecurityContext(context);
+            }
+            FreeCredentialsHandle(&self.credentials);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::events::{self, AppEvent, EventSink};
+use crate::filter::RequestFilter;
+use crate::jsonpath::{self, Segment};
+use crate::NetworkRequest;
+
+// Hits kept per watch for `get_watch_hits`
+const MAX_HITS_PER_WATCH: usize = 200;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "src/bindings/")]
+pub enum WatchSource {
+    #[default]
+    ResponseBody,
+    RequestBody,
+}
+
+// Extracts values from matching traffic as it arrives, e.g. the cart total
+// returned by every `/checkout` call
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct Watch {
+    pub id: String,
+    pub name: Option<String>,
+    pub filter: RequestFilter,
+    pub path: String,
+    pub source: WatchSource,
+    #[serde(skip)]
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "src/bindings/")]
+pub struct WatchHit {
+    pub watch_id: String,
+    pub request_id: String,
+    pub url: String,
+    #[ts(type = "number")]
+    pub timestamp: u64,
+    #[ts(type = "Array<unknown>")]
+    pub values: Vec<Value>,
+}
+
+#[derive(Debug, Default)]
+pub struct Watches {
+    pub watches: Vec<Watch>,
+    pub hits: HashMap<String, Vec<WatchHit>>,
+}
+
+pub type WatchStore = Arc<Mutex<Watches>>;
+
+fn evaluate_watch(watch: &Watch, request: &NetworkRequest) -> Option<WatchHit> {
+    if !watch.filter.matches(request) {
+        return None;
+    }
+    let body = match watch.source {
+        WatchSource::ResponseBody => request.response.as_ref()?.body.as_ref()?,
+        WatchSource::RequestBody => request.body.as_ref()?,
+    };
+    let value: Value = serde_json::from_str(body).ok()?;
+    let values: Vec<Value> = jsonpath::select(&value, &watch.segments)
+        .into_iter()
+        .cloned()
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+
+    Some(WatchHit {
+        watch_id: watch.id.clone(),
+        request_id: request.id.clone(),
+        url: request.url.clone(),
+        timestamp: request.timestamp,
+        values,
+    })
+}
+
+// Runs every watch against a newly stored request, emitting `watch-hit` per match
+pub fn evaluate(watches: &WatchStore, request: &NetworkRequest, sink: &dyn EventSink) {
+    let hits: Vec<WatchHit> = {
+        let mut state = watches.lock().unwrap();
+        let hits: Vec<WatchHit> = state
+            .watches
+            .iter()
+            .filter_map(|watch| evaluate_watch(watch, request))
+            .collect();
+        for hit in &hits {
+            let log = state.hits.entry(hit.watch_id.clone()).or_default();
+            log.push(hit.clone());
+            let overflow = log.len().saturating_sub(MAX_HITS_PER_WATCH);
+            log.drain(..overflow);
+        }
+        hits
+    };
+
+    for hit in hits {
+        if let Err(e) = events::emit(sink, AppEvent::WatchHit(&hit)) {
+            println!("Failed to emit watch-hit event: {}", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn add_watch(
+    watches: tauri::State<'_, WatchStore>,
+    path: String,
+    filter: Option<RequestFilter>,
+    source: Option<WatchSource>,
+    name: Option<String>,
+) -> Result<Watch, String> {
+    let segments = jsonpath::parse(&path)?;
+    filter.as_ref().map_or(Ok(()), RequestFilter::validate)?;
+    let watch = Watch {
+        id: Uuid::new_v4().to_string(),
+        name,
+        filter: filter.unwrap_or_default(),
+        path,
+        source: source.unwrap_or_default(),
+        segments,
+    };
+    let mut state = watches.lock().map_err(|e| e.to_string())?;
+    state.watches.push(watch.clone());
+    Ok(watch)
+}
+
+#[tauri::command]
+pub async fn remove_watch(watches: tauri::State<'_, WatchStore>, id: String) -> Result<(), String> {
+    let mut state = watches.lock().map_err(|e| e.to_string())?;
+    state.watches.retain(|w| w.id != id);
+    state.hits.remove(&id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_watches(watches: tauri::State<'_, WatchStore>) -> Result<Vec<Watch>, String> {
+    let state = watches.lock().map_err(|e| e.to_string())?;
+    Ok(state.watches.clone())
+}
+
+#[tauri::command]
+pub async fn get_watch_hits(
+    watches: tauri::State<'_, WatchStore>,
+    id: String,
+) -> Result<Vec<WatchHit>, String> {
+    let state = watches.lock().map_err(|e| e.to_string())?;
+    Ok(state.hits.get(&id).cloned().unwrap_or_default())
+}
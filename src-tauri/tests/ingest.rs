@@ -0,0 +1,841 @@
+// End-to-end tests for the ingest pipeline: a real WebSocket server on a free
+// port, fake clients, and a recording event sink in place of the app
+use futures_util::{SinkExt, StreamExt};
+use network_observer_lib::config::ObserverConfig;
+use network_observer_lib::events::{EventSink, Events};
+use network_observer_lib::floodguard::ConnectionLimits;
+use network_observer_lib::lifecycle::RequestState;
+use network_observer_lib::limits::{EvictionPolicy, StoreLimits};
+use network_observer_lib::server::{self, IngestState};
+use network_observer_lib::storage::RequestStorage;
+use network_observer_lib::NetworkRequest;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Default)]
+struct RecordedEvents(Mutex<Vec<(String, Value)>>);
+
+impl EventSink for RecordedEvents {
+    fn emit_event(&self, name: &str, payload: Value) -> Result<(), String> {
+        self.0.lock().unwrap().push((name.to_string(), payload));
+        Ok(())
+    }
+}
+
+impl RecordedEvents {
+    fn named(&self, name: &str) -> Vec<Value> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(n, _)| n == name)
+            .map(|(_, payload)| payload.clone())
+            .collect()
+    }
+}
+
+// Serves `stored` once and records every write
+#[derive(Default)]
+struct MockStorage {
+    stored: Vec<NetworkRequest>,
+    writes: Arc<Mutex<Vec<String>>>,
+}
+
+impl RequestStorage for MockStorage {
+    fn load(&mut self) -> Result<Vec<NetworkRequest>, String> {
+        Ok(std::mem::take(&mut self.stored))
+    }
+
+    fn put(&mut self, request: &NetworkRequest) -> Result<(), String> {
+        self.writes.lock().unwrap().push(request.id.clone());
+        Ok(())
+    }
+
+    fn remove(&mut self, ids: &[String]) -> Result<(), String> {
+        let removed = ids.iter().map(|id| format!("-{}", id));
+        self.writes.lock().unwrap().extend(removed);
+        Ok(())
+    }
+
+    fn replace_all(&mut self, _requests: &[NetworkRequest]) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+struct Harness {
+    state: IngestState,
+    events: Arc<RecordedEvents>,
+    addr: SocketAddr,
+}
+
+async fn start() -> Harness {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let state = IngestState::new();
+    let events = Arc::new(RecordedEvents::default());
+    let sink: Events = events.clone();
+    tokio::spawn(server::serve(listener, state.clone(), sink));
+    Harness {
+        state,
+        events,
+        addr,
+    }
+}
+
+impl Harness {
+    async fn connect(&self) -> Client {
+        let connected = self.state.clients().len() + 1;
+        let (client, _) = connect_async(format!("ws://{}", self.addr)).await.unwrap();
+        // The server registers the client once the handshake completes on its side
+        eventually(|| self.state.clients().len() >= connected).await;
+        client
+    }
+}
+
+async fn send(client: &mut Client, message: Value) {
+    client
+        .send(Message::Text(message.to_string()))
+        .await
+        .unwrap();
+}
+
+async fn receive(client: &mut Client) -> Value {
+    let message = tokio::time::timeout(Duration::from_secs(5), client.next())
+        .await
+        .expect("timed out waiting for a server message")
+        .unwrap()
+        .unwrap();
+    serde_json::from_str(message.to_text().unwrap()).unwrap()
+}
+
+async fn eventually(condition: impl Fn() -> bool) {
+    for _ in 0..250 {
+        if condition() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("condition not met within 5s");
+}
+
+fn request(id: &str, url: &str) -> Value {
+    json!({
+        "id": id,
+        "url": url,
+        "method": "GET",
+        "headers": [["accept", "application/json"]],
+        "body": null,
+        "response": {
+            "status": 200,
+            "status_text": "OK",
+            "headers": [["content-type", "application/json"]],
+            "body": "{\"ok\":true}"
+        },
+        "timestamp": 1_700_000_000_000u64,
+        "duration": 12
+    })
+}
+
+#[tokio::test]
+async fn stores_untyped_requests_and_emits_new_request() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+
+    send(
+        &mut client,
+        request("req_1", "https://api.example.com/users"),
+    )
+    .await;
+    eventually(|| harness.state.requests().len() == 1).await;
+
+    let stored = &harness.state.requests()[0];
+    assert_eq!(stored.id, "req_1");
+    assert_eq!(stored.response.as_ref().unwrap().status, 200);
+    let emitted = harness.events.named("new-request");
+    assert_eq!(emitted.len(), 1);
+    assert_eq!(emitted[0]["id"], "req_1");
+    assert_eq!(harness.events.named("websocket-connected").len(), 1);
+}
+
+#[tokio::test]
+async fn merges_duplicates_into_the_first_request() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+
+    send(
+        &mut client,
+        request("req_1", "https://api.example.com/users"),
+    )
+    .await;
+    send(
+        &mut client,
+        request("req_2", "https://api.example.com/users"),
+    )
+    .await;
+    send(
+        &mut client,
+        request("req_3", "https://api.example.com/orders"),
+    )
+    .await;
+    eventually(|| harness.state.requests().len() == 2).await;
+    eventually(|| harness.state.requests()[0].aliases == ["req_2"]).await;
+
+    let ids: Vec<String> = harness.state.requests().into_iter().map(|r| r.id).collect();
+    assert_eq!(ids, ["req_1", "req_3"]);
+    assert_eq!(harness.events.named("new-request").len(), 2);
+    let provenance = json!(harness.state.requests()[0].provenance);
+    assert_eq!(provenance[1]["kind"], "duplicate-merged");
+    assert_eq!(provenance[1]["detail"], "req_2");
+}
+
+#[tokio::test]
+async fn completes_started_requests_by_id() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+
+    let mut start = request("req_1", "https://api.example.com/slow");
+    start["type"] = json!("request-start");
+    start["response"] = Value::Null;
+    start["duration"] = Value::Null;
+    send(&mut client, start).await;
+    eventually(|| harness.state.requests().len() == 1).await;
+    assert!(harness.state.requests()[0].response.is_none());
+
+    send(
+        &mut client,
+        json!({
+            "type": "request-end",
+            "id": "req_1",
+            "duration": 250,
+            "response": { "status": 201, "status_text": "Created", "headers": [], "body": null }
+        }),
+    )
+    .await;
+    eventually(|| harness.state.requests()[0].response.is_some()).await;
+
+    let completed = &harness.state.requests()[0];
+    assert_eq!(completed.response.as_ref().unwrap().status, 201);
+    assert_eq!(completed.duration, Some(250));
+    let updated = harness.events.named("request-updated");
+    assert_eq!(updated.len(), 1);
+    assert_eq!(updated[0]["response"]["status"], 201);
+    let kinds: Vec<Value> = completed
+        .provenance
+        .iter()
+        .map(|entry| json!(entry)["kind"].clone())
+        .collect();
+    assert_eq!(kinds, ["captured", "response-attached"]);
+}
+
+#[tokio::test]
+async fn tracks_lifecycle_states_and_rejects_reopening() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+
+    let mut start = request("req_1", "https://api.example.com/upload");
+    start["type"] = json!("request-start");
+    start["response"] = Value::Null;
+    start["duration"] = Value::Null;
+    send(&mut client, start).await;
+    eventually(|| harness.state.requests().len() == 1).await;
+    send(
+        &mut client,
+        json!({ "type": "request-state", "id": "req_1", "state": "headers-received", "timestamp": 1_700_000_000_040u64 }),
+    )
+    .await;
+    eventually(|| harness.state.requests()[0].state == RequestState::HeadersReceived).await;
+
+    send(
+        &mut client,
+        json!({
+            "type": "request-end",
+            "id": "req_1",
+            "duration": 90,
+            "state": "failed",
+            "error": "connection reset",
+            "response": { "status": 0, "status_text": "", "headers": [], "body": null }
+        }),
+    )
+    .await;
+    // A late report can't reopen a finished request
+    send(
+        &mut client,
+        json!({ "type": "request-state", "id": "req_1", "state": "complete" }),
+    )
+    .await;
+    eventually(|| harness.state.requests()[0].state == RequestState::Failed).await;
+
+    let states: Vec<_> = harness.state.requests()[0]
+        .transitions
+        .iter()
+        .map(|t| (t.state, t.at))
+        .collect();
+    assert_eq!(
+        states,
+        [
+            (RequestState::Pending, 1_700_000_000_000),
+            (RequestState::HeadersReceived, 1_700_000_000_040),
+            (RequestState::Failed, 1_700_000_000_090),
+        ]
+    );
+    let changes = harness.events.named("request-state-changed");
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[1]["from"], "headers-received");
+    assert_eq!(changes[1]["detail"], "connection reset");
+}
+
+#[tokio::test]
+async fn answers_hello_and_processes_batches() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+
+    send(
+        &mut client,
+        json!({ "type": "hello", "protocol": 1, "client": "tests", "client_version": "1.0.0" }),
+    )
+    .await;
+    let welcome = receive(&mut client).await;
+    assert_eq!(welcome["type"], "welcome");
+    assert_eq!(welcome["protocol"], 2);
+
+    let mut first = request("req_1", "https://api.example.com/a");
+    first["type"] = json!("network-request");
+    let marker = json!({ "type": "marker", "kind": "custom", "label": "checkout", "timestamp": 5 });
+    send(
+        &mut client,
+        json!({ "type": "batch", "messages": [first, request("req_2", "https://api.example.com/b"), marker] }),
+    )
+    .await;
+    eventually(|| harness.state.requests().len() == 2 && harness.state.markers().len() == 1).await;
+
+    assert_eq!(
+        harness.state.markers()[0].label.as_deref(),
+        Some("checkout")
+    );
+    assert_eq!(harness.events.named("marker-added").len(), 1);
+    let clients = harness.state.clients();
+    assert_eq!(clients[0].client.as_deref(), Some("tests"));
+    assert_eq!(clients[0].messages_received, 2);
+}
+
+#[tokio::test]
+async fn imports_offline_queues_in_timestamp_order() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+
+    send(&mut client, request("live", "https://api.example.com/live")).await;
+    eventually(|| harness.state.requests().len() == 1).await;
+
+    let mut older = request("queued_1", "https://api.example.com/queued");
+    older["timestamp"] = json!(1_600_000_000_000u64);
+    // Already stored, so skipped
+    let repeat = request("live", "https://api.example.com/live");
+    send(
+        &mut client,
+        json!({ "type": "offline-queue", "requests": [older, repeat] }),
+    )
+    .await;
+    eventually(|| harness.state.requests().len() == 2).await;
+
+    let ids: Vec<String> = harness.state.requests().into_iter().map(|r| r.id).collect();
+    assert_eq!(ids, ["queued_1", "live"]);
+    let imported = harness.events.named("requests-imported");
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn rejects_invalid_messages_with_an_error_reply() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+
+    let mut invalid = request("req_1", "https://api.example.com/users");
+    invalid["response"]["status"] = json!("200");
+    send(&mut client, invalid).await;
+    let error = receive(&mut client).await;
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["code"], "invalid_message");
+    assert_eq!(error["id"], "req_1");
+    assert_eq!(error["path"], "response.status");
+
+    client
+        .send(Message::Text("{not json".into()))
+        .await
+        .unwrap();
+    let error = receive(&mut client).await;
+    assert_eq!(error["code"], "invalid_json");
+
+    assert!(harness.state.requests().is_empty());
+    assert_eq!(harness.events.named("unparsed-message").len(), 2);
+    assert_eq!(harness.state.clients()[0].parse_failures, 2);
+}
+
+#[tokio::test]
+async fn delivers_commands_to_every_client() {
+    let harness = start().await;
+    let mut first = harness.connect().await;
+    let mut second = harness.connect().await;
+
+    let command = json!({ "type": "set-rules", "rules": [] });
+    assert_eq!(harness.state.send_command(&command).await, 2);
+
+    assert_eq!(receive(&mut first).await, command);
+    assert_eq!(receive(&mut second).await, command);
+    eventually(|| harness.state.clients().iter().all(|c| c.commands_sent == 1)).await;
+}
+
+#[tokio::test]
+async fn forgets_clients_that_disconnect() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+
+    client.close(None).await.unwrap();
+    eventually(|| harness.state.clients().is_empty()).await;
+    assert_eq!(harness.state.send_command(&json!({})).await, 0);
+}
+
+#[tokio::test]
+async fn reports_readiness_once_listening() {
+    let state = IngestState::new();
+    assert!(state.ready(Duration::from_millis(50)).await.is_err());
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let events: Events = Arc::new(RecordedEvents::default());
+    let waiting = tokio::spawn({
+        let state = state.clone();
+        async move { state.ready(Duration::from_secs(5)).await }
+    });
+    tokio::spawn(server::serve(listener, state.clone(), events));
+
+    assert_eq!(waiting.await.unwrap(), Ok(addr));
+}
+
+#[tokio::test]
+async fn evicts_over_the_store_limits() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+    harness.state.set_store_limits(StoreLimits {
+        max_requests: Some(2),
+        max_body_bytes: None,
+        policy: EvictionPolicy::Fifo,
+        overflow_to_disk: false,
+    });
+
+    for (id, url) in [
+        ("req_1", "https://api.example.com/a"),
+        ("req_2", "https://api.example.com/b"),
+        ("req_3", "https://api.example.com/c"),
+    ] {
+        send(&mut client, request(id, url)).await;
+    }
+    eventually(|| harness.events.named("new-request").len() == 3).await;
+
+    let ids: Vec<String> = harness.state.requests().into_iter().map(|r| r.id).collect();
+    assert_eq!(ids, ["req_2", "req_3"]);
+    let deleted = harness.events.named("requests-deleted");
+    assert_eq!(deleted, [json!({ "ids": ["req_1"] })]);
+
+    // A tighter byte limit applies to what is already stored
+    let body_bytes = "{\"ok\":true}".len() as u64;
+    let evicted = harness.state.set_store_limits(StoreLimits {
+        max_requests: None,
+        max_body_bytes: Some(body_bytes),
+        policy: EvictionPolicy::Fifo,
+        overflow_to_disk: false,
+    });
+    assert_eq!(evicted, ["req_2"]);
+}
+
+#[tokio::test]
+async fn overflows_to_disk_instead_of_evicting() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+    harness.state.set_store_limits(StoreLimits {
+        max_requests: Some(2),
+        max_body_bytes: None,
+        policy: EvictionPolicy::Fifo,
+        overflow_to_disk: true,
+    });
+
+    for (id, url) in [
+        ("req_1", "https://api.example.com/a"),
+        ("req_2", "https://api.example.com/b"),
+        ("req_3", "https://api.example.com/c"),
+    ] {
+        send(&mut client, request(id, url)).await;
+    }
+    eventually(|| harness.events.named("new-request").len() == 3).await;
+
+    let live: Vec<String> = harness.state.requests().into_iter().map(|r| r.id).collect();
+    assert_eq!(live, ["req_2", "req_3"]);
+    // Still part of the capture, so nothing was deleted
+    assert!(harness.events.named("requests-deleted").is_empty());
+    // Found while queued for the segment and once written to it
+    for _ in 0..2 {
+        let all = harness.state.all_requests();
+        let ids: Vec<&str> = all.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["req_1", "req_2", "req_3"]);
+        assert_eq!(all[0].response.as_ref().unwrap().status, 200);
+        harness.state.spill_overflow();
+    }
+}
+
+#[tokio::test]
+async fn keeps_endpoint_stats_up_to_date() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+    for (id, url) in [
+        ("req_1", "https://api.example.com/users/1"),
+        ("req_2", "https://api.example.com/users/2"),
+        ("req_3", "https://api.example.com/health"),
+    ] {
+        send(&mut client, request(id, url)).await;
+    }
+    let mut pending = request("req_4", "https://api.example.com/users/3");
+    pending["type"] = json!("request-start");
+    pending["response"] = Value::Null;
+    pending["duration"] = Value::Null;
+    send(&mut client, pending).await;
+    eventually(|| harness.events.named("new-request").len() == 4).await;
+
+    let stats = harness.state.stats();
+    let users = &stats[0];
+    assert_eq!(
+        users.value.as_deref(),
+        Some("GET api.example.com/users/{id}")
+    );
+    assert_eq!((users.count, users.pending, users.errors), (3, 1, 0));
+    assert_eq!(stats[1].count, 1);
+
+    send(
+        &mut client,
+        json!({
+            "type": "request-end",
+            "id": "req_4",
+            "duration": 40,
+            "response": { "status": 503, "status_text": "Unavailable", "headers": [], "body": null }
+        }),
+    )
+    .await;
+    eventually(|| harness.events.named("request-updated").len() == 1).await;
+    let users = harness.state.stats().remove(0);
+    assert_eq!((users.count, users.pending, users.errors), (3, 0, 1));
+}
+
+#[tokio::test]
+async fn spills_large_bodies_to_disk() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+    let body = format!("[{}0]", "1,".repeat(1024 * 1024));
+    let mut message = request("req_1", "https://api.example.com/export");
+    message["response"]["body"] = json!(body);
+    send(&mut client, message).await;
+    eventually(|| harness.events.named("new-request").len() == 1).await;
+
+    let response = harness.state.requests().remove(0).response.unwrap();
+    let spilled = response.body_file.expect("body spilled");
+    assert_eq!(spilled.size, body.len() as u64);
+    assert!(response.body.unwrap().len() < body.len());
+    // Compressed by default
+    let file = std::fs::read(&spilled.path).unwrap();
+    assert_eq!(spilled.compressed_size, Some(file.len() as u64));
+    assert!(file.len() < body.len() / 10);
+    assert_eq!(zstd::decode_all(file.as_slice()).unwrap(), body.as_bytes());
+    std::fs::remove_file(&spilled.path).ok();
+}
+
+#[tokio::test]
+async fn shares_identical_bodies_between_entries() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+    let body = json!({ "items": (0..100).collect::<Vec<_>>() }).to_string();
+    for id in ["req_1", "req_2"] {
+        let mut message = request(id, &format!("https://api.example.com/poll?{}", id));
+        message["response"]["body"] = json!(body);
+        send(&mut client, message).await;
+    }
+    eventually(|| harness.events.named("new-request").len() == 2).await;
+
+    let requests = harness.state.requests();
+    let bodies: Vec<_> = requests
+        .iter()
+        .map(|r| r.response.as_ref().unwrap().body.clone().unwrap())
+        .collect();
+    assert_eq!(&*bodies[0], body);
+    assert_eq!(bodies[0].as_ptr(), bodies[1].as_ptr());
+}
+
+#[tokio::test]
+async fn loads_from_and_writes_through_to_storage() {
+    let harness = start().await;
+    let stored = serde_json::from_value(request("stored_1", "https://api.example.com/a")).unwrap();
+    let storage = MockStorage {
+        stored: vec![stored],
+        ..Default::default()
+    };
+    let writes = storage.writes.clone();
+    assert_eq!(harness.state.attach_storage(Box::new(storage)), Ok(1));
+
+    let mut client = harness.connect().await;
+    send(&mut client, request("req_1", "https://api.example.com/b")).await;
+    eventually(|| harness.events.named("new-request").len() == 1).await;
+
+    let ids: Vec<String> = harness.state.requests().into_iter().map(|r| r.id).collect();
+    assert_eq!(ids, ["stored_1", "req_1"]);
+    // Writes go out in batches
+    assert!(writes.lock().unwrap().is_empty());
+    harness.state.flush_storage();
+    assert_eq!(*writes.lock().unwrap(), ["req_1"]);
+}
+
+#[tokio::test]
+async fn removes_evicted_requests_from_storage() {
+    let harness = start().await;
+    harness.state.set_store_limits(StoreLimits {
+        max_requests: Some(1),
+        max_body_bytes: None,
+        policy: EvictionPolicy::Fifo,
+        overflow_to_disk: false,
+    });
+    let stored = ["stored_1", "stored_2"].map(|id| {
+        let mut stored = request(id, "https://api.example.com/a");
+        stored["state"] = json!("complete");
+        serde_json::from_value(stored).unwrap()
+    });
+    let storage = MockStorage {
+        stored: stored.into(),
+        ..Default::default()
+    };
+    let writes = storage.writes.clone();
+    assert_eq!(harness.state.attach_storage(Box::new(storage)), Ok(2));
+    let ids: Vec<String> = harness.state.requests().into_iter().map(|r| r.id).collect();
+    assert_eq!(ids, ["stored_2"]);
+
+    let mut client = harness.connect().await;
+    send(&mut client, request("req_1", "https://api.example.com/b")).await;
+    eventually(|| harness.events.named("new-request").len() == 1).await;
+    harness.state.flush_storage();
+
+    let mut writes = writes.lock().unwrap().clone();
+    writes.sort();
+    assert_eq!(writes, ["-stored_1", "-stored_2", "req_1"]);
+}
+
+#[tokio::test]
+async fn attaches_hints_to_common_failures() {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    let harness = start().await;
+    let mut client = harness.connect().await;
+    let claims = URL_SAFE_NO_PAD.encode(json!({ "exp": 1_600_000_000u64 }).to_string());
+    let mut expired = request("req_1", "https://api.example.com/me");
+    expired["headers"] = json!([[
+        "authorization",
+        format!("Bearer eyJhbGciOiJIUzI1NiJ9.{}.sig", claims)
+    ]]);
+    expired["response"]["status"] = json!(401);
+    let mut too_large = request("req_2", "https://api.example.com/upload");
+    too_large["response"]["status"] = json!(413);
+    send(&mut client, expired).await;
+    send(&mut client, too_large).await;
+    send(&mut client, request("req_3", "https://api.example.com/ok")).await;
+    eventually(|| harness.events.named("new-request").len() == 3).await;
+
+    let kinds: Vec<Vec<Value>> = harness
+        .state
+        .requests()
+        .iter()
+        .map(|r| {
+            r.hints
+                .iter()
+                .map(|h| serde_json::to_value(h).unwrap()["kind"].clone())
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            vec![json!("expired-token")],
+            vec![json!("payload-too-large")],
+            vec![]
+        ]
+    );
+}
+
+// The close frame a connection turned away by the flood guard gets
+async fn rejected(harness: &Harness) -> (CloseCode, String) {
+    let (mut client, _) = connect_async(format!("ws://{}", harness.addr))
+        .await
+        .unwrap();
+    let message = tokio::time::timeout(Duration::from_secs(5), client.next())
+        .await
+        .expect("timed out waiting for a close frame")
+        .unwrap()
+        .unwrap();
+    match message {
+        Message::Close(Some(frame)) => (frame.code, frame.reason.to_string()),
+        other => panic!("expected a close frame, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn closes_connections_over_the_flood_limits() {
+    let harness = start().await;
+    harness
+        .state
+        .set_connection_limits(ConnectionLimits {
+            max_connections: 1,
+            max_connects: 10,
+            window_secs: 60,
+        })
+        .unwrap();
+    let _client = harness.connect().await;
+
+    let (code, reason) = rejected(&harness).await;
+    assert_eq!(code, CloseCode::Policy);
+    assert!(reason.contains("Too many open connections"), "{}", reason);
+
+    harness
+        .state
+        .set_connection_limits(ConnectionLimits {
+            max_connections: 10,
+            max_connects: 1,
+            window_secs: 60,
+        })
+        .unwrap();
+    let (code, reason) = rejected(&harness).await;
+    assert_eq!(code, CloseCode::Again);
+    assert!(reason.contains("Reconnecting too fast"), "{}", reason);
+
+    // Only the accepted connection reaches the app
+    assert_eq!(harness.events.named("websocket-connected").len(), 1);
+    assert_eq!(harness.state.clients().len(), 1);
+}
+
+#[tokio::test]
+async fn applies_config_changes_to_live_traffic() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+    let sink: Events = harness.events.clone();
+    let config: ObserverConfig = serde_json::from_value(json!({
+        "redact": ["accept"],
+        "ignore": [{ "url_contains": "/health" }],
+        "dedup_window_secs": 0
+    }))
+    .unwrap();
+    harness.state.apply_config(config, &sink).unwrap();
+    assert_eq!(harness.events.named("config-changed").len(), 1);
+
+    send(
+        &mut client,
+        request("req_1", "https://api.example.com/health"),
+    )
+    .await;
+    send(
+        &mut client,
+        request("req_2", "https://api.example.com/users"),
+    )
+    .await;
+    // Identical to the last one; kept since the dedup window is off
+    send(
+        &mut client,
+        request("req_3", "https://api.example.com/users"),
+    )
+    .await;
+    eventually(|| harness.state.requests().len() == 2).await;
+
+    let requests = harness.state.requests();
+    let ids: Vec<&str> = requests.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, ["req_2", "req_3"]);
+    assert_eq!(requests[0].headers.get("accept"), Some("REDACTED"));
+}
+
+#[tokio::test]
+async fn collapses_repeats_into_occurrence_counts() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+    let sink: Events = harness.events.clone();
+    let config: ObserverConfig = serde_json::from_value(json!({
+        "dedup_window_secs": 0,
+        "collapse_repeats": true
+    }))
+    .unwrap();
+    harness.state.apply_config(config, &sink).unwrap();
+
+    for (i, timestamp) in [1_000u64, 2_000, 3_000].into_iter().enumerate() {
+        let mut repeat = request(&format!("req_{}", i), "https://api.example.com/poll");
+        repeat["timestamp"] = json!(timestamp);
+        send(&mut client, repeat).await;
+    }
+    eventually(|| harness.events.named("request-updated").len() == 2).await;
+
+    let requests = harness.state.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].id, "req_0");
+    let occurrences = requests[0].occurrences.as_ref().unwrap();
+    assert_eq!(occurrences.count, 3);
+    assert_eq!(occurrences.timestamps, [1_000, 2_000, 3_000]);
+}
+
+#[tokio::test]
+async fn keeps_each_clients_requests_apart() {
+    let harness = start().await;
+    let mut first = harness.connect().await;
+    let mut second = harness.connect().await;
+
+    // The same call from two apps at once isn't a duplicate
+    send(
+        &mut first,
+        request("req_1", "https://api.example.com/users"),
+    )
+    .await;
+    eventually(|| harness.state.requests().len() == 1).await;
+    send(
+        &mut second,
+        request("req_2", "https://api.example.com/users"),
+    )
+    .await;
+    eventually(|| harness.state.requests().len() == 2).await;
+
+    let requests = harness.state.requests();
+    let senders: Vec<&str> = requests
+        .iter()
+        .map(|r| r.client_id.as_deref().unwrap())
+        .collect();
+    assert_ne!(senders[0], senders[1]);
+    let mut connected: Vec<String> = harness.state.clients().into_iter().map(|c| c.id).collect();
+    connected.sort();
+    let mut expected: Vec<String> = senders.iter().map(|id| id.to_string()).collect();
+    expected.sort();
+    assert_eq!(connected, expected);
+}
+
+#[tokio::test]
+async fn breaks_down_ingest_time_by_stage() {
+    let harness = start().await;
+    let mut client = harness.connect().await;
+    send(&mut client, request("req_1", "https://api.example.com/a")).await;
+    eventually(|| harness.state.requests().len() == 1).await;
+    assert_eq!(harness.state.perf_breakdown().stages[0].samples, 0);
+
+    harness.state.set_perf_mode(true, None);
+    send(&mut client, request("req_2", "https://api.example.com/b")).await;
+    eventually(|| harness.state.requests().len() == 2).await;
+
+    let breakdown = harness.state.perf_breakdown();
+    assert!(breakdown.enabled);
+    assert_eq!(breakdown.sampled_frames, 1);
+    let samples: Vec<u64> = breakdown.stages.iter().map(|s| s.samples).collect();
+    // Frame and message both parsed; each later stage timed once
+    assert_eq!(samples, [2, 1, 1, 1, 1, 1, 1]);
+    let share: f64 = breakdown.stages.iter().map(|s| s.share).sum();
+    assert!((share - 1.0).abs() < 1e-9);
+}
@@ -1,8 +1,8 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { listen } from "@tauri-apps/api/event";
 import "./App.css";
-import { NetworkRequest } from "@/types";
+import { ClearResult, NetworkRequest } from "@/types";
+import { listenTo } from "@/events";
 import { NetworkRequestList } from "@/components/NetworkRequestList";
 import { NetworkRequestDetails } from "@/components/NetworkRequestDetails";
 import { ResizableLayout } from "@/components/ResizableLayout";
@@ -16,36 +16,95 @@ function App() {
   );
   const [searchFilter, setSearchFilter] = useState("");
   const [isListening, setIsListening] = useState(false);
+  const [canUndoClear, setCanUndoClear] = useState(false);
+  const undoTimerRef = useRef<number | undefined>(undefined);
+  // The store stream being shown; chunks from any other are ignored
+  const streamRef = useRef<string | null>(null);
 
   useEffect(() => {
-    // Load initial requests
-    loadRequests();
+    // The store arrives in chunks, each acknowledged so the backend sends the
+    // next. A newer load replaces one still arriving.
+    const unlistenChunks = listenTo("store-chunk", (chunk) => {
+      if (chunk.seq === 0) {
+        if (streamRef.current && streamRef.current !== chunk.stream_id) {
+          invoke("cancel_store_stream", { streamId: streamRef.current }).catch(
+            () => {}
+          );
+        }
+        streamRef.current = chunk.stream_id;
+        setRequests(chunk.requests);
+      } else if (chunk.stream_id === streamRef.current) {
+        setRequests((prev) => [...prev, ...chunk.requests]);
+      } else {
+        return;
+      }
+      invoke("ack_store_chunk", { streamId: chunk.stream_id, seq: chunk.seq });
+    });
+
+    // Load initial requests once chunks can be received
+    unlistenChunks.then(() => loadRequests());
 
     // Listen for new requests from WebSocket
-    const unlistenRequests = listen<NetworkRequest>("new-request", (event) => {
-      setRequests((prev) => [...prev, event.payload]);
+    const unlistenRequests = listenTo("new-request", (request) => {
+      setRequests((prev) => [...prev, request]);
       setIsListening(true);
     });
 
+    // Requests reported with request-start are completed in place
+    const unlistenCompleted = listenTo("request-updated", (updated) => {
+      setRequests((prev) =>
+        prev.map((r) => (r.id === updated.id ? updated : r))
+      );
+      setSelectedRequest((prev) =>
+        prev && prev.id === updated.id ? updated : prev
+      );
+    });
+
+    // Offline queues are merged into the timeline by timestamp, so reload in order
+    const unlistenImported = listenTo("requests-imported", () => {
+      loadRequests();
+    });
+
+    // Bulk edits and deletes arrive as a single event with the affected ids
+    const unlistenUpdated = listenTo("requests-updated", () => {
+      loadRequests();
+    });
+    const unlistenRestored = listenTo("requests-restored", () => {
+      loadRequests();
+    });
+    const unlistenReplaced = listenTo("store-replaced", () => {
+      loadRequests();
+    });
+    const unlistenDeleted = listenTo("requests-deleted", ({ ids }) => {
+      const deleted = new Set(ids);
+      setRequests((prev) => prev.filter((r) => !deleted.has(r.id)));
+      setSelectedRequest((prev) =>
+        prev && deleted.has(prev.id) ? null : prev
+      );
+    });
+
     // Listen for WebSocket connections
-    const unlistenConnection = listen<string>(
-      "websocket-connected",
-      (event) => {
-        console.log("WebSocket client connected:", event.payload);
-        setIsListening(true);
-      }
-    );
+    const unlistenConnection = listenTo("websocket-connected", (peer) => {
+      console.log("WebSocket client connected:", peer);
+      setIsListening(true);
+    });
 
     return () => {
+      unlistenChunks.then((fn) => fn());
       unlistenRequests.then((fn) => fn());
+      unlistenCompleted.then((fn) => fn());
+      unlistenImported.then((fn) => fn());
+      unlistenUpdated.then((fn) => fn());
+      unlistenRestored.then((fn) => fn());
+      unlistenReplaced.then((fn) => fn());
+      unlistenDeleted.then((fn) => fn());
       unlistenConnection.then((fn) => fn());
     };
   }, []);
 
   const loadRequests = async () => {
     try {
-      const reqs = await invoke<NetworkRequest[]>("get_requests");
-      setRequests(reqs);
+      await invoke("start_store_stream");
     } catch (error) {
       console.error("Failed to load requests:", error);
     }
@@ -53,14 +112,32 @@ function App() {
 
   const clearRequests = async () => {
     try {
-      await invoke("clear_requests");
-      setRequests([]);
-      setSelectedRequest(null);
+      const { undo_secs } = await invoke<ClearResult>("clear_requests");
+      // Pinned requests survive a clear
+      setRequests((prev) => prev.filter((r) => r.pinned));
+      setSelectedRequest((prev) => (prev && prev.pinned ? prev : null));
+      setCanUndoClear(true);
+      window.clearTimeout(undoTimerRef.current);
+      undoTimerRef.current = window.setTimeout(
+        () => setCanUndoClear(false),
+        undo_secs * 1000
+      );
     } catch (error) {
       console.error("Failed to clear requests:", error);
     }
   };
 
+  // The restored requests come back through requests-restored
+  const undoClear = async () => {
+    setCanUndoClear(false);
+    window.clearTimeout(undoTimerRef.current);
+    try {
+      await invoke("undo_clear");
+    } catch (error) {
+      console.error("Failed to undo clear:", error);
+    }
+  };
+
   return (
     <div className="bg-background flex h-screen flex-col overflow-hidden">
       {/* Global Header */}
@@ -104,6 +181,7 @@ function App() {
             searchFilter={searchFilter}
             onSearchChange={setSearchFilter}
             onClear={clearRequests}
+            onUndoClear={canUndoClear ? undoClear : undefined}
           />
         }
         rightPanel={
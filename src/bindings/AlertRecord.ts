@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AlertRecord = { id: string, rule_id: string, rule_name: string, request_id: string, url: string, status: number | null, fired_at: number, screenshot_path: string | null, screenshot_error: string | null, };
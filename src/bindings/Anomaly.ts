@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AnomalyKind } from "./AnomalyKind";
+
+export type Anomaly = { id: string, request_id: string, endpoint: string, detected_at: number, kind: AnomalyKind, z_score: number | null, detail: string, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AnomalyConfig = { min_samples: number, z_threshold: number, rare_status_ratio: number, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AnonymizeProfile = { hash_hosts: boolean, strip_cookies: boolean, strip_auth: boolean, scrub_query: boolean, scrub_bodies: boolean, drop_bodies: boolean, };
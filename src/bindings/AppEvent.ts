@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AlertRecord } from "./AlertRecord";
+import type { Anomaly } from "./Anomaly";
+import type { BulkResult } from "./BulkResult";
+import type { CaptureStatus } from "./CaptureStatus";
+import type { ClientLagWarning } from "./ClientLagWarning";
+import type { ExportProgress } from "./ExportProgress";
+import type { LoadedSession } from "./LoadedSession";
+import type { Marker } from "../../packages/client-js/src/generated/Marker";
+import type { NetworkRequest } from "../../packages/client-js/src/generated/NetworkRequest";
+import type { ObserverConfig } from "./ObserverConfig";
+import type { ReplayOutcome } from "./ReplayOutcome";
+import type { SessionInfo } from "./SessionInfo";
+import type { StateChange } from "./StateChange";
+import type { StoreChunk } from "./StoreChunk";
+import type { UnparsedMessage } from "./UnparsedMessage";
+import type { WatchHit } from "./WatchHit";
+
+export type AppEvent = { "event": "websocket-connected", "payload": string } | { "event": "new-request", "payload": NetworkRequest } | { "event": "request-updated", "payload": NetworkRequest } | { "event": "request-state-changed", "payload": StateChange } | { "event": "requests-imported", "payload": Array<NetworkRequest> } | { "event": "requests-updated", "payload": BulkResult } | { "event": "requests-deleted", "payload": BulkResult } | { "event": "requests-pruned", "payload": BulkResult } | { "event": "requests-restored", "payload": BulkResult } | { "event": "store-replaced", "payload": LoadedSession } | { "event": "marker-added", "payload": Marker } | { "event": "alert-fired", "payload": AlertRecord } | { "event": "alert-updated", "payload": AlertRecord } | { "event": "anomaly-detected", "payload": Anomaly } | { "event": "watch-hit", "payload": WatchHit } | { "event": "capture-paused", "payload": CaptureStatus } | { "event": "capture-resumed", "payload": CaptureStatus } | { "event": "replay-progress", "payload": ReplayOutcome } | { "event": "export-progress", "payload": ExportProgress } | { "event": "store-chunk", "payload": StoreChunk } | { "event": "unparsed-message", "payload": UnparsedMessage } | { "event": "session-switched", "payload": SessionInfo } | { "event": "session-imported", "payload": SessionInfo } | { "event": "client-lagging", "payload": ClientLagWarning } | { "event": "config-changed", "payload": ObserverConfig };
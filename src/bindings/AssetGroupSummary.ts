@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AssetGroupSummary = { group: string, count: number, errors: number, total_bytes: number, first_timestamp: number, last_timestamp: number, slowest_ms: number | null, ids: Array<string>, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AuthTimelineEvent } from "./AuthTimelineEvent";
+
+export type AuthFailureBurst = { start: number, end: number, failure_count: number, status_counts: { [key in number]?: number }, refresh_count: number, timeline: Array<AuthTimelineEvent>, insight: string, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AuthEventKind } from "./AuthEventKind";
+
+export type AuthTimelineEvent = { request_id: string, timestamp: number, method: string, url: string, status: number | null, kind: AuthEventKind, location: string | null, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type BaselineSummary = { endpoint: string, samples: number, typical_latency_ms: number | null, typical_size_bytes: number | null, typical_status: string | null, };
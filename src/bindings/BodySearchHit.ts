@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PartMatches } from "./PartMatches";
+
+export type BodySearchHit = { request_id: string, parts: Array<PartMatches>, };
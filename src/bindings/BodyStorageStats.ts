@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type BodyStorageStats = { spilled_bodies: number, compressed_bodies: number, raw_bytes: number, stored_bytes: number, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SyntaxLanguage } from "./SyntaxLanguage";
+import type { Token } from "./Token";
+
+export type BodyTokens = { language: SyntaxLanguage, tokens: Array<Token>, truncated: boolean, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RequestFilter } from "./RequestFilter";
+
+export type BreakRule = { id: string, name: string | null, filter: RequestFilter, once: boolean, triggered: boolean, };
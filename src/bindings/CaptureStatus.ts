@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PauseReason } from "./PauseReason";
+
+export type CaptureStatus = { paused: boolean, paused_at: number | null, reason: PauseReason | null, dropped: number, };
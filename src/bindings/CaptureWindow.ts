@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RequestFilter } from "./RequestFilter";
+import type { WindowState } from "./WindowState";
+
+export type CaptureWindow = { id: string, name: string | null, start_at: number | null, start_filter: RequestFilter | null, stop_at: number | null, duration_ms: number | null, state: WindowState, started_at: number | null, };
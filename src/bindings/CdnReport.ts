@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CacheStatus } from "./CacheStatus";
+import type { EndpointCacheStats } from "./EndpointCacheStats";
+import type { PopCount } from "./PopCount";
+
+export type CdnReport = { requests_scanned: number, cdn_requests: number, counts: { [key in CacheStatus]?: number }, hit_ratio: number | null, pops: Array<PopCount>, endpoints: Array<EndpointCacheStats>, };
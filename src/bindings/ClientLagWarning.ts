@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ClientLagWarning = { client_id: string, peer: string, dropped: number, total_dropped: number, queued: number, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ClientStats = { id: string, peer: string, connected_at: number, client: string | null, client_version: string | null, protocol: number, messages_received: number, bytes_received: number, parse_failures: number, last_message_at: number | null, commands_sent: number, commands_lagged: number, };
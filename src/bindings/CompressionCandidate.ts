@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ContentClass } from "../../packages/client-js/src/generated/ContentClass";
+
+export type CompressionCandidate = { request_id: string, url: string, content_class: ContentClass | null, size: number, estimated_gzip_size: number, estimated_savings: number, };
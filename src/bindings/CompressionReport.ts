@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CompressionCandidate } from "./CompressionCandidate";
+import type { EndpointCompression } from "./EndpointCompression";
+
+export type CompressionReport = { responses_scanned: number, compressed: number, compressible: number, encoded_bytes: number, decoded_bytes: number, compression_ratio: number | null, estimated_savings: number, endpoints: Array<EndpointCompression>, candidates: Array<CompressionCandidate>, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ConnectionLimits } from "./ConnectionLimits";
+
+export type ConnectionGuardStatus = { limits: ConnectionLimits, open: { [key in string]?: number }, rejected: number, };
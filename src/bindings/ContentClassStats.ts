@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ContentClass } from "../../packages/client-js/src/generated/ContentClass";
+
+export type ContentClassStats = { class: ContentClass, count: number, total_bytes: number, mismatched: number, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DerivedSource } from "./DerivedSource";
+import type { Extractor } from "./Extractor";
+
+export type DerivedField = { name: string, source: DerivedSource, header: string | null, extractor: Extractor | null, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CacheStatus } from "./CacheStatus";
+
+export type EndpointCacheStats = { endpoint: string, requests: number, counts: { [key in CacheStatus]?: number }, hit_ratio: number | null, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type EndpointCompression = { endpoint: string, responses: number, compressed: number, encodings: { [key in string]?: number }, encoded_bytes: number, decoded_bytes: number, compression_ratio: number | null, compressible: number, estimated_savings: number, };
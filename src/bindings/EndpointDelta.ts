@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GroupStats } from "./GroupStats";
+
+export type EndpointDelta = { endpoint: string, baseline: GroupStats, candidate: GroupStats, avg_duration_delta_ms: number | null, p95_duration_delta_ms: number | null, error_rate_delta: number, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExportStatus } from "./ExportStatus";
+
+export type ExportProgress = { job_id: string, path: string, status: ExportStatus, written: number, total: number, error: string | null, };
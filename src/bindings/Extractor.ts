@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type Extractor = { "kind": "regex", pattern: string, group: string | null, } | { "kind": "json_path", path: string, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type GroupStats = { value: string | null, count: number, errors: number, pending: number, error_rate: number, avg_duration_ms: number | null, p95_duration_ms: number | null, total_response_bytes: number, };
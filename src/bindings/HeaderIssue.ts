@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HeaderIssueKind } from "./HeaderIssueKind";
+import type { HeaderSide } from "./HeaderSide";
+
+export type HeaderIssue = { request_id: string, url: string, side: HeaderSide, header: string, kind: HeaderIssueKind, detail: string, };
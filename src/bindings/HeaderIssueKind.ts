@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type HeaderIssueKind = "conflicting_duplicate" | "duplicate" | "invalid_name" | "invalid_value" | "non_ascii_value" | "oversized" | "oversized_block";
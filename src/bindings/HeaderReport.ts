@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HeaderIssue } from "./HeaderIssue";
+import type { HeaderIssueKind } from "./HeaderIssueKind";
+
+export type HeaderReport = { requests_scanned: number, counts: { [key in HeaderIssueKind]?: number }, issues: Array<HeaderIssue>, };
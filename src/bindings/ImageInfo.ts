@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImageFormat } from "./ImageFormat";
+import type { ImageIssueKind } from "./ImageIssueKind";
+
+export type ImageInfo = { request_id: string, url: string, format: ImageFormat, width: number | null, height: number | null, bytes: number | null, bytes_per_pixel: number | null, has_alpha: boolean | null, issues: Array<ImageIssueKind>, };
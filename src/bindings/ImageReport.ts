@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImageFormat } from "./ImageFormat";
+import type { ImageInfo } from "./ImageInfo";
+import type { ImageIssueKind } from "./ImageIssueKind";
+
+export type ImageReport = { images_scanned: number, images_measured: number, total_bytes: number, formats: { [key in ImageFormat]?: number }, counts: { [key in ImageIssueKind]?: number }, images: Array<ImageInfo>, };
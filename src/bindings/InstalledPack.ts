@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RulePack } from "./RulePack";
+
+export type InstalledPack = { pack: RulePack, enabled: boolean, source: string, installed_at: number, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JsonKind } from "./JsonKind";
+
+export type JsonNode = { key: string, pointer: string, kind: JsonKind, value: string | number | boolean | null, size: number, children: Array<JsonNode> | null, truncated: boolean, };
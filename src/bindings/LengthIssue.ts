@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HeaderSide } from "./HeaderSide";
+import type { LengthIssueKind } from "./LengthIssueKind";
+import type { LengthSource } from "./LengthSource";
+
+export type LengthIssue = { request_id: string, url: string, side: HeaderSide, kind: LengthIssueKind, declared: number | null, actual: number | null, measured_by: LengthSource | null, detail: string, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LengthIssue } from "./LengthIssue";
+import type { LengthIssueKind } from "./LengthIssueKind";
+
+export type LengthReport = { requests_scanned: number, bodies_checked: number, counts: { [key in LengthIssueKind]?: number }, issues: Array<LengthIssue>, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AssetGroupSummary } from "./AssetGroupSummary";
+import type { RequestSummary } from "./RequestSummary";
+
+export type ListingRow = { "kind": "request" } & RequestSummary | { "kind": "asset_group" } & AssetGroupSummary;
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LoadMode } from "./LoadMode";
+
+export type LoadedSession = { path: string, mode: LoadMode, request_count: number, evicted: number, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RequestFilter } from "./RequestFilter";
+import type { StoreLimits } from "./StoreLimits";
+
+export type ObserverConfig = { redact: Array<string>, ignore: Array<RequestFilter>, limits: StoreLimits | null, dedup_window_secs: number, collapse_repeats: boolean, import_dir: string | null, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type PageOutcome = { url: string, status: number | null, duration_ms: number, items: number, error: string | null, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PageOutcome } from "./PageOutcome";
+import type { PaginationEnd } from "./PaginationEnd";
+
+export type PaginatedReplay = { id: string, pages: Array<PageOutcome>, items: Array<unknown>, end: PaginationEnd, };
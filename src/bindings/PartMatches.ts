@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SearchPart } from "./SearchPart";
+import type { TextMatch } from "./TextMatch";
+
+export type PartMatches = { part: SearchPart, header: number | null, count: number, matches: Array<TextMatch>, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type PauseReason = { "kind": "manual" } | { "kind": "scheduled" } | { "kind": "break", rule_id: string, rule_name: string | null, request_id: string, url: string, };
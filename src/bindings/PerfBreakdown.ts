@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { StageTiming } from "./StageTiming";
+
+export type PerfBreakdown = { enabled: boolean, sample_every: number, since: number | null, sampled_frames: number, stages: Array<StageTiming>, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RequestSummary } from "./RequestSummary";
+
+export type QueryResult = { total: number, requests: Array<RequestSummary>, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ReplayOutcome = { index: number, method: string, original_url: string, url: string, original_status: number, status: number | null, duration_ms: number, error: string | null, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ReplayOutcome } from "./ReplayOutcome";
+
+export type ReplayReport = { id: string, har_path: string, base_url: string, started_at: number, finished_at: number, total: number, completed: number, failed: number, status_changed: number, outcomes: Array<ReplayOutcome>, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ContentClass } from "../../packages/client-js/src/generated/ContentClass";
+import type { RegexFilter } from "./RegexFilter";
+
+export type RequestFilter = { url_contains: string | null, host: string | null, method: string | null, status_min: number | null, status_max: number | null, min_duration_ms: number | null, content_class: ContentClass | null, pending: boolean | null, has_field: string | null, field: string | null, derived: string | null, form_field: string | null, filter_regex: RegexFilter | null, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ProvenanceEntry } from "../../packages/client-js/src/generated/ProvenanceEntry";
+import type { StateTransition } from "../../packages/client-js/src/generated/StateTransition";
+
+export type RequestHistory = { request_id: string, aliases: Array<string>, provenance: Array<ProvenanceEntry>, transitions: Array<StateTransition>, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NetworkRequest } from "../../packages/client-js/src/generated/NetworkRequest";
+
+export type RequestPage = { requests: Array<NetworkRequest>, next_cursor: string | null, total: number, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ContentClass } from "../../packages/client-js/src/generated/ContentClass";
+
+export type RequestSummary = { id: string, method: string, url: string, host: string, status: number | null, timestamp: number, duration: number | null, response_size: number, content_class: ContentClass | null, tags: Array<string>, derived: { [key in string]?: string }, preflight_id: string | null, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ResendOutcome = { id: string, method: string, url: string, original_status: number | null, status: number | null, duration_ms: number, body: string | null, error: string | null, };
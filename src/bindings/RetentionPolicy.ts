@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type RetentionPolicy = { max_age_minutes: number | null, max_storage_bytes: number | null, };
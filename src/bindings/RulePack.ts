@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RequestFilter } from "./RequestFilter";
+import type { TagRule } from "./TagRule";
+
+export type RulePack = { name: string, version: string, description: string | null, ignore: Array<RequestFilter>, redact: Array<string>, tag: Array<TagRule>, };
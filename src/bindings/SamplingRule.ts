@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RequestFilter } from "./RequestFilter";
+
+export type SamplingRule = { id: string, name: string | null, filter: RequestFilter, keep_one_in: number, keep_errors: boolean, seen: number, sampled_out: number, sampled_out_duration_ms: number, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SecretKind } from "./SecretKind";
+
+export type SecretInfo = { name: string, kind: SecretKind, created_at: number, rotated_at: number | null, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ServerTimingMetric } from "../../packages/client-js/src/generated/ServerTimingMetric";
+
+export type ServerTimings = { request_id: string, client_duration_ms: number | null, metrics: Array<ServerTimingMetric>, server_total_ms: number | null, unaccounted_ms: number | null, };
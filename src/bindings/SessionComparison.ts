@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EndpointDelta } from "./EndpointDelta";
+import type { GroupStats } from "./GroupStats";
+
+export type SessionComparison = { baseline: string, candidate: string, generated_at: number, generated_at_text: string, baseline_requests: number, candidate_requests: number, changed: Array<EndpointDelta>, new_endpoints: Array<GroupStats>, removed_endpoints: Array<GroupStats>, suppressed_endpoints: number, };
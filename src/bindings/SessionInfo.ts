@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type SessionInfo = { name: string, clients: Array<string>, created_at: number, request_count: number, active: boolean, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NetworkRequest } from "../../packages/client-js/src/generated/NetworkRequest";
+
+export type SnapshotDiff = { added: Array<NetworkRequest>, changed: Array<NetworkRequest>, removed: Array<string>, };
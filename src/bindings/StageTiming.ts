@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PerfStage } from "./PerfStage";
+
+export type StageTiming = { stage: PerfStage, samples: number, total_ms: number, mean_us: number, p95_us: number | null, max_us: number, share: number, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RequestState } from "../../packages/client-js/src/generated/RequestState";
+
+export type StateChange = { id: string, from: RequestState, to: RequestState, at: number, detail: string | null, };
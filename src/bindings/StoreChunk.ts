@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NetworkRequest } from "../../packages/client-js/src/generated/NetworkRequest";
+
+export type StoreChunk = { stream_id: string, seq: number, requests: Array<NetworkRequest>, sent: number, total: number, done: boolean, };
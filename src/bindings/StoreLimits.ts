@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EvictionPolicy } from "./EvictionPolicy";
+
+export type StoreLimits = { max_requests: number | null, max_body_bytes: number | null, policy: EvictionPolicy, overflow_to_disk: boolean, };
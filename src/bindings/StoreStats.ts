@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { StoreLimits } from "./StoreLimits";
+
+export type StoreStats = { total: number, estimated_bytes: number, body_bytes: number, per_host: { [key in string]?: number }, per_status_class: { [key in string]?: number }, oldest: number | null, newest: number | null, limits: StoreLimits, overflowed: number, capacity: number | null, near_limit: boolean, };
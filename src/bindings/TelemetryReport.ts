@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type TelemetryReport = { app_version: string, os: string, feature_usage: Record<string, number>, ingest_volume: Record<string, number>, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TelemetryReport } from "./TelemetryReport";
+
+export type TelemetryStatus = { enabled: boolean, switched_off: boolean, report_path: string | null, crash_report_path: string | null, report: TelemetryReport | null, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type TokenKind = "key" | "string" | "number" | "literal" | "keyword" | "regex" | "comment" | "punctuation" | "tag" | "attribute" | "meta";
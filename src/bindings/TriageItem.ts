@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RequestSummary } from "./RequestSummary";
+import type { SeverityReason } from "./SeverityReason";
+
+export type TriageItem = { request: RequestSummary, score: number, reasons: Array<SeverityReason>, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ProxyAuth } from "./ProxyAuth";
+
+export type UpstreamProxy = { url: string, auth: ProxyAuth, credentials_secret: string | null, bypass: Array<string>, };
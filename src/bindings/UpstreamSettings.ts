@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { UpstreamProxy } from "./UpstreamProxy";
+
+export type UpstreamSettings = { active_project: string | null, projects: { [key in string]?: UpstreamProxy }, };
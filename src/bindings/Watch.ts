@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RequestFilter } from "./RequestFilter";
+import type { WatchSource } from "./WatchSource";
+
+export type Watch = { id: string, name: string | null, filter: RequestFilter, path: string, source: WatchSource, };
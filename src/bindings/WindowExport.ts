@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Marker } from "../../packages/client-js/src/generated/Marker";
+import type { NetworkRequest } from "../../packages/client-js/src/generated/NetworkRequest";
+
+export type WindowExport = { start_ts: number, end_ts: number, exported_at: number, request_version: number, markers: Array<Marker>, requests: Array<NetworkRequest>, };
@@ -1,7 +1,8 @@
-import { NetworkRequest } from "@/types";
+import { Headers, Initiator, NetworkRequest } from "@/types";
 import { ContentBlock } from "./ContentBlock";
 import { useScrollManager } from "@/hooks/useScrollManager";
 import { CopyButton } from "./CopyButton";
+import { statusLabel } from "@/utils/requestState";
 
 interface NetworkRequestDetailsProps {
   request: NetworkRequest;
@@ -14,6 +15,35 @@ export function NetworkRequestDetails({ request }: NetworkRequestDetailsProps) {
     return new Date(timestamp).toLocaleTimeString();
   };
 
+  const formatHeaders = (headers: Headers) => {
+    return headers.map(([name, value]) => `${name}: ${value}`).join("\n");
+  };
+
+  const formatInitiator = (initiator: Initiator) => {
+    const frames = initiator.stack.map((frame) => {
+      const location = frame.original ?? frame;
+      const position = [location.file, location.line, location.column]
+        .filter((part) => part !== null)
+        .join(":");
+      return `at ${location.function ?? "<anonymous>"} (${position})`;
+    });
+    return [initiator.component && `Component: ${initiator.component}`, ...frames]
+      .filter(Boolean)
+      .join("\n");
+  };
+
+  // `application/x-www-form-urlencoded` bodies, one decoded field per line
+  const formatForm = (headers: Headers, body: string) => {
+    const contentType = headers.find(
+      ([name]) => name.toLowerCase() === "content-type",
+    )?.[1];
+    const mime = contentType?.split(";")[0].trim().toLowerCase();
+    if (mime !== "application/x-www-form-urlencoded") return null;
+    return [...new URLSearchParams(body)]
+      .map(([name, value]) => `${name} = ${value}`)
+      .join("\n");
+  };
+
   const getStatusColor = (status?: number) => {
     if (!status) return "text-gray-500";
     if (status >= 200 && status < 300) return "text-green-600";
@@ -22,6 +52,10 @@ export function NetworkRequestDetails({ request }: NetworkRequestDetailsProps) {
     return "text-gray-500";
   };
 
+  const requestForm = request.body
+    ? formatForm(request.headers, request.body)
+    : null;
+
   return (
     <div className="bg-card relative flex h-full flex-col">
       {/* Request Header */}
@@ -34,7 +68,7 @@ export function NetworkRequestDetails({ request }: NetworkRequestDetailsProps) {
             <span
               className={`text-lg font-medium ${getStatusColor(request.response?.status)}`}
             >
-              {request.response?.status || "Pending"}
+              {statusLabel(request)}
             </span>
           </div>
           <div className="text-muted-foreground text-xs">
@@ -67,11 +101,19 @@ export function NetworkRequestDetails({ request }: NetworkRequestDetailsProps) {
           </div>
         </div>
 
+        {/* Initiator */}
+        {request.initiator && (
+          <ContentBlock
+            title="Initiator"
+            content={formatInitiator(request.initiator)}
+          />
+        )}
+
         {/* Request Headers */}
-        {Object.entries(request.headers).length > 0 && (
+        {request.headers.length > 0 && (
           <ContentBlock
             title="Request Headers"
-            content={JSON.stringify(request.headers, null, 2)}
+            content={formatHeaders(request.headers)}
           />
         )}
 
@@ -80,12 +122,17 @@ export function NetworkRequestDetails({ request }: NetworkRequestDetailsProps) {
           <ContentBlock title="Request Body" content={request.body} />
         )}
 
+        {/* Request Form */}
+        {requestForm && (
+          <ContentBlock title="Request Form" content={requestForm} />
+        )}
+
         {/* Response Headers */}
         {request.response &&
-          Object.entries(request.response.headers).length > 0 && (
+          request.response.headers.length > 0 && (
             <ContentBlock
               title="Response Headers"
-              content={JSON.stringify(request.response.headers, null, 2)}
+              content={formatHeaders(request.response.headers)}
             />
           )}
 
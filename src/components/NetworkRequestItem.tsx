@@ -1,4 +1,5 @@
 import { NetworkRequest } from "@/types";
+import { statusLabel } from "@/utils/requestState";
 
 interface NetworkRequestItemProps {
   request: NetworkRequest;
@@ -23,7 +24,7 @@ export function NetworkRequestItem({
     return "text-gray-500";
   };
 
-  const hasGraphQLErrors = (responseBody?: string): boolean => {
+  const hasGraphQLErrors = (responseBody: string | null): boolean => {
     if (!responseBody) return false;
     try {
       const parsed = JSON.parse(responseBody);
@@ -50,7 +51,7 @@ export function NetworkRequestItem({
           <span
             className={`status-indicator ${getStatusStyle(request.response?.status)}`}
           >
-            {request.response?.status || "Pending"}
+            {statusLabel(request)}
           </span>
           {request.response?.status &&
             hasGraphQLErrors(request.response.body) && (
@@ -74,6 +75,18 @@ export function NetworkRequestItem({
           {request.duration}ms
         </div>
       )}
+      {Object.keys(request.derived ?? {}).length > 0 && (
+        <div className="mt-1 flex flex-wrap gap-1">
+          {Object.entries(request.derived).map(([name, value]) => (
+            <span
+              key={name}
+              className="bg-muted text-muted-foreground rounded px-1.5 py-0.5 text-xs"
+            >
+              {name}={value}
+            </span>
+          ))}
+        </div>
+      )}
     </div>
   );
 }
@@ -9,6 +9,8 @@ interface NetworkRequestListProps {
   searchFilter: string;
   onSearchChange: (filter: string) => void;
   onClear: () => void;
+  // Shown while the last clear can still be undone
+  onUndoClear?: () => void;
 }
 
 export function NetworkRequestList({
@@ -18,6 +20,7 @@ export function NetworkRequestList({
   searchFilter,
   onSearchChange,
   onClear,
+  onUndoClear,
 }: NetworkRequestListProps) {
   const filteredRequests = requests.filter((req) => {
     const searchTerm = searchFilter.toLowerCase();
@@ -45,6 +48,14 @@ export function NetworkRequestList({
           >
             Clear
           </button>
+          {onUndoClear && (
+            <button
+              onClick={onUndoClear}
+              className="bg-background text-foreground hover:bg-muted focus:ring-ring rounded-md border px-4 py-2 text-sm font-medium transition-all duration-150 focus:ring-2 focus:outline-none"
+            >
+              Undo
+            </button>
+          )}
         </div>
       </div>
 
@@ -0,0 +1,18 @@
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { AppEvent } from "@/types";
+
+export type AppEventName = AppEvent["event"];
+
+export type AppEventPayload<E extends AppEventName> = Extract<
+  AppEvent,
+  { event: E }
+>["payload"];
+
+// listen() with the event name and payload type checked against the backend's
+// generated AppEvent catalog
+export function listenTo<E extends AppEventName>(
+  event: E,
+  handler: (payload: AppEventPayload<E>) => void
+): Promise<UnlistenFn> {
+  return listen<AppEventPayload<E>>(event, (e) => handler(e.payload));
+}
@@ -1,18 +1,20 @@
-export interface NetworkRequest {
-  id: string;
-  url: string;
-  method: string;
-  headers: Record<string, string>;
-  body?: string;
-  response?: NetworkResponse;
-  timestamp: number;
-  duration?: number;
-}
-
-export interface NetworkResponse {
-  status: number;
-  status_text: string;
-  headers: Record<string, string>;
-  body?: string;
-}
-
+// Backend models, generated from the Rust structs by ts-rs (`cargo test` in
+// src-tauri). Wire protocol types are shared with packages/client-js; types that
+// only the desktop app sees live in ./bindings.
+export type { Attachment } from "../packages/client-js/src/generated/Attachment";
+export type { ContentClass } from "../packages/client-js/src/generated/ContentClass";
+export type { Headers } from "../packages/client-js/src/generated/Headers";
+export type { Initiator } from "../packages/client-js/src/generated/Initiator";
+export type { Marker } from "../packages/client-js/src/generated/Marker";
+export type { NetworkRequest } from "../packages/client-js/src/generated/NetworkRequest";
+export type { NetworkResponse } from "../packages/client-js/src/generated/NetworkResponse";
+export type { RequestNote } from "../packages/client-js/src/generated/RequestNote";
+export type { RequestState } from "../packages/client-js/src/generated/RequestState";
+export type { StackFrame } from "../packages/client-js/src/generated/StackFrame";
+export type { StateTransition } from "../packages/client-js/src/generated/StateTransition";
+export type { AppEvent } from "./bindings/AppEvent";
+export type { BulkResult } from "./bindings/BulkResult";
+export type { ClearResult } from "./bindings/ClearResult";
+export type { RequestFilter } from "./bindings/RequestFilter";
+export type { RequestSummary } from "./bindings/RequestSummary";
+export type { StateChange } from "./bindings/StateChange";
@@ -0,0 +1,14 @@
+import type { NetworkRequest, RequestState } from "@/types";
+
+const STATE_LABELS: Record<RequestState, string> = {
+  pending: "Pending",
+  "headers-received": "Receiving",
+  complete: "Complete",
+  failed: "Failed",
+  aborted: "Aborted",
+  mocked: "Mocked",
+};
+
+// Status code when there is one worth showing, otherwise where the request is
+export const statusLabel = (request: NetworkRequest): string | number =>
+  request.response?.status || STATE_LABELS[request.state];